@@ -18,15 +18,31 @@ pub enum Error {
     #[error("File could not be read as UTF8: {0:?}")]
     ReadUtf8(PathBuf),
 
+    #[error("File could not be read as {1}: {0:?}")]
+    ReadEncoding(PathBuf, String),
+
+    #[error("Unknown encoding: {0}")]
+    UnknownEncoding(String),
+
     #[error("Include error")]
     Include {
         #[from]
         source: Box<Error>,
     },
 
+    #[error("Macro expansion error at {origin:?}")]
+    MacroExpansion {
+        #[source]
+        source: Box<Error>,
+        origin: Option<(PathBuf, usize)>,
+    },
+
     #[error("Parse error: {0:?}")]
     Parse(Option<(PathBuf, usize)>),
 
+    #[error("Version error: {0:?}")]
+    Version(Vec<(Option<(PathBuf, usize)>, String)>),
+
     #[error("Preprocess error: {0:?}")]
     Preprocess(Option<(PathBuf, usize)>),
 
@@ -48,6 +48,36 @@ fn impl_node(ast: &DeriveInput) -> TokenStream {
         }
     };
 
+    let locate_mut = match ast.data {
+        Enum(ref data) => {
+            let mut items = quote! {};
+            for v in &data.variants {
+                let ident = &v.ident;
+                let item = quote! {
+                    #name::#ident(x) => { x.locate_mut(f) },
+                };
+                items = quote! {
+                    #items
+                    #item
+                };
+            }
+
+            quote! {
+                match self {
+                    #items
+                }
+            }
+        }
+        Struct(_) => {
+            quote! {
+                self.nodes.locate_mut(f)
+            }
+        }
+        _ => {
+            quote! {}
+        }
+    };
+
     let gen = quote! {
         impl<'a> Node<'a> for #name {
             fn next(&'a self) -> RefNodes<'a> {
@@ -55,6 +85,12 @@ fn impl_node(ast: &DeriveInput) -> TokenStream {
             }
         }
 
+        impl NodeMut for #name {
+            fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+                #locate_mut
+            }
+        }
+
         impl<'a> From<&'a #name> for RefNodes<'a> {
             fn from(x: &'a #name) -> Self {
                 vec![RefNode::#name(x)].into()
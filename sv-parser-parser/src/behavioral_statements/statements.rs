@@ -92,6 +92,15 @@ pub(crate) fn statement_item(s: Span) -> IResult<Span, StatementItem> {
         map(expect_property_statement, |x| {
             StatementItem::ExpectPropertyStatement(Box::new(x))
         }),
+        alt((
+            map(
+                preceded(extension(Extension::VerilogAms), contribution_statement),
+                |x| StatementItem::ContributionStatement(Box::new(x)),
+            ),
+            map(preceded(macro_preserving, macro_usage_statement), |x| {
+                StatementItem::MacroUsage(Box::new(x))
+            }),
+        )),
     ))(s)
 }
 
@@ -50,11 +50,12 @@ pub(crate) fn local_parameter_declaration_type(
 ) -> IResult<Span, LocalParameterDeclaration> {
     let (s, a) = keyword("localparam")(s)?;
     let (s, b) = keyword("type")(s)?;
-    let (s, c) = list_of_type_assignments(s)?;
+    let (s, c) = opt(preceded(ieee1800_2023, forward_type))(s)?;
+    let (s, d) = list_of_type_assignments(s)?;
     Ok((
         s,
         LocalParameterDeclaration::Type(Box::new(LocalParameterDeclarationType {
-            nodes: (a, b, c),
+            nodes: (a, b, c, d),
         })),
     ))
 }
@@ -98,10 +99,13 @@ pub(crate) fn data_type_or_implicit_parameter_declaration_param(
 pub(crate) fn parameter_declaration_type(s: Span) -> IResult<Span, ParameterDeclaration> {
     let (s, a) = keyword("parameter")(s)?;
     let (s, b) = keyword("type")(s)?;
-    let (s, c) = list_of_type_assignments(s)?;
+    let (s, c) = opt(preceded(ieee1800_2023, forward_type))(s)?;
+    let (s, d) = list_of_type_assignments(s)?;
     Ok((
         s,
-        ParameterDeclaration::Type(Box::new(ParameterDeclarationType { nodes: (a, b, c) })),
+        ParameterDeclaration::Type(Box::new(ParameterDeclarationType {
+            nodes: (a, b, c, d),
+        })),
     ))
 }
 
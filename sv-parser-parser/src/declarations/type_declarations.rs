@@ -327,6 +327,20 @@ pub(crate) fn type_declaration_keyword(s: Span) -> IResult<Span, TypeDeclaration
     ))(s)
 }
 
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn forward_type(s: Span) -> IResult<Span, ForwardType> {
+    alt((
+        map(keyword("enum"), |x| ForwardType::Enum(Box::new(x))),
+        map(keyword("struct"), |x| ForwardType::Struct(Box::new(x))),
+        map(keyword("union"), |x| ForwardType::Union(Box::new(x))),
+        map(keyword("class"), |x| ForwardType::Class(Box::new(x))),
+        map(pair(keyword("interface"), keyword("class")), |x| {
+            ForwardType::InterfaceClass(Box::new(x))
+        }),
+    ))(s)
+}
+
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn net_type_declaration(s: Span) -> IResult<Span, NetTypeDeclaration> {
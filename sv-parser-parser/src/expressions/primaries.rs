@@ -6,6 +6,10 @@ use crate::*;
 #[packrat_parser]
 pub(crate) fn constant_primary(s: Span) -> IResult<Span, ConstantPrimary> {
     alt((
+        map(
+            preceded(macro_preserving, macro_usage_constant_expression),
+            |x| ConstantPrimary::MacroUsage(Box::new(x)),
+        ),
         // BNF-WA
         map(keyword("$"), |x| ConstantPrimary::Dollar(Box::new(x))),
         map(keyword("null"), |x| ConstantPrimary::Null(Box::new(x))),
@@ -45,6 +49,10 @@ pub(crate) fn constant_primary(s: Span) -> IResult<Span, ConstantPrimary> {
 #[packrat_parser]
 pub(crate) fn constant_primary_without_cast(s: Span) -> IResult<Span, ConstantPrimary> {
     alt((
+        map(
+            preceded(macro_preserving, macro_usage_constant_expression),
+            |x| ConstantPrimary::MacroUsage(Box::new(x)),
+        ),
         // BNF-WA
         map(keyword("$"), |x| ConstantPrimary::Dollar(Box::new(x))),
         map(keyword("null"), |x| ConstantPrimary::Null(Box::new(x))),
@@ -192,6 +200,9 @@ pub(crate) fn module_path_primary_mintypmax_expression(
 #[packrat_parser]
 pub(crate) fn primary(s: Span) -> IResult<Span, Primary> {
     alt((
+        map(preceded(macro_preserving, macro_usage_expression), |x| {
+            Primary::MacroUsage(Box::new(x))
+        }),
         terminated(
             primary_hierarchical,
             peek(not(alt((
@@ -5,7 +5,10 @@ use crate::*;
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn string_literal(s: Span) -> IResult<Span, StringLiteral> {
-    let (s, a) = ws(string_literal_impl)(s)?;
+    let (s, a) = ws(alt((
+        preceded(ieee1800_2023, triple_quoted_string_impl),
+        string_literal_impl,
+    )))(s)?;
     Ok((s, StringLiteral { nodes: a }))
 }
 
@@ -38,3 +41,24 @@ pub(crate) fn string_literal_impl(s: Span) -> IResult<Span, Locate> {
 
     Ok((s, into_locate(a)))
 }
+
+#[tracable_parser]
+pub(crate) fn triple_quoted_string_impl(s: Span) -> IResult<Span, Locate> {
+    let (s, a) = tag("\"\"\"")(s)?;
+    let (s, b) = many0(alt((
+        is_not("\\\""),
+        map(pair(tag("\\"), take(1usize)), |(x, y)| {
+            concat(x, y).unwrap()
+        }),
+        terminated(tag("\""), peek(not(tag("\"\"")))),
+    )))(s)?;
+    let (s, c) = tag("\"\"\"")(s)?;
+
+    let mut ret = a;
+    for x in b {
+        ret = concat(ret, x).unwrap();
+    }
+    let ret = concat(ret, c).unwrap();
+
+    Ok((s, into_locate(ret)))
+}
@@ -0,0 +1,2 @@
+pub mod verilog_ams;
+pub(crate) use verilog_ams::*;
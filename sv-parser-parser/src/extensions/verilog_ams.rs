@@ -0,0 +1,221 @@
+use crate::*;
+
+// -----------------------------------------------------------------------------
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn discipline_declaration(s: Span) -> IResult<Span, DisciplineDeclaration> {
+    let (s, a) = keyword("discipline")(s)?;
+    let (s, b) = discipline_identifier(s)?;
+    let (s, c) = opt(symbol(";"))(s)?;
+    let (s, (d, e)) = many_till(discipline_item, keyword("enddiscipline"))(s)?;
+    Ok((
+        s,
+        DisciplineDeclaration {
+            nodes: (a, b, c, d, e),
+        },
+    ))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn discipline_item(s: Span) -> IResult<Span, DisciplineItem> {
+    alt((
+        map(nature_binding, |x| DisciplineItem::NatureBinding(Box::new(x))),
+        map(discipline_domain_binding, |x| {
+            DisciplineItem::DisciplineDomainBinding(Box::new(x))
+        }),
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn nature_binding(s: Span) -> IResult<Span, NatureBinding> {
+    let (s, a) = potential_or_flow(s)?;
+    let (s, b) = nature_identifier(s)?;
+    let (s, c) = symbol(";")(s)?;
+    Ok((s, NatureBinding { nodes: (a, b, c) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn potential_or_flow(s: Span) -> IResult<Span, PotentialOrFlow> {
+    alt((
+        map(keyword("potential"), |x| {
+            PotentialOrFlow::Potential(Box::new(x))
+        }),
+        map(keyword("flow"), |x| PotentialOrFlow::Flow(Box::new(x))),
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn discipline_domain_binding(s: Span) -> IResult<Span, DisciplineDomainBinding> {
+    let (s, a) = keyword("domain")(s)?;
+    let (s, b) = discrete_or_continuous(s)?;
+    let (s, c) = symbol(";")(s)?;
+    Ok((s, DisciplineDomainBinding { nodes: (a, b, c) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn discrete_or_continuous(s: Span) -> IResult<Span, DiscreteOrContinuous> {
+    alt((
+        map(keyword("discrete"), |x| {
+            DiscreteOrContinuous::Discrete(Box::new(x))
+        }),
+        map(keyword("continuous"), |x| {
+            DiscreteOrContinuous::Continuous(Box::new(x))
+        }),
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn nature_declaration(s: Span) -> IResult<Span, NatureDeclaration> {
+    let (s, a) = keyword("nature")(s)?;
+    let (s, b) = nature_identifier(s)?;
+    let (s, c) = opt(pair(symbol(":"), parent_nature))(s)?;
+    let (s, d) = opt(symbol(";"))(s)?;
+    let (s, (e, f)) = many_till(nature_item, keyword("endnature"))(s)?;
+    Ok((
+        s,
+        NatureDeclaration {
+            nodes: (a, b, c, d, e, f),
+        },
+    ))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn parent_nature(s: Span) -> IResult<Span, ParentNature> {
+    alt((
+        parent_nature_discipline_identifier,
+        map(nature_identifier, |x| {
+            ParentNature::NatureIdentifier(Box::new(x))
+        }),
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn parent_nature_discipline_identifier(s: Span) -> IResult<Span, ParentNature> {
+    let (s, a) = discipline_identifier(s)?;
+    let (s, b) = symbol(".")(s)?;
+    let (s, c) = potential_or_flow(s)?;
+    Ok((s, ParentNature::DisciplineIdentifier(Box::new((a, b, c)))))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn nature_item(s: Span) -> IResult<Span, NatureItem> {
+    let (s, a) = nature_attribute(s)?;
+    let (s, b) = symbol(";")(s)?;
+    Ok((s, NatureItem { nodes: (a, b) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn nature_attribute(s: Span) -> IResult<Span, NatureAttribute> {
+    let (s, a) = nature_attribute_identifier(s)?;
+    let (s, b) = symbol("=")(s)?;
+    let (s, c) = expression(s)?;
+    Ok((s, NatureAttribute { nodes: (a, b, c) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn branch_declaration(s: Span) -> IResult<Span, BranchDeclaration> {
+    let (s, a) = keyword("branch")(s)?;
+    let (s, b) = paren(pair(
+        branch_terminal,
+        opt(pair(symbol(","), branch_terminal)),
+    ))(s)?;
+    let (s, c) = list_of_branch_identifiers(s)?;
+    let (s, d) = symbol(";")(s)?;
+    Ok((
+        s,
+        BranchDeclaration {
+            nodes: (a, b, c, d),
+        },
+    ))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn list_of_branch_identifiers(s: Span) -> IResult<Span, ListOfBranchIdentifiers> {
+    let (s, a) = list(symbol(","), branch_identifier)(s)?;
+    Ok((s, ListOfBranchIdentifiers { nodes: (a,) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn branch_terminal(s: Span) -> IResult<Span, BranchTerminal> {
+    let (s, a) = net_identifier(s)?;
+    let (s, b) = opt(bracket(constant_range_expression))(s)?;
+    Ok((s, BranchTerminal { nodes: (a, b) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn analog_construct(s: Span) -> IResult<Span, AnalogConstruct> {
+    let (s, a) = keyword("analog")(s)?;
+    let (s, b) = opt(keyword("initial"))(s)?;
+    let (s, c) = statement(s)?;
+    Ok((s, AnalogConstruct { nodes: (a, b, c) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn analog_function_declaration(s: Span) -> IResult<Span, AnalogFunctionDeclaration> {
+    let (s, a) = keyword("analog")(s)?;
+    let (s, b) = function_declaration(s)?;
+    Ok((s, AnalogFunctionDeclaration { nodes: (a, b) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn contribution_statement(s: Span) -> IResult<Span, ContributionStatement> {
+    let (s, a) = branch_lvalue(s)?;
+    let (s, b) = symbol("<+")(s)?;
+    let (s, c) = expression(s)?;
+    let (s, d) = symbol(";")(s)?;
+    Ok((
+        s,
+        ContributionStatement {
+            nodes: (a, b, c, d),
+        },
+    ))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn branch_lvalue(s: Span) -> IResult<Span, BranchLvalue> {
+    let (s, a) = nature_attribute_identifier(s)?;
+    let (s, b) = paren(list(symbol(","), branch_terminal))(s)?;
+    Ok((s, BranchLvalue { nodes: (a, b) }))
+}
+
+#[tracable_parser]
+pub(crate) fn discipline_identifier(s: Span) -> IResult<Span, DisciplineIdentifier> {
+    let (s, a) = identifier(s)?;
+    Ok((s, DisciplineIdentifier { nodes: (a,) }))
+}
+
+#[tracable_parser]
+pub(crate) fn nature_identifier(s: Span) -> IResult<Span, NatureIdentifier> {
+    let (s, a) = identifier(s)?;
+    Ok((s, NatureIdentifier { nodes: (a,) }))
+}
+
+#[tracable_parser]
+pub(crate) fn nature_attribute_identifier(s: Span) -> IResult<Span, NatureAttributeIdentifier> {
+    let (s, a) = identifier(s)?;
+    Ok((s, NatureAttributeIdentifier { nodes: (a,) }))
+}
+
+#[tracable_parser]
+pub(crate) fn branch_identifier(s: Span) -> IResult<Span, BranchIdentifier> {
+    let (s, a) = identifier(s)?;
+    Ok((s, BranchIdentifier { nodes: (a,) }))
+}
@@ -43,6 +43,9 @@ pub(crate) fn compiler_directive(s: Span) -> IResult<Span, CompilerDirective> {
         map(endcelldefine_compiler_directive, |x| {
             CompilerDirective::EndcelldefineDriveCompilerDirective(Box::new(x))
         }),
+        map(protected_envelope, |x| {
+            CompilerDirective::ProtectedEnvelope(Box::new(x))
+        }),
         map(pragma, |x| CompilerDirective::Pragma(Box::new(x))),
         map(line_compiler_directive, |x| {
             CompilerDirective::LineCompilerDirective(Box::new(x))
@@ -102,6 +105,9 @@ pub(crate) fn compiler_directive_without_resetall(s: Span) -> IResult<Span, Comp
         map(endcelldefine_compiler_directive, |x| {
             CompilerDirective::EndcelldefineDriveCompilerDirective(Box::new(x))
         }),
+        map(protected_envelope, |x| {
+            CompilerDirective::ProtectedEnvelope(Box::new(x))
+        }),
         map(pragma, |x| CompilerDirective::Pragma(Box::new(x))),
         map(line_compiler_directive, |x| {
             CompilerDirective::LineCompilerDirective(Box::new(x))
@@ -460,12 +466,12 @@ pub(crate) fn conditional_compiler_directive(
 pub(crate) fn ifdef_directive(s: Span) -> IResult<Span, IfdefDirective> {
     let (s, a) = symbol("`")(s)?;
     let (s, b) = keyword("ifdef")(s)?;
-    let (s, c) = text_macro_identifier(s)?;
+    let (s, c) = ifdef_condition(s)?;
     let (s, d) = ifdef_group_of_lines(s)?;
     let (s, e) = many0(tuple((
         symbol("`"),
         keyword("elsif"),
-        text_macro_identifier,
+        ifdef_condition,
         elsif_group_of_lines,
     )))(s)?;
     let (s, f) = opt(tuple((symbol("`"), keyword("else"), else_group_of_lines)))(s)?;
@@ -484,12 +490,12 @@ pub(crate) fn ifdef_directive(s: Span) -> IResult<Span, IfdefDirective> {
 pub(crate) fn ifndef_directive(s: Span) -> IResult<Span, IfndefDirective> {
     let (s, a) = symbol("`")(s)?;
     let (s, b) = keyword("ifndef")(s)?;
-    let (s, c) = text_macro_identifier(s)?;
+    let (s, c) = ifdef_condition(s)?;
     let (s, d) = ifndef_group_of_lines(s)?;
     let (s, e) = many0(tuple((
         symbol("`"),
         keyword("elsif"),
-        text_macro_identifier,
+        ifdef_condition,
         elsif_group_of_lines,
     )))(s)?;
     let (s, f) = opt(tuple((symbol("`"), keyword("else"), else_group_of_lines)))(s)?;
@@ -503,6 +509,85 @@ pub(crate) fn ifndef_directive(s: Span) -> IResult<Span, IfndefDirective> {
     ))
 }
 
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn ifdef_condition(s: Span) -> IResult<Span, IfdefCondition> {
+    alt((
+        map(text_macro_identifier, |x| {
+            IfdefCondition::TextMacroIdentifier(Box::new(x))
+        }),
+        map(preceded(ieee1800_2023, paren(ifdef_macro_expression)), |x| {
+            IfdefCondition::IfdefMacroExpression(Box::new(x))
+        }),
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn ifdef_macro_expression(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    let (s, a) = ifdef_macro_expression_or(s)?;
+    let (s, b) = opt(pair(
+        map(alt((symbol("->"), symbol("<->"))), |x| {
+            BinaryLogicalOperator { nodes: (x,) }
+        }),
+        ifdef_macro_expression,
+    ))(s)?;
+    let a = if let Some((b, c)) = b {
+        IfdefMacroExpression::Binary(Box::new(IfdefMacroExpressionBinary { nodes: (a, b, c) }))
+    } else {
+        a
+    };
+    Ok((s, a))
+}
+
+#[tracable_parser]
+pub(crate) fn ifdef_macro_expression_or(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    let (s, a) = ifdef_macro_expression_and(s)?;
+    let (s, b) = many0(pair(
+        map(symbol("||"), |x| BinaryLogicalOperator { nodes: (x,) }),
+        ifdef_macro_expression_and,
+    ))(s)?;
+    Ok((s, ifdef_macro_expression_binary(a, b)))
+}
+
+#[tracable_parser]
+pub(crate) fn ifdef_macro_expression_and(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    let (s, a) = ifdef_macro_expression_primary(s)?;
+    let (s, b) = many0(pair(
+        map(symbol("&&"), |x| BinaryLogicalOperator { nodes: (x,) }),
+        ifdef_macro_expression_primary,
+    ))(s)?;
+    Ok((s, ifdef_macro_expression_binary(a, b)))
+}
+
+#[tracable_parser]
+pub(crate) fn ifdef_macro_expression_primary(s: Span) -> IResult<Span, IfdefMacroExpression> {
+    alt((
+        map(text_macro_identifier, |x| {
+            IfdefMacroExpression::TextMacroIdentifier(Box::new(x))
+        }),
+        map(paren(ifdef_macro_expression), |x| {
+            IfdefMacroExpression::Paren(Box::new(x))
+        }),
+        map(pair(symbol("!"), ifdef_macro_expression_primary), |x| {
+            IfdefMacroExpression::Not(Box::new(IfdefMacroExpressionNot { nodes: x }))
+        }),
+    ))(s)
+}
+
+fn ifdef_macro_expression_binary(
+    a: IfdefMacroExpression,
+    b: Vec<(BinaryLogicalOperator, IfdefMacroExpression)>,
+) -> IfdefMacroExpression {
+    let mut ret = a;
+    for (b, c) in b {
+        ret = IfdefMacroExpression::Binary(Box::new(IfdefMacroExpressionBinary {
+            nodes: (ret, b, c),
+        }));
+    }
+    ret
+}
+
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn ifdef_group_of_lines(s: Span) -> IResult<Span, IfdefGroupOfLines> {
@@ -746,6 +831,63 @@ pub(crate) fn pragma_keyword(s: Span) -> IResult<Span, PragmaKeyword> {
     Ok((s, PragmaKeyword { nodes: (a,) }))
 }
 
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn protected_envelope(s: Span) -> IResult<Span, ProtectedEnvelope> {
+    let (s, a) = protect_pragma("begin_protected")(s)?;
+    let (s, b) = many0(preceded(
+        peek(not(protect_pragma("end_protected"))),
+        protected_envelope_item,
+    ))(s)?;
+    let (s, c) = protect_pragma("end_protected")(s)?;
+    Ok((s, ProtectedEnvelope { nodes: (a, b, c) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn protected_envelope_item(s: Span) -> IResult<Span, ProtectedEnvelopeItem> {
+    alt((
+        map(pragma, |x| ProtectedEnvelopeItem::Pragma(Box::new(x))),
+        map(protected_block, |x| {
+            ProtectedEnvelopeItem::ProtectedBlock(Box::new(x))
+        }),
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn protected_block(s: Span) -> IResult<Span, ProtectedBlock> {
+    let (s, a) = recognize(many1(preceded(
+        peek(not(pair(space0, tag("`pragma")))),
+        pair(opt(is_not("\n")), tag("\n")),
+    )))(s)?;
+    Ok((
+        s,
+        ProtectedBlock {
+            nodes: (into_locate(a),),
+        },
+    ))
+}
+
+/// Match `` `pragma protect `` including the specified keyword.
+pub(crate) fn protect_pragma<'a>(
+    keyword: &'static str,
+) -> impl Fn(Span<'a>) -> IResult<Span<'a>, Pragma> {
+    move |s: Span<'a>| {
+        let (t, (a, b)) = consumed(pragma)(s)?;
+        let words: Vec<&str> = a
+            .fragment()
+            .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+            .filter(|x| !x.is_empty())
+            .collect();
+        if words.get(1) == Some(&"protect") && words.contains(&keyword) {
+            Ok((t, b))
+        } else {
+            Err(Err::Error(make_error(s, ErrorKind::Verify)))
+        }
+    }
+}
+
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn identifier_pragma(s: Span) -> IResult<Span, Identifier> {
@@ -829,6 +971,10 @@ pub(crate) fn keywords_directive(s: Span) -> IResult<Span, KeywordsDirective> {
 #[packrat_parser]
 pub(crate) fn version_specifier(s: Span) -> IResult<Span, VersionSpecifier> {
     let (s, a) = alt((
+        map(keyword("1800-2023"), |x| {
+            begin_keywords("1800-2023");
+            x
+        }),
         map(keyword("1800-2017"), |x| {
             begin_keywords("1800-2017");
             x
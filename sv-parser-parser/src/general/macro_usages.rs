@@ -0,0 +1,67 @@
+use crate::*;
+
+// -----------------------------------------------------------------------------
+
+/// The marker starting a macro usage kept by the macro-preserving preprocessor.
+pub const MACRO_USAGE_BEGIN: &str = "\u{2}";
+/// The marker between the original usage text and the expanded text.
+pub const MACRO_USAGE_SEPARATOR: &str = "\u{1f}";
+/// The marker ending the expanded text.
+pub const MACRO_USAGE_END: &str = "\u{3}";
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn macro_usage(s: Span) -> IResult<Span, MacroUsage> {
+    // The usage text starts with a backtick, which would be taken as a directive by `symbol`
+    let (s, a) = symbol_exact(MACRO_USAGE_BEGIN)(s)?;
+    let (s, b) = map(take_until(MACRO_USAGE_SEPARATOR), into_locate)(s)?;
+    let (s, c) = symbol(MACRO_USAGE_SEPARATOR)(s)?;
+    Ok((s, MacroUsage { nodes: (a, b, c) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn macro_usage_expression(s: Span) -> IResult<Span, MacroUsageExpression> {
+    let (s, a) = macro_usage(s)?;
+    let (s, b) = expression(s)?;
+    let (s, c) = symbol(MACRO_USAGE_END)(s)?;
+    Ok((s, MacroUsageExpression { nodes: (a, b, c) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn macro_usage_constant_expression(
+    s: Span,
+) -> IResult<Span, MacroUsageConstantExpression> {
+    let (s, a) = macro_usage(s)?;
+    let (s, b) = constant_expression(s)?;
+    let (s, c) = symbol(MACRO_USAGE_END)(s)?;
+    Ok((s, MacroUsageConstantExpression { nodes: (a, b, c) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn macro_usage_statement(s: Span) -> IResult<Span, MacroUsageStatement> {
+    let (s, a) = macro_usage(s)?;
+    let (s, b) = statement_or_null(s)?;
+    let (s, c) = symbol(MACRO_USAGE_END)(s)?;
+    Ok((s, MacroUsageStatement { nodes: (a, b, c) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn macro_usage_module_or_generate_item(
+    s: Span,
+) -> IResult<Span, MacroUsageModuleOrGenerateItem> {
+    let (s, a) = macro_usage(s)?;
+    let (s, (b, c)) = many_till(module_or_generate_item, symbol(MACRO_USAGE_END))(s)?;
+    Ok((s, MacroUsageModuleOrGenerateItem { nodes: (a, b, c) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn macro_usage_class_item(s: Span) -> IResult<Span, MacroUsageClassItem> {
+    let (s, a) = macro_usage(s)?;
+    let (s, (b, c)) = many_till(class_item, symbol(MACRO_USAGE_END))(s)?;
+    Ok((s, MacroUsageClassItem { nodes: (a, b, c) }))
+}
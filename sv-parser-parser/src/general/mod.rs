@@ -2,7 +2,9 @@ pub mod attributes;
 pub mod comments;
 pub mod compiler_directives;
 pub mod identifiers;
+pub mod macro_usages;
 pub(crate) use attributes::*;
 pub(crate) use comments::*;
 pub(crate) use compiler_directives::*;
 pub(crate) use identifiers::*;
+pub(crate) use macro_usages::*;
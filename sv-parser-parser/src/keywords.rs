@@ -1194,6 +1194,9 @@ pub(crate) const KEYWORDS_1800_2012: &[&str] = &[
     "soft",
 ];
 
+// IEEE1800-2023 doesn't add reserved keywords
+pub(crate) const KEYWORDS_1800_2023: &[&str] = KEYWORDS_1800_2017;
+
 pub(crate) const KEYWORDS_1800_2017: &[&str] = &[
     "accept_on",
     "alias",
@@ -1467,3 +1470,17 @@ pub(crate) const KEYWORDS_DIRECTIVE: &[&str] = &[
     "undef",
     "undefineall",
 ];
+
+pub(crate) const KEYWORDS_VERILOG_AMS: &[&str] = &[
+    "analog",
+    "branch",
+    "continuous",
+    "discipline",
+    "discrete",
+    "domain",
+    "enddiscipline",
+    "endnature",
+    "flow",
+    "nature",
+    "potential",
+];
@@ -0,0 +1,278 @@
+use crate::*;
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A keyword of the active `begin_keywords version
+    Keyword,
+    /// A simple identifier or a system task/function identifier
+    Identifier,
+    EscapedIdentifier,
+    Number,
+    String,
+    Operator,
+    Comment,
+    /// A compiler directive or a text macro usage
+    Directive,
+    WhiteSpace,
+    /// A character which can't start any token
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub locate: Locate,
+}
+
+impl Token {
+    pub fn str<'a>(&self, s: &'a str) -> &'a str {
+        self.locate.str(s)
+    }
+}
+
+/// Operators and punctuations ordered by length for the longest match
+const OPERATORS: &[&str] = &[
+    "<<<=", ">>>=", "===", "!==", "==?", "!=?", "<<<", ">>>", "<<=", ">>=", "<->", "->>", "|->",
+    "|=>", "#-#", "#=#", "**", "==", "!=", "&&", "||", "<=", ">=", "<<", ">>", "->", "++", "--",
+    "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "~&", "~|", "~^", "^~", "::", ".*", "+:", "-:",
+    ":=", ":/", "##", "@@", "``", "`\"",
+];
+
+const TIME_UNITS: &[&str] = &["step", "ms", "us", "ns", "ps", "fs", "s"];
+
+/// Split the text into tokens.
+///
+/// The text isn't required to be preprocessed or to be valid, and every byte of the text belongs
+/// to exactly one token. Keywords follow `begin_keywords and `end_keywords in the text.
+pub fn tokenize(s: &str) -> Vec<Token> {
+    let mut lexer = Lexer {
+        s,
+        pos: 0,
+        line: 1,
+        versions: Vec::new(),
+        begin_keywords: false,
+    };
+    let mut ret = Vec::new();
+    while let Some(x) = lexer.next_token() {
+        ret.push(x);
+    }
+    ret
+}
+
+struct Lexer<'a> {
+    s: &'a str,
+    pos: usize,
+    line: u32,
+    versions: Vec<Option<Version>>,
+    /// The last directive is `begin_keywords
+    begin_keywords: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn peek(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        let c = self.peek(0)?;
+        let rest = self.rest();
+        let (kind, len) = if c.is_ascii_whitespace() {
+            (
+                TokenKind::WhiteSpace,
+                take_while(rest, 0, |x| x.is_ascii_whitespace()),
+            )
+        } else if rest.starts_with("//") {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some(x) = rest.strip_prefix("/*") {
+            (
+                TokenKind::Comment,
+                x.find("*/").map_or(rest.len(), |x| x + 4),
+            )
+        } else if rest.starts_with("\"\"\"")
+            && matches!(self.version(), Some(Version::Ieee1800_2023))
+        {
+            (TokenKind::String, triple_quoted_string_len(rest))
+        } else if c == '"' {
+            (TokenKind::String, string_len(rest))
+        } else if c == '\\' {
+            (
+                TokenKind::EscapedIdentifier,
+                take_while(rest, 1, |x| !x.is_ascii_whitespace()),
+            )
+        } else if c == '`' && self.peek(1).is_some_and(is_identifier_start) {
+            (
+                TokenKind::Directive,
+                take_while(rest, 1, is_identifier_char),
+            )
+        } else if is_identifier_start(c)
+            || (c == '$' && self.peek(1).is_some_and(is_identifier_start))
+        {
+            let len = take_while(rest, 1, is_identifier_char);
+            let kind = if keywords(self.version()).contains(&&rest[..len]) {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Identifier
+            };
+            (kind, len)
+        } else if c.is_ascii_digit() {
+            let len = decimal_len(rest);
+            // A size adjacent to a based literal is a part of the literal
+            let size = rest[..len].bytes().all(|x| x.is_ascii_digit() || x == b'_');
+            match rest[len..].starts_with('\'') {
+                true if size => (
+                    TokenKind::Number,
+                    len + based_len(&rest[len..]).unwrap_or(0),
+                ),
+                _ => (TokenKind::Number, len),
+            }
+        } else if c == '\'' && based_len(rest).is_some() {
+            (TokenKind::Number, based_len(rest).unwrap_or(1))
+        } else if let Some(x) = OPERATORS.iter().find(|x| rest.starts_with(*x)) {
+            (TokenKind::Operator, x.len())
+        } else if c.is_ascii_punctuation() {
+            (TokenKind::Operator, 1)
+        } else {
+            (TokenKind::Unknown, c.len_utf8())
+        };
+
+        let text = &rest[..len];
+        match kind {
+            TokenKind::Directive => {
+                self.begin_keywords = text == "`begin_keywords";
+                if text == "`end_keywords" {
+                    self.versions.pop();
+                }
+            }
+            TokenKind::String if self.begin_keywords => {
+                self.versions
+                    .push(keywords_version(text.trim_matches('"')).or_else(|| self.version()));
+                self.begin_keywords = false;
+            }
+            TokenKind::WhiteSpace | TokenKind::Comment => (),
+            _ => self.begin_keywords = false,
+        }
+
+        let ret = Token {
+            kind,
+            locate: Locate {
+                offset: self.pos,
+                line: self.line,
+                len,
+            },
+        };
+        self.pos += len;
+        self.line += text.matches('\n').count() as u32;
+        Some(ret)
+    }
+
+    fn version(&self) -> Option<Version> {
+        self.versions.last().copied().flatten()
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Get the byte length of the prefix where `f` is satisfied after skipping `skip` bytes.
+fn take_while<F: Fn(char) -> bool>(s: &str, skip: usize, f: F) -> usize {
+    s[skip..].find(|x| !f(x)).map_or(s.len(), |x| x + skip)
+}
+
+fn string_len(s: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return i + 1,
+            // An unterminated string ends at the end of line
+            '\n' => return i,
+            _ => (),
+        }
+    }
+    s.len()
+}
+
+fn triple_quoted_string_len(s: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(3) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' if s[i..].starts_with("\"\"\"") => return i + 3,
+            _ => (),
+        }
+    }
+    s.len()
+}
+
+fn is_digits(c: char) -> bool {
+    c.is_ascii_digit() || c == '_'
+}
+
+/// Get the length of a decimal, real or time literal.
+fn decimal_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut len = take_while(s, 0, is_digits);
+    if bytes.get(len) == Some(&b'.') && bytes.get(len + 1).is_some_and(u8::is_ascii_digit) {
+        len = take_while(s, len + 1, is_digits);
+    }
+    if let Some(b'e') | Some(b'E') = bytes.get(len) {
+        let sign = matches!(bytes.get(len + 1), Some(b'+') | Some(b'-')) as usize;
+        if bytes.get(len + 1 + sign).is_some_and(u8::is_ascii_digit) {
+            len = take_while(s, len + 1 + sign, is_digits);
+        }
+    }
+    for unit in TIME_UNITS {
+        if s[len..].starts_with(unit) {
+            let end = len + unit.len();
+            if !s[end..].starts_with(is_identifier_char) {
+                return end;
+            }
+        }
+    }
+    len
+}
+
+/// Get the length of a based literal or an unbased unsized literal starting with `'`.
+fn based_len(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    match bytes.get(1) {
+        Some(b'0') | Some(b'1') | Some(b'x') | Some(b'X') | Some(b'z') | Some(b'Z')
+            if !s[2..].starts_with(is_identifier_char) =>
+        {
+            return Some(2)
+        }
+        _ => (),
+    }
+    let signed = matches!(bytes.get(1), Some(b's') | Some(b'S')) as usize;
+    let base = *bytes.get(1 + signed)?;
+    let value = |c: char| match base {
+        b'b' | b'B' => matches!(c, '0' | '1'),
+        b'o' | b'O' => matches!(c, '0'..='7'),
+        b'd' | b'D' => c.is_ascii_digit(),
+        _ => c.is_ascii_hexdigit(),
+    };
+    if !b"bBoOdDhH".contains(&base) {
+        return None;
+    }
+    let value = |c: char| value(c) || matches!(c, 'x' | 'X' | 'z' | 'Z' | '?' | '_');
+    let len = 2 + signed;
+    let space = take_while(s, len, |x| x == ' ' || x == '\t');
+    if s[space..].starts_with(value) {
+        Some(take_while(s, space, value))
+    } else {
+        None
+    }
+}
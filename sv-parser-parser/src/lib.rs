@@ -1,16 +1,20 @@
 #![allow(clippy::many_single_char_names, clippy::module_inception)]
 
 pub mod keywords;
+pub mod lexer;
 #[macro_use]
 pub mod utils;
 pub(crate) use keywords::*;
 pub(crate) use utils::*;
+pub use utils::{Extension, Standard};
+pub use general::macro_usages::{MACRO_USAGE_BEGIN, MACRO_USAGE_END, MACRO_USAGE_SEPARATOR};
 
 mod tests;
 
 pub mod behavioral_statements;
 pub mod declarations;
 pub mod expressions;
+pub mod extensions;
 pub mod general;
 pub mod instantiations;
 pub mod preprocessor;
@@ -21,6 +25,7 @@ pub mod udp_declaration_and_instantiation;
 pub(crate) use behavioral_statements::*;
 pub(crate) use declarations::*;
 pub(crate) use expressions::*;
+pub(crate) use extensions::*;
 pub(crate) use general::*;
 pub(crate) use instantiations::*;
 pub(crate) use preprocessor::*;
@@ -100,6 +105,61 @@ pub fn sv_parser_incomplete(s: Span) -> IResult<Span, SourceText> {
     source_text_incomplete(s)
 }
 
+/// Parse with the given grammar options.
+///
+/// `standard` selects the keywords and the grammar used outside of `begin_keywords, `extensions`
+/// enables grammar extensions, and `macro_preserving` accepts the macro usages kept by the
+/// macro-preserving preprocessor.
+pub fn sv_parser_with<'a>(
+    s: Span<'a>,
+    standard: Option<Standard>,
+    extensions: &[Extension],
+    macro_preserving: bool,
+) -> IResult<Span<'a>, SourceText> {
+    init_with(standard, extensions, macro_preserving);
+    source_text(s)
+}
+
+pub fn sv_parser_incomplete_with<'a>(
+    s: Span<'a>,
+    standard: Option<Standard>,
+    extensions: &[Extension],
+    macro_preserving: bool,
+) -> IResult<Span<'a>, SourceText> {
+    init_with(standard, extensions, macro_preserving);
+    source_text_incomplete(s)
+}
+
+pub fn description_parser_with<'a>(
+    s: Span<'a>,
+    standard: Option<Standard>,
+    extensions: &[Extension],
+    macro_preserving: bool,
+) -> IResult<Span<'a>, Description> {
+    init_with(standard, extensions, macro_preserving);
+    description(s)
+}
+
+pub fn module_item_parser_with<'a>(
+    s: Span<'a>,
+    standard: Option<Standard>,
+    extensions: &[Extension],
+    macro_preserving: bool,
+) -> IResult<Span<'a>, ModuleItem> {
+    init_with(standard, extensions, macro_preserving);
+    module_item(s)
+}
+
+pub fn non_port_module_item_parser_with<'a>(
+    s: Span<'a>,
+    standard: Option<Standard>,
+    extensions: &[Extension],
+    macro_preserving: bool,
+) -> IResult<Span<'a>, NonPortModuleItem> {
+    init_with(standard, extensions, macro_preserving);
+    non_port_module_item(s)
+}
+
 pub fn lib_parser(s: Span) -> IResult<Span, LibraryText> {
     init();
     library_text(s)
@@ -115,8 +175,25 @@ pub fn pp_parser(s: Span) -> IResult<Span, PreprocessorText> {
     preprocessor_text(s)
 }
 
+/// Parse the text before preprocessing with the standard used outside of `begin_keywords.
+pub fn pp_parser_with(s: Span, standard: Option<Standard>) -> IResult<Span, PreprocessorText> {
+    init_with(standard, &[], false);
+    preprocessor_text(s)
+}
+
 fn init() {
     nom_packrat::init!();
     clear_directive();
     clear_version();
+    clear_extensions();
+    set_macro_preserving(false);
+}
+
+fn init_with(standard: Option<Standard>, extensions: &[Extension], macro_preserving: bool) {
+    init();
+    if let Some(standard) = standard {
+        set_standard(standard);
+    }
+    set_extensions(extensions);
+    set_macro_preserving(macro_preserving);
 }
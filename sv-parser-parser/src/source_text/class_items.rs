@@ -18,6 +18,9 @@ pub(crate) fn class_item(s: Span) -> IResult<Span, ClassItem> {
             ClassItem::ParameterDeclaration(Box::new(x))
         }),
         map(symbol(";"), |x| ClassItem::Empty(Box::new(x))),
+        map(preceded(macro_preserving, macro_usage_class_item), |x| {
+            ClassItem::MacroUsage(Box::new(x))
+        }),
     ))(s)
 }
 
@@ -341,3 +344,36 @@ pub(crate) fn new(s: Span) -> IResult<Span, New> {
     let (s, a) = keyword("new")(s)?;
     Ok((s, New { nodes: (a,) }))
 }
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn dynamic_override_specifiers(s: Span) -> IResult<Span, DynamicOverrideSpecifiers> {
+    let (s, _) = ieee1800_2023(s)?;
+    let (s, a) = opt(initial_or_extends_specifier)(s)?;
+    let (s, b) = opt(final_specifier)(s)?;
+    if a.is_none() && b.is_none() {
+        return Err(Err::Error(make_error(s, ErrorKind::Verify)));
+    }
+    Ok((s, DynamicOverrideSpecifiers { nodes: (a, b) }))
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn initial_or_extends_specifier(s: Span) -> IResult<Span, InitialOrExtendsSpecifier> {
+    alt((
+        map(pair(symbol(":"), keyword("initial")), |x| {
+            InitialOrExtendsSpecifier::Initial(Box::new(x))
+        }),
+        map(pair(symbol(":"), keyword("extends")), |x| {
+            InitialOrExtendsSpecifier::Extends(Box::new(x))
+        }),
+    ))(s)
+}
+
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn final_specifier(s: Span) -> IResult<Span, FinalSpecifier> {
+    let (s, a) = symbol(":")(s)?;
+    let (s, b) = keyword("final")(s)?;
+    Ok((s, FinalSpecifier { nodes: (a, b) }))
+}
@@ -142,6 +142,11 @@ pub(crate) fn module_or_generate_item(s: Span) -> IResult<Span, ModuleOrGenerate
         module_or_generate_item_module_item,
         module_or_generate_item_gate,
         module_or_generate_item_udp,
+        module_or_generate_item_analog,
+        map(
+            preceded(macro_preserving, macro_usage_module_or_generate_item),
+            |x| ModuleOrGenerateItem::MacroUsage(Box::new(x)),
+        ),
     ))(s)
 }
 
@@ -178,6 +183,18 @@ pub(crate) fn module_or_generate_item_udp(s: Span) -> IResult<Span, ModuleOrGene
     ))
 }
 
+#[tracable_parser]
+#[packrat_parser]
+pub(crate) fn module_or_generate_item_analog(s: Span) -> IResult<Span, ModuleOrGenerateItem> {
+    let (s, _) = extension(Extension::VerilogAms)(s)?;
+    let (s, a) = many0(attribute_instance)(s)?;
+    let (s, b) = analog_construct(s)?;
+    Ok((
+        s,
+        ModuleOrGenerateItem::Analog(Box::new(ModuleOrGenerateItemAnalog { nodes: (a, b) })),
+    ))
+}
+
 #[tracable_parser]
 #[packrat_parser]
 pub(crate) fn module_or_generate_item_module(s: Span) -> IResult<Span, ModuleOrGenerateItem> {
@@ -220,6 +237,17 @@ pub(crate) fn module_or_generate_item_declaration(
         }),
         module_or_generate_item_declaration_clocking,
         module_or_generate_item_declaration_disable,
+        map(
+            preceded(extension(Extension::VerilogAms), branch_declaration),
+            |x| ModuleOrGenerateItemDeclaration::BranchDeclaration(Box::new(x)),
+        ),
+        map(
+            preceded(
+                extension(Extension::VerilogAms),
+                analog_function_declaration,
+            ),
+            |x| ModuleOrGenerateItemDeclaration::AnalogFunctionDeclaration(Box::new(x)),
+        ),
     ))(s)
 }
 
@@ -82,11 +82,12 @@ pub(crate) fn parameter_port_declaration_type_list(
     s: Span,
 ) -> IResult<Span, ParameterPortDeclaration> {
     let (s, a) = keyword("type")(s)?;
-    let (s, b) = list_of_type_assignments(s)?;
+    let (s, b) = opt(preceded(ieee1800_2023, forward_type))(s)?;
+    let (s, c) = list_of_type_assignments(s)?;
     Ok((
         s,
         ParameterPortDeclaration::TypeList(Box::new(ParameterPortDeclarationTypeList {
-            nodes: (a, b),
+            nodes: (a, b, c),
         })),
     ))
 }
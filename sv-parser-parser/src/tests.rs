@@ -470,6 +470,125 @@ mod unit {
             Ok((_, _))
         );
     }
+
+    #[test]
+    fn test_ieee1800_2023() {
+        test!(
+            source_text,
+            r##"`begin_keywords "1800-2023"
+                class :final c #(parameter type enum E = e_t, type class C = int);
+                  weak_reference #(C) r;
+                  localparam type struct S = s_t;
+                  function :initial :final void f(const ref static int x);
+                  endfunction
+                  extern task :extends t(ref static int y);
+                  pure virtual function :final int g();
+                  string s = """multi "line"
+                    string""";
+                endclass
+                `end_keywords"##,
+            Ok((_, _))
+        );
+        test!(
+            source_text,
+            r##"class :final c; endclass"##,
+            Err(_)
+        );
+        test!(
+            source_text,
+            r##"class c; string s = """a"""; endclass"##,
+            Err(_)
+        );
+        test!(
+            preprocessor_text,
+            r##"`begin_keywords "1800-2023"
+                `ifdef (A && !(B || C) -> D) a `elsif (!E <-> F) b `else c `endif
+                `end_keywords"##,
+            Ok((_, _))
+        );
+        test!(
+            ifdef_directive,
+            r##"`ifdef (A && B) a `endif"##,
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_verilog_ams() {
+        let src = r##"discipline electrical;
+                potential Voltage;
+                flow Current;
+                domain continuous;
+              enddiscipline
+              nature Voltage
+                units = "V";
+                access = V;
+                abstol = 1e-6;
+              endnature
+              nature Vsense : Voltage;
+              endnature
+              module res(p, n);
+                inout p, n;
+                electrical p, n;
+                parameter real r = 1.0;
+                branch (p, n) res_br;
+                analog function real half;
+                  input x;
+                  real x;
+                  half = x / 2;
+                endfunction
+                analog begin
+                  I(res_br) <+ V(res_br) / r;
+                  V(p, n) <+ half(ddt(I(p, n)));
+                end
+                analog initial $display("res");
+              endmodule"##;
+        set_extensions(&[Extension::VerilogAms]);
+        test!(source_text, src, Ok((_, _)));
+        clear_extensions();
+        test!(source_text, src, Err(_));
+        test!(
+            module_declaration,
+            r##"module m; wire analog, branch; endmodule"##,
+            Ok((_, _))
+        );
+    }
+
+    #[test]
+    fn test_macro_usage() {
+        set_macro_preserving(true);
+        test!(
+            expression,
+            "a + \u{2}`ADD(b, 1)\u{1f}(b + 1)\u{3}",
+            Ok((_, Expression::Binary(_)))
+        );
+        test!(
+            statement_item,
+            "\u{2}`INFO(\"x\")\u{1f}$display(\"x\");\u{3}",
+            Ok((_, StatementItem::MacroUsage(_)))
+        );
+        test!(
+            module_declaration,
+            "module m; \u{2}`REGS\u{1f}logic a; logic b;\u{3} endmodule",
+            Ok((_, _))
+        );
+        test!(
+            class_declaration,
+            "class c; \u{2}`GET\u{1f}function int get(); endfunction\u{3} endclass",
+            Ok((_, _))
+        );
+        test!(
+            statement_item,
+            "\u{2}`LHS\u{1f}a =\u{3} 0;",
+            Err(_)
+        );
+        set_macro_preserving(false);
+        test!(
+            statement_item,
+            "\u{2}`INFO(\"x\")\u{1f}$display(\"x\");\u{3}",
+            Err(_)
+        );
+    }
 }
 
 mod spec {
@@ -15938,6 +16057,85 @@ mod error {
     }
 }
 
+mod lexer {
+    use crate::lexer::*;
+
+    #[test]
+    fn test_tokenize() {
+        let src = r##"module a; // comment
+  logic \bus[0] = 8'hF_f + 'x + 1.5e3 + 10ns; /* block
+   comment */
+`begin_keywords "1364-1995"
+  logic = "str\"ing" <<<= $display(`FOO);
+`end_keywords
+  logic # ¥"##;
+        let tokens = tokenize(src);
+        let mut end = 0;
+        for x in &tokens {
+            assert_eq!(x.locate.offset, end);
+            end = x.locate.offset + x.locate.len;
+        }
+        assert_eq!(end, src.len());
+
+        let tokens: Vec<(TokenKind, &str, u32)> = tokens
+            .iter()
+            .filter(|x| x.kind != TokenKind::WhiteSpace)
+            .map(|x| (x.kind, x.str(src), x.locate.line))
+            .collect();
+        assert_eq!(
+            tokens[..12],
+            [
+                (TokenKind::Keyword, "module", 1),
+                (TokenKind::Identifier, "a", 1),
+                (TokenKind::Operator, ";", 1),
+                (TokenKind::Comment, "// comment", 1),
+                (TokenKind::Keyword, "logic", 2),
+                (TokenKind::EscapedIdentifier, "\\bus[0]", 2),
+                (TokenKind::Operator, "=", 2),
+                (TokenKind::Number, "8'hF_f", 2),
+                (TokenKind::Operator, "+", 2),
+                (TokenKind::Number, "'x", 2),
+                (TokenKind::Operator, "+", 2),
+                (TokenKind::Number, "1.5e3", 2),
+            ]
+        );
+        assert!(tokens.contains(&(TokenKind::Number, "10ns", 2)));
+        assert!(tokens.contains(&(TokenKind::Directive, "`begin_keywords", 4)));
+        // `logic` isn't a keyword of IEEE1364-1995
+        assert!(tokens.contains(&(TokenKind::Identifier, "logic", 5)));
+        assert!(tokens.contains(&(TokenKind::String, "\"str\\\"ing\"", 5)));
+        assert!(tokens.contains(&(TokenKind::Operator, "<<<=", 5)));
+        assert!(tokens.contains(&(TokenKind::Identifier, "$display", 5)));
+        assert!(tokens.contains(&(TokenKind::Directive, "`FOO", 5)));
+        assert!(tokens.contains(&(TokenKind::Keyword, "logic", 7)));
+        assert_eq!(tokens.last(), Some(&(TokenKind::Unknown, "¥", 7)));
+    }
+
+    #[test]
+    fn test_tokenize_triple_quoted_string() {
+        let src = r##"`begin_keywords "1800-2023"
+s = """a "b"
+c""";
+`end_keywords
+s = """d""";"##;
+        let tokens: Vec<(TokenKind, &str)> = tokenize(src)
+            .iter()
+            .filter(|x| x.kind == TokenKind::String)
+            .map(|x| (x.kind, x.str(src)))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (TokenKind::String, "\"1800-2023\""),
+                (TokenKind::String, "\"\"\"a \"b\"\nc\"\"\""),
+                (TokenKind::String, "\"\""),
+                (TokenKind::String, "\"d\""),
+                (TokenKind::String, "\"\""),
+            ]
+        );
+    }
+}
+
 #[test]
 fn debug() {
     test!(
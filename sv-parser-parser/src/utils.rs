@@ -365,30 +365,91 @@ pub(crate) enum Version {
     Ieee1800_2009,
     Ieee1800_2012,
     Ieee1800_2017,
+    Ieee1800_2023,
     Directive,
 }
 
+/// A language standard selecting the keywords and the grammar used without `begin_keywords
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Standard {
+    Ieee1364_1995,
+    Ieee1364_2001,
+    Ieee1364_2005,
+    Ieee1800_2005,
+    Ieee1800_2009,
+    Ieee1800_2012,
+    Ieee1800_2017,
+    Ieee1800_2023,
+}
+
+impl Standard {
+    fn version(self) -> Version {
+        match self {
+            Standard::Ieee1364_1995 => Version::Ieee1364_1995,
+            Standard::Ieee1364_2001 => Version::Ieee1364_2001,
+            Standard::Ieee1364_2005 => Version::Ieee1364_2005,
+            Standard::Ieee1800_2005 => Version::Ieee1800_2005,
+            Standard::Ieee1800_2009 => Version::Ieee1800_2009,
+            Standard::Ieee1800_2012 => Version::Ieee1800_2012,
+            Standard::Ieee1800_2017 => Version::Ieee1800_2017,
+            Standard::Ieee1800_2023 => Version::Ieee1800_2023,
+        }
+    }
+
+    /// The reserved keywords of the standard
+    pub fn keywords(self) -> &'static [&'static str] {
+        keywords(Some(self.version()))
+    }
+}
+
+impl std::fmt::Display for Standard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let x = match self {
+            Standard::Ieee1364_1995 => "IEEE 1364-1995",
+            Standard::Ieee1364_2001 => "IEEE 1364-2001",
+            Standard::Ieee1364_2005 => "IEEE 1364-2005",
+            Standard::Ieee1800_2005 => "IEEE 1800-2005",
+            Standard::Ieee1800_2009 => "IEEE 1800-2009",
+            Standard::Ieee1800_2012 => "IEEE 1800-2012",
+            Standard::Ieee1800_2017 => "IEEE 1800-2017",
+            Standard::Ieee1800_2023 => "IEEE 1800-2023",
+        };
+        write!(f, "{}", x)
+    }
+}
+
 thread_local!(
     static CURRENT_VERSION: core::cell::RefCell<Vec<Version>> = {
         core::cell::RefCell::new(Vec::new())
     }
 );
 
+thread_local!(
+    static DEFAULT_VERSION: core::cell::Cell<Option<Version>> = const {
+        core::cell::Cell::new(None)
+    }
+);
+
 pub(crate) fn begin_keywords(version: &str) {
-    CURRENT_VERSION.with(|current_version| match version {
-        "1364-1995" => current_version.borrow_mut().push(Version::Ieee1364_1995),
-        "1364-2001" => current_version.borrow_mut().push(Version::Ieee1364_2001),
-        "1364-2001-noconfig" => current_version
-            .borrow_mut()
-            .push(Version::Ieee1364_2001Noconfig),
-        "1364-2005" => current_version.borrow_mut().push(Version::Ieee1364_2005),
-        "1800-2005" => current_version.borrow_mut().push(Version::Ieee1800_2005),
-        "1800-2009" => current_version.borrow_mut().push(Version::Ieee1800_2009),
-        "1800-2012" => current_version.borrow_mut().push(Version::Ieee1800_2012),
-        "1800-2017" => current_version.borrow_mut().push(Version::Ieee1800_2017),
-        "directive" => current_version.borrow_mut().push(Version::Directive),
-        _ => (),
-    });
+    if let Some(version) = keywords_version(version) {
+        CURRENT_VERSION.with(|current_version| current_version.borrow_mut().push(version));
+    }
+}
+
+pub(crate) fn keywords_version(version: &str) -> Option<Version> {
+    match version {
+        "1364-1995" => Some(Version::Ieee1364_1995),
+        "1364-2001" => Some(Version::Ieee1364_2001),
+        "1364-2001-noconfig" => Some(Version::Ieee1364_2001Noconfig),
+        "1364-2005" => Some(Version::Ieee1364_2005),
+        "1800-2005" => Some(Version::Ieee1800_2005),
+        "1800-2009" => Some(Version::Ieee1800_2009),
+        "1800-2012" => Some(Version::Ieee1800_2012),
+        "1800-2017" => Some(Version::Ieee1800_2017),
+        "1800-2023" => Some(Version::Ieee1800_2023),
+        "directive" => Some(Version::Directive),
+        _ => None,
+    }
 }
 
 pub(crate) fn end_keywords() {
@@ -400,14 +461,89 @@ pub(crate) fn end_keywords() {
 pub(crate) fn current_version() -> Option<Version> {
     CURRENT_VERSION.with(|current_version| match current_version.borrow().last() {
         Some(x) => Some(*x),
-        None => None,
+        None => DEFAULT_VERSION.with(|x| x.get()),
     })
 }
 
+pub(crate) fn set_standard(standard: Standard) {
+    DEFAULT_VERSION.with(|x| x.set(Some(standard.version())));
+}
+
 pub(crate) fn clear_version() {
     CURRENT_VERSION.with(|current_version| {
         current_version.borrow_mut().clear();
     });
+    DEFAULT_VERSION.with(|x| x.set(None));
+}
+
+/// Succeed without consuming input only if IEEE1800-2023 is selected.
+pub(crate) fn ieee1800_2023(s: Span) -> IResult<Span, ()> {
+    if matches!(current_version(), Some(Version::Ieee1800_2023)) {
+        Ok((s, ()))
+    } else {
+        Err(Err::Error(make_error(s, ErrorKind::Verify)))
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+/// An opt-in grammar extension for constructs outside of IEEE1800.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Extension {
+    /// The structural and analog subset of Verilog-AMS.
+    VerilogAms,
+}
+
+thread_local!(
+    static EXTENSIONS: core::cell::RefCell<Vec<Extension>> = const {
+        core::cell::RefCell::new(Vec::new())
+    }
+);
+
+pub(crate) fn set_extensions(extensions: &[Extension]) {
+    EXTENSIONS.with(|x| *x.borrow_mut() = extensions.to_vec());
+}
+
+pub(crate) fn clear_extensions() {
+    EXTENSIONS.with(|x| x.borrow_mut().clear());
+}
+
+pub(crate) fn is_extension(extension: Extension) -> bool {
+    EXTENSIONS.with(|x| x.borrow().contains(&extension))
+}
+
+/// Succeed without consuming input only if the extension is enabled.
+pub(crate) fn extension<'a>(
+    extension: Extension,
+) -> impl Fn(Span<'a>) -> IResult<Span<'a>, ()> {
+    move |s: Span<'a>| {
+        if is_extension(extension) {
+            Ok((s, ()))
+        } else {
+            Err(Err::Error(make_error(s, ErrorKind::Verify)))
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+thread_local!(
+    static MACRO_PRESERVING: core::cell::Cell<bool> = const {
+        core::cell::Cell::new(false)
+    }
+);
+
+pub(crate) fn set_macro_preserving(enable: bool) {
+    MACRO_PRESERVING.with(|x| x.set(enable));
+}
+
+/// Succeed without consuming input only if the text is from the macro-preserving preprocessor.
+pub(crate) fn macro_preserving(s: Span) -> IResult<Span, ()> {
+    if MACRO_PRESERVING.with(|x| x.get()) {
+        Ok((s, ()))
+    } else {
+        Err(Err::Error(make_error(s, ErrorKind::Verify)))
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -425,7 +561,15 @@ pub(crate) fn concat<'a>(a: Span<'a>, b: Span<'a>) -> Option<Span<'a>> {
 }
 
 pub(crate) fn is_keyword(s: &Span) -> bool {
-    let keywords = match current_version() {
+    let version = current_version();
+    keywords(version).contains(s.fragment())
+        || (!matches!(version, Some(Version::Directive))
+            && is_extension(Extension::VerilogAms)
+            && KEYWORDS_VERILOG_AMS.contains(s.fragment()))
+}
+
+pub(crate) fn keywords(version: Option<Version>) -> &'static [&'static str] {
+    match version {
         Some(Version::Ieee1364_1995) => KEYWORDS_1364_1995,
         Some(Version::Ieee1364_2001) => KEYWORDS_1364_2001,
         Some(Version::Ieee1364_2001Noconfig) => KEYWORDS_1364_2001_NOCONFIG,
@@ -434,15 +578,10 @@ pub(crate) fn is_keyword(s: &Span) -> bool {
         Some(Version::Ieee1800_2009) => KEYWORDS_1800_2009,
         Some(Version::Ieee1800_2012) => KEYWORDS_1800_2012,
         Some(Version::Ieee1800_2017) => KEYWORDS_1800_2017,
+        Some(Version::Ieee1800_2023) => KEYWORDS_1800_2023,
         Some(Version::Directive) => KEYWORDS_DIRECTIVE,
         None => KEYWORDS_1800_2017,
-    };
-    for k in keywords {
-        if s.fragment() == k {
-            return true;
-        }
     }
-    false
 }
 
 pub(crate) fn into_locate(s: Span) -> Locate {
@@ -1,5 +1,6 @@
 #![allow(clippy::type_complexity)]
 #![recursion_limit = "256"]
 
+pub mod macro_index;
 pub mod preprocess;
 pub mod range;
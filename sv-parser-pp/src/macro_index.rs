@@ -0,0 +1,156 @@
+use crate::preprocess::Define;
+use crate::range::Range;
+use std::path::{Path, PathBuf};
+
+/// Cross-reference of text macro definitions and usages collected by the preprocessor.
+///
+/// Every location is the range of the directive or usage in its source file. Locations are
+/// `None` for macros given by the caller, such as command-line `+define+`, and for items
+/// appearing in the expansion of another macro.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MacroIndex {
+    pub definitions: Vec<MacroDefinition>,
+    pub usages: Vec<MacroUsage>,
+    pub redefinitions: Vec<MacroRedefinition>,
+    pub undefinitions: Vec<MacroUndefinition>,
+    pub checks: Vec<MacroCheck>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MacroDefinition {
+    pub identifier: String,
+    /// `None` for a macro given by the caller without a definition
+    pub define: Option<Define>,
+    pub origin: Option<(PathBuf, Range)>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MacroUsage {
+    pub identifier: String,
+    /// Actual arguments in order, with `None` for empty arguments
+    pub arguments: Vec<Option<String>>,
+    pub origin: Option<(PathBuf, Range)>,
+    /// The range of the expansion in the preprocessed text, or `None` for a usage in `include or
+    /// in a macro expansion
+    pub expansion: Option<Range>,
+}
+
+/// A definition of a macro which is already defined.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MacroRedefinition {
+    pub identifier: String,
+    pub previous: Option<Define>,
+    pub origin: Option<(PathBuf, Range)>,
+    /// The arguments or the body differ from the previous definition, which deserves a warning
+    pub differs: bool,
+}
+
+/// An `undef of a macro, or a macro defined at `undefineall.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MacroUndefinition {
+    pub identifier: String,
+    pub defined: bool,
+    pub origin: Option<(PathBuf, Range)>,
+}
+
+/// A macro name tested by `ifdef, `ifndef or `elsif.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MacroCheck {
+    pub identifier: String,
+    pub defined: bool,
+    pub origin: Option<(PathBuf, Range)>,
+}
+
+impl MacroIndex {
+    pub fn definitions_of<'a>(
+        &'a self,
+        identifier: &'a str,
+    ) -> impl Iterator<Item = &'a MacroDefinition> + 'a {
+        self.definitions
+            .iter()
+            .filter(move |x| x.identifier == identifier)
+    }
+
+    pub fn usages_of<'a>(
+        &'a self,
+        identifier: &'a str,
+    ) -> impl Iterator<Item = &'a MacroUsage> + 'a {
+        self.usages
+            .iter()
+            .filter(move |x| x.identifier == identifier)
+    }
+
+    /// Get the definitions in the source which are neither used nor tested by `ifdef.
+    pub fn unused(&self) -> Vec<&MacroDefinition> {
+        self.definitions
+            .iter()
+            .filter(|x| x.origin.is_some())
+            .filter(|x| {
+                self.usages.iter().all(|y| y.identifier != x.identifier)
+                    && self.checks.iter().all(|y| y.identifier != x.identifier)
+            })
+            .collect()
+    }
+
+    pub(crate) fn merge(&mut self, other: MacroIndex) {
+        self.definitions.extend(other.definitions);
+        self.usages.extend(other.usages);
+        self.redefinitions.extend(other.redefinitions);
+        self.undefinitions.extend(other.undefinitions);
+        self.checks.extend(other.checks);
+    }
+
+    /// Merge the index of a macro expansion, whose locations aren't in any source file.
+    pub(crate) fn merge_expansion(&mut self, mut other: MacroIndex) {
+        other.for_each_origin(|x| *x = None);
+        other.usages.iter_mut().for_each(|x| x.expansion = None);
+        self.merge(other);
+    }
+
+    /// Shift the expansions of the usages in the preprocessed text.
+    pub(crate) fn offset_expansions(&mut self, base: usize) {
+        for x in self.usages.iter_mut().filter_map(|x| x.expansion.as_mut()) {
+            x.offset(base);
+        }
+    }
+
+    /// Shift the expansions after the replaced range of the preprocessed text.
+    pub(crate) fn apply_text_edit(&mut self, range: Range, text: &str) {
+        let shift = |x: usize| x + text.len() - (range.end - range.begin);
+        for x in self.usages.iter_mut().filter_map(|x| x.expansion.as_mut()) {
+            if x.begin >= range.end {
+                *x = Range::new(shift(x.begin), shift(x.end));
+            }
+        }
+    }
+
+    /// Shift the locations after the replaced range of the source file.
+    pub(crate) fn apply_edit(&mut self, path: &Path, range: Range, text: &str) {
+        let shift = |x: usize| x + text.len() - (range.end - range.begin);
+        self.for_each_origin(|x| {
+            if let Some((ref origin_path, ref mut origin_range)) = x {
+                if origin_path == path && origin_range.begin >= range.end {
+                    *origin_range = Range::new(shift(origin_range.begin), shift(origin_range.end));
+                }
+            }
+        });
+    }
+
+    fn for_each_origin<F: FnMut(&mut Option<(PathBuf, Range)>)>(&mut self, mut f: F) {
+        self.definitions.iter_mut().for_each(|x| f(&mut x.origin));
+        self.usages.iter_mut().for_each(|x| f(&mut x.origin));
+        self.redefinitions.iter_mut().for_each(|x| f(&mut x.origin));
+        self.undefinitions.iter_mut().for_each(|x| f(&mut x.origin));
+        self.checks.iter_mut().for_each(|x| f(&mut x.origin));
+    }
+}
+
+/// Whether two definitions of a macro are the same except for whitespaces.
+pub(crate) fn same_definition(a: &Define, b: &Define) -> bool {
+    let normalize = |x: &Define| {
+        x.text
+            .as_ref()
+            .map(|x| x.text.split_whitespace().collect::<Vec<_>>().join(" "))
+    };
+    a.arguments == b.arguments && normalize(a) == normalize(b)
+}
@@ -1,17 +1,25 @@
+use crate::macro_index::{
+    same_definition, MacroCheck, MacroDefinition, MacroIndex, MacroRedefinition,
+    MacroUndefinition, MacroUsage,
+};
 use crate::range::Range;
 use nom::combinator::all_consuming;
 use nom_greedyerror::error_position;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryInto;
 use std::fs::File;
 use std::hash::BuildHasher;
 use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
 use sv_parser_error::Error;
-use sv_parser_parser::{pp_parser, Span, SpanInfo};
+use sv_parser_parser::lexer::{tokenize, Token, TokenKind};
+use sv_parser_parser::{
+    pp_parser, pp_parser_with, Span, SpanInfo, Standard, MACRO_USAGE_BEGIN, MACRO_USAGE_END,
+    MACRO_USAGE_SEPARATOR,
+};
 use sv_parser_syntaxtree::{
-    IncludeCompilerDirective, Locate, NodeEvent, RefNode, SourceDescription, TextMacroUsage,
-    WhiteSpace,
+    Identifier, IfdefCondition, IfdefMacroExpression, IncludeCompilerDirective, Locate, NodeEvent,
+    RefNode, SourceDescription, TextMacroIdentifier, TextMacroUsage, WhiteSpace,
 };
 use std::collections::hash_map::RandomState;
 
@@ -21,6 +29,8 @@ const RECURSIVE_LIMIT: usize = 64;
 pub struct PreprocessedText {
     text: String,
     origins: BTreeMap<Range, Origin>,
+    lines: HashMap<PathBuf, SourceLines>,
+    macro_index: MacroIndex,
 }
 
 #[derive(Debug)]
@@ -29,11 +39,104 @@ pub struct Origin {
     origin: Option<(PathBuf, Range)>,
 }
 
+/// Line starts of a source file and the `line directives in it.
+#[derive(Debug)]
+struct SourceLines {
+    starts: Vec<usize>,
+    directives: Vec<LineDirective>,
+    /// Offsets in the decoded text and the file from which the difference between them changes,
+    /// by a stripped BOM or a decoding other than UTF-8.
+    offsets: Vec<(usize, usize)>,
+}
+
+/// A `line directive renaming the lines from `begin`, which is the start of the next line.
+#[derive(Debug)]
+struct LineDirective {
+    begin: usize,
+    line: usize,
+    path: PathBuf,
+}
+
+impl SourceLines {
+    fn new(s: &str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(s.match_indices('\n').map(|(x, _)| x + 1));
+        SourceLines {
+            starts,
+            directives: Vec::new(),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Byte offset in the file of the offset in the decoded text.
+    fn source_offset(&self, pos: usize) -> usize {
+        let i = self.offsets.partition_point(|x| x.0 <= pos);
+        match i {
+            0 => pos,
+            _ => {
+                let (text_pos, file_pos) = self.offsets[i - 1];
+                file_pos + pos - text_pos
+            }
+        }
+    }
+
+    /// 1-based line and column of the offset.
+    fn line_column(&self, pos: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|x| *x <= pos);
+        (line, pos - self.starts[line - 1] + 1)
+    }
+
+    fn logical_line_column(&self, pos: usize) -> Option<(&PathBuf, usize, usize)> {
+        let (line, column) = self.line_column(pos);
+        let directive = self.directives.iter().rev().find(|x| x.begin <= pos)?;
+        let (begin, _) = self.line_column(directive.begin);
+        Some((&directive.path, directive.line + line - begin, column))
+    }
+
+    fn apply_edit(&mut self, range: Range, text: &str) {
+        let shift = |x: usize| x + text.len() - (range.end - range.begin);
+        let mut starts: Vec<usize> = self
+            .starts
+            .iter()
+            .filter(|x| **x <= range.begin)
+            .copied()
+            .collect();
+        starts.extend(text.match_indices('\n').map(|(x, _)| range.begin + x + 1));
+        starts.extend(
+            self.starts
+                .iter()
+                .filter(|x| **x > range.end)
+                .map(|x| shift(*x)),
+        );
+        self.starts = starts;
+        for directive in &mut self.directives {
+            if directive.begin > range.end {
+                directive.begin = shift(directive.begin);
+            }
+        }
+        // The replacement is assumed to take the same bytes in the file as in the text
+        self.offsets = self
+            .offsets
+            .iter()
+            .filter(|x| x.0 <= range.begin || x.0 > range.end)
+            .map(|x| {
+                if x.0 > range.end {
+                    (shift(x.0), shift(x.1))
+                } else {
+                    *x
+                }
+            })
+            .collect();
+    }
+}
+
 impl PreprocessedText {
     fn new() -> Self {
         PreprocessedText {
             text: String::new(),
             origins: BTreeMap::new(),
+            lines: HashMap::new(),
+            macro_index: MacroIndex::default(),
         }
     }
 
@@ -61,12 +164,96 @@ impl PreprocessedText {
             origin.range.offset(base);
             self.origins.insert(range, origin);
         }
+        for (path, lines) in other.lines {
+            self.lines.entry(path).or_insert(lines);
+        }
+        let mut macro_index = other.macro_index;
+        macro_index.offset_expansions(base);
+        self.macro_index.merge(macro_index);
     }
 
     pub fn text(&self) -> &str {
         &self.text
     }
 
+    /// Get the definitions and usages of text macros in the preprocessed sources.
+    pub fn macro_index(&self) -> &MacroIndex {
+        &self.macro_index
+    }
+
+    /// Apply the edit of the source file to the preprocessed text.
+    ///
+    /// The edit is applied only if the source range is copied verbatim to just one place,
+    /// and neither the range nor the replacement can change the preprocessing result. Comments
+    /// and strings can be edited as long as no directive or macro usage appears or disappears.
+    /// Returns the replaced range of the preprocessed text.
+    pub fn apply_edit<T: AsRef<Path>>(
+        &mut self,
+        path: T,
+        range: Range,
+        text: &str,
+    ) -> Option<Range> {
+        let mut target = None;
+        for origin in self.origins.values() {
+            if let Some((ref origin_path, ref origin_range)) = origin.origin {
+                if origin_path != path.as_ref() {
+                    continue;
+                }
+                if origin_range.begin <= range.begin && range.end <= origin_range.end {
+                    let verbatim = origin.range.end - origin.range.begin
+                        == origin_range.end - origin_range.begin;
+                    if target.is_some() || !verbatim {
+                        return None;
+                    }
+                    let begin = origin.range.begin + range.begin - origin_range.begin;
+                    target = Some((
+                        origin.range,
+                        Range::new(begin, begin + range.end - range.begin),
+                    ));
+                } else if origin_range.begin < range.end && range.begin < origin_range.end {
+                    return None;
+                }
+            }
+        }
+        let (segment, pp_range) = target?;
+
+        // The segment is tokenized as a whole because a comment or a string may begin on an
+        // earlier line, and the segment never splits a token.
+        let local = Range::new(pp_range.begin - segment.begin, pp_range.end - segment.begin);
+        if !is_local_edit(&self.text[segment.begin..segment.end], local, text) {
+            return None;
+        }
+
+        self.text.replace_range(pp_range.begin..pp_range.end, text);
+        let shift = |x: usize| x + text.len() - (pp_range.end - pp_range.begin);
+        let origins = std::mem::take(&mut self.origins);
+        for (_, mut origin) in origins {
+            if origin.range.begin == segment.begin {
+                origin.range.end = shift(origin.range.end);
+                if let Some((_, ref mut origin_range)) = origin.origin {
+                    origin_range.end = shift(origin_range.end);
+                }
+            } else if origin.range.begin > segment.begin {
+                origin.range = Range::new(shift(origin.range.begin), shift(origin.range.end));
+                if let Some((ref origin_path, ref mut origin_range)) = origin.origin {
+                    if origin_path == path.as_ref() && origin_range.begin >= range.end {
+                        *origin_range =
+                            Range::new(shift(origin_range.begin), shift(origin_range.end));
+                    }
+                }
+            }
+            if origin.range.begin != origin.range.end {
+                self.origins.insert(origin.range, origin);
+            }
+        }
+        if let Some(lines) = self.lines.get_mut(path.as_ref()) {
+            lines.apply_edit(range, text);
+        }
+        self.macro_index.apply_edit(path.as_ref(), range, text);
+        self.macro_index.apply_text_edit(pp_range, text);
+        Some(pp_range)
+    }
+
     pub fn origin(&self, pos: usize) -> Option<(&PathBuf, usize)> {
         let origin = self.origins.get(&Range::new(pos, pos + 1));
         if let Some(origin) = origin {
@@ -80,6 +267,39 @@ impl PreprocessedText {
             None
         }
     }
+
+    /// Get the byte offset in the source file of the position.
+    ///
+    /// Unlike `origin`, which gives the offset in the decoded source text, the offset counts
+    /// the bytes of the file, including a stripped BOM and the bytes of non-UTF-8 characters.
+    pub fn source_offset(&self, pos: usize) -> Option<(&PathBuf, usize)> {
+        let (path, pos) = self.origin(pos)?;
+        let offset = self.lines.get(path).map_or(pos, |x| x.source_offset(pos));
+        Some((path, offset))
+    }
+
+    /// Get the 1-based line and column in the source file of the position.
+    pub fn line_column(&self, pos: usize) -> Option<(&PathBuf, usize, usize)> {
+        let (path, pos) = self.origin(pos)?;
+        let (line, column) = self.lines.get(path)?.line_column(pos);
+        Some((path, line, column))
+    }
+
+    /// Get the 1-based line and column of the position with `line directives applied.
+    ///
+    /// IEEE1800-2017 Clause 22.12 gives the line following a `line directive the line number
+    /// and file name specified by the directive. Lines without a preceding directive are
+    /// reported same as `line_column`.
+    pub fn logical_line_column(&self, pos: usize) -> Option<(&PathBuf, usize, usize)> {
+        let (path, pos) = self.origin(pos)?;
+        let lines = self.lines.get(path)?;
+        if let Some(x) = lines.logical_line_column(pos) {
+            Some(x)
+        } else {
+            let (line, column) = lines.line_column(pos);
+            Some((path, line, column))
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -131,6 +351,219 @@ pub fn preprocess<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
         strip_comments,
         ignore_include,
         0, // include_depth
+        &PreprocessOptions::default(),
+    )
+}
+
+/// Options of `preprocess_with` and `preprocess_str_with`, which can be combined.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PreprocessOptions {
+    /// The standard used outside of `begin_keywords
+    ///
+    /// Directives newer than the standard like `ifdef expressions of IEEE1800-2023 are rejected,
+    /// and so are they without the standard as the parser does.
+    pub standard: Option<Standard>,
+    /// Skip regions between comment pragmas like `// synopsys translate_off`
+    ///
+    /// The pragma comments themselves are kept. A region is closed at the end of each file.
+    pub translate_off: Option<TranslateOff>,
+    /// The encoding to decode the source files
+    ///
+    /// `PreprocessedText::source_offset` gives byte offsets in the original files.
+    pub encoding: Encoding,
+    /// Keep each text macro usage in front of its expansion, except the usages in the set
+    ///
+    /// A usage is emitted as `MACRO_USAGE_BEGIN`, the usage text, `MACRO_USAGE_SEPARATOR`, the
+    /// expansion and `MACRO_USAGE_END`, which the parser turns into a macro usage node.
+    /// Usages in the set, given as the file and the byte offset of the usage, are expanded as
+    /// usual, and so are usages whose expansion leaves a bracket or a block like `begin` unclosed
+    /// or ends with an operator. Usages in macro expansions are never kept.
+    pub macro_preserving: Option<HashSet<(PathBuf, usize)>>,
+}
+
+/// Comment pragmas delimiting regions which are skipped like inactive `ifdef regions.
+///
+/// Each pair is the text of the comments starting and ending a region, such as
+/// `("synopsys translate_off", "synopsys translate_on")`. Comment markers are excluded and
+/// consecutive whitespaces in a comment are compared as a single space.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TranslateOff {
+    pub pragmas: Vec<(String, String)>,
+}
+
+impl TranslateOff {
+    pub fn new(pragmas: &[(&str, &str)]) -> Self {
+        TranslateOff {
+            pragmas: pragmas
+                .iter()
+                .map(|(x, y)| (String::from(*x), String::from(*y)))
+                .collect(),
+        }
+    }
+
+    /// Get the comment ending the region if the comment starts a region.
+    fn start(&self, comment: &str) -> Option<&str> {
+        let comment = normalize_comment(comment);
+        self.pragmas
+            .iter()
+            .find(|(x, _)| normalize_comment(x) == comment)
+            .map(|(_, x)| x.as_str())
+    }
+}
+
+impl Default for TranslateOff {
+    /// `translate_off`/`translate_on` with `synopsys`, `pragma` and `synthesis` prefixes
+    fn default() -> Self {
+        TranslateOff::new(&[
+            ("synopsys translate_off", "synopsys translate_on"),
+            ("pragma translate_off", "pragma translate_on"),
+            ("synthesis translate_off", "synthesis translate_on"),
+        ])
+    }
+}
+
+/// Remove comment markers and normalize whitespaces.
+fn normalize_comment(s: &str) -> String {
+    let s = match s.strip_prefix("//") {
+        Some(x) => x,
+        None => s.trim_start_matches("/*").trim_end_matches("*/"),
+    };
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Character encoding of source files.
+///
+/// A UTF-8 BOM at the start of a file is stripped for `Utf8` and `Utf8Lossy`, and a BOM of
+/// any Unicode encoding for `Label`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum Encoding {
+    /// UTF-8, failing with `Error::ReadUtf8` on invalid bytes
+    #[default]
+    Utf8,
+    /// UTF-8 with invalid bytes replaced by U+FFFD
+    Utf8Lossy,
+    /// ISO-8859-1, mapping each byte to the code point of the same value
+    Latin1,
+    /// An encoding given by its WHATWG label like "shift_jis" or "euc-jp", failing with
+    /// `Error::ReadEncoding` on malformed bytes
+    Label(String),
+}
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+// Decode the bytes of the file, returning the text and the offsets of `SourceLines`.
+fn decode(
+    bytes: &[u8],
+    encoding: &Encoding,
+    path: &Path,
+) -> Result<(String, Vec<(usize, usize)>), Error> {
+    let mut text = String::with_capacity(bytes.len());
+    let mut offsets: Vec<(usize, usize)> = Vec::new();
+
+    // Record the offsets if the difference between them changes
+    let mark = |offsets: &mut Vec<(usize, usize)>, text_pos: usize, file_pos: usize| {
+        let expected = offsets.last().map_or(text_pos, |x| x.1 + text_pos - x.0);
+        if expected != file_pos {
+            offsets.push((text_pos, file_pos));
+        }
+    };
+
+    match encoding {
+        Encoding::Utf8 | Encoding::Utf8Lossy => {
+            let bom = if bytes.starts_with(UTF8_BOM) {
+                UTF8_BOM.len()
+            } else {
+                0
+            };
+            mark(&mut offsets, 0, bom);
+            let mut pos = bom;
+            loop {
+                match std::str::from_utf8(&bytes[pos..]) {
+                    Ok(x) => {
+                        text.push_str(x);
+                        break;
+                    }
+                    Err(_) if *encoding == Encoding::Utf8 => {
+                        return Err(Error::ReadUtf8(PathBuf::from(path)));
+                    }
+                    Err(e) => {
+                        let valid = pos + e.valid_up_to();
+                        // The bytes before the error are valid UTF-8
+                        text.push_str(std::str::from_utf8(&bytes[pos..valid]).unwrap());
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        pos = e.error_len().map_or(bytes.len(), |x| valid + x);
+                        mark(&mut offsets, text.len(), pos);
+                    }
+                }
+            }
+        }
+        Encoding::Latin1 => {
+            for (i, x) in bytes.iter().enumerate() {
+                text.push(char::from(*x));
+                if *x >= 0x80 {
+                    mark(&mut offsets, text.len(), i + 1);
+                }
+            }
+        }
+        Encoding::Label(label) => {
+            let encoding = encoding_rs::Encoding::for_label(label.as_bytes())
+                .ok_or_else(|| Error::UnknownEncoding(label.clone()))?;
+            let (encoding, bom) = encoding_rs::Encoding::for_bom(bytes).unwrap_or((encoding, 0));
+            mark(&mut offsets, 0, bom);
+
+            // Decode runs of ASCII at once as they are the same bytes in ASCII-compatible
+            // encodings, and the others byte by byte to know where each character starts
+            let mut decoder = encoding.new_decoder_without_bom_handling();
+            let mut pos = bom;
+            // Whether no incomplete character is left in the decoder
+            let mut boundary = true;
+            while pos < bytes.len() {
+                let len = if boundary && encoding.is_ascii_compatible() {
+                    encoding_rs::Encoding::ascii_valid_up_to(&bytes[pos..]).max(1)
+                } else {
+                    1
+                };
+                let last = pos + len == bytes.len();
+                let text_len = text.len();
+                let max_len = decoder.max_utf8_buffer_length_without_replacement(len);
+                text.reserve(max_len.unwrap_or(16));
+                let src = &bytes[pos..pos + len];
+                let (result, _) = decoder.decode_to_string_without_replacement(src, &mut text, last);
+                if let encoding_rs::DecoderResult::Malformed(_, _) = result {
+                    return Err(Error::ReadEncoding(
+                        PathBuf::from(path),
+                        String::from(encoding.name()),
+                    ));
+                }
+                pos += len;
+                boundary = text.len() != text_len;
+                if boundary {
+                    mark(&mut offsets, text.len(), pos);
+                }
+            }
+        }
+    }
+
+    Ok((text, offsets))
+}
+
+/// Preprocess with the options.
+pub fn preprocess_with<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    path: T,
+    pre_defines: &Defines<V>,
+    include_paths: &[U],
+    strip_comments: bool,
+    ignore_include: bool,
+    options: &PreprocessOptions,
+) -> Result<(PreprocessedText, Defines), Error> {
+    preprocess_inner(
+        path,
+        pre_defines,
+        include_paths,
+        strip_comments,
+        ignore_include,
+        0, // include_depth
+        options,
     )
 }
 
@@ -141,6 +574,7 @@ fn preprocess_inner<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     strip_comments: bool,
     ignore_include: bool,
     include_depth: usize,
+    options: &PreprocessOptions,
 ) -> Result<(PreprocessedText, Defines), Error> {
 
     let f = File::open(path.as_ref()).map_err(|x| Error::File {
@@ -148,22 +582,28 @@ fn preprocess_inner<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
         path: PathBuf::from(path.as_ref()),
     })?;
     let mut reader = BufReader::new(f);
-    let mut s = String::new();
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(|x| Error::File {
+        source: x,
+        path: PathBuf::from(path.as_ref()),
+    })?;
+    let (s, offsets) = decode(&bytes, &options.encoding, path.as_ref())?;
 
-    if let Err(_) = reader.read_to_string(&mut s) {
-        Err(Error::ReadUtf8(PathBuf::from(path.as_ref())))
-    } else {
-        preprocess_str(
-            &s,
-            path,
-            pre_defines,
-            include_paths,
-            ignore_include,
-            strip_comments,
-            0, // resolve_depth
-            include_depth,
-        )
+    let (mut ret, defines) = preprocess_str_inner(
+        &s,
+        path.as_ref(),
+        pre_defines,
+        include_paths,
+        ignore_include,
+        strip_comments,
+        0, // resolve_depth
+        include_depth,
+        options,
+    )?;
+    if let Some(lines) = ret.lines.get_mut(path.as_ref()) {
+        lines.offsets = offsets;
     }
+    Ok((ret, defines))
 }
 
 struct SkipNodes<'a> {
@@ -204,6 +644,270 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     resolve_depth: usize,
     include_depth: usize,
 ) -> Result<(PreprocessedText, Defines), Error> {
+    preprocess_str_inner(
+        s,
+        path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        strip_comments,
+        resolve_depth,
+        include_depth,
+        &PreprocessOptions::default(),
+    )
+}
+
+/// Preprocess the string with the options.
+///
+/// `encoding` of the options is used for included files.
+pub fn preprocess_str_with<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    s: &str,
+    path: T,
+    pre_defines: &Defines<V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    strip_comments: bool,
+    options: &PreprocessOptions,
+) -> Result<(PreprocessedText, Defines), Error> {
+    preprocess_str_inner(
+        s,
+        path,
+        pre_defines,
+        include_paths,
+        ignore_include,
+        strip_comments,
+        0, // resolve_depth
+        0, // include_depth
+        options,
+    )
+}
+
+/// The expansion of a text macro usage.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MacroExpansion {
+    /// Text after each step expanding the macro usages of the previous step by one level.
+    ///
+    /// The first step is the macro text with the actual arguments of the usage. Steps are
+    /// textual, so directives like `define in macro texts don't affect later steps.
+    pub steps: Vec<String>,
+    /// Text after all nested macros are expanded, same as the preprocessor output.
+    pub text: String,
+}
+
+/// Expand the text macro usage in the source text `s`.
+///
+/// Returns `None` if the macro is defined without text. Errors are wrapped by
+/// `Error::MacroExpansion` with the position of the usage.
+pub fn expand_text_macro_usage<T: AsRef<Path>, U: AsRef<Path>>(
+    x: &TextMacroUsage,
+    s: &str,
+    path: T,
+    defines: &Defines,
+    include_paths: &[U],
+) -> Result<Option<MacroExpansion>, Error> {
+    let locate: Locate = x.try_into().unwrap();
+    let error = |x: Error| Error::MacroExpansion {
+        source: Box::new(x),
+        origin: Some((PathBuf::from(path.as_ref()), locate.offset)),
+    };
+
+    let mut steps = match substitute_text_macro_usage(x, s, defines).map_err(error)? {
+        Some((text, _)) => vec![text],
+        None => return Ok(None),
+    };
+    while let Some(text) = expand_text_macro_step(steps.last().unwrap(), defines).map_err(error)? {
+        if steps.len() > RECURSIVE_LIMIT {
+            return Err(error(Error::ExceedRecursiveLimit));
+        }
+        steps.push(text);
+    }
+
+    let text = resolve_text_macro_usage(
+        x,
+        s,
+        path.as_ref(),
+        defines,
+        include_paths,
+        false, // strip_comments
+        1,     // resolve_depth
+        None,  // standard
+    )
+    .map_err(error)?
+    .map(|x| x.0)
+    .unwrap_or_default();
+
+    Ok(Some(MacroExpansion { steps, text }))
+}
+
+/// Expand the first text macro usage in the string like "`FOO(1, 2)".
+///
+/// See `expand_text_macro_usage` for details. Returns `None` if the string has no usage.
+pub fn expand_text_macro_str<T: AsRef<Path>, U: AsRef<Path>>(
+    s: &str,
+    path: T,
+    defines: &Defines,
+    include_paths: &[U],
+) -> Result<Option<MacroExpansion>, Error> {
+    let span = Span::new_extra(s, SpanInfo::default());
+    let (_, pp_text) = all_consuming(pp_parser)(span).map_err(|x| match x {
+        nom::Err::Error(e) | nom::Err::Failure(e) => {
+            Error::Preprocess(error_position(&e).map(|x| (PathBuf::from(path.as_ref()), x)))
+        }
+        nom::Err::Incomplete(_) => Error::Preprocess(None),
+    })?;
+
+    for node in &pp_text {
+        if let RefNode::TextMacroUsage(x) = node {
+            return expand_text_macro_usage(x, s, path, defines, include_paths);
+        }
+    }
+    Ok(None)
+}
+
+// Substitute every text macro usage in the text by one level.
+fn expand_text_macro_step(s: &str, defines: &Defines) -> Result<Option<String>, Error> {
+    let span = Span::new_extra(s, SpanInfo::default());
+    let (_, pp_text) = all_consuming(pp_parser)(span).map_err(|_| Error::Preprocess(None))?;
+
+    let mut ret = String::new();
+    let mut last = 0;
+    for node in &pp_text {
+        if let RefNode::TextMacroUsage(x) = node {
+            // The trailing whitespaces of the usage are kept
+            let range = text_macro_usage_range(x);
+            ret.push_str(&s[last..range.begin]);
+            if let Some((text, _)) = substitute_text_macro_usage(x, s, defines)? {
+                ret.push_str(&text);
+            }
+            last = range.end;
+        }
+    }
+
+    if last == 0 {
+        Ok(None)
+    } else {
+        ret.push_str(&s[last..]);
+        Ok(Some(ret))
+    }
+}
+
+// Range of the text macro usage excluding its trailing whitespaces.
+fn text_macro_usage_range(x: &TextMacroUsage) -> Range {
+    let (_, ref name, ref args) = x.nodes;
+    let end: Locate = match args {
+        Some(args) => args.nodes.2.nodes.0,
+        None => match name.nodes.0 {
+            Identifier::SimpleIdentifier(ref x) => x.nodes.0,
+            Identifier::EscapedIdentifier(ref x) => x.nodes.0,
+        },
+    };
+    let locate: Locate = x.try_into().unwrap();
+    Range::new(locate.offset, end.offset + end.len)
+}
+
+// Brackets and keywords which open a construct, and the tokens closing it
+const EXPANSION_BLOCKS: &[(&str, &[&str])] = &[
+    ("(", &[")"]),
+    ("[", &["]"]),
+    ("{", &["}"]),
+    ("begin", &["end"]),
+    ("fork", &["join", "join_any", "join_none"]),
+    ("case", &["endcase"]),
+    ("casex", &["endcase"]),
+    ("casez", &["endcase"]),
+    ("randcase", &["endcase"]),
+    ("function", &["endfunction"]),
+    ("task", &["endtask"]),
+    ("class", &["endclass"]),
+    ("generate", &["endgenerate"]),
+];
+
+// Check whether the edit of the text changes only the tokens touching the edited range, and none
+// of them is a directive or a macro usage. An edit opening a comment or a string which swallows
+// the following tokens is rejected.
+fn is_local_edit(s: &str, range: Range, text: &str) -> bool {
+    let mut edited = String::from(s);
+    edited.replace_range(range.begin..range.end, text);
+    let before = tokenize(s);
+    let after = tokenize(&edited);
+    let edited_end = range.begin + text.len();
+
+    let same = |x: &Token, y: &Token, shift: bool| {
+        let offset = if shift {
+            x.locate.offset + text.len() - (range.end - range.begin)
+        } else {
+            x.locate.offset
+        };
+        x.kind == y.kind && x.locate.len == y.locate.len && offset == y.locate.offset
+    };
+    let head = before
+        .iter()
+        .zip(&after)
+        .take_while(|(x, y)| x.locate.offset + x.locate.len < range.begin && same(x, y, false))
+        .count();
+    let tail = before[head..]
+        .iter()
+        .rev()
+        .zip(after[head..].iter().rev())
+        .take_while(|(x, y)| x.locate.offset > range.end && same(x, y, true))
+        .count();
+
+    let touched = |tokens: &[Token], end: usize| {
+        tokens.iter().all(|x| {
+            x.kind != TokenKind::Directive
+                && x.locate.offset <= end
+                && range.begin <= x.locate.offset + x.locate.len
+        })
+    };
+    touched(&before[head..before.len() - tail], range.end)
+        && touched(&after[head..after.len() - tail], edited_end)
+}
+
+// Check whether the expansion consists of whole constructs, which a macro usage node requires.
+// A usage expanding to a part of a construct like `lhs =` is expanded as usual instead.
+fn is_balanced_expansion(text: &str) -> bool {
+    let mut stack = Vec::new();
+    let mut last = None;
+    for token in tokenize(text) {
+        match token.kind {
+            TokenKind::WhiteSpace | TokenKind::Comment => continue,
+            TokenKind::Directive | TokenKind::Unknown => return false,
+            TokenKind::Keyword | TokenKind::Operator => {
+                let t = token.str(text);
+                if let Some(i) = EXPANSION_BLOCKS.iter().position(|(x, _)| *x == t) {
+                    stack.push(i);
+                } else if EXPANSION_BLOCKS.iter().any(|(_, x)| x.contains(&t)) {
+                    match stack.pop() {
+                        Some(i) if EXPANSION_BLOCKS[i].1.contains(&t) => (),
+                        _ => return false,
+                    }
+                }
+            }
+            _ => (),
+        }
+        last = Some(token);
+    }
+    match last {
+        Some(x) if x.kind == TokenKind::Operator => {
+            stack.is_empty() && [";", ")", "]", "}"].contains(&x.str(text))
+        }
+        Some(_) => stack.is_empty(),
+        None => false,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn preprocess_str_inner<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    s: &str,
+    path: T,
+    pre_defines: &Defines<V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    strip_comments: bool,
+    resolve_depth: usize,
+    include_depth: usize,
+    options: &PreprocessOptions,
+) -> Result<(PreprocessedText, Defines), Error> {
 
     // IEEE1800-2017 Clause 22.4, page 675
     // A file included in the source using the `include compiler directive
@@ -218,6 +922,7 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     let mut skip = false;
     let mut skip_whitespace = false;
     let mut skip_nodes = SkipNodes::new();
+    let mut translate_on: Option<&str> = None;
     let mut defines = HashMap::new();
 
     let mut last_item_line = None;
@@ -257,6 +962,7 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
     }
 
     let span = Span::new_extra(&s, SpanInfo::default());
+    let pp_parser = |s| pp_parser_with(s, options.standard);
     let (_, pp_text) = all_consuming(pp_parser)(span).map_err(|x| match x {
         nom::Err::Incomplete(_) => Error::Preprocess(None),
         nom::Err::Error(e) => {
@@ -277,6 +983,28 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
 
     let mut ret = PreprocessedText::new();
 
+    // Macros given by the caller are recorded only once at the top level
+    if resolve_depth == 0 && include_depth == 0 {
+        let mut pre_defines: Vec<_> = pre_defines.iter().collect();
+        pre_defines.sort_by(|x, y| x.0.cmp(y.0));
+        for (k, v) in pre_defines {
+            ret.macro_index.definitions.push(MacroDefinition {
+                identifier: k.clone(),
+                define: v.clone(),
+                origin: None,
+            });
+        }
+    }
+
+    // Macro texts are preprocessed as strings too, but only source files have lines
+    let mut source_lines = if resolve_depth == 0 {
+        Some(SourceLines::new(s))
+    } else {
+        None
+    };
+    // The physical line following the last `line directive, and its logical line and file
+    let mut line_directive: Option<(usize, usize, String)> = None;
+
     for n in pp_text.into_iter().event() {
         match n.clone() {
             NodeEvent::Enter(x) => {
@@ -294,6 +1022,23 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
             continue;
         }
 
+        // Skip a region between translate_off/translate_on comments except the pragmas
+        if let (Some(translate_off), NodeEvent::Enter(RefNode::Comment(x))) =
+            (&options.translate_off, &n)
+        {
+            let locate: Locate = (*x).try_into().unwrap();
+            let comment = locate.str(s);
+            match translate_on {
+                Some(on) if normalize_comment(on) == normalize_comment(comment) => {
+                    translate_on = None;
+                }
+                Some(_) => continue,
+                None => translate_on = translate_off.start(comment),
+            }
+        } else if translate_on.is_some() {
+            continue;
+        }
+
         match n.clone() {
             NodeEvent::Enter(RefNode::SourceDescriptionNotDirective(x)) => {
                 let locate: Locate = x.try_into().unwrap();
@@ -415,11 +1160,37 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
             NodeEvent::Leave(RefNode::Pragma(_)) => {
                 skip_whitespace = false;
             }
+            NodeEvent::Enter(RefNode::ProtectedEnvelope(x)) => {
+                // Keep the envelope as is because the protected blocks aren't source text
+                skip_nodes.push(x.into());
+                skip = true;
+
+                let locate: Locate = x.try_into().unwrap();
+                let range = Range::new(locate.offset, locate.offset + locate.len);
+                ret.push(locate.str(s), Some((path.as_ref(), range)));
+            }
             NodeEvent::Enter(RefNode::LineCompilerDirective(x)) => {
                 let locate: Locate = x.try_into().unwrap();
                 let range = Range::new(locate.offset, locate.offset + locate.len);
                 ret.push(locate.str(&s), Some((path.as_ref(), range)));
                 skip_whitespace = true;
+
+                let (_, _, ref number, ref filename, ref level) = x.nodes;
+                let level: Locate = level.nodes.0.nodes.0;
+                let end = level.offset + level.len;
+                let begin = s[end..].find('\n').map_or(s.len(), |x| end + x + 1);
+                if let Ok(line) = get_str(number.into(), s).trim().parse::<usize>() {
+                    let filename: Locate = filename.nodes.0;
+                    let filename = filename.str(s).trim_matches('"');
+                    line_directive = Some((level.line as usize + 1, line, String::from(filename)));
+                    if let Some(ref mut source_lines) = source_lines {
+                        source_lines.directives.push(LineDirective {
+                            begin,
+                            line,
+                            path: PathBuf::from(filename),
+                        });
+                    }
+                }
             }
             NodeEvent::Leave(RefNode::LineCompilerDirective(_)) => {
                 skip_whitespace = false;
@@ -445,22 +1216,47 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
             NodeEvent::Enter(RefNode::UndefineCompilerDirective(x)) => {
                 let (_, _, ref name) = x.nodes;
                 let id = identifier((&name.nodes.0).into(), &s).unwrap();
-                defines.remove(&id);
+                let defined = defines.remove(&id).is_some();
 
                 let locate: Locate = x.try_into().unwrap();
                 let range = Range::new(locate.offset, locate.offset + locate.len);
                 ret.push(locate.str(&s), Some((path.as_ref(), range)));
+                ret.macro_index.undefinitions.push(MacroUndefinition {
+                    identifier: id,
+                    defined,
+                    origin: Some((PathBuf::from(path.as_ref()), range)),
+                });
                 skip_whitespace = true;
             }
             NodeEvent::Leave(RefNode::UndefineCompilerDirective(_)) => {
                 skip_whitespace = false;
             }
             NodeEvent::Enter(RefNode::UndefineallCompilerDirective(x)) => {
-                defines.clear();
-
                 let locate: Locate = x.try_into().unwrap();
                 let range = Range::new(locate.offset, locate.offset + locate.len);
                 ret.push(locate.str(&s), Some((path.as_ref(), range)));
+
+                // Predefined coverage macros have no MacroDefinition to be paired with
+                let mut ids: Vec<_> = defines
+                    .drain()
+                    .filter(|(k, v)| {
+                        let text = v.as_ref().and_then(|x| x.text.as_ref());
+                        !sv_cov_pre_defines.iter().any(|(id, value)| {
+                            k == id
+                                && text.map(|x| x.origin.is_none() && x.text == *value)
+                                    == Some(true)
+                        })
+                    })
+                    .map(|(k, _)| k)
+                    .collect();
+                ids.sort();
+                for id in ids {
+                    ret.macro_index.undefinitions.push(MacroUndefinition {
+                        identifier: id,
+                        defined: true,
+                        origin: Some((PathBuf::from(path.as_ref()), range)),
+                    });
+                }
                 skip_whitespace = true;
             }
             NodeEvent::Leave(RefNode::UndefineallCompilerDirective(_)) => {
@@ -471,9 +1267,14 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                 skip_nodes.push(keyword.into());
                 skip_nodes.push(ifid.into());
 
-                let ifid = identifier(ifid.into(), &s).unwrap();
+                push_macro_checks(&mut ret.macro_index, ifid, &defines, s, path.as_ref());
+                for (_, _, elsifid, _) in elsif {
+                    push_macro_checks(&mut ret.macro_index, elsifid, &defines, s, path.as_ref());
+                }
+
+                let predefined = is_predefined_condition(ifid, s);
                 let mut hit = false;
-                if defines.contains_key(&ifid) || is_predefined_text_macro(&ifid) {
+                if ifdef_condition(ifid, &defines, s) {
                     hit = true;
                 } else {
                     skip_nodes.push(ifbody.into());
@@ -484,10 +1285,9 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                     skip_nodes.push(keyword.into());
                     skip_nodes.push(elsifid.into());
 
-                    let elsifid = identifier(elsifid.into(), &s).unwrap();
                     if hit {
                         skip_nodes.push(elsifbody.into());
-                    } else if defines.contains_key(&elsifid) || is_predefined_text_macro(&ifid) {
+                    } else if ifdef_condition(elsifid, &defines, s) || predefined {
                         hit = true;
                     } else {
                         skip_nodes.push(elsifbody.into());
@@ -519,9 +1319,14 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                 skip_nodes.push(keyword.into());
                 skip_nodes.push(ifid.into());
 
-                let ifid = identifier(ifid.into(), &s).unwrap();
+                push_macro_checks(&mut ret.macro_index, ifid, &defines, s, path.as_ref());
+                for (_, _, elsifid, _) in elsif {
+                    push_macro_checks(&mut ret.macro_index, elsifid, &defines, s, path.as_ref());
+                }
+
+                let predefined = is_predefined_condition(ifid, s);
                 let mut hit = false;
-                if !defines.contains_key(&ifid) && !is_predefined_text_macro(&ifid) {
+                if !ifdef_condition(ifid, &defines, s) {
                     hit = true;
                 } else {
                     skip_nodes.push(ifbody.into());
@@ -532,10 +1337,9 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                     skip_nodes.push(keyword.into());
                     skip_nodes.push(elsifid.into());
 
-                    let elsifid = identifier(elsifid.into(), &s).unwrap();
                     if hit {
                         skip_nodes.push(elsifbody.into());
-                    } else if defines.contains_key(&elsifid) || is_predefined_text_macro(&ifid) {
+                    } else if ifdef_condition(elsifid, &defines, s) || predefined {
                         hit = true;
                     } else {
                         skip_nodes.push(elsifbody.into());
@@ -598,6 +1402,27 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                         text: define_text,
                     };
 
+                    let locate: Locate = x.try_into().unwrap();
+                    let range = Range::new(locate.offset, locate.offset + locate.len);
+                    let origin = Some((PathBuf::from(path.as_ref()), range));
+                    if let Some(previous) = defines.get(&id) {
+                        let differs = match previous {
+                            Some(previous) => !same_definition(previous, &define),
+                            None => !define.arguments.is_empty() || define.text.is_some(),
+                        };
+                        ret.macro_index.redefinitions.push(MacroRedefinition {
+                            identifier: id.clone(),
+                            previous: previous.clone(),
+                            origin: origin.clone(),
+                            differs,
+                        });
+                    }
+                    ret.macro_index.definitions.push(MacroDefinition {
+                        identifier: id.clone(),
+                        define: Some(define.clone()),
+                        origin,
+                    });
+
                     defines.insert(id, Some(define));
                 }
 
@@ -644,7 +1469,8 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                         skip_nodes.push(keyword.into());
                         skip_nodes.push(x.into());
 
-                        if let Some((p, _, _)) = resolve_text_macro_usage(
+                        ret.macro_index.usages.push(macro_usage(x, s, path.as_ref()));
+                        if let Some((p, _, _, index)) = resolve_text_macro_usage(
                             x,
                             s,
                             path.as_ref(),
@@ -652,7 +1478,9 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                             include_paths,
                             strip_comments,
                             resolve_depth + 1,
+                            options.standard,
                         )? {
+                            ret.macro_index.merge_expansion(index);
                             let p = p.trim().trim_matches('"');
                             PathBuf::from(p)
                         } else {
@@ -691,7 +1519,8 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                         include_paths,
                         strip_comments,
                         false, // ignore_include
-                        include_depth + 1).map_err(
+                        include_depth + 1,
+                        options).map_err(
                         |x| Error::Include {
                             source: Box::new(x),
                         },
@@ -703,7 +1532,10 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                 skip_nodes.push(x.into());
                 skip = true;
 
-                if let Some((text, origin, new_defines)) = resolve_text_macro_usage(
+                let usage = ret.macro_index.usages.len();
+                ret.macro_index.usages.push(macro_usage(x, s, path.as_ref()));
+
+                let resolved = resolve_text_macro_usage(
                     x,
                     s,
                     path.as_ref(),
@@ -711,11 +1543,39 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                     include_paths,
                     strip_comments,
                     resolve_depth + 1,
-                )? {
+                    options.standard,
+                )?;
+
+                // In the macro-preserving mode, the usage is kept with its expansion between
+                // markers so that the parser can build a node of the usage.
+                let range = text_macro_usage_range(x);
+                let preserve = match (&options.macro_preserving, &resolved) {
+                    (Some(fallback), Some((text, _, _, _))) => {
+                        resolve_depth == 0
+                            && !fallback.contains(&(PathBuf::from(path.as_ref()), range.begin))
+                            && is_balanced_expansion(text)
+                    }
+                    _ => false,
+                };
+                if preserve {
+                    ret.push::<PathBuf>(MACRO_USAGE_BEGIN, None);
+                    ret.push(&s[range.begin..range.end], Some((path.as_ref(), range)));
+                    ret.push::<PathBuf>(MACRO_USAGE_SEPARATOR, None);
+                }
+
+                if let Some((text, origin, new_defines, index)) = resolved {
+                    let begin = ret.text.len();
                     ret.push(&text, origin);
+                    let expansion = Range::new(begin, ret.text.len());
+                    ret.macro_index.usages[usage].expansion = Some(expansion);
+                    ret.macro_index.merge_expansion(index);
                     defines = new_defines;
                 }
 
+                if preserve {
+                    ret.push::<PathBuf>(MACRO_USAGE_END, None);
+                }
+
                 // Push the trailing whitespace attached to either
                 // TextMacroIdentifier or Option<Paren<ListOfActualArguments>>.
                 let (ref _symbol, ref id, ref args) = x.nodes;
@@ -757,22 +1617,30 @@ pub fn preprocess_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
                 let (_, ref x) = x.nodes;
                 let locate: Locate = x.try_into().unwrap();
                 let x = locate.str(s);
+                // `line directives change the file and line of following lines
+                let (file, line) = match line_directive {
+                    Some((begin, line, ref file)) => {
+                        (file.clone(), line + locate.line as usize - begin)
+                    }
+                    None => (
+                        path.as_ref().to_string_lossy().into_owned(),
+                        locate.line as usize,
+                    ),
+                };
                 if x.starts_with("__FILE__") {
-                    ret.push::<PathBuf>(
-                        &x.replace(
-                            "__FILE__",
-                            &format!("\"{}\"", path.as_ref().to_string_lossy()),
-                        ),
-                        None,
-                    );
+                    ret.push::<PathBuf>(&x.replace("__FILE__", &format!("\"{}\"", file)), None);
                 } else if x.starts_with("__LINE__") {
-                    ret.push::<PathBuf>(&x.replace("__LINE__", &format!("{}", locate.line)), None);
+                    ret.push::<PathBuf>(&x.replace("__LINE__", &format!("{}", line)), None);
                 }
             }
             _ => (),
         }
     }
 
+    if let Some(source_lines) = source_lines {
+        ret.lines.insert(PathBuf::from(path.as_ref()), source_lines);
+    }
+
     Ok((ret, defines))
 }
 
@@ -795,6 +1663,97 @@ fn identifier(node: RefNode, s: &str) -> Option<String> {
     None
 }
 
+fn ifdef_condition(x: &IfdefCondition, defines: &Defines, s: &str) -> bool {
+    match x {
+        IfdefCondition::TextMacroIdentifier(x) => is_defined(x, defines, s),
+        IfdefCondition::IfdefMacroExpression(x) => {
+            ifdef_macro_expression(&x.nodes.1, defines, s)
+        }
+    }
+}
+
+fn ifdef_macro_expression(x: &IfdefMacroExpression, defines: &Defines, s: &str) -> bool {
+    match x {
+        IfdefMacroExpression::TextMacroIdentifier(x) => is_defined(x, defines, s),
+        IfdefMacroExpression::Paren(x) => ifdef_macro_expression(&x.nodes.1, defines, s),
+        IfdefMacroExpression::Not(x) => !ifdef_macro_expression(&x.nodes.1, defines, s),
+        IfdefMacroExpression::Binary(x) => {
+            let (ref a, ref op, ref b) = x.nodes;
+            let a = ifdef_macro_expression(a, defines, s);
+            let b = ifdef_macro_expression(b, defines, s);
+            let op: Locate = op.nodes.0.nodes.0;
+            match op.str(s) {
+                "&&" => a && b,
+                "||" => a || b,
+                "->" => !a || b,
+                _ => a == b,
+            }
+        }
+    }
+}
+
+// `elsif is also taken when the `ifdef/`ifndef identifier is a predefined macro.
+fn is_predefined_condition(x: &IfdefCondition, s: &str) -> bool {
+    match x {
+        IfdefCondition::TextMacroIdentifier(x) => {
+            is_predefined_text_macro(&identifier((&**x).into(), s).unwrap())
+        }
+        IfdefCondition::IfdefMacroExpression(_) => false,
+    }
+}
+
+fn is_defined(x: &TextMacroIdentifier, defines: &Defines, s: &str) -> bool {
+    let id = identifier(x.into(), s).unwrap();
+    defines.contains_key(&id) || is_predefined_text_macro(&id)
+}
+
+fn macro_usage(x: &TextMacroUsage, s: &str, path: &Path) -> MacroUsage {
+    let (_, ref name, ref args) = x.nodes;
+    let mut arguments = Vec::new();
+    if let Some(args) = args {
+        let (_, ref args, _) = args.nodes;
+        let (ref args,) = args.nodes;
+        for arg in args.contents() {
+            arguments.push(arg.as_ref().map(|x| String::from(x.nodes.0.str(s).trim_end())));
+        }
+    }
+    let locate: Locate = x.try_into().unwrap();
+    MacroUsage {
+        identifier: identifier((&name.nodes.0).into(), s).unwrap(),
+        arguments,
+        origin: Some((
+            PathBuf::from(path),
+            Range::new(locate.offset, locate.offset + locate.len),
+        )),
+        expansion: None,
+    }
+}
+
+fn push_macro_checks(
+    index: &mut MacroIndex,
+    x: &IfdefCondition,
+    defines: &Defines,
+    s: &str,
+    path: &Path,
+) {
+    for node in x {
+        if let RefNode::TextMacroIdentifier(x) = node {
+            let locate = match x.nodes.0 {
+                Identifier::SimpleIdentifier(ref x) => x.nodes.0,
+                Identifier::EscapedIdentifier(ref x) => x.nodes.0,
+            };
+            index.checks.push(MacroCheck {
+                identifier: identifier(x.into(), s).unwrap(),
+                defined: is_defined(x, defines, s),
+                origin: Some((
+                    PathBuf::from(path),
+                    Range::new(locate.offset, locate.offset + locate.len),
+                )),
+            });
+        }
+    }
+}
+
 fn get_str(node: RefNode, s: &str) -> String {
     let mut ret = String::from("");
     for x in node {
@@ -903,6 +1862,7 @@ fn split_text(s: &str) -> Vec<String> {
     ret
 }
 
+#[allow(clippy::too_many_arguments)]
 fn resolve_text_macro_usage<T: AsRef<Path>, U: AsRef<Path>>(
     x: &TextMacroUsage,
     s: &str,
@@ -911,14 +1871,47 @@ fn resolve_text_macro_usage<T: AsRef<Path>, U: AsRef<Path>>(
     include_paths: &[U],
     strip_comments: bool,
     resolve_depth: usize,
-) -> Result<Option<(String, Option<(PathBuf, Range)>, Defines)>, Error> {
-    let (_, ref name, ref args) = x.nodes;
-    let id = identifier((&name.nodes.0).into(), &s).unwrap();
-
+    standard: Option<Standard>,
+) -> Result<Option<(String, Option<(PathBuf, Range)>, Defines, MacroIndex)>, Error> {
     if resolve_depth > RECURSIVE_LIMIT {
         return Err(Error::ExceedRecursiveLimit);
     }
 
+    if let Some((replaced, origin)) = substitute_text_macro_usage(x, s, defines)? {
+        let (replaced, new_defines) = preprocess_str_inner(
+            &replaced,
+            path.as_ref(),
+            defines,
+            include_paths,
+            false,
+            strip_comments,
+            resolve_depth,
+            0, // include_depth
+            &PreprocessOptions {
+                standard,
+                ..Default::default()
+            },
+        )?;
+        Ok(Some((
+            String::from(replaced.text()),
+            origin,
+            new_defines,
+            replaced.macro_index,
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
+// Replace the usage by the macro text with the actual arguments, without expanding nested usages.
+fn substitute_text_macro_usage(
+    x: &TextMacroUsage,
+    s: &str,
+    defines: &Defines,
+) -> Result<Option<(String, Option<(PathBuf, Range)>)>, Error> {
+    let (_, ref name, ref args) = x.nodes;
+    let id = identifier((&name.nodes.0).into(), &s).unwrap();
+
     let mut args_str = String::from("");
     let mut actual_args = Vec::new();
     let no_args = args.is_none();
@@ -998,21 +1991,7 @@ fn resolve_text_macro_usage<T: AsRef<Path>, U: AsRef<Path>>(
                 replaced.push_str(&paren);
             }
 
-            let (replaced, new_defines) = preprocess_str(
-                &replaced,
-                path.as_ref(),
-                &defines,
-                include_paths,
-                false,
-                strip_comments,
-                resolve_depth,
-                0, // include_depth
-            )?;
-            Ok(Some((
-                String::from(replaced.text()),
-                text.origin.clone(),
-                new_defines,
-            )))
+            Ok(Some((replaced, text.origin.clone())))
         } else {
             Ok(None)
         }
@@ -1383,6 +2362,30 @@ mod tests {
         );
     } // }}}
 
+    #[test]
+    fn ifdef_expression() { // {{{
+        let preprocess_standard = |standard: Option<Standard>| {
+            preprocess_with(
+                testfile_path("ifdef_expression.sv"),
+                &HashMap::new(),
+                &[] as &[String],
+                false, // strip_comments
+                false, // ignore_include
+                &PreprocessOptions {
+                    standard,
+                    ..Default::default()
+                },
+            )
+        };
+        let (ret, _) = preprocess_standard(Some(Standard::Ieee1800_2023)).unwrap();
+        assert_eq!(
+            ret.text(),
+            testfile_contents("expected/ifdef_expression.sv")
+        );
+        assert!(preprocess_standard(Some(Standard::Ieee1800_2017)).is_err());
+        assert!(preprocess_standard(None).is_err());
+    } // }}}
+
     #[test]
     fn ifdef_predefined() { // {{{
         let mut defines = HashMap::new();
@@ -1612,6 +2615,189 @@ mod tests {
         );
     } // }}}
 
+    #[test]
+    fn line_macro() { // {{{
+        let (ret, _) = preprocess_usualargs("line_macro.sv").unwrap();
+        assert_eq!(
+            ret.text(),
+            testfile_contents("expected/line_macro.sv")
+        );
+    } // }}}
+
+    #[test]
+    fn line_origin() { // {{{
+        let (ret, _) = preprocess_usualargs("line_macro.sv").unwrap();
+        let path = PathBuf::from(testfile_path("line_macro.sv"));
+
+        let pos = ret.text().find("module").unwrap();
+        assert_eq!(ret.line_column(pos), Some((&path, 2, 1)));
+        assert_eq!(ret.logical_line_column(pos), Some((&path, 2, 1)));
+
+        let pos = ret.text().find("initial").unwrap();
+        assert_eq!(ret.line_column(pos), Some((&path, 4, 3)));
+        assert_eq!(
+            ret.logical_line_column(pos),
+            Some((&PathBuf::from("orig.sv"), 100, 3))
+        );
+
+        let pos = ret.text().find("endmodule").unwrap();
+        assert_eq!(ret.line_column(pos), Some((&path, 7, 1)));
+        assert_eq!(
+            ret.logical_line_column(pos),
+            Some((&PathBuf::from("orig.sv"), 103, 1))
+        );
+    } // }}}
+
+    #[test]
+    fn encoding() { // {{{
+        let include_paths = [testfile_path("")];
+        let preprocess_encoding = |s: &str, encoding: Encoding| {
+            preprocess_with(
+                testfile_path(s),
+                &HashMap::new(),
+                &include_paths,
+                false,
+                false,
+                &PreprocessOptions {
+                    encoding,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let path = PathBuf::from(testfile_path("err_ReadUtf8.sv"));
+        let (ret, _) = preprocess_encoding("err_ReadUtf8.sv", Encoding::Utf8Lossy).unwrap();
+        assert!(ret.text().contains("X\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}X"));
+        let pos = ret.text().find("endmodule").unwrap();
+        assert_eq!(ret.source_offset(pos), Some((&path, 81)));
+
+        let (ret, _) = preprocess_encoding("err_ReadUtf8.sv", Encoding::Latin1).unwrap();
+        assert!(ret.text().contains("X\u{f1}\u{f2}\u{f3}\u{f4}\u{f5}\u{f6}X"));
+        let pos = ret.text().find("endmodule").unwrap();
+        assert_eq!(ret.source_offset(pos), Some((&path, 81)));
+        assert_eq!(ret.line_column(pos), Some((&path, 4, 1)));
+
+        let path = PathBuf::from(testfile_path("encoding_bom.sv"));
+        let (ret, _) = preprocess_encoding("encoding_bom.sv", Encoding::Utf8).unwrap();
+        assert!(ret.text().starts_with("module"));
+        assert_eq!(ret.origin(0), Some((&path, 0)));
+        assert_eq!(ret.source_offset(0), Some((&path, 3)));
+
+        let path = PathBuf::from(testfile_path("encoding_sjis.sv"));
+        let (ret, _) =
+            preprocess_encoding("encoding_sjis.sv", Encoding::Label(String::from("shift_jis")))
+                .unwrap();
+        assert!(ret.text().starts_with("// 日本語のコメント\n"));
+        let pos = ret.text().find("endmodule").unwrap();
+        assert_eq!(ret.source_offset(pos), Some((&path, 30)));
+
+        let path = PathBuf::from(testfile_path("err_ReadEncoding.sv"));
+        match preprocess_encoding("err_ReadEncoding.sv", Encoding::Label(String::from("sjis"))) {
+            Err(Error::ReadEncoding(x, y)) => {
+                assert_eq!(x, path);
+                assert_eq!(y, "Shift_JIS");
+            }
+            _ => panic!("Error::ReadEncoding not raised."),
+        }
+
+        match preprocess_encoding("encoding_sjis.sv", Encoding::Label(String::from("x"))) {
+            Err(Error::UnknownEncoding(x)) => assert_eq!(x, "x"),
+            _ => panic!("Error::UnknownEncoding not raised."),
+        }
+    } // }}}
+
+    #[test]
+    fn macro_index() { // {{{
+        let mut defines = HashMap::new();
+        defines.insert(String::from("C"), None);
+        let include_paths = [testfile_path("")];
+        let (ret, _) = preprocess(
+            testfile_path("macro_index.sv"),
+            &defines,
+            &include_paths,
+            false,
+            false,
+        )
+        .unwrap();
+        let index = ret.macro_index();
+        let ids = |x: Vec<&str>| x.into_iter().map(String::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            index.definitions.iter().map(|x| x.identifier.clone()).collect::<Vec<_>>(),
+            ids(vec!["C", "A", "B", "UNUSED", "A", "B"])
+        );
+        assert_eq!(index.definitions[0].origin, None);
+        let (ref path, range) = index.definitions[1].origin.clone().unwrap();
+        assert_eq!(path, &PathBuf::from(testfile_path("macro_index.sv")));
+        assert_eq!(range.begin, 0);
+
+        let usages: Vec<_> = index
+            .usages
+            .iter()
+            .map(|x| (x.identifier.as_str(), x.arguments.clone(), x.origin.is_some()))
+            .collect();
+        assert_eq!(
+            usages,
+            vec![
+                ("A", vec![Some(String::from("a")), Some(String::from("b"))], true),
+                ("B", vec![], true),
+                ("A", vec![Some(String::from("2")), None], false),
+            ]
+        );
+
+        let redefinitions: Vec<_> = index
+            .redefinitions
+            .iter()
+            .map(|x| (x.identifier.as_str(), x.differs))
+            .collect();
+        assert_eq!(redefinitions, vec![("A", false), ("B", true)]);
+
+        let undefinitions: Vec<_> = index
+            .undefinitions
+            .iter()
+            .map(|x| (x.identifier.as_str(), x.defined))
+            .collect();
+        assert_eq!(undefinitions, vec![("B", true)]);
+
+        let checks: Vec<_> = index
+            .checks
+            .iter()
+            .map(|x| (x.identifier.as_str(), x.defined))
+            .collect();
+        assert_eq!(checks, vec![("C", true), ("B", true), ("behavioral", false)]);
+
+        assert_eq!(index.usages_of("A").count(), 2);
+        assert_eq!(index.definitions_of("B").count(), 2);
+        let unused: Vec<_> = index.unused().iter().map(|x| x.identifier.as_str()).collect();
+        assert_eq!(unused, vec!["UNUSED"]);
+    } // }}}
+
+    #[test]
+    fn macro_expansion() { // {{{
+        let src = "`define A(x, y=1) (x + y)\n\
+                   `define B(z) `A(z,) * `A(z, 2)\n\
+                   `define C `B(3)\n\
+                   `define D(p, q) p q\n";
+        let (_, defines) =
+            preprocess_str(src, "", &HashMap::new(), &[""], false, false, 0, 0).unwrap();
+
+        let ret = expand_text_macro_str("`C", "", &defines, &[""]).unwrap().unwrap();
+        assert_eq!(ret.steps, vec!["`B(3)", "`A(3,) * `A(3, 2)", "(3 + 1) * (3 + 2)"]);
+        assert_eq!(ret.text, "(3 + 1) * (3 + 2)");
+
+        let ret = expand_text_macro_str("x = 1;", "", &defines, &[""]).unwrap();
+        assert_eq!(ret, None);
+
+        let ret = expand_text_macro_str("  `D(1)", "test.sv", &defines, &[""]);
+        match ret {
+            Err(Error::MacroExpansion { source, origin }) => {
+                assert_eq!(origin, Some((PathBuf::from("test.sv"), 2)));
+                assert!(matches!(*source, Error::DefineArgNotFound(ref x) if x == "q"));
+            }
+            x => panic!("{:?}", x),
+        }
+    } // }}}
+
     #[test]
     fn macro_arguments() { // {{{
         let (ret, _) = preprocess_usualargs("macro_arguments.sv").unwrap();
@@ -1717,6 +2903,15 @@ mod tests {
         );
     } // }}}
 
+    #[test]
+    fn protect_envelope() { // {{{
+        let (ret, _) = preprocess_usualargs("protect_envelope.sv").unwrap();
+        assert_eq!(
+            ret.text(),
+            testfile_contents("protect_envelope.sv")
+        );
+    } // }}}
+
     #[test]
     fn resetall() { // {{{
         let (ret, _) = preprocess_usualargs("resetall.sv").unwrap();
@@ -1744,6 +2939,44 @@ mod tests {
         );
     } // }}}
 
+    #[test]
+    fn translate_off() { // {{{
+        let include_paths = [testfile_path("")];
+        let options = PreprocessOptions {
+            translate_off: Some(TranslateOff::default()),
+            ..Default::default()
+        };
+        let (ret, _) = preprocess_with(
+            testfile_path("translate_off.sv"), // path
+            &HashMap::new(),                   // pre_defines
+            &include_paths,                    // include_paths
+            false,                             // strip_comments
+            false,                             // ignore_include
+            &options,                          // options
+        ).unwrap();
+        assert_eq!(
+            ret.text(),
+            testfile_contents("expected/translate_off.sv")
+        );
+
+        let options = PreprocessOptions {
+            translate_off: Some(TranslateOff::new(&[("rtl_synthesis off", "rtl_synthesis on")])),
+            ..Default::default()
+        };
+        let (ret, _) = preprocess_str_with(
+            &testfile_contents("translate_off.sv"),
+            testfile_path("translate_off.sv"),
+            &HashMap::new(),
+            &include_paths,
+            false, // ignore_include
+            true,  // strip_comments
+            &options,
+        ).unwrap();
+        assert!(ret.text().contains("wire b"));
+        assert!(ret.text().contains("wire c"));
+        assert!(!ret.text().contains("wire d"));
+    } // }}}
+
     #[test]
     fn undef() { // {{{
         let (ret, _) = preprocess_usualargs("undef.sv").unwrap();
@@ -1753,6 +2986,19 @@ mod tests {
         );
     } // }}}
 
+    #[test]
+    fn balanced_expansion() { // {{{
+        assert!(is_balanced_expansion("(a + b)"));
+        assert!(is_balanced_expansion("begin a = f(b[0]); end"));
+        assert!(is_balanced_expansion("fork a(); join_none"));
+        assert!(is_balanced_expansion("int"));
+        assert!(!is_balanced_expansion("lhs ="));
+        assert!(!is_balanced_expansion("begin a = 0;"));
+        assert!(!is_balanced_expansion("f(a, b"));
+        assert!(!is_balanced_expansion("begin a = {b, c); end"));
+        assert!(!is_balanced_expansion(" "));
+    } // }}}
+
     #[test]
     fn undefineall() { // {{{
         let (ret, _) = preprocess_usualargs("undefineall.sv").unwrap();
@@ -1760,5 +3006,13 @@ mod tests {
             ret.text(),
             testfile_contents("expected/undefineall.sv")
         );
+
+        // Only `define directives are undefined; SV_COV_* are predefined.
+        let undefinitions = &ret.macro_index().undefinitions;
+        assert_eq!(
+            undefinitions.iter().map(|x| x.identifier.as_str()).collect::<Vec<_>>(),
+            vec!["BAR", "FOO"]
+        );
+        assert!(undefinitions.iter().all(|x| x.defined));
     } // }}}
 }
@@ -0,0 +1,3 @@
+// {̃Rg
+module M;
+endmodule
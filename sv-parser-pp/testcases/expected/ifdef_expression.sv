@@ -0,0 +1,10 @@
+`define A
+`define B
+module A;
+wire a = 1'b1;
+
+wire c = 1'b1;
+
+wire d = 1'b1;
+
+endmodule
@@ -0,0 +1,7 @@
+// `line directives change the values of `__FILE__ and `__LINE__.
+module M;
+`line 100 "orig.sv" 0
+  initial $display("orig.sv", 100);
+
+  initial $display(102);
+endmodule
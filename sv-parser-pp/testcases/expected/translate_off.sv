@@ -0,0 +1,10 @@
+module A;
+wire a;
+// synopsys translate_off
+// synopsys translate_on
+
+/* pragma translate_off *//*pragma   translate_on*/
+// rtl_synthesis off
+wire d;
+// rtl_synthesis on
+endmodule
@@ -0,0 +1,17 @@
+`define A
+`define B
+module A;
+`ifdef (A && B)
+  wire a = 1'b1;
+`endif
+`ifdef (A && !(B || C))
+  wire b = 1'b1;
+`elsif (C -> D)
+  wire c = 1'b1;
+`endif
+`ifndef ((A <-> C) || !B)
+  wire d = 1'b1;
+`else
+  wire e = 1'b1;
+`endif
+endmodule
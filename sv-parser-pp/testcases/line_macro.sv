@@ -0,0 +1,7 @@
+// `line directives change the values of `__FILE__ and `__LINE__.
+module M;
+`line 100 "orig.sv" 0
+  initial $display(`__FILE__, `__LINE__);
+
+  initial $display(`__LINE__);
+endmodule
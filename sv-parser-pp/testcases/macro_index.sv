@@ -0,0 +1,13 @@
+`define A(x, y=1) (x + y)
+`define B `A(2,)
+`define UNUSED 0
+`ifdef C
+`elsif B
+`endif
+module M;
+  initial $display(`A(a, b), `B);
+endmodule
+`define A(x, y=1) (x  +  y)
+`define B 3
+`undef B
+`include "included.svh"
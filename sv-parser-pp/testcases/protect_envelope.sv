@@ -0,0 +1,18 @@
+module secret (a, b);
+  input a;
+  output b;
+`pragma protect begin_protected
+`pragma protect encrypt_agent="Vendor", encrypt_agent_info="1.0"
+`pragma protect key_keyowner="Vendor", key_keyname="VK1", key_method="rsa"
+`pragma protect encoding=(enctype="base64", line_length=76, bytes=8)
+`pragma protect key_block
+ZW5jcnlw/*dGVk*/
+`pragma protect data_method="aes128-cbc"
+`pragma protect encoding=(enctype="base64", line_length=76, bytes=24)
+`pragma protect data_block
+//Ym9keSBv`ZiB0aGU=
+"ZGVzaWdu
+`pragma protect end_protected
+endmodule
+module open_module;
+endmodule
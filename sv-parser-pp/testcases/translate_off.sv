@@ -0,0 +1,16 @@
+module A;
+wire a;
+// synopsys translate_off
+`define FOO
+wire b = $random;
+// synopsys translate_on
+`ifdef FOO
+wire c;
+`endif
+/* pragma translate_off */
+initial $display("x");
+/*pragma   translate_on*/
+// rtl_synthesis off
+wire d;
+// rtl_synthesis on
+endmodule
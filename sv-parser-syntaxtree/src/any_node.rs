@@ -392,6 +392,54 @@ where
     }
 }
 
+impl<
+        'a,
+        T0: 'a,
+        T1: 'a,
+        T2: 'a,
+        T3: 'a,
+        T4: 'a,
+        T5: 'a,
+        T6: 'a,
+        T7: 'a,
+        T8: 'a,
+        T9: 'a,
+        T10: 'a,
+        T11: 'a,
+    > From<&'a (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11)> for RefNodes<'a>
+where
+    &'a T0: Into<RefNodes<'a>>,
+    &'a T1: Into<RefNodes<'a>>,
+    &'a T2: Into<RefNodes<'a>>,
+    &'a T3: Into<RefNodes<'a>>,
+    &'a T4: Into<RefNodes<'a>>,
+    &'a T5: Into<RefNodes<'a>>,
+    &'a T6: Into<RefNodes<'a>>,
+    &'a T7: Into<RefNodes<'a>>,
+    &'a T8: Into<RefNodes<'a>>,
+    &'a T9: Into<RefNodes<'a>>,
+    &'a T10: Into<RefNodes<'a>>,
+    &'a T11: Into<RefNodes<'a>>,
+{
+    fn from(x: &'a (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11)) -> Self {
+        let mut ret = Vec::new();
+        let (t0, t1, t2, t3, t4, t5, t6, t7, t8, t9, t10, t11) = x;
+        ret.append(&mut t0.into().0);
+        ret.append(&mut t1.into().0);
+        ret.append(&mut t2.into().0);
+        ret.append(&mut t3.into().0);
+        ret.append(&mut t4.into().0);
+        ret.append(&mut t5.into().0);
+        ret.append(&mut t6.into().0);
+        ret.append(&mut t7.into().0);
+        ret.append(&mut t8.into().0);
+        ret.append(&mut t9.into().0);
+        ret.append(&mut t10.into().0);
+        ret.append(&mut t11.into().0);
+        ret.into()
+    }
+}
+
 impl<'a, T> From<&'a Paren<T>> for RefNodes<'a>
 where
     &'a T: Into<RefNodes<'a>>,
@@ -482,3 +530,84 @@ where
         ret.into()
     }
 }
+
+// -----------------------------------------------------------------------------
+
+impl<T: NodeMut> NodeMut for Vec<T> {
+    fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+        for x in self {
+            x.locate_mut(f);
+        }
+    }
+}
+
+impl<T: NodeMut> NodeMut for Option<T> {
+    fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+        if let Some(x) = self {
+            x.locate_mut(f);
+        }
+    }
+}
+
+impl<T: NodeMut> NodeMut for Box<T> {
+    fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+        (**self).locate_mut(f);
+    }
+}
+
+macro_rules! impl_node_mut_tuple {
+    ($($t:ident: $i:tt),+) => {
+        impl<$($t: NodeMut),+> NodeMut for ($($t,)+) {
+            fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+                $(self.$i.locate_mut(f);)+
+            }
+        }
+    };
+}
+
+impl_node_mut_tuple!(T0: 0);
+impl_node_mut_tuple!(T0: 0, T1: 1);
+impl_node_mut_tuple!(T0: 0, T1: 1, T2: 2);
+impl_node_mut_tuple!(T0: 0, T1: 1, T2: 2, T3: 3);
+impl_node_mut_tuple!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4);
+impl_node_mut_tuple!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4, T5: 5);
+impl_node_mut_tuple!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4, T5: 5, T6: 6);
+impl_node_mut_tuple!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4, T5: 5, T6: 6, T7: 7);
+impl_node_mut_tuple!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4, T5: 5, T6: 6, T7: 7, T8: 8);
+impl_node_mut_tuple!(T0: 0, T1: 1, T2: 2, T3: 3, T4: 4, T5: 5, T6: 6, T7: 7, T8: 8, T9: 9);
+impl_node_mut_tuple!(
+    T0: 0, T1: 1, T2: 2, T3: 3, T4: 4, T5: 5, T6: 6, T7: 7, T8: 8, T9: 9, T10: 10
+);
+impl_node_mut_tuple!(
+    T0: 0, T1: 1, T2: 2, T3: 3, T4: 4, T5: 5, T6: 6, T7: 7, T8: 8, T9: 9, T10: 10, T11: 11
+);
+
+impl<T: NodeMut> NodeMut for Paren<T> {
+    fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+        self.nodes.locate_mut(f);
+    }
+}
+
+impl<T: NodeMut> NodeMut for Brace<T> {
+    fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+        self.nodes.locate_mut(f);
+    }
+}
+
+impl<T: NodeMut> NodeMut for Bracket<T> {
+    fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+        self.nodes.locate_mut(f);
+    }
+}
+
+impl<T: NodeMut> NodeMut for ApostropheBrace<T> {
+    fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+        self.nodes.locate_mut(f);
+    }
+}
+
+impl<T: NodeMut, U: NodeMut> NodeMut for List<T, U> {
+    fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+        self.nodes.locate_mut(f);
+    }
+}
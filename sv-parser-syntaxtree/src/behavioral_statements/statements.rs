@@ -44,6 +44,8 @@ pub enum StatementItem {
     RandsequenceStatement(Box<RandsequenceStatement>),
     RandcaseStatement(Box<RandcaseStatement>),
     ExpectPropertyStatement(Box<ExpectPropertyStatement>),
+    ContributionStatement(Box<ContributionStatement>),
+    MacroUsage(Box<MacroUsageStatement>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
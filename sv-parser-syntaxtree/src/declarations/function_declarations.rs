@@ -10,7 +10,12 @@ pub enum FunctionDataTypeOrImplicit {
 
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct FunctionDeclaration {
-    pub nodes: (Keyword, Option<Lifetime>, FunctionBodyDeclaration),
+    pub nodes: (
+        Keyword,
+        Option<DynamicOverrideSpecifiers>,
+        Option<Lifetime>,
+        FunctionBodyDeclaration,
+    ),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -58,6 +63,7 @@ pub enum InterfaceIdentifierOrClassScope {
 pub struct FunctionPrototype {
     pub nodes: (
         Keyword,
+        Option<DynamicOverrideSpecifiers>,
         DataTypeOrVoid,
         FunctionIdentifier,
         Option<Paren<Option<TfPortList>>>,
@@ -4,7 +4,12 @@ use crate::*;
 
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct TaskDeclaration {
-    pub nodes: (Keyword, Option<Lifetime>, TaskBodyDeclaration),
+    pub nodes: (
+        Keyword,
+        Option<DynamicOverrideSpecifiers>,
+        Option<Lifetime>,
+        TaskBodyDeclaration,
+    ),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -70,6 +75,7 @@ pub struct TfPortItem {
 pub enum TfPortDirection {
     PortDirection(Box<PortDirection>),
     ConstRef(Box<(Keyword, Keyword)>),
+    RefStatic(Box<(Option<Keyword>, Keyword, Keyword)>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -86,5 +92,10 @@ pub struct TfPortDeclaration {
 
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct TaskPrototype {
-    pub nodes: (Keyword, TaskIdentifier, Option<Paren<Option<TfPortList>>>),
+    pub nodes: (
+        Keyword,
+        Option<DynamicOverrideSpecifiers>,
+        TaskIdentifier,
+        Option<Paren<Option<TfPortList>>>,
+    ),
 }
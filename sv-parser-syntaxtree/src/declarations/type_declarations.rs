@@ -174,6 +174,15 @@ pub enum TypeDeclarationKeyword {
     InterfaceClass(Box<(Keyword, Keyword)>),
 }
 
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum ForwardType {
+    Enum(Box<Keyword>),
+    Struct(Box<Keyword>),
+    Union(Box<Keyword>),
+    Class(Box<Keyword>),
+    InterfaceClass(Box<(Keyword, Keyword)>),
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub enum NetTypeDeclaration {
     DataType(Box<NetTypeDeclarationDataType>),
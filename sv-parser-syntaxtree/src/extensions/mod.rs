@@ -0,0 +1,2 @@
+pub mod verilog_ams;
+pub use verilog_ams::*;
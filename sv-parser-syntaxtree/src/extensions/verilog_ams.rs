@@ -0,0 +1,133 @@
+use crate::*;
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct DisciplineDeclaration {
+    pub nodes: (
+        Keyword,
+        DisciplineIdentifier,
+        Option<Symbol>,
+        Vec<DisciplineItem>,
+        Keyword,
+    ),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum DisciplineItem {
+    NatureBinding(Box<NatureBinding>),
+    DisciplineDomainBinding(Box<DisciplineDomainBinding>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct NatureBinding {
+    pub nodes: (PotentialOrFlow, NatureIdentifier, Symbol),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum PotentialOrFlow {
+    Potential(Box<Keyword>),
+    Flow(Box<Keyword>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct DisciplineDomainBinding {
+    pub nodes: (Keyword, DiscreteOrContinuous, Symbol),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum DiscreteOrContinuous {
+    Discrete(Box<Keyword>),
+    Continuous(Box<Keyword>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct NatureDeclaration {
+    pub nodes: (
+        Keyword,
+        NatureIdentifier,
+        Option<(Symbol, ParentNature)>,
+        Option<Symbol>,
+        Vec<NatureItem>,
+        Keyword,
+    ),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum ParentNature {
+    NatureIdentifier(Box<NatureIdentifier>),
+    DisciplineIdentifier(Box<(DisciplineIdentifier, Symbol, PotentialOrFlow)>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct NatureItem {
+    pub nodes: (NatureAttribute, Symbol),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct NatureAttribute {
+    pub nodes: (NatureAttributeIdentifier, Symbol, Expression),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct BranchDeclaration {
+    pub nodes: (
+        Keyword,
+        Paren<(BranchTerminal, Option<(Symbol, BranchTerminal)>)>,
+        ListOfBranchIdentifiers,
+        Symbol,
+    ),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct ListOfBranchIdentifiers {
+    pub nodes: (List<Symbol, BranchIdentifier>,),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct BranchTerminal {
+    pub nodes: (NetIdentifier, Option<Bracket<ConstantRangeExpression>>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct AnalogConstruct {
+    pub nodes: (Keyword, Option<Keyword>, Statement),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct AnalogFunctionDeclaration {
+    pub nodes: (Keyword, FunctionDeclaration),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct ContributionStatement {
+    pub nodes: (BranchLvalue, Symbol, Expression, Symbol),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct BranchLvalue {
+    pub nodes: (
+        NatureAttributeIdentifier,
+        Paren<List<Symbol, BranchTerminal>>,
+    ),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct DisciplineIdentifier {
+    pub nodes: (Identifier,),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct NatureIdentifier {
+    pub nodes: (Identifier,),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct NatureAttributeIdentifier {
+    pub nodes: (Identifier,),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct BranchIdentifier {
+    pub nodes: (Identifier,),
+}
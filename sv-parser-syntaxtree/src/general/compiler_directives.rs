@@ -18,6 +18,7 @@ pub enum CompilerDirective {
     CelldefineDriveCompilerDirective(Box<CelldefineDriveCompilerDirective>),
     EndcelldefineDriveCompilerDirective(Box<EndcelldefineDriveCompilerDirective>),
     Pragma(Box<Pragma>),
+    ProtectedEnvelope(Box<ProtectedEnvelope>),
     LineCompilerDirective(Box<LineCompilerDirective>),
     PositionCompilerDirective(Box<PositionCompilerDirective>),
     KeywordsDirective(Box<KeywordsDirective>),
@@ -131,9 +132,9 @@ pub struct IfdefDirective {
     pub nodes: (
         Symbol,
         Keyword,
-        TextMacroIdentifier,
+        IfdefCondition,
         IfdefGroupOfLines,
-        Vec<(Symbol, Keyword, TextMacroIdentifier, ElsifGroupOfLines)>,
+        Vec<(Symbol, Keyword, IfdefCondition, ElsifGroupOfLines)>,
         Option<(Symbol, Keyword, ElseGroupOfLines)>,
         Symbol,
         Keyword,
@@ -145,15 +146,48 @@ pub struct IfndefDirective {
     pub nodes: (
         Symbol,
         Keyword,
-        TextMacroIdentifier,
+        IfdefCondition,
         IfndefGroupOfLines,
-        Vec<(Symbol, Keyword, TextMacroIdentifier, ElsifGroupOfLines)>,
+        Vec<(Symbol, Keyword, IfdefCondition, ElsifGroupOfLines)>,
         Option<(Symbol, Keyword, ElseGroupOfLines)>,
         Symbol,
         Keyword,
     ),
 }
 
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum IfdefCondition {
+    TextMacroIdentifier(Box<TextMacroIdentifier>),
+    IfdefMacroExpression(Box<Paren<IfdefMacroExpression>>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum IfdefMacroExpression {
+    TextMacroIdentifier(Box<TextMacroIdentifier>),
+    Paren(Box<Paren<IfdefMacroExpression>>),
+    Not(Box<IfdefMacroExpressionNot>),
+    Binary(Box<IfdefMacroExpressionBinary>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct IfdefMacroExpressionNot {
+    pub nodes: (Symbol, IfdefMacroExpression),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct IfdefMacroExpressionBinary {
+    pub nodes: (
+        IfdefMacroExpression,
+        BinaryLogicalOperator,
+        IfdefMacroExpression,
+    ),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct BinaryLogicalOperator {
+    pub nodes: (Symbol,),
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct IfdefGroupOfLines {
     pub nodes: (Vec<SourceDescription>,),
@@ -276,6 +310,22 @@ pub struct PragmaKeyword {
     pub nodes: (SimpleIdentifier,),
 }
 
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct ProtectedEnvelope {
+    pub nodes: (Pragma, Vec<ProtectedEnvelopeItem>, Pragma),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum ProtectedEnvelopeItem {
+    Pragma(Box<Pragma>),
+    ProtectedBlock(Box<ProtectedBlock>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct ProtectedBlock {
+    pub nodes: (Locate,),
+}
+
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct LineCompilerDirective {
     pub nodes: (Symbol, Keyword, Number, StringLiteral, Level),
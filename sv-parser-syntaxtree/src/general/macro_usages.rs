@@ -0,0 +1,36 @@
+use crate::*;
+
+// -----------------------------------------------------------------------------
+
+/// A text macro usage kept by the macro-preserving preprocessor.
+///
+/// The locate is the original usage text, and the expanded text follows this node.
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct MacroUsage {
+    pub nodes: (Symbol, Locate, Symbol),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct MacroUsageExpression {
+    pub nodes: (MacroUsage, Expression, Symbol),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct MacroUsageConstantExpression {
+    pub nodes: (MacroUsage, ConstantExpression, Symbol),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct MacroUsageStatement {
+    pub nodes: (MacroUsage, StatementOrNull, Symbol),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct MacroUsageModuleOrGenerateItem {
+    pub nodes: (MacroUsage, Vec<ModuleOrGenerateItem>, Symbol),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct MacroUsageClassItem {
+    pub nodes: (MacroUsage, Vec<ClassItem>, Symbol),
+}
@@ -2,7 +2,9 @@ pub mod attributes;
 pub mod comments;
 pub mod compiler_directives;
 pub mod identifiers;
+pub mod macro_usages;
 pub use attributes::*;
 pub use comments::*;
 pub use compiler_directives::*;
 pub use identifiers::*;
+pub use macro_usages::*;
@@ -9,6 +9,7 @@ pub mod any_node;
 pub mod behavioral_statements;
 pub mod declarations;
 pub mod expressions;
+pub mod extensions;
 pub mod general;
 pub mod instantiations;
 pub mod preprocessor;
@@ -21,6 +22,7 @@ pub use any_node::*;
 pub use behavioral_statements::*;
 pub use declarations::*;
 pub use expressions::*;
+pub use extensions::*;
 pub use general::*;
 pub use instantiations::*;
 pub use preprocessor::*;
@@ -59,6 +61,18 @@ impl<'a> Node<'a> for Locate {
     }
 }
 
+/// Mutable access to `Locate`s in a node.
+pub trait NodeMut {
+    /// Apply `f` to all `Locate`s in the node in order.
+    fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate));
+}
+
+impl NodeMut for Locate {
+    fn locate_mut(&mut self, f: &mut dyn FnMut(&mut Locate)) {
+        f(self)
+    }
+}
+
 impl<'a> IntoIterator for &'a Locate {
     type Item = RefNode<'a>;
     type IntoIter = Iter<'a>;
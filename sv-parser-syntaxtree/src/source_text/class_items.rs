@@ -12,6 +12,7 @@ pub enum ClassItem {
     LocalParameterDeclaration(Box<(LocalParameterDeclaration, Symbol)>),
     ParameterDeclaration(Box<(ParameterDeclaration, Symbol)>),
     Empty(Box<Symbol>),
+    MacroUsage(Box<MacroUsageClassItem>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -183,3 +184,19 @@ pub struct ClassConstructorDeclaration {
 pub struct New {
     pub nodes: (Keyword,),
 }
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct DynamicOverrideSpecifiers {
+    pub nodes: (Option<InitialOrExtendsSpecifier>, Option<FinalSpecifier>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub enum InitialOrExtendsSpecifier {
+    Initial(Box<(Symbol, Keyword)>),
+    Extends(Box<(Symbol, Keyword)>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct FinalSpecifier {
+    pub nodes: (Symbol, Keyword),
+}
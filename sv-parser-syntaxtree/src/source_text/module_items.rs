@@ -71,6 +71,13 @@ pub enum ModuleOrGenerateItem {
     Udp(Box<ModuleOrGenerateItemUdp>),
     Module(Box<ModuleOrGenerateItemModule>),
     ModuleItem(Box<ModuleOrGenerateItemModuleItem>),
+    Analog(Box<ModuleOrGenerateItemAnalog>),
+    MacroUsage(Box<MacroUsageModuleOrGenerateItem>),
+}
+
+#[derive(Clone, Debug, PartialEq, Node)]
+pub struct ModuleOrGenerateItemAnalog {
+    pub nodes: (Vec<AttributeInstance>, AnalogConstruct),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -105,6 +112,8 @@ pub enum ModuleOrGenerateItemDeclaration {
     ClockingDeclaration(Box<ClockingDeclaration>),
     Clocking(Box<ModuleOrGenerateItemDeclarationClocking>),
     Disable(Box<ModuleOrGenerateItemDeclarationDisable>),
+    BranchDeclaration(Box<BranchDeclaration>),
+    AnalogFunctionDeclaration(Box<AnalogFunctionDeclaration>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
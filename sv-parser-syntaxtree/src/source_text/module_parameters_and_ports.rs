@@ -40,7 +40,7 @@ pub struct ParameterPortDeclarationParamList {
 
 #[derive(Clone, Debug, PartialEq, Node)]
 pub struct ParameterPortDeclarationTypeList {
-    pub nodes: (Keyword, ListOfTypeAssignments),
+    pub nodes: (Keyword, Option<ForwardType>, ListOfTypeAssignments),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -23,6 +23,8 @@ pub enum Description {
     PackageItem(Box<DescriptionPackageItem>),
     BindDirective(Box<DescriptionBindDirective>),
     ConfigDeclaration(Box<ConfigDeclaration>),
+    DisciplineDeclaration(Box<DisciplineDeclaration>),
+    NatureDeclaration(Box<NatureDeclaration>),
 }
 
 #[derive(Clone, Debug, PartialEq, Node)]
@@ -313,6 +315,7 @@ pub struct ClassDeclaration {
     pub nodes: (
         Option<Virtual>,
         Keyword,
+        Option<FinalSpecifier>,
         Option<Lifetime>,
         ClassIdentifier,
         Option<ParameterPortList>,
@@ -0,0 +1,440 @@
+use crate::types::TypeResolver;
+use crate::utils::*;
+use crate::*;
+
+// -----------------------------------------------------------------------------
+
+/// An attribute specification in an attribute instance `(* name = value *)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute {
+    pub name: String,
+    /// The evaluated value; an attribute without a value is 1
+    pub value: Option<i64>,
+    /// The text of the value expression
+    pub expression: Option<String>,
+    pub locate: Locate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PragmaSource {
+    /// A comment like `// synopsys translate_off`
+    Comment,
+    /// A `pragma directive
+    Directive,
+}
+
+/// A region between paired pragmas like `translate_off`/`translate_on`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PragmaRegion {
+    pub source: PragmaSource,
+    /// The tool prefix of a comment such as `synopsys` or `verilator`, or `pragma`
+    pub tool: String,
+    /// The pragma name such as `translate`, `coverage` or `lint`
+    pub name: String,
+    /// Words after the switch such as the rule name of `lint_off WIDTH`
+    pub args: Vec<String>,
+    /// The pragma starting the region
+    pub begin: Locate,
+    /// The pragma ending the region; `None` if the region continues to the end of the text
+    pub end: Option<Locate>,
+}
+
+impl PragmaRegion {
+    /// Get the range of the preprocessed text covered by the region.
+    pub fn range(&self, text_len: usize) -> (usize, usize) {
+        let end = self.end.map_or(text_len, |x| x.offset + x.len);
+        (self.begin.offset, end)
+    }
+}
+
+/// Finds the attribute instances and pragma regions applying to nodes.
+pub struct Annotations {
+    owners: Vec<Owner>,
+    regions: Vec<PragmaRegion>,
+    text_len: usize,
+}
+
+/// A node with attribute instances preceding its other children
+struct Owner {
+    begin: usize,
+    end: usize,
+    /// The end of the last attribute instance
+    attributes_end: usize,
+    attributes: Vec<Attribute>,
+}
+
+impl Annotations {
+    pub fn new(tree: &SyntaxTree) -> Self {
+        let resolver = TypeResolver::new(tree);
+        let mut owners = Vec::new();
+        let mut pending: Vec<(usize, Vec<Attribute>, usize)> = Vec::new();
+        let mut ancestors: Vec<RefNode> = Vec::new();
+        let mut scope: Vec<(String, usize)> = Vec::new();
+        let mut whitespace = 0;
+        let mut pragmas = Vec::new();
+
+        for event in tree.into_iter().event() {
+            let node = match event {
+                NodeEvent::Enter(x) => x,
+                NodeEvent::Leave(x) => {
+                    if let RefNode::WhiteSpace(_) = x {
+                        whitespace -= 1;
+                    }
+                    ancestors.pop();
+                    let depth = ancestors.len();
+                    if pending.last().map(|x| x.0) == Some(depth) {
+                        let (_, attributes, attributes_end) = pending.pop().unwrap();
+                        if let Some((begin, end)) = span(vec![x]) {
+                            owners.push(Owner {
+                                begin,
+                                end,
+                                attributes_end,
+                                attributes,
+                            });
+                        }
+                    }
+                    if scope.last().map(|x| x.1) == Some(depth) {
+                        scope.pop();
+                    }
+                    continue;
+                }
+            };
+
+            match node {
+                RefNode::WhiteSpace(_) => whitespace += 1,
+                RefNode::Comment(x) => {
+                    if let Some(x) = comment_pragma(tree, &x.nodes.0) {
+                        pragmas.push(x);
+                    }
+                }
+                RefNode::Pragma(x) => {
+                    if let Some(x) = directive_pragma(tree, x) {
+                        pragmas.push(x);
+                    }
+                }
+                RefNode::AttributeInstance(x) if whitespace == 0 => {
+                    // Attributes in a header apply to the whole declaration
+                    let owner = match ancestors.last() {
+                        Some(RefNode::ModuleNonansiHeader(_))
+                        | Some(RefNode::ModuleAnsiHeader(_))
+                        | Some(RefNode::InterfaceNonansiHeader(_))
+                        | Some(RefNode::InterfaceAnsiHeader(_))
+                        | Some(RefNode::ProgramNonansiHeader(_))
+                        | Some(RefNode::ProgramAnsiHeader(_)) => ancestors.len() - 2,
+                        _ => ancestors.len() - 1,
+                    };
+                    let scope = scope.last().map_or(UNIT_SCOPE, |x| x.0.as_str());
+                    let attributes: Vec<Attribute> = x
+                        .nodes
+                        .1
+                        .contents()
+                        .into_iter()
+                        .filter_map(|x| attribute(tree, &resolver, scope, x))
+                        .collect();
+                    let end = span(x).map_or(0, |x| x.1);
+                    match pending.last_mut() {
+                        Some(x) if x.0 == owner => {
+                            x.1.extend(attributes);
+                            x.2 = end;
+                        }
+                        _ => pending.push((owner, attributes, end)),
+                    }
+                }
+                _ => (),
+            }
+
+            if let Some(name) = scope_name(tree, &node) {
+                scope.push((name, ancestors.len()));
+            }
+            ancestors.push(node);
+        }
+
+        owners.sort_by_key(|x| (x.begin, std::cmp::Reverse(x.end)));
+        Annotations {
+            owners,
+            regions: regions(pragmas),
+            text_len: tree.text.text().len(),
+        }
+    }
+
+    /// Get the attributes applying to the node.
+    ///
+    /// Attributes of enclosing constructs are inherited, and an inner attribute overrides an
+    /// outer one with the same name.
+    pub fn attributes<'a, T: Into<RefNodes<'a>>>(&self, node: T) -> Vec<&Attribute> {
+        let (begin, end) = match span(node) {
+            Some(x) => x,
+            None => return Vec::new(),
+        };
+        let mut ret: Vec<&Attribute> = Vec::new();
+        for owner in &self.owners {
+            if owner.begin > begin {
+                break;
+            }
+            if owner.attributes_end <= begin && end <= owner.end {
+                for x in &owner.attributes {
+                    ret.retain(|y| y.name != x.name);
+                    ret.push(x);
+                }
+            }
+        }
+        ret
+    }
+
+    /// Get the pragma regions covering the node.
+    pub fn pragmas<'a, T: Into<RefNodes<'a>>>(&self, node: T) -> Vec<&PragmaRegion> {
+        let (begin, end) = match span(node) {
+            Some(x) => x,
+            None => return Vec::new(),
+        };
+        self.regions
+            .iter()
+            .filter(|x| {
+                let range = x.range(self.text_len);
+                range.0 <= begin && end <= range.1
+            })
+            .collect()
+    }
+
+    /// Get all pragma regions in the order of appearance.
+    pub fn regions(&self) -> &[PragmaRegion] {
+        &self.regions
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+fn attribute(
+    tree: &SyntaxTree,
+    resolver: &TypeResolver,
+    scope: &str,
+    x: &AttrSpec,
+) -> Option<Attribute> {
+    let name = identifier(tree, &x.nodes.0)?;
+    let locate = identifier_locate(&x.nodes.0)?;
+    let (value, expression) = match &x.nodes.1 {
+        Some((_, x)) => (
+            resolver.eval(scope, x),
+            tree.get_str_trim(x).map(String::from),
+        ),
+        None => (Some(1), None),
+    };
+    Some(Attribute {
+        name,
+        value,
+        expression,
+        locate,
+    })
+}
+
+/// Tool prefixes of pragma comments
+const TOOLS: &[&str] = &[
+    "altera",
+    "ambit",
+    "cadence",
+    "coverage",
+    "lint",
+    "pragma",
+    "rtl_synthesis",
+    "spyglass",
+    "synopsys",
+    "synthesis",
+    "verilator",
+    "xilinx",
+];
+
+/// A pragma switching a region
+struct Switch {
+    source: PragmaSource,
+    tool: String,
+    name: String,
+    args: Vec<String>,
+    open: bool,
+    locate: Locate,
+}
+
+fn comment_pragma(tree: &SyntaxTree, locate: &Locate) -> Option<Switch> {
+    let s = tree.get_str(locate)?;
+    let s = match s.strip_prefix("//") {
+        Some(x) => x,
+        None => s.trim_start_matches("/*").trim_end_matches("*/"),
+    };
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let tool = *words.first()?;
+    if !TOOLS.contains(&tool) {
+        return None;
+    }
+    switch(PragmaSource::Comment, tool, &words[1..], *locate)
+}
+
+fn directive_pragma(tree: &SyntaxTree, x: &Pragma) -> Option<Switch> {
+    let locate = span(x).map(|(begin, end)| Locate {
+        offset: begin,
+        line: x.nodes.0.nodes.0.line,
+        len: end - begin,
+    })?;
+    let mut words = vec![tree.get_str_trim(&x.nodes.2)?];
+    if let Some(ref list) = x.nodes.3 {
+        for expression in list.contents() {
+            words.push(tree.get_str_trim(expression)?);
+        }
+    }
+    switch(PragmaSource::Directive, "pragma", &words, locate)
+}
+
+/// Parse words like `translate_off`, `coverage off` or `lint_on WIDTH` after the tool prefix.
+fn switch(source: PragmaSource, tool: &str, words: &[&str], locate: Locate) -> Option<Switch> {
+    for (i, word) in words.iter().enumerate() {
+        let (stem, open) = match *word {
+            "off" | "begin" => ("", true),
+            "on" | "end" => ("", false),
+            _ => match (word.strip_suffix("_off"), word.strip_suffix("_on")) {
+                (Some(x), _) => (x, true),
+                (_, Some(x)) => (x, false),
+                _ => continue,
+            },
+        };
+        let mut name: Vec<&str> = words[..i].to_vec();
+        if !stem.is_empty() {
+            name.push(stem);
+        }
+        let name = if name.is_empty() {
+            String::from(tool)
+        } else {
+            name.join(" ")
+        };
+        return Some(Switch {
+            source,
+            tool: String::from(tool),
+            name,
+            args: words[i + 1..].iter().map(|x| String::from(*x)).collect(),
+            open,
+            locate,
+        });
+    }
+    None
+}
+
+/// Pair opening and closing pragmas.
+///
+/// A closing pragma without arguments closes all open regions of the same tool and name.
+fn regions(mut pragmas: Vec<Switch>) -> Vec<PragmaRegion> {
+    pragmas.sort_by_key(|x| x.locate.offset);
+    let mut ret: Vec<PragmaRegion> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    for x in pragmas {
+        if x.open {
+            open.push(ret.len());
+            ret.push(PragmaRegion {
+                source: x.source,
+                tool: x.tool,
+                name: x.name,
+                args: x.args,
+                begin: x.locate,
+                end: None,
+            });
+        } else {
+            open.retain(|i| {
+                let region = &mut ret[*i];
+                let matched = region.tool == x.tool
+                    && region.name == x.name
+                    && (x.args.is_empty() || region.args == x.args);
+                if matched {
+                    region.end = Some(x.locate);
+                }
+                !matched
+            });
+        }
+    }
+    ret
+}
+
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_annotations() {
+        let src = r##"(* top *)
+module m #(parameter W = 4);
+  (* keep, max_fanout = W * 2, src = "a.v" *)
+  wire a;
+  // synopsys translate_off
+  wire b;
+  /* verilator lint_off WIDTH */
+  (* keep = 0 *) wire c;
+  // synopsys translate_on
+  wire d;
+  `pragma coverage off
+  wire e;
+endmodule"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let annotations = Annotations::new(&tree);
+
+        let wire = |name: &str| {
+            let node = tree
+                .into_iter()
+                .find(|x| match x {
+                    RefNode::NetDeclaration(_) => {
+                        identifier(&tree, vec![unwrap_node!(x.clone(), NetIdentifier).unwrap()])
+                            .as_deref()
+                            == Some(name)
+                    }
+                    _ => false,
+                })
+                .unwrap();
+            vec![node]
+        };
+
+        let attributes: Vec<(&str, Option<i64>)> = annotations
+            .attributes(wire("a"))
+            .iter()
+            .map(|x| (x.name.as_str(), x.value))
+            .collect();
+        assert_eq!(
+            attributes,
+            vec![
+                ("top", Some(1)),
+                ("keep", Some(1)),
+                ("max_fanout", Some(8)),
+                ("src", None)
+            ]
+        );
+        let attributes = annotations.attributes(wire("c"));
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes[1].name, "keep");
+        assert_eq!(attributes[1].value, Some(0));
+        assert_eq!(attributes[1].expression.as_deref(), Some("0"));
+        assert_eq!(annotations.attributes(wire("d")).len(), 1);
+
+        let pragmas = |name: &str| -> Vec<(String, String, Vec<String>)> {
+            annotations
+                .pragmas(wire(name))
+                .iter()
+                .map(|x| (x.tool.clone(), x.name.clone(), x.args.clone()))
+                .collect()
+        };
+        let translate = (
+            String::from("synopsys"),
+            String::from("translate"),
+            Vec::new(),
+        );
+        let lint = (
+            String::from("verilator"),
+            String::from("lint"),
+            vec![String::from("WIDTH")],
+        );
+        let coverage = (String::from("pragma"), String::from("coverage"), Vec::new());
+        assert_eq!(pragmas("a"), vec![]);
+        assert_eq!(pragmas("b"), vec![translate.clone()]);
+        assert_eq!(pragmas("c"), vec![translate, lint.clone()]);
+        assert_eq!(pragmas("d"), vec![lint.clone()]);
+        assert_eq!(pragmas("e"), vec![lint, coverage]);
+        assert_eq!(annotations.regions()[2].source, PragmaSource::Directive);
+        assert_eq!(annotations.regions()[2].end, None);
+    }
+}
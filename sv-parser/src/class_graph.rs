@@ -0,0 +1,942 @@
+use crate::utils::*;
+use crate::*;
+use std::collections::HashMap;
+
+const RECURSIVE_LIMIT: usize = 64;
+
+// -----------------------------------------------------------------------------
+
+/// A class or interface class declared in a `SyntaxTree`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassInfo {
+    pub name: String,
+    /// The scoped name of the package, design element or class which encloses the declaration
+    pub scope: String,
+    pub is_interface: bool,
+    pub is_virtual: bool,
+    pub parameters: Vec<String>,
+    pub extends: Option<ClassRef>,
+    pub implements: Vec<ClassRef>,
+    pub properties: Vec<Property>,
+    pub methods: Vec<Method>,
+    pub locate: Locate,
+}
+
+impl ClassInfo {
+    /// Get the name qualified by the enclosing scope like `pkg::cls`.
+    ///
+    /// Classes in the compilation unit scope are not qualified.
+    pub fn scoped_name(&self) -> String {
+        qualify(&self.scope, &self.name)
+    }
+}
+
+/// A reference to a class by `extends` or `implements`.
+///
+/// References through typedefs and type parameters are resolved to the class and its
+/// specialization. `text` keeps the reference as written.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassRef {
+    pub name: String,
+    /// The scope of the referenced class; `None` for the compilation unit scope
+    pub package: Option<String>,
+    /// Parameter values of the specialization as `(name, value)`; `name` is `None` for ordered ones
+    pub parameters: Vec<(Option<String>, String)>,
+    pub text: String,
+}
+
+impl ClassRef {
+    /// Get the scoped name of the referenced class like `ClassInfo::scoped_name`.
+    pub fn scoped_name(&self) -> String {
+        qualify(self.package.as_deref().unwrap_or(UNIT_SCOPE), &self.name)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Qualifiers {
+    pub is_static: bool,
+    pub is_protected: bool,
+    pub is_local: bool,
+    pub is_rand: bool,
+    pub is_randc: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub data_type: String,
+    pub is_const: bool,
+    pub qualifiers: Qualifiers,
+    pub locate: Locate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MethodKind {
+    Function,
+    Task,
+    Constructor,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Method {
+    pub name: String,
+    pub kind: MethodKind,
+    pub is_virtual: bool,
+    pub is_pure: bool,
+    pub is_extern: bool,
+    pub qualifiers: Qualifiers,
+    pub locate: Locate,
+    /// The location of the out-of-block definition of an `extern` method
+    pub definition: Option<Locate>,
+}
+
+// -----------------------------------------------------------------------------
+
+/// Inheritance graph of classes in a `SyntaxTree`.
+#[derive(Clone, Debug, Default)]
+pub struct ClassGraph {
+    classes: Vec<ClassInfo>,
+}
+
+#[derive(Default)]
+struct Scope {
+    parent: Option<String>,
+    imports: Vec<String>,
+    typedefs: HashMap<String, ClassRef>,
+}
+
+impl ClassGraph {
+    pub fn new(tree: &SyntaxTree) -> Self {
+        let mut classes = Vec::new();
+        let mut scopes: HashMap<String, Scope> = HashMap::new();
+        let mut definitions = Vec::new();
+
+        let mut stack = vec![String::from(UNIT_SCOPE)];
+        for event in tree.into_iter().event() {
+            match event {
+                NodeEvent::Enter(node) => {
+                    let parent = stack.last().cloned().unwrap();
+                    let name = scope_name(tree, &node).map(|x| qualify(&parent, &x));
+                    match node {
+                        RefNode::ClassDeclaration(x) => classes.push(class(tree, x, &parent)),
+                        RefNode::InterfaceClassDeclaration(x) => {
+                            classes.push(interface_class(tree, x, &parent))
+                        }
+                        RefNode::TypeDeclarationDataType(x) => {
+                            let (_, ref data_type, ref id, ref dimensions, _) = x.nodes;
+                            if let (Some(name), Some(x), true) = (
+                                identifier(tree, id),
+                                data_type_ref(tree, data_type),
+                                dimensions.is_empty(),
+                            ) {
+                                scopes
+                                    .entry(parent.clone())
+                                    .or_default()
+                                    .typedefs
+                                    .insert(name, x);
+                            }
+                        }
+                        RefNode::TypeAssignment(x) => {
+                            let (ref id, ref data_type) = x.nodes;
+                            if let (Some(name), Some(x)) = (
+                                identifier(tree, id),
+                                data_type.as_ref().and_then(|(_, x)| data_type_ref(tree, x)),
+                            ) {
+                                scopes
+                                    .entry(parent.clone())
+                                    .or_default()
+                                    .typedefs
+                                    .insert(name, x);
+                            }
+                        }
+                        RefNode::PackageImportItemAsterisk(x) => {
+                            if let Some(package) = identifier(tree, &x.nodes.0) {
+                                scopes
+                                    .entry(parent.clone())
+                                    .or_default()
+                                    .imports
+                                    .push(package);
+                            }
+                        }
+                        RefNode::FunctionBodyDeclarationWithoutPort(x) => {
+                            let (_, ref scope, ref id, _, _, _, _, _) = x.nodes;
+                            definition(tree, &mut definitions, &parent, scope.as_ref(), id.into());
+                        }
+                        RefNode::FunctionBodyDeclarationWithPort(x) => {
+                            let (_, ref scope, ref id, _, _, _, _, _, _) = x.nodes;
+                            definition(tree, &mut definitions, &parent, scope.as_ref(), id.into());
+                        }
+                        RefNode::TaskBodyDeclarationWithoutPort(x) => {
+                            let (ref scope, ref id, _, _, _, _, _) = x.nodes;
+                            definition(tree, &mut definitions, &parent, scope.as_ref(), id.into());
+                        }
+                        RefNode::TaskBodyDeclarationWithPort(x) => {
+                            let (ref scope, ref id, _, _, _, _, _, _) = x.nodes;
+                            definition(tree, &mut definitions, &parent, scope.as_ref(), id.into());
+                        }
+                        RefNode::ClassConstructorDeclaration(x) => {
+                            let (_, ref scope, ref new, _, _, _, _, _, _, _) = x.nodes;
+                            if let (Some(scope), Some(locate)) = (scope, unwrap_locate!(new)) {
+                                definitions.push(Definition {
+                                    scope: parent.clone(),
+                                    class: scope,
+                                    name: String::from("new"),
+                                    locate: *locate,
+                                });
+                            }
+                        }
+                        _ => (),
+                    }
+                    if let Some(name) = name {
+                        scopes.entry(name.clone()).or_default().parent = Some(parent);
+                        stack.push(name);
+                    }
+                }
+                NodeEvent::Leave(node) => {
+                    if scope_name(tree, &node).is_some() {
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        let mut ret = ClassGraph { classes };
+
+        // Resolve extends/implements through typedefs and type parameters
+        let mut resolved = Vec::new();
+        for x in &ret.classes {
+            let extends = x
+                .extends
+                .as_ref()
+                .map(|r| ret.resolve_ref(&scopes, &x.scoped_name(), r, 0));
+            let implements: Vec<ClassRef> = x
+                .implements
+                .iter()
+                .map(|r| ret.resolve_ref(&scopes, &x.scoped_name(), r, 0))
+                .collect();
+            resolved.push((extends, implements));
+        }
+        for (x, (extends, implements)) in ret.classes.iter_mut().zip(resolved) {
+            x.extends = extends;
+            x.implements = implements;
+        }
+
+        // Match out-of-block definitions to extern methods
+        for x in definitions {
+            let (ref class_type, _) = x.class.nodes;
+            let (ref package, ref id) = class_type.nodes.0.nodes;
+            let class = match identifier(tree, id) {
+                Some(x) => x,
+                None => continue,
+            };
+            let package = package.as_ref().and_then(|x| package_scope_name(tree, x));
+            let index = match ret.find(&scopes, &x.scope, package.as_deref(), &class) {
+                Some(x) => x,
+                None => continue,
+            };
+            for m in &mut ret.classes[index].methods {
+                if m.name == x.name && m.is_extern {
+                    m.definition = Some(x.locate);
+                }
+            }
+        }
+
+        ret
+    }
+
+    /// Get all classes in declaration order.
+    pub fn classes(&self) -> &[ClassInfo] {
+        &self.classes
+    }
+
+    /// Get the class of the specified scoped name like `pkg::cls`.
+    pub fn get(&self, name: &str) -> Option<&ClassInfo> {
+        self.classes.iter().find(|x| x.scoped_name() == name)
+    }
+
+    /// Get the base class of the specified class, if it is declared in the tree.
+    pub fn base(&self, name: &str) -> Option<&ClassInfo> {
+        let x = self.get(name)?.extends.as_ref()?;
+        self.get_ref(x)
+    }
+
+    /// Get the base classes from the nearest.
+    pub fn ancestors(&self, name: &str) -> Vec<&ClassInfo> {
+        let mut ret: Vec<&ClassInfo> = Vec::new();
+        let mut name = String::from(name);
+        while let Some(x) = self.base(&name) {
+            if ret.iter().any(|y| std::ptr::eq(*y, x)) || ret.len() > RECURSIVE_LIMIT {
+                break;
+            }
+            ret.push(x);
+            name = x.scoped_name();
+        }
+        ret
+    }
+
+    /// Get the classes which directly extend the specified class.
+    pub fn derived(&self, name: &str) -> Vec<&ClassInfo> {
+        self.classes
+            .iter()
+            .filter(|x| x.extends.as_ref().map(|x| x.scoped_name() == name) == Some(true))
+            .collect()
+    }
+
+    /// Get the classes which directly implement the specified interface class.
+    pub fn implementors(&self, name: &str) -> Vec<&ClassInfo> {
+        self.classes
+            .iter()
+            .filter(|x| x.implements.iter().any(|x| x.scoped_name() == name))
+            .collect()
+    }
+
+    /// Find the method visible from the specified class, searching the base classes.
+    pub fn find_method(&self, class: &str, method: &str) -> Option<(&ClassInfo, &Method)> {
+        let x = self.get(class)?;
+        std::iter::once(x)
+            .chain(self.ancestors(class))
+            .find_map(|x| x.methods.iter().find(|m| m.name == method).map(|m| (x, m)))
+    }
+
+    /// Find the method of a base class which is overridden by the method of the specified class.
+    pub fn overridden(&self, class: &str, method: &str) -> Option<(&ClassInfo, &Method)> {
+        if method == "new" {
+            return None;
+        }
+        let x = self.get(class)?;
+        x.methods.iter().find(|m| m.name == method)?;
+        self.ancestors(class)
+            .into_iter()
+            .find_map(|x| x.methods.iter().find(|m| m.name == method).map(|m| (x, m)))
+    }
+
+    /// Check whether the method is virtual.
+    ///
+    /// A method overriding a virtual method is virtual even if `virtual` is omitted.
+    pub fn is_virtual(&self, class: &str, method: &str) -> bool {
+        let mut class = String::from(class);
+        for _ in 0..RECURSIVE_LIMIT {
+            match self.find_method(&class, method) {
+                Some((_, m)) if m.is_virtual => return true,
+                Some((x, _)) => match self.base(&x.scoped_name()) {
+                    Some(x) => class = x.scoped_name(),
+                    None => return false,
+                },
+                None => return false,
+            }
+        }
+        false
+    }
+
+    // -------------------------------------------------------------------------
+
+    fn get_ref(&self, x: &ClassRef) -> Option<&ClassInfo> {
+        self.get(&x.scoped_name())
+    }
+
+    fn scope_chain(&self, scopes: &HashMap<String, Scope>, scope: &str) -> Vec<String> {
+        let mut ret = vec![String::from(scope)];
+        let mut scope = scopes.get(scope);
+        while let Some(parent) = scope.and_then(|x| x.parent.as_ref()) {
+            if ret.contains(parent) {
+                break;
+            }
+            ret.push(parent.clone());
+            scope = scopes.get(parent);
+        }
+        if !ret.iter().any(|x| x == UNIT_SCOPE) {
+            ret.push(String::from(UNIT_SCOPE));
+        }
+        let imports: Vec<String> = ret
+            .iter()
+            .filter_map(|x| scopes.get(x))
+            .flat_map(|x| x.imports.iter().cloned())
+            .collect();
+        ret.extend(imports);
+        ret
+    }
+
+    /// Find the index of class visible from `scope`.
+    fn find(
+        &self,
+        scopes: &HashMap<String, Scope>,
+        scope: &str,
+        package: Option<&str>,
+        name: &str,
+    ) -> Option<usize> {
+        let chain = match package {
+            Some(x) => vec![String::from(x)],
+            None => self.scope_chain(scopes, scope),
+        };
+        for scope in &chain {
+            if let Some(x) = self
+                .classes
+                .iter()
+                .position(|x| x.name == name && &x.scope == scope)
+            {
+                return Some(x);
+            }
+        }
+        None
+    }
+
+    fn resolve_ref(
+        &self,
+        scopes: &HashMap<String, Scope>,
+        scope: &str,
+        x: &ClassRef,
+        depth: usize,
+    ) -> ClassRef {
+        if depth > RECURSIVE_LIMIT {
+            return x.clone();
+        }
+        let chain = match &x.package {
+            Some(package) => vec![package.clone()],
+            None => self.scope_chain(scopes, scope),
+        };
+        for s in &chain {
+            if let Some(c) = self
+                .classes
+                .iter()
+                .find(|c| c.name == x.name && &c.scope == s)
+            {
+                let mut ret = x.clone();
+                ret.package = Some(c.scope.clone()).filter(|x| x != UNIT_SCOPE);
+                return ret;
+            }
+            if let Some(y) = scopes.get(s).and_then(|y| y.typedefs.get(&x.name)) {
+                let mut ret = self.resolve_ref(scopes, s, y, depth + 1);
+                ret.text = x.text.clone();
+                return ret;
+            }
+        }
+        x.clone()
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+/// An out-of-block method definition.
+struct Definition<'a> {
+    scope: String,
+    class: &'a ClassScope,
+    name: String,
+    locate: Locate,
+}
+
+fn definition<'a>(
+    tree: &SyntaxTree,
+    definitions: &mut Vec<Definition<'a>>,
+    scope: &str,
+    class: Option<&'a InterfaceIdentifierOrClassScope>,
+    id: RefNode,
+) {
+    if let Some(InterfaceIdentifierOrClassScope::ClassScope(class)) = class {
+        if let (Some(name), Some(locate)) = (
+            identifier(tree, vec![id.clone()]),
+            identifier_locate(vec![id]),
+        ) {
+            definitions.push(Definition {
+                scope: String::from(scope),
+                class,
+                name,
+                locate,
+            });
+        }
+    }
+}
+
+fn class(tree: &SyntaxTree, x: &ClassDeclaration, scope: &str) -> ClassInfo {
+    let (
+        ref virtual_,
+        _,
+        _,
+        _,
+        ref id,
+        ref params,
+        ref extends,
+        ref implements,
+        _,
+        ref items,
+        _,
+        _,
+    ) = x.nodes;
+    let name = identifier(tree, id).unwrap_or_default();
+    let mut ret = ClassInfo {
+        name,
+        scope: String::from(scope),
+        is_interface: false,
+        is_virtual: virtual_.is_some(),
+        parameters: parameters(tree, params.as_ref()),
+        extends: extends
+            .as_ref()
+            .map(|(_, x, _)| class_type_ref(tree, &x.nodes.0, x.nodes.1.as_ref(), x)),
+        implements: implements
+            .as_ref()
+            .map(|(_, x)| {
+                x.contents()
+                    .into_iter()
+                    .map(|x| class_type_ref(tree, &x.nodes.0, x.nodes.1.as_ref(), x))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        properties: Vec::new(),
+        methods: Vec::new(),
+        locate: identifier_locate(id).unwrap_or_default(),
+    };
+
+    for item in items {
+        match item {
+            ClassItem::Property(x) => match &x.nodes.1 {
+                ClassProperty::NonConst(x) => {
+                    let (ref qualifiers, ref declaration) = x.nodes;
+                    let qualifiers = property_qualifiers(qualifiers);
+                    if let DataDeclaration::Variable(x) = declaration {
+                        let (ref const_, _, _, ref data_type, ref list, _) = x.nodes;
+                        let data_type = tree.get_str_trim(data_type).unwrap_or("");
+                        for x in list.nodes.0.contents() {
+                            if let (Some(name), Some(locate)) =
+                                (identifier(tree, x), identifier_locate(x))
+                            {
+                                ret.properties.push(Property {
+                                    name,
+                                    data_type: String::from(data_type),
+                                    is_const: const_.is_some(),
+                                    qualifiers: qualifiers.clone(),
+                                    locate,
+                                });
+                            }
+                        }
+                    }
+                }
+                ClassProperty::Const(x) => {
+                    let (_, ref qualifiers, ref data_type, ref id, _, _) = x.nodes;
+                    if let (Some(name), Some(locate)) =
+                        (identifier(tree, id), identifier_locate(id))
+                    {
+                        ret.properties.push(Property {
+                            name,
+                            data_type: String::from(tree.get_str_trim(data_type).unwrap_or("")),
+                            is_const: true,
+                            qualifiers: item_qualifiers(qualifiers.iter()),
+                            locate,
+                        });
+                    }
+                }
+            },
+            ClassItem::Method(x) => {
+                if let Some(x) = method(tree, &x.nodes.1) {
+                    ret.methods.push(x);
+                }
+            }
+            _ => (),
+        }
+    }
+    ret
+}
+
+fn interface_class(tree: &SyntaxTree, x: &InterfaceClassDeclaration, scope: &str) -> ClassInfo {
+    let (_, _, ref id, ref params, ref extends, _, ref items, _, _) = x.nodes;
+    let mut ret = ClassInfo {
+        name: identifier(tree, id).unwrap_or_default(),
+        scope: String::from(scope),
+        is_interface: true,
+        is_virtual: true,
+        parameters: parameters(tree, params.as_ref()),
+        extends: None,
+        // An interface class can extend multiple interface classes
+        implements: extends
+            .as_ref()
+            .map(|(_, x)| {
+                x.contents()
+                    .into_iter()
+                    .map(|x| class_type_ref(tree, &x.nodes.0, x.nodes.1.as_ref(), x))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        properties: Vec::new(),
+        methods: Vec::new(),
+        locate: identifier_locate(id).unwrap_or_default(),
+    };
+    for item in items {
+        if let InterfaceClassItem::Method(x) = item {
+            let (_, _, ref prototype, _) = x.nodes.1.nodes;
+            if let Some(mut x) = prototype_method(tree, prototype) {
+                x.is_virtual = true;
+                x.is_pure = true;
+                ret.methods.push(x);
+            }
+        }
+    }
+    ret
+}
+
+fn method(tree: &SyntaxTree, x: &ClassMethod) -> Option<Method> {
+    match x {
+        ClassMethod::Task(x) => {
+            let (ref qualifiers, ref declaration) = x.nodes;
+            let id: RefNode = match &declaration.nodes.3 {
+                TaskBodyDeclaration::WithoutPort(x) => (&x.nodes.1).into(),
+                TaskBodyDeclaration::WithPort(x) => (&x.nodes.1).into(),
+            };
+            let mut ret = new_method(tree, id, MethodKind::Task)?;
+            method_qualifiers(&mut ret, qualifiers);
+            Some(ret)
+        }
+        ClassMethod::Function(x) => {
+            let (ref qualifiers, ref declaration) = x.nodes;
+            let id: RefNode = match &declaration.nodes.3 {
+                FunctionBodyDeclaration::WithoutPort(x) => (&x.nodes.2).into(),
+                FunctionBodyDeclaration::WithPort(x) => (&x.nodes.2).into(),
+            };
+            let mut ret = new_method(tree, id, MethodKind::Function)?;
+            method_qualifiers(&mut ret, qualifiers);
+            Some(ret)
+        }
+        ClassMethod::PureVirtual(x) => {
+            let (_, _, ref qualifiers, ref prototype, _) = x.nodes;
+            let mut ret = prototype_method(tree, prototype)?;
+            ret.is_virtual = true;
+            ret.is_pure = true;
+            ret.qualifiers = item_qualifiers(qualifiers.iter());
+            Some(ret)
+        }
+        ClassMethod::ExternMethod(x) => {
+            let (_, ref qualifiers, ref prototype, _) = x.nodes;
+            let mut ret = prototype_method(tree, prototype)?;
+            ret.is_extern = true;
+            method_qualifiers(&mut ret, qualifiers);
+            Some(ret)
+        }
+        ClassMethod::Constructor(x) => {
+            let (ref qualifiers, ref declaration) = x.nodes;
+            let mut ret = new_method(tree, (&declaration.nodes.2).into(), MethodKind::Constructor)?;
+            method_qualifiers(&mut ret, qualifiers);
+            Some(ret)
+        }
+        ClassMethod::ExternConstructor(x) => {
+            let (_, ref qualifiers, ref prototype) = x.nodes;
+            let mut ret = new_method(tree, (&prototype.nodes.1).into(), MethodKind::Constructor)?;
+            ret.is_extern = true;
+            method_qualifiers(&mut ret, qualifiers);
+            Some(ret)
+        }
+    }
+}
+
+fn prototype_method(tree: &SyntaxTree, x: &MethodPrototype) -> Option<Method> {
+    match x {
+        MethodPrototype::TaskPrototype(x) => {
+            new_method(tree, (&x.nodes.2).into(), MethodKind::Task)
+        }
+        MethodPrototype::FunctionPrototype(x) => {
+            new_method(tree, (&x.nodes.3).into(), MethodKind::Function)
+        }
+    }
+}
+
+fn new_method(tree: &SyntaxTree, id: RefNode, kind: MethodKind) -> Option<Method> {
+    let (name, locate) = if kind == MethodKind::Constructor {
+        // `new` is a keyword, not an identifier
+        let locate = *unwrap_locate!(id)?;
+        (String::from("new"), locate)
+    } else {
+        (
+            identifier(tree, vec![id.clone()])?,
+            identifier_locate(vec![id])?,
+        )
+    };
+    Some(Method {
+        name,
+        kind,
+        is_virtual: false,
+        is_pure: false,
+        is_extern: false,
+        qualifiers: Qualifiers::default(),
+        locate,
+        definition: None,
+    })
+}
+
+fn method_qualifiers(method: &mut Method, qualifiers: &[MethodQualifier]) {
+    let mut items = Vec::new();
+    for x in qualifiers {
+        match x {
+            MethodQualifier::Virtual(_) => method.is_virtual = true,
+            MethodQualifier::PureVirtual(_) => {
+                method.is_virtual = true;
+                method.is_pure = true;
+            }
+            MethodQualifier::ClassItemQualifier(x) => items.push(&**x),
+        }
+    }
+    method.qualifiers = item_qualifiers(items.into_iter());
+}
+
+fn property_qualifiers(qualifiers: &[PropertyQualifier]) -> Qualifiers {
+    let mut items = Vec::new();
+    let mut ret = Qualifiers::default();
+    for x in qualifiers {
+        match x {
+            PropertyQualifier::RandomQualifier(x) => match &**x {
+                RandomQualifier::Rand(_) => ret.is_rand = true,
+                RandomQualifier::Randc(_) => ret.is_randc = true,
+            },
+            PropertyQualifier::ClassItemQualifier(x) => items.push(&**x),
+        }
+    }
+    let items = item_qualifiers(items.into_iter());
+    Qualifiers {
+        is_rand: ret.is_rand,
+        is_randc: ret.is_randc,
+        ..items
+    }
+}
+
+fn item_qualifiers<'a, I: Iterator<Item = &'a ClassItemQualifier>>(qualifiers: I) -> Qualifiers {
+    let mut ret = Qualifiers::default();
+    for x in qualifiers {
+        match x {
+            ClassItemQualifier::Static(_) => ret.is_static = true,
+            ClassItemQualifier::Protected(_) => ret.is_protected = true,
+            ClassItemQualifier::Local(_) => ret.is_local = true,
+        }
+    }
+    ret
+}
+
+fn parameters(tree: &SyntaxTree, x: Option<&ParameterPortList>) -> Vec<String> {
+    let mut ret = Vec::new();
+    if let Some(x) = x {
+        for node in x {
+            match node {
+                RefNode::ParamAssignment(x) => ret.extend(identifier(tree, &x.nodes.0)),
+                RefNode::TypeAssignment(x) => ret.extend(identifier(tree, &x.nodes.0)),
+                _ => (),
+            }
+        }
+    }
+    ret
+}
+
+fn class_type_ref<'a, T: Into<RefNodes<'a>>>(
+    tree: &SyntaxTree,
+    id: &PsClassIdentifier,
+    params: Option<&ParameterValueAssignment>,
+    text: T,
+) -> ClassRef {
+    let (ref package, ref id) = id.nodes;
+    ClassRef {
+        name: identifier(tree, id).unwrap_or_default(),
+        package: package.as_ref().and_then(|x| package_scope_name(tree, x)),
+        parameters: params
+            .map(|x| parameter_values(tree, x))
+            .unwrap_or_default(),
+        text: String::from(tree.get_str_trim(text).unwrap_or("")),
+    }
+}
+
+/// Get a reference to class from the data type of typedef or type parameter.
+fn data_type_ref(tree: &SyntaxTree, x: &DataType) -> Option<ClassRef> {
+    match x {
+        DataType::ClassType(x) => {
+            let (ref id, ref params, ref members) = x.nodes;
+            if members.is_empty() {
+                Some(class_type_ref(tree, id, params.as_ref(), &**x))
+            } else {
+                None
+            }
+        }
+        DataType::Type(x) => {
+            let (ref scope, ref id, ref dimensions) = x.nodes;
+            if !dimensions.is_empty() {
+                return None;
+            }
+            let package = match scope {
+                Some(PackageScopeOrClassScope::PackageScope(x)) => {
+                    Some(package_scope_name(tree, x)?)
+                }
+                Some(PackageScopeOrClassScope::ClassScope(_)) => return None,
+                None => None,
+            };
+            Some(ClassRef {
+                name: identifier(tree, id)?,
+                package,
+                parameters: Vec::new(),
+                text: String::from(tree.get_str_trim(&**x).unwrap_or("")),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn parameter_values(
+    tree: &SyntaxTree,
+    x: &ParameterValueAssignment,
+) -> Vec<(Option<String>, String)> {
+    let text = |x: &ParamExpression| String::from(tree.get_str_trim(x).unwrap_or(""));
+    match &x.nodes.1.nodes.1 {
+        Some(ListOfParameterAssignments::Ordered(x)) => x
+            .nodes
+            .0
+            .contents()
+            .into_iter()
+            .map(|x| (None, text(&x.nodes.0)))
+            .collect(),
+        Some(ListOfParameterAssignments::Named(x)) => x
+            .nodes
+            .0
+            .contents()
+            .into_iter()
+            .map(|x| {
+                let (_, ref id, ref value) = x.nodes;
+                (
+                    identifier(tree, id),
+                    value.nodes.1.as_ref().map(text).unwrap_or_default(),
+                )
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_class_graph() {
+        let src = r##"package pkg;
+  virtual class base #(type T = int) extends uvm_object;
+    rand T data;
+    local static int count;
+    pure virtual function void run();
+    virtual task body(); endtask
+    extern function new(string name);
+  endclass
+  function base::new(string name);
+  endfunction
+endpackage
+
+interface class printable;
+  pure virtual function string sprint();
+endclass
+
+import pkg::*;
+typedef base#(.T(byte)) byte_base;
+
+class child extends byte_base implements printable;
+  protected randc bit [3:0] mode;
+  const int id = 1;
+  function void run(); endfunction
+  task body(); endtask
+  extern protected virtual function string sprint();
+endclass
+
+function string child::sprint();
+endfunction
+
+class wrapper #(type B = child) extends B;
+endclass"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let graph = ClassGraph::new(&tree);
+
+        assert!(graph.get("base").is_none());
+        let base = graph.get("pkg::base").unwrap();
+        assert_eq!(base.scope, "pkg");
+        assert!(base.is_virtual);
+        assert_eq!(base.parameters, vec![String::from("T")]);
+        assert_eq!(base.extends.as_ref().unwrap().name, "uvm_object");
+        assert!(graph.base("pkg::base").is_none());
+        assert!(base.properties[0].qualifiers.is_rand);
+        assert!(base.properties[1].qualifiers.is_local);
+        assert!(base.properties[1].qualifiers.is_static);
+        let run = &base.methods[0];
+        assert!(run.is_pure && run.is_virtual);
+        let new = &base.methods[2];
+        assert_eq!(new.kind, MethodKind::Constructor);
+        assert!(new.is_extern);
+        assert!(new.definition.is_some());
+
+        let child = graph.get("child").unwrap();
+        let extends = child.extends.as_ref().unwrap();
+        assert_eq!(extends.name, "base");
+        assert_eq!(extends.package.as_deref(), Some("pkg"));
+        assert_eq!(
+            extends.parameters,
+            vec![(Some(String::from("T")), String::from("byte"))]
+        );
+        assert_eq!(extends.text, "byte_base");
+        assert_eq!(child.implements[0].name, "printable");
+        assert!(child.properties[0].qualifiers.is_randc);
+        assert!(child.properties[0].qualifiers.is_protected);
+        assert!(child.properties[1].is_const);
+        let sprint = &child.methods[2];
+        assert!(sprint.is_extern && sprint.is_virtual && sprint.qualifiers.is_protected);
+        assert_eq!(tree.get_str(&sprint.definition.unwrap()), Some("sprint"));
+
+        assert!(graph.is_virtual("child", "run"));
+        assert!(graph.is_virtual("child", "body"));
+        assert_eq!(graph.overridden("child", "run").unwrap().0.name, "base");
+        assert_eq!(graph.base("wrapper").unwrap().name, "child");
+        let ancestors: Vec<&str> = graph
+            .ancestors("wrapper")
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect();
+        assert_eq!(ancestors, vec!["child", "base"]);
+        assert_eq!(
+            graph.find_method("wrapper", "run").unwrap().0.scoped_name(),
+            "child"
+        );
+        assert_eq!(graph.derived("pkg::base")[0].name, "child");
+        assert_eq!(graph.implementors("printable")[0].name, "child");
+    }
+    #[test]
+    fn test_scoped_name() {
+        let src = r##"package p1;
+  class item; endclass
+endpackage
+
+package p2;
+  class item; endclass
+  class sub extends item; endclass
+endpackage
+
+package p3;
+  import p2::*;
+  class sub extends item; endclass
+  class other extends p1::item; endclass
+endpackage
+
+class top extends item;
+  extern function void f();
+endclass
+
+function void item::f();
+endfunction"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let graph = ClassGraph::new(&tree);
+
+        assert_eq!(graph.base("p2::sub").unwrap().scoped_name(), "p2::item");
+        assert_eq!(graph.base("p3::sub").unwrap().scoped_name(), "p2::item");
+        assert_eq!(graph.base("p3::other").unwrap().scoped_name(), "p1::item");
+        let derived: Vec<String> = graph
+            .derived("p2::item")
+            .iter()
+            .map(|x| x.scoped_name())
+            .collect();
+        assert_eq!(derived, vec!["p2::sub", "p3::sub"]);
+        assert_eq!(graph.derived("p1::item")[0].scoped_name(), "p3::other");
+
+        // `item` is not visible from the compilation unit scope
+        let top = graph.get("top").unwrap();
+        assert_eq!(top.extends.as_ref().unwrap().package, None);
+        assert!(graph.base("top").is_none());
+        assert!(graph.get("p1::item").unwrap().methods.is_empty());
+        assert!(top.methods[0].definition.is_none());
+    }
+}
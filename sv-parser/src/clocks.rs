@@ -0,0 +1,398 @@
+use crate::dataflow::{AlwaysKind, Construct, ConstructKind, Dataflow};
+use crate::utils::*;
+use crate::*;
+use std::collections::HashMap;
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Edge {
+    Posedge,
+    Negedge,
+    Edge,
+    /// No edge identifier
+    Level,
+}
+
+/// A term of the event expression of a procedural block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventTerm {
+    /// The expression as written
+    pub signal: String,
+    pub edge: Edge,
+    /// The `iff` qualifier as written
+    pub iff: Option<String>,
+    pub locate: Locate,
+}
+
+/// An asynchronous reset in the sensitivity list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reset {
+    pub event: EventTerm,
+    /// Whether the reset is asserted at high, which is decided by the edge
+    pub active_high: bool,
+}
+
+/// An `always_ff` or `always` block with edge events.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClockedBlock {
+    pub module: String,
+    pub kind: AlwaysKind,
+    /// All terms of the event expression in order
+    pub events: Vec<EventTerm>,
+    pub clock: EventTerm,
+    pub resets: Vec<Reset>,
+    /// Variables assigned in the block
+    pub flops: Vec<String>,
+    pub locate: Locate,
+}
+
+/// A flop-inferring variable with its clock and resets.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Flop {
+    pub module: String,
+    pub variable: String,
+    pub clock: EventTerm,
+    pub resets: Vec<Reset>,
+    /// The location of the block assigning the variable
+    pub locate: Locate,
+}
+
+/// A flop read by a block clocked by another clock in the same module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DomainCrossing {
+    pub module: String,
+    pub signal: String,
+    /// The clock of the flop
+    pub from: String,
+    /// The clock of the block reading the flop
+    pub to: String,
+    pub locate: Locate,
+}
+
+/// Extracts clocks and asynchronous resets of procedural blocks.
+///
+/// The clock of a block with several edge events is the event whose signal isn't tested by the
+/// leading `if` statement of the block, and the others tested there are asynchronous resets.
+/// Clocks are compared by the signal names in the module, so crossings through ports need to be
+/// traced across the hierarchy by the caller.
+pub struct ClockDomains {
+    blocks: Vec<ClockedBlock>,
+    crossings: Vec<DomainCrossing>,
+}
+
+impl ClockDomains {
+    pub fn new(tree: &SyntaxTree) -> Self {
+        let dataflow = Dataflow::new(tree);
+
+        let mut blocks = Vec::new();
+        for node in tree {
+            match node {
+                RefNode::ModuleDeclaration(_)
+                | RefNode::InterfaceDeclaration(_)
+                | RefNode::ProgramDeclaration(_) => (),
+                _ => continue,
+            }
+            if let Some(name) = scope_name(tree, &node) {
+                blocks.append(&mut clocked_blocks(tree, &name, node));
+            }
+        }
+
+        let mut crossings = Vec::new();
+        for block in &mut blocks {
+            let module = match dataflow.module(&block.module) {
+                Some(x) => x,
+                None => continue,
+            };
+            let construct = Construct {
+                kind: ConstructKind::Always(block.kind),
+                locate: block.locate,
+            };
+            for x in &module.drivers {
+                if x.construct == construct && !block.flops.contains(&x.signal) {
+                    block.flops.push(x.signal.clone());
+                }
+            }
+        }
+        let clocks: HashMap<(&str, &str), &str> = blocks
+            .iter()
+            .flat_map(|x| {
+                x.flops
+                    .iter()
+                    .map(move |y| ((x.module.as_str(), y.as_str()), x.clock.signal.as_str()))
+            })
+            .collect();
+        for block in &blocks {
+            let module = match dataflow.module(&block.module) {
+                Some(x) => x,
+                None => continue,
+            };
+            let construct = Construct {
+                kind: ConstructKind::Always(block.kind),
+                locate: block.locate,
+            };
+            for x in &module.loads {
+                if x.construct != construct {
+                    continue;
+                }
+                if let Some(clock) = clocks.get(&(block.module.as_str(), x.signal.as_str())) {
+                    if *clock != block.clock.signal {
+                        crossings.push(DomainCrossing {
+                            module: block.module.clone(),
+                            signal: x.signal.clone(),
+                            from: String::from(*clock),
+                            to: block.clock.signal.clone(),
+                            locate: x.locate,
+                        });
+                    }
+                }
+            }
+        }
+
+        ClockDomains { blocks, crossings }
+    }
+
+    pub fn blocks(&self) -> &[ClockedBlock] {
+        &self.blocks
+    }
+
+    pub fn flops(&self) -> Vec<Flop> {
+        let mut ret = Vec::new();
+        for block in &self.blocks {
+            for x in &block.flops {
+                ret.push(Flop {
+                    module: block.module.clone(),
+                    variable: x.clone(),
+                    clock: block.clock.clone(),
+                    resets: block.resets.clone(),
+                    locate: block.locate,
+                });
+            }
+        }
+        ret
+    }
+
+    pub fn flop(&self, module: &str, variable: &str) -> Option<Flop> {
+        self.flops()
+            .into_iter()
+            .find(|x| x.module == module && x.variable == variable)
+    }
+
+    /// Get flops read by blocks of another clock in the same module.
+    pub fn crossings(&self) -> &[DomainCrossing] {
+        &self.crossings
+    }
+}
+
+/// Get the clocked blocks directly in the design element.
+fn clocked_blocks(tree: &SyntaxTree, module: &str, node: RefNode) -> Vec<ClockedBlock> {
+    let mut ret = Vec::new();
+    walk_items(vec![node], |node| match node {
+        RefNode::AlwaysConstruct(x) => {
+            ret.extend(clocked_block(tree, module, x));
+            false
+        }
+        _ => true,
+    });
+    ret
+}
+
+fn clocked_block(tree: &SyntaxTree, module: &str, x: &AlwaysConstruct) -> Option<ClockedBlock> {
+    let (ref keyword, ref statement) = x.nodes;
+    let kind = match keyword {
+        AlwaysKeyword::Always(_) => AlwaysKind::Always,
+        AlwaysKeyword::AlwaysFf(_) => AlwaysKind::AlwaysFf,
+        AlwaysKeyword::AlwaysComb(_) | AlwaysKeyword::AlwaysLatch(_) => return None,
+    };
+    let (control, body) = match &statement.nodes.2 {
+        StatementItem::ProceduralTimingControlStatement(x) => match &x.nodes.0 {
+            ProceduralTimingControl::EventControl(y) => (&**y, &x.nodes.1),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    // Terms with the identifiers in the expressions
+    let mut terms = Vec::new();
+    match control {
+        EventControl::EventIdentifier(x) => {
+            let term = EventTerm {
+                signal: String::from(tree.get_str_trim(&x.nodes.1)?),
+                edge: Edge::Level,
+                iff: None,
+                locate: *unwrap_locate!(&x.nodes.1)?,
+            };
+            terms.push((term, identifiers(tree, (&x.nodes.1).into())));
+        }
+        EventControl::EventExpression(x) => event_terms(tree, &x.nodes.1.nodes.1, &mut terms),
+        _ => return None,
+    }
+
+    let tested = reset_conditions(tree, body);
+    let edges: Vec<(&EventTerm, bool)> = terms
+        .iter()
+        .filter(|(x, _)| x.edge != Edge::Level)
+        .map(|(x, y)| (x, y.iter().any(|y| tested.contains(y))))
+        .collect();
+    let clock = edges
+        .iter()
+        .find(|(_, tested)| !tested)
+        .or_else(|| edges.last())?
+        .0;
+    let resets = edges
+        .iter()
+        .filter(|(x, tested)| *tested && *x != clock)
+        .map(|(x, _)| Reset {
+            event: (*x).clone(),
+            active_high: x.edge != Edge::Negedge,
+        })
+        .collect();
+
+    Some(ClockedBlock {
+        module: String::from(module),
+        kind,
+        clock: clock.clone(),
+        events: terms.iter().map(|x| x.0.clone()).collect(),
+        resets,
+        flops: Vec::new(),
+        locate: *unwrap_locate!(x)?,
+    })
+}
+
+fn event_terms(tree: &SyntaxTree, x: &EventExpression, ret: &mut Vec<(EventTerm, Vec<String>)>) {
+    match x {
+        EventExpression::Expression(x) => {
+            let (ref edge, ref expr, ref iff) = x.nodes;
+            let edge = match edge {
+                Some(EdgeIdentifier::Posedge(_)) => Edge::Posedge,
+                Some(EdgeIdentifier::Negedge(_)) => Edge::Negedge,
+                Some(EdgeIdentifier::Edge(_)) => Edge::Edge,
+                None => Edge::Level,
+            };
+            let signal = tree.get_str_trim(expr).map(String::from);
+            let locate = unwrap_locate!(expr).copied();
+            if let (Some(signal), Some(locate)) = (signal, locate) {
+                let term = EventTerm {
+                    signal,
+                    edge,
+                    iff: iff
+                        .as_ref()
+                        .and_then(|(_, x)| tree.get_str_trim(x))
+                        .map(String::from),
+                    locate,
+                };
+                ret.push((term, identifiers(tree, expr.into())));
+            }
+        }
+        EventExpression::Or(x) => {
+            event_terms(tree, &x.nodes.0, ret);
+            event_terms(tree, &x.nodes.2, ret);
+        }
+        EventExpression::Comma(x) => {
+            event_terms(tree, &x.nodes.0, ret);
+            event_terms(tree, &x.nodes.2, ret);
+        }
+        EventExpression::Paren(x) => event_terms(tree, &x.nodes.0.nodes.1, ret),
+        EventExpression::Sequence(_) => (),
+    }
+}
+
+fn identifiers(tree: &SyntaxTree, node: RefNode) -> Vec<String> {
+    node.into_iter()
+        .filter_map(|x| match x {
+            RefNode::Identifier(x) => identifier(tree, x),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Get identifiers in the conditions of the leading `if` statement except the last `else` branch.
+fn reset_conditions(tree: &SyntaxTree, x: &StatementOrNull) -> Vec<String> {
+    let mut x = match x {
+        StatementOrNull::Statement(x) => &x.nodes.2,
+        StatementOrNull::Attribute(_) => return Vec::new(),
+    };
+    // `begin` ... `end` with the `if` statement only
+    while let StatementItem::SeqBlock(y) = x {
+        match y.nodes.3.as_slice() {
+            [StatementOrNull::Statement(y)] => x = &y.nodes.2,
+            _ => return Vec::new(),
+        }
+    }
+    let mut ret = Vec::new();
+    if let StatementItem::ConditionalStatement(x) = x {
+        let (_, _, ref first, _, ref rest, _) = x.nodes;
+        for x in std::iter::once(first).chain(rest.iter().map(|x| &x.2)) {
+            ret.append(&mut identifiers(tree, (&x.nodes.1).into()));
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_clocks() {
+        let src = r##"module top(input logic clk_a, input logic clk_b, input logic rst_n, input logic rst,
+           input logic en, input logic d);
+  logic a0, a1, b0, b1, c0;
+
+  always_ff @(posedge clk_a or negedge rst_n) begin
+    if (!rst_n) begin
+      a0 <= 1'b0;
+      a1 <= 1'b0;
+    end else begin
+      a0 <= d;
+      a1 <= a0;
+    end
+  end
+
+  always @(posedge rst, posedge clk_b iff en)
+    if (rst) b0 <= 1'b0;
+    else b0 <= a1;
+
+  always_ff @(negedge clk_b) begin
+    b1 <= b0;
+  end
+
+  always @(d) c0 = d;
+endmodule"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let domains = ClockDomains::new(&tree);
+
+        let blocks = domains.blocks();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].clock.signal, "clk_a");
+        assert_eq!(blocks[0].clock.edge, Edge::Posedge);
+        assert_eq!(blocks[0].resets.len(), 1);
+        assert_eq!(blocks[0].resets[0].event.signal, "rst_n");
+        assert!(!blocks[0].resets[0].active_high);
+        assert_eq!(blocks[0].flops, vec!["a0", "a1"]);
+
+        assert_eq!(blocks[1].kind, AlwaysKind::Always);
+        assert_eq!(blocks[1].events.len(), 2);
+        assert_eq!(blocks[1].clock.signal, "clk_b");
+        assert_eq!(blocks[1].clock.iff.as_deref(), Some("en"));
+        assert_eq!(blocks[1].resets[0].event.signal, "rst");
+        assert!(blocks[1].resets[0].active_high);
+
+        assert_eq!(blocks[2].clock.edge, Edge::Negedge);
+        assert!(blocks[2].resets.is_empty());
+
+        let flop = domains.flop("top", "b1").unwrap();
+        assert_eq!(flop.clock.signal, "clk_b");
+        assert!(domains.flop("top", "c0").is_none());
+
+        let crossings: Vec<(&str, &str, &str)> = domains
+            .crossings()
+            .iter()
+            .map(|x| (x.signal.as_str(), x.from.as_str(), x.to.as_str()))
+            .collect();
+        assert_eq!(crossings, vec![("a1", "clk_a", "clk_b")]);
+    }
+}
@@ -0,0 +1,605 @@
+use crate::dataflow::{AlwaysKind, Construct, ConstructKind, Dataflow};
+use crate::types::{TypeKind, TypeResolver};
+use crate::utils::*;
+use crate::*;
+use std::collections::HashMap;
+
+// -----------------------------------------------------------------------------
+
+/// A variable assigned in a combinational block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssignedVariable {
+    pub name: String,
+    /// The conditions of a path not assigning the variable, which is `None` if every path assigns it
+    pub incomplete: Option<Vec<String>>,
+}
+
+/// An `always_comb`, `always @*` or `always` block with a sensitivity list without edges.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CombBlock {
+    pub module: String,
+    pub kind: AlwaysKind,
+    /// The signals of the explicit sensitivity list
+    pub sensitivity: Option<Vec<String>>,
+    pub variables: Vec<AssignedVariable>,
+    pub locate: Locate,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CombDiagnostic {
+    /// The variable keeps its value in some path, so a latch is inferred
+    Latch {
+        module: String,
+        variable: String,
+        path: Vec<String>,
+        locate: Locate,
+    },
+    /// The signal is read in the block but is missing in the sensitivity list
+    IncompleteSensitivity {
+        module: String,
+        signal: String,
+        locate: Locate,
+    },
+}
+
+/// Checks paths of combinational blocks.
+///
+/// `case` statements are complete if they have `default`, are `unique` or `priority`, or list
+/// all members of the enum type of the case expression. `for` loops whose condition is true at
+/// the initial values, `foreach`, `forever` and `do` ... `while` loops execute the body at least
+/// once. Variables declared in the block are not checked.
+pub struct CombAnalysis {
+    blocks: Vec<CombBlock>,
+    sensitivity: Vec<CombDiagnostic>,
+}
+
+impl CombAnalysis {
+    pub fn new(tree: &SyntaxTree) -> Self {
+        let resolver = TypeResolver::new(tree);
+        let dataflow = Dataflow::new(tree);
+
+        let mut blocks = Vec::new();
+        let mut sensitivity = Vec::new();
+        for node in tree {
+            match node {
+                RefNode::ModuleDeclaration(_)
+                | RefNode::InterfaceDeclaration(_)
+                | RefNode::ProgramDeclaration(_) => (),
+                _ => continue,
+            }
+            let module = match scope_name(tree, &node) {
+                Some(x) => x,
+                None => continue,
+            };
+            let paths = Paths {
+                tree,
+                resolver: &resolver,
+                module: &module,
+            };
+            for x in always_constructs(node) {
+                let block = match paths.block(x) {
+                    Some(x) => x,
+                    None => continue,
+                };
+                if let (Some(list), Some(flow)) = (&block.sensitivity, dataflow.module(&module)) {
+                    let construct = Construct {
+                        kind: ConstructKind::Always(block.kind),
+                        locate: block.locate,
+                    };
+                    let mut missing: Vec<&str> = Vec::new();
+                    for x in &flow.loads {
+                        let name = x.signal.as_str();
+                        if x.construct != construct
+                            || list.iter().any(|x| x == name)
+                            || missing.contains(&name)
+                            || flow.drivers_of(name).any(|x| x.construct == construct)
+                        {
+                            continue;
+                        }
+                        missing.push(name);
+                        sensitivity.push(CombDiagnostic::IncompleteSensitivity {
+                            module: module.clone(),
+                            signal: x.signal.clone(),
+                            locate: x.locate,
+                        });
+                    }
+                }
+                blocks.push(block);
+            }
+        }
+        CombAnalysis {
+            blocks,
+            sensitivity,
+        }
+    }
+
+    pub fn blocks(&self) -> &[CombBlock] {
+        &self.blocks
+    }
+
+    /// Check inferred latches and incomplete sensitivity lists.
+    pub fn check(&self) -> Vec<CombDiagnostic> {
+        let mut ret = Vec::new();
+        for block in &self.blocks {
+            for x in &block.variables {
+                if let Some(path) = &x.incomplete {
+                    ret.push(CombDiagnostic::Latch {
+                        module: block.module.clone(),
+                        variable: x.name.clone(),
+                        path: path.clone(),
+                        locate: block.locate,
+                    });
+                }
+            }
+        }
+        ret.extend(self.sensitivity.iter().cloned());
+        ret
+    }
+}
+
+/// Get `always` constructs directly in the design element.
+fn always_constructs<'a>(node: RefNode<'a>) -> Vec<&'a AlwaysConstruct> {
+    let mut ret = Vec::new();
+    walk_items(vec![node], |node| match node {
+        RefNode::AlwaysConstruct(x) => {
+            ret.push(x);
+            false
+        }
+        _ => true,
+    });
+    ret
+}
+
+/// Get the root names of the variables assigned by the node.
+fn lvalue_roots(tree: &SyntaxTree, node: RefNode) -> Vec<String> {
+    let mut ret = Vec::new();
+    for x in node {
+        let id: RefNode = match x {
+            RefNode::VariableLvalueIdentifier(x) => (&x.nodes.1).into(),
+            RefNode::NonrangeVariableLvalue(x) => (&x.nodes.1).into(),
+            RefNode::BlockingAssignmentHierarchicalVariable(x) => (&x.nodes.1).into(),
+            _ => continue,
+        };
+        if let Some(x) = identifier(tree, vec![id]) {
+            if !ret.contains(&x) {
+                ret.push(x);
+            }
+        }
+    }
+    ret
+}
+
+/// Whether the node has an assignment statement to the variable.
+fn assigns(tree: &SyntaxTree, node: RefNode, name: &str) -> bool {
+    node.into_iter().any(|x| match x {
+        RefNode::BlockingAssignment(_) | RefNode::NonblockingAssignment(_) => {
+            lvalue_roots(tree, x).iter().any(|x| x == name)
+        }
+        _ => false,
+    })
+}
+
+/// A `case` statement normalized over `case`, `case` ... `inside` and `case` ... `matches`.
+struct Case<'b> {
+    /// The conditions and the negated conditions of items
+    items: Vec<(String, String, &'b StatementOrNull)>,
+    default: Option<&'b StatementOrNull>,
+    complete: bool,
+}
+
+struct Paths<'a, 'b> {
+    tree: &'a SyntaxTree,
+    resolver: &'b TypeResolver<'a>,
+    module: &'b str,
+}
+
+impl<'a, 'b> Paths<'a, 'b> {
+    fn text<'c, T: Into<RefNodes<'c>>>(&self, x: T) -> String {
+        self.tree
+            .get_str_trim(x)
+            .map(String::from)
+            .unwrap_or_default()
+    }
+
+    fn block(&self, x: &AlwaysConstruct) -> Option<CombBlock> {
+        let tree = self.tree;
+        let (ref keyword, ref statement) = x.nodes;
+        let (kind, sensitivity) = match keyword {
+            AlwaysKeyword::AlwaysComb(_) => (AlwaysKind::AlwaysComb, None),
+            AlwaysKeyword::Always(_) => {
+                let x = match &statement.nodes.2 {
+                    StatementItem::ProceduralTimingControlStatement(x) => x,
+                    _ => return None,
+                };
+                let control = match &x.nodes.0 {
+                    ProceduralTimingControl::EventControl(x) => &**x,
+                    _ => return None,
+                };
+                match control {
+                    EventControl::Asterisk(_) | EventControl::ParenAsterisk(_) => {
+                        (AlwaysKind::Always, None)
+                    }
+                    EventControl::EventExpression(x) => {
+                        let mut list = Vec::new();
+                        for node in &x.nodes.1.nodes.1 {
+                            match node {
+                                RefNode::EdgeIdentifier(_) => return None,
+                                RefNode::Identifier(x) => list.extend(identifier(tree, x)),
+                                _ => (),
+                            }
+                        }
+                        (AlwaysKind::Always, Some(list))
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        // Variables declared in the block
+        let mut locals = Vec::new();
+        for node in statement {
+            if let RefNode::BlockItemDeclaration(x) = node {
+                for x in x {
+                    if let RefNode::VariableDeclAssignment(x) = x {
+                        locals.extend(identifier(tree, x));
+                    }
+                }
+            }
+        }
+        let mut names: Vec<String> = Vec::new();
+        for node in statement {
+            match node {
+                RefNode::BlockingAssignment(_) | RefNode::NonblockingAssignment(_) => (),
+                _ => continue,
+            }
+            for x in lvalue_roots(tree, node) {
+                if !names.contains(&x) && !locals.contains(&x) {
+                    names.push(x);
+                }
+            }
+        }
+        let variables = names
+            .into_iter()
+            .map(|name| {
+                let incomplete = self.statement(statement, &name);
+                AssignedVariable { name, incomplete }
+            })
+            .collect();
+
+        Some(CombBlock {
+            module: String::from(self.module),
+            kind,
+            sensitivity,
+            variables,
+            locate: *unwrap_locate!(x)?,
+        })
+    }
+
+    /// Get a path not assigning the variable.
+    fn statement_or_null(&self, x: &StatementOrNull, name: &str) -> Option<Vec<String>> {
+        match x {
+            StatementOrNull::Statement(x) => self.statement(x, name),
+            StatementOrNull::Attribute(_) => Some(Vec::new()),
+        }
+    }
+
+    fn statement(&self, x: &Statement, name: &str) -> Option<Vec<String>> {
+        let tree = self.tree;
+        // Conditions of statements not assigning the variable don't matter
+        if !assigns(tree, x.into(), name) {
+            return Some(Vec::new());
+        }
+        match &x.nodes.2 {
+            StatementItem::BlockingAssignment(_) | StatementItem::NonblockingAssignment(_) => None,
+            StatementItem::SeqBlock(x) => {
+                let mut ret = Vec::new();
+                for x in &x.nodes.3 {
+                    ret.append(&mut self.statement_or_null(x, name)?);
+                }
+                Some(ret)
+            }
+            StatementItem::ProceduralTimingControlStatement(x) => {
+                self.statement_or_null(&x.nodes.1, name)
+            }
+            StatementItem::ConditionalStatement(x) => {
+                let (_, _, ref predicate, ref statement, ref else_ifs, ref otherwise) = x.nodes;
+                let mut guard = Vec::new();
+                let branches = std::iter::once((predicate, statement))
+                    .chain(else_ifs.iter().map(|x| (&x.2, &x.3)));
+                for (predicate, statement) in branches {
+                    let predicate = self.text(&predicate.nodes.1);
+                    if let Some(mut path) = self.statement_or_null(statement, name) {
+                        guard.push(predicate);
+                        guard.append(&mut path);
+                        return Some(guard);
+                    }
+                    guard.push(format!("!({})", predicate));
+                }
+                if let Some((_, statement)) = otherwise {
+                    let mut path = self.statement_or_null(statement, name)?;
+                    guard.append(&mut path);
+                }
+                Some(guard)
+            }
+            StatementItem::CaseStatement(x) => {
+                let case = self.case(x);
+                for (condition, _, statement) in &case.items {
+                    if let Some(mut path) = self.statement_or_null(statement, name) {
+                        let mut ret = vec![condition.clone()];
+                        ret.append(&mut path);
+                        return Some(ret);
+                    }
+                }
+                let others: Vec<&str> = case.items.iter().map(|x| x.1.as_str()).collect();
+                match case.default {
+                    Some(statement) => {
+                        let mut path = self.statement_or_null(statement, name)?;
+                        let mut ret = vec![others.join(" && ")];
+                        ret.append(&mut path);
+                        Some(ret)
+                    }
+                    None if case.complete => None,
+                    None => Some(vec![others.join(" && ")]),
+                }
+            }
+            StatementItem::LoopStatement(x) => match &**x {
+                LoopStatement::Forever(x) => self.statement_or_null(&x.nodes.1, name),
+                LoopStatement::DoWhile(x) => self.statement_or_null(&x.nodes.1, name),
+                LoopStatement::Foreach(x) => self.statement(&x.nodes.2, name),
+                LoopStatement::Repeat(x) => {
+                    let count = &x.nodes.1.nodes.1;
+                    if self.resolver.eval(self.module, count).unwrap_or(0) > 0 {
+                        self.statement_or_null(&x.nodes.2, name)
+                    } else {
+                        Some(vec![format!("{} <= 0", self.text(count))])
+                    }
+                }
+                LoopStatement::While(x) => {
+                    Some(vec![format!("!({})", self.text(&x.nodes.1.nodes.1))])
+                }
+                LoopStatement::For(x) => {
+                    let (ref init, _, ref condition, _, _) = x.nodes.1.nodes.1;
+                    let condition = match condition {
+                        Some(x) => x,
+                        None => return self.statement_or_null(&x.nodes.2, name),
+                    };
+                    if self.entered(init.as_ref(), condition) {
+                        self.statement_or_null(&x.nodes.2, name)
+                    } else {
+                        Some(vec![format!("!({})", self.text(condition))])
+                    }
+                }
+            },
+            _ => Some(Vec::new()),
+        }
+    }
+
+    /// Whether the condition of a `for` loop is true at the initial values.
+    fn entered(&self, init: Option<&ForInitialization>, condition: &Expression) -> bool {
+        let mut locals = HashMap::new();
+        match init {
+            Some(ForInitialization::Declaration(x)) => {
+                for x in x.nodes.0.contents() {
+                    for (id, _, expr) in x.nodes.2.contents() {
+                        let value = self.resolver.eval_with(self.module, expr, &locals);
+                        if let (Some(id), Some(value)) = (identifier(self.tree, id), value) {
+                            locals.insert(id, value);
+                        }
+                    }
+                }
+            }
+            Some(ForInitialization::ListOfVariableAssignments(x)) => {
+                for x in x.nodes.0.contents() {
+                    let (ref lvalue, _, ref expr) = x.nodes;
+                    let value = self.resolver.eval_with(self.module, expr, &locals);
+                    let id = lvalue_roots(self.tree, lvalue.into()).pop();
+                    if let (Some(id), Some(value)) = (id, value) {
+                        locals.insert(id, value);
+                    }
+                }
+            }
+            None => (),
+        }
+        matches!(
+            self.resolver.eval_with(self.module, condition, &locals),
+            Some(x) if x != 0
+        )
+    }
+
+    fn case<'c>(&self, x: &'c CaseStatement) -> Case<'c> {
+        let mut items = Vec::new();
+        let mut default = None;
+        let unique = match x {
+            CaseStatement::Normal(x) => {
+                let (ref unique, _, ref expr, ref first, ref rest, _) = x.nodes;
+                let expr = self.text(&expr.nodes.1);
+                let mut labels = Vec::new();
+                for x in std::iter::once(first).chain(rest) {
+                    match x {
+                        CaseItem::NonDefault(x) => {
+                            let x_labels: Vec<String> =
+                                x.nodes.0.contents().iter().map(|x| self.text(*x)).collect();
+                            let (condition, negation) = match x_labels.as_slice() {
+                                [label] => (
+                                    format!("{} == {}", expr, label),
+                                    format!("{} != {}", expr, label),
+                                ),
+                                _ => {
+                                    let condition = x_labels
+                                        .iter()
+                                        .map(|x| format!("{} == {}", expr, x))
+                                        .collect::<Vec<_>>()
+                                        .join(" || ");
+                                    (format!("({})", condition), format!("!({})", condition))
+                                }
+                            };
+                            labels.extend(x_labels);
+                            items.push((condition, negation, &x.nodes.2));
+                        }
+                        CaseItem::Default(x) => default = Some(&x.nodes.2),
+                    }
+                }
+                let complete = self.enum_complete(&expr, &labels);
+                return Case {
+                    items,
+                    default,
+                    complete: complete || is_full(unique),
+                };
+            }
+            CaseStatement::Inside(x) => {
+                let (ref unique, _, ref expr, _, ref first, ref rest, _) = x.nodes;
+                let expr = self.text(&expr.nodes.1);
+                for x in std::iter::once(first).chain(rest) {
+                    match x {
+                        CaseInsideItem::NonDefault(x) => {
+                            let condition =
+                                format!("{} inside {{{}}}", expr, self.text(&x.nodes.0));
+                            items.push((
+                                condition.clone(),
+                                format!("!({})", condition),
+                                &x.nodes.2,
+                            ));
+                        }
+                        CaseInsideItem::Default(x) => default = Some(&x.nodes.2),
+                    }
+                }
+                unique
+            }
+            CaseStatement::Matches(x) => {
+                let (ref unique, _, ref expr, _, ref first, ref rest, _) = x.nodes;
+                let expr = self.text(&expr.nodes.1);
+                for x in std::iter::once(first).chain(rest) {
+                    match x {
+                        CasePatternItem::NonDefault(x) => {
+                            let condition = format!("{} matches {}", expr, self.text(&x.nodes.0));
+                            items.push((
+                                condition.clone(),
+                                format!("!({})", condition),
+                                &x.nodes.3,
+                            ));
+                        }
+                        CasePatternItem::Default(x) => default = Some(&x.nodes.2),
+                    }
+                }
+                unique
+            }
+        };
+        Case {
+            items,
+            default,
+            complete: is_full(unique),
+        }
+    }
+
+    /// Whether the labels are all members of the enum type of the case expression.
+    fn enum_complete(&self, expr: &str, labels: &[String]) -> bool {
+        match self
+            .resolver
+            .variable_type(self.module, expr)
+            .map(|x| x.kind)
+        {
+            Some(TypeKind::Enum { members, .. }) => {
+                members.iter().all(|x| labels.contains(&x.name))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// `unique` and `priority` declare that one of the items always matches.
+fn is_full(x: &Option<UniquePriority>) -> bool {
+    matches!(
+        x,
+        Some(UniquePriority::Unique(_)) | Some(UniquePriority::Priority(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_comb() {
+        let src = r##"module top(input logic en, input logic [1:0] sel, input logic [3:0] a, input logic [3:0] b,
+           output logic [3:0] y0, output logic [3:0] y1, output logic [3:0] y2,
+           output logic [3:0] y3, output logic [3:0] y4, output logic [3:0] y5,
+           output logic [3:0] y6);
+  typedef enum logic [1:0] {S0, S1, S2} state_t;
+  state_t state;
+
+  always_comb begin
+    logic [3:0] t;
+    if (en) t = a;
+    y0 = '0;
+    if (en) y0 = a;
+    if (en) y1 = a;
+    else if (sel == 2'd0) y1 = b;
+  end
+
+  always_comb begin
+    case (sel)
+      2'd0: y2 = a;
+      2'd1, 2'd2: y2 = b;
+    endcase
+    unique case (sel)
+      2'd0: y3 = a;
+      2'd1: y3 = b;
+    endcase
+    case (state)
+      S0: y4 = a;
+      S1: y4 = b;
+      S2: y4 = '0;
+    endcase
+  end
+
+  always @* begin
+    for (int i = 0; i < 4; i++) y5[i] = a[i];
+    while (en) y6 = a;
+  end
+
+  always @(a or en) begin
+    y6 = en ? a : b;
+  end
+endmodule"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let analysis = CombAnalysis::new(&tree);
+        assert_eq!(analysis.blocks().len(), 4);
+        assert_eq!(
+            analysis.blocks()[3].sensitivity,
+            Some(vec![String::from("a"), String::from("en")])
+        );
+
+        let names: Vec<&str> = analysis.blocks()[0]
+            .variables
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["y0", "y1"]);
+
+        let check = analysis.check();
+        let diagnostics: Vec<(&str, Vec<&str>)> = check
+            .iter()
+            .map(|x| match x {
+                CombDiagnostic::Latch { variable, path, .. } => {
+                    (variable.as_str(), path.iter().map(|x| x.as_str()).collect())
+                }
+                CombDiagnostic::IncompleteSensitivity { signal, .. } => {
+                    (signal.as_str(), vec!["sensitivity"])
+                }
+            })
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                ("y1", vec!["!(en)", "!(sel == 2'd0)"]),
+                ("y2", vec!["sel != 2'd0 && !(sel == 2'd1 || sel == 2'd2)"]),
+                ("y6", vec!["!(en)"]),
+                ("b", vec!["sensitivity"]),
+            ]
+        );
+    }
+}
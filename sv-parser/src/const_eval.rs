@@ -0,0 +1,859 @@
+use crate::utils::*;
+use crate::*;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::hash::BuildHasher;
+
+// -----------------------------------------------------------------------------
+
+/// Provides the values of named constants to `eval_const`.
+pub trait EvalContext {
+    /// Get the value of a parameter, localparam, genvar or enum member.
+    ///
+    /// `package` is the package or class name if the identifier is scoped (`pkg::NAME`).
+    fn value(&self, package: Option<&str>, name: &str) -> Option<i64>;
+
+    /// Get the bit width of a named type or variable for `$bits(name)`.
+    fn bits(&self, _package: Option<&str>, _name: &str) -> Option<u64> {
+        None
+    }
+
+    /// Get the bit width of a data type for `$bits(data_type)` and casts.
+    fn data_type_bits(&self, _tree: &SyntaxTree, _data_type: &DataType) -> Option<u64> {
+        None
+    }
+}
+
+impl<S: BuildHasher> EvalContext for HashMap<String, i64, S> {
+    fn value(&self, package: Option<&str>, name: &str) -> Option<i64> {
+        if let Some(package) = package {
+            self.get(&format!("{}::{}", package, name)).copied()
+        } else {
+            self.get(name).copied()
+        }
+    }
+}
+
+/// Evaluate a constant expression.
+///
+/// The specified node is typically `ConstantExpression`, `Expression`, `ConstantParamExpression`
+/// or one of the mintypmax variants. `None` is returned if the expression can't be evaluated,
+/// for example because it contains `x`/`z` digits, real numbers or unknown identifiers.
+pub fn eval_const<'a, T: Into<RefNode<'a>>, C: EvalContext + ?Sized>(
+    tree: &SyntaxTree,
+    node: T,
+    context: &C,
+) -> Option<i64> {
+    Evaluator { tree, context }
+        .eval(node.into())
+        .map(|x| x.value)
+}
+
+/// Get the width of a sized literal or the result of a constant expression if it is known.
+pub fn eval_const_width<'a, T: Into<RefNode<'a>>, C: EvalContext + ?Sized>(
+    tree: &SyntaxTree,
+    node: T,
+    context: &C,
+) -> Option<u64> {
+    Evaluator { tree, context }
+        .eval(node.into())
+        .and_then(|x| x.width)
+}
+
+/// Parse the text of an integral number literal such as `8'hff`, `'d10` or `42`.
+///
+/// The returned tuple is the value and the size if the literal is sized.
+pub fn parse_number(s: &str) -> Option<(i64, Option<u64>)> {
+    let s: String = s
+        .chars()
+        .filter(|x| !x.is_whitespace() && *x != '_')
+        .collect();
+    if let Some(pos) = s.find('\'') {
+        let size = if pos == 0 {
+            None
+        } else {
+            Some(s[..pos].parse::<u64>().ok()?)
+        };
+        let mut rest = s[pos + 1..].chars();
+        let mut base = rest.next()?;
+        if base == 's' || base == 'S' {
+            base = rest.next()?;
+        }
+        let radix = match base.to_ascii_lowercase() {
+            'b' => 2,
+            'o' => 8,
+            'd' => 10,
+            'h' => 16,
+            _ => return None,
+        };
+        let digits: String = rest.collect();
+        let value = u64::from_str_radix(&digits, radix).ok()? as i64;
+        let value = match size {
+            Some(size) if size < 64 => value & ((1i64 << size) - 1),
+            _ => value,
+        };
+        Some((value, size))
+    } else {
+        Some((s.parse::<u64>().ok()? as i64, None))
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug)]
+struct Value {
+    value: i64,
+    width: Option<u64>,
+}
+
+impl Value {
+    fn new(value: i64, width: Option<u64>) -> Self {
+        Value { value, width }
+    }
+
+    fn bool(x: bool) -> Self {
+        Value::new(x as i64, Some(1))
+    }
+
+    fn masked(self) -> Self {
+        match self.width {
+            Some(w) if w < 64 => Value::new(self.value & ((1i64 << w) - 1), self.width),
+            _ => self,
+        }
+    }
+}
+
+struct Evaluator<'b, C: ?Sized> {
+    tree: &'b SyntaxTree,
+    context: &'b C,
+}
+
+impl<'b, C: EvalContext + ?Sized> Evaluator<'b, C> {
+    fn eval(&self, node: RefNode) -> Option<Value> {
+        match node {
+            RefNode::ConstantParamExpression(
+                ConstantParamExpression::ConstantMintypmaxExpression(x),
+            ) => self.eval((&**x).into()),
+            RefNode::ParamExpression(ParamExpression::MintypmaxExpression(x)) => {
+                self.eval((&**x).into())
+            }
+            RefNode::ConstantMintypmaxExpression(x) => match x {
+                ConstantMintypmaxExpression::Unary(x) => self.eval((&**x).into()),
+                ConstantMintypmaxExpression::Ternary(x) => self.eval((&x.nodes.2).into()),
+            },
+            RefNode::MintypmaxExpression(x) => match x {
+                MintypmaxExpression::Expression(x) => self.eval((&**x).into()),
+                MintypmaxExpression::Ternary(x) => self.eval((&x.nodes.2).into()),
+            },
+            RefNode::GenvarExpression(x) => self.eval((&x.nodes.0).into()),
+            RefNode::ConstantExpression(ConstantExpression::ConstantPrimary(x)) => {
+                self.eval((&**x).into())
+            }
+            RefNode::ConstantExpression(ConstantExpression::Unary(x)) => {
+                let (ref op, _, ref x) = x.nodes;
+                self.unary(op.nodes.0.nodes.0, x.into())
+            }
+            RefNode::Expression(Expression::Primary(x)) => self.eval((&**x).into()),
+            RefNode::Expression(Expression::Unary(x)) => {
+                let (ref op, _, ref x) = x.nodes;
+                self.unary(op.nodes.0.nodes.0, x.into())
+            }
+            RefNode::ConstantExpression(_) | RefNode::Expression(_) => self.chain(node),
+            RefNode::ConstantPrimary(x) => self.constant_primary(x),
+            RefNode::Primary(x) => self.primary(x),
+            _ => None,
+        }
+    }
+
+    fn constant_primary(&self, x: &ConstantPrimary) -> Option<Value> {
+        match x {
+            ConstantPrimary::PrimaryLiteral(x) => self.literal(x),
+            ConstantPrimary::PsParameter(x) => {
+                let (ref id, ref select) = x.nodes;
+                let value = match id {
+                    PsParameterIdentifier::Scope(x) => {
+                        let (ref scope, ref id) = x.nodes;
+                        let package = scope.as_ref().and_then(|x| self.scope_name(x));
+                        let name = identifier(self.tree, id)?;
+                        self.context.value(package.as_deref(), &name)?
+                    }
+                    PsParameterIdentifier::Generate(_) => return None,
+                };
+                self.constant_select(Value::new(value, None), select)
+            }
+            ConstantPrimary::Specparam(x) => self.named(None, (&x.nodes.0).into()),
+            ConstantPrimary::GenvarIdentifier(x) => self.named(None, (&**x).into()),
+            ConstantPrimary::FormalPort(x) => {
+                let value = self.named(None, (&x.nodes.0).into())?;
+                self.constant_select(value, &x.nodes.1)
+            }
+            ConstantPrimary::Enum(x) => {
+                let (ref scope, ref id) = x.nodes;
+                let package = self.scope_name(scope);
+                let name = identifier(self.tree, id)?;
+                let value = self.context.value(package.as_deref(), &name)?;
+                Some(Value::new(value, None))
+            }
+            ConstantPrimary::Concatenation(x) => {
+                let (ref concat, ref select) = x.nodes;
+                if select.is_some() {
+                    return None;
+                }
+                let (ref list,) = concat.nodes;
+                self.concat(list.nodes.1.contents().into_iter().map(|x| x.into()))
+            }
+            ConstantPrimary::MultipleConcatenation(x) => {
+                let (ref concat, ref select) = x.nodes;
+                if select.is_some() {
+                    return None;
+                }
+                let (_, ref inner, _) = concat.nodes.0.nodes;
+                let (ref count, ref concat) = inner;
+                let count = self.eval(count.into())?;
+                let (ref list,) = concat.nodes;
+                let item = self.concat(list.nodes.1.contents().into_iter().map(|x| x.into()))?;
+                self.replicate(count, item)
+            }
+            ConstantPrimary::ConstantFunctionCall(x) => self.function_call(&x.nodes.0),
+            ConstantPrimary::MintypmaxExpression(x) => self.eval((&x.nodes.0.nodes.1).into()),
+            ConstantPrimary::ConstantCast(x) => {
+                let (ref casting_type, _, ref expr) = x.nodes;
+                let value = self.eval((&expr.nodes.1).into())?;
+                self.cast(casting_type, value)
+            }
+            _ => None,
+        }
+    }
+
+    fn primary(&self, x: &Primary) -> Option<Value> {
+        match x {
+            Primary::PrimaryLiteral(x) => self.literal(x),
+            Primary::Hierarchical(x) => {
+                let (ref scope, ref id, ref select) = x.nodes;
+                let package = self.qualifier_name(scope.as_ref())?;
+                let (_, ref path, ref id) = id.nodes;
+                if !path.is_empty() {
+                    return None;
+                }
+                let name = identifier(self.tree, id)?;
+                let value = self.context.value(package.as_deref(), &name)?;
+                self.select(Value::new(value, None), select)
+            }
+            Primary::Concatenation(x) => {
+                let (ref concat, ref select) = x.nodes;
+                if select.is_some() {
+                    return None;
+                }
+                let (ref list,) = concat.nodes;
+                self.concat(list.nodes.1.contents().into_iter().map(|x| x.into()))
+            }
+            Primary::MultipleConcatenation(x) => {
+                let (ref concat, ref select) = x.nodes;
+                if select.is_some() {
+                    return None;
+                }
+                let (_, ref inner, _) = concat.nodes.0.nodes;
+                let (ref count, ref concat) = inner;
+                let count = self.eval(count.into())?;
+                let (ref list,) = concat.nodes;
+                let item = self.concat(list.nodes.1.contents().into_iter().map(|x| x.into()))?;
+                self.replicate(count, item)
+            }
+            Primary::FunctionSubroutineCall(x) => self.function_call(x),
+            Primary::MintypmaxExpression(x) => self.eval((&x.nodes.0.nodes.1).into()),
+            Primary::Cast(x) => {
+                let (ref casting_type, _, ref expr) = x.nodes;
+                let value = self.eval((&expr.nodes.1).into())?;
+                self.cast(casting_type, value)
+            }
+            _ => None,
+        }
+    }
+
+    fn literal(&self, x: &PrimaryLiteral) -> Option<Value> {
+        match x {
+            PrimaryLiteral::Number(x) => match &**x {
+                Number::IntegralNumber(x) => {
+                    let (value, width) = parse_number(self.tree.get_str_trim(&**x)?)?;
+                    Some(Value::new(value, width))
+                }
+                Number::RealNumber(_) => None,
+            },
+            PrimaryLiteral::UnbasedUnsizedLiteral(x) => match self.tree.get_str_trim(&**x)? {
+                "'0" => Some(Value::new(0, None)),
+                "'1" => Some(Value::new(-1, None)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn named(&self, package: Option<&str>, node: RefNode) -> Option<Value> {
+        let name = identifier(self.tree, vec![node])?;
+        self.context
+            .value(package, &name)
+            .map(|x| Value::new(x, None))
+    }
+
+    fn scope_name(&self, x: &PackageScopeOrClassScope) -> Option<String> {
+        match x {
+            PackageScopeOrClassScope::PackageScope(x) => package_scope_name(self.tree, x),
+            PackageScopeOrClassScope::ClassScope(x) => {
+                let (ref class_type, _) = x.nodes;
+                self.class_name(class_type)
+            }
+        }
+    }
+
+    /// Get the class name qualified by the package scope if any.
+    fn class_name(&self, x: &ClassType) -> Option<String> {
+        let (ref package, ref id) = x.nodes.0.nodes;
+        let name = identifier(self.tree, id)?;
+        match package
+            .as_ref()
+            .and_then(|x| package_scope_name(self.tree, x))
+        {
+            Some(package) => Some(qualify(&package, &name)),
+            None => Some(name),
+        }
+    }
+
+    /// Get the package or class name of the qualifier of hierarchical primary.
+    ///
+    /// `Some(None)` is returned if there is no qualifier, and `None` if the qualifier is not
+    /// a package or class scope.
+    fn qualifier_name(&self, x: Option<&ClassQualifierOrPackageScope>) -> Option<Option<String>> {
+        match x {
+            Some(ClassQualifierOrPackageScope::PackageScope(x)) => {
+                Some(Some(package_scope_name(self.tree, x)?))
+            }
+            Some(ClassQualifierOrPackageScope::ClassQualifier(x)) => match &x.nodes {
+                (None, None) => Some(None),
+                (None, Some(ImplicitClassHandleOrClassScope::ClassScope(x))) => {
+                    let (ref class_type, _) = x.nodes;
+                    Some(Some(self.class_name(class_type)?))
+                }
+                _ => None,
+            },
+            None => Some(None),
+        }
+    }
+
+    fn function_call(&self, x: &FunctionSubroutineCall) -> Option<Value> {
+        match &x.nodes.0 {
+            SubroutineCall::TfCall(x) => {
+                // A bare identifier in a constant expression is parsed as a function call
+                // without arguments.
+                let (ref id, _, ref args) = x.nodes;
+                if args.is_some() {
+                    return None;
+                }
+                match id {
+                    PsOrHierarchicalTfIdentifier::PackageScope(x) => {
+                        let (ref scope, ref id) = x.nodes;
+                        let package = match scope {
+                            Some(ImplicitClassHandleOrClassScopeOrPackageScope::PackageScope(
+                                x,
+                            )) => Some(package_scope_name(self.tree, x)?),
+                            Some(ImplicitClassHandleOrClassScopeOrPackageScope::ClassScope(x)) => {
+                                let (ref class_type, _) = x.nodes;
+                                Some(self.class_name(class_type)?)
+                            }
+                            Some(_) => return None,
+                            None => None,
+                        };
+                        self.named(package.as_deref(), id.into())
+                    }
+                    PsOrHierarchicalTfIdentifier::HierarchicalTfIdentifier(_) => None,
+                }
+            }
+            SubroutineCall::SystemTfCall(x) => self.system_call(x),
+            _ => None,
+        }
+    }
+
+    fn system_call(&self, x: &SystemTfCall) -> Option<Value> {
+        let (name, arg) = match x {
+            SystemTfCall::ArgOptionl(x) => {
+                let (ref id, ref args) = x.nodes;
+                let name = self.tree.get_str_trim(id)?;
+                let arg = match args.as_ref().map(|x| &x.nodes.1) {
+                    Some(ListOfArguments::Ordered(x)) => {
+                        let (ref list, _) = x.nodes;
+                        list.nodes.0.as_ref().map(|x| x.into())
+                    }
+                    _ => None,
+                };
+                (name, arg)
+            }
+            SystemTfCall::ArgDataType(x) => {
+                let (ref id, ref args) = x.nodes;
+                let name = self.tree.get_str_trim(id)?;
+                let (ref data_type, _) = args.nodes.1;
+                if name == "$bits" {
+                    return self.bits_of_data_type(data_type);
+                }
+                (name, Some(data_type.into()))
+            }
+            SystemTfCall::ArgExpression(x) => {
+                let (ref id, ref args) = x.nodes;
+                let name = self.tree.get_str_trim(id)?;
+                let (ref list, _) = args.nodes.1;
+                (name, list.nodes.0.as_ref().map(|x| x.into()))
+            }
+        };
+        let arg: RefNode = arg?;
+        match name {
+            "$clog2" => {
+                let value = self.eval(arg)?.value;
+                let mut ret = 0;
+                while (1i128 << ret) < value as i128 {
+                    ret += 1;
+                }
+                Some(Value::new(ret, None))
+            }
+            "$bits" => {
+                if let Some((package, name)) = self.simple_name(arg.clone()) {
+                    if let Some(bits) = self.context.bits(package.as_deref(), &name) {
+                        return Some(Value::new(bits as i64, None));
+                    }
+                }
+                let width = self.eval(arg)?.width.unwrap_or(32);
+                Some(Value::new(width as i64, None))
+            }
+            "$signed" | "$unsigned" => self.eval(arg),
+            _ => None,
+        }
+    }
+
+    fn bits_of_data_type(&self, x: &DataType) -> Option<Value> {
+        if let DataType::Type(t) = x {
+            let (ref scope, ref id, ref dims) = t.nodes;
+            if dims.is_empty() {
+                let package = scope.as_ref().and_then(|x| self.scope_name(x));
+                let name = identifier(self.tree, id)?;
+                if let Some(bits) = self.context.bits(package.as_deref(), &name) {
+                    return Some(Value::new(bits as i64, None));
+                }
+            }
+        }
+        let bits = self.context.data_type_bits(self.tree, x)?;
+        Some(Value::new(bits as i64, None))
+    }
+
+    /// Get the name if the node is a plain (optionally package scoped) identifier.
+    fn simple_name(&self, node: RefNode) -> Option<(Option<String>, String)> {
+        match node {
+            RefNode::Expression(Expression::Primary(x)) => self.simple_name((&**x).into()),
+            RefNode::Primary(Primary::Hierarchical(x)) => {
+                let (ref scope, ref id, ref select) = x.nodes;
+                let package = self.qualifier_name(scope.as_ref())?;
+                if !id.nodes.1.is_empty() || self.tree.get_str_trim(select).is_some() {
+                    return None;
+                }
+                Some((package, identifier(self.tree, &id.nodes.2)?))
+            }
+            RefNode::Primary(Primary::FunctionSubroutineCall(x)) => match &x.nodes.0 {
+                SubroutineCall::TfCall(x) if x.nodes.2.is_none() => match &x.nodes.0 {
+                    PsOrHierarchicalTfIdentifier::PackageScope(x) => {
+                        let (ref scope, ref id) = x.nodes;
+                        let package = match scope {
+                            Some(ImplicitClassHandleOrClassScopeOrPackageScope::PackageScope(
+                                x,
+                            )) => Some(package_scope_name(self.tree, x)?),
+                            Some(_) => return None,
+                            None => None,
+                        };
+                        Some((package, identifier(self.tree, id)?))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            },
+            RefNode::DataType(DataType::Type(x)) => {
+                let (ref scope, ref id, ref dims) = x.nodes;
+                if !dims.is_empty() {
+                    return None;
+                }
+                let package = scope.as_ref().and_then(|x| self.scope_name(x));
+                Some((package, identifier(self.tree, id)?))
+            }
+            _ => None,
+        }
+    }
+
+    fn cast(&self, x: &CastingType, value: Value) -> Option<Value> {
+        match x {
+            CastingType::ConstantPrimary(x) => {
+                let width = self.constant_primary(x)?.value;
+                if width <= 0 {
+                    return None;
+                }
+                Some(Value::new(value.value, Some(width as u64)).masked())
+            }
+            CastingType::SimpleType(x) => {
+                let width = match &**x {
+                    SimpleType::IntegerType(x) => match &**x {
+                        IntegerType::IntegerAtomType(x) => Some(match &**x {
+                            IntegerAtomType::Byte(_) => 8,
+                            IntegerAtomType::Shortint(_) => 16,
+                            IntegerAtomType::Int(_) | IntegerAtomType::Integer(_) => 32,
+                            IntegerAtomType::Longint(_) | IntegerAtomType::Time(_) => 64,
+                        }),
+                        IntegerType::IntegerVectorType(_) => Some(1),
+                    },
+                    SimpleType::PsTypeIdentifier(x) => {
+                        let name = identifier(self.tree, &x.nodes.1)?;
+                        self.context.bits(None, &name)
+                    }
+                    _ => None,
+                };
+                Some(Value::new(value.value, width).masked())
+            }
+            _ => Some(value),
+        }
+    }
+
+    fn concat<'c, I: Iterator<Item = RefNode<'c>>>(&self, items: I) -> Option<Value> {
+        let mut value = 0i64;
+        let mut width = 0u64;
+        for item in items {
+            let item = self.eval(item)?;
+            let w = item.width?;
+            value = if w >= 64 {
+                item.value
+            } else {
+                (value << w) | item.masked().value
+            };
+            width += w;
+        }
+        Some(Value::new(value, Some(width)))
+    }
+
+    fn replicate(&self, count: Value, item: Value) -> Option<Value> {
+        let w = item.width?;
+        let mut value = 0i64;
+        for _ in 0..count.value {
+            value = if w >= 64 {
+                item.value
+            } else {
+                (value << w) | item.value
+            };
+        }
+        Some(Value::new(value, Some(w * count.value.max(0) as u64)))
+    }
+
+    fn constant_select(&self, value: Value, x: &ConstantSelect) -> Option<Value> {
+        let (ref member, ref bits, ref part) = x.nodes;
+        if member.is_some() {
+            return None;
+        }
+        let mut value = value;
+        for bit in &bits.nodes.0 {
+            let index = self.eval((&bit.nodes.1).into())?.value;
+            value = Value::new((value.value >> index) & 1, Some(1));
+        }
+        if let Some(part) = part {
+            let (msb, lsb) = match &part.nodes.1 {
+                ConstantPartSelectRange::ConstantRange(x) => {
+                    let msb = self.eval((&x.nodes.0).into())?.value;
+                    let lsb = self.eval((&x.nodes.2).into())?.value;
+                    (msb, lsb)
+                }
+                ConstantPartSelectRange::ConstantIndexedRange(x) => {
+                    self.indexed_range(&x.nodes.0, &x.nodes.1, &x.nodes.2)?
+                }
+            };
+            value = Self::part_select(value, msb, lsb);
+        }
+        Some(value)
+    }
+
+    fn select(&self, value: Value, x: &Select) -> Option<Value> {
+        let (ref member, ref bits, ref part) = x.nodes;
+        if member.is_some() {
+            return None;
+        }
+        let mut value = value;
+        for bit in &bits.nodes.0 {
+            let index = self.eval((&bit.nodes.1).into())?.value;
+            value = Value::new((value.value >> index) & 1, Some(1));
+        }
+        if let Some(part) = part {
+            let (msb, lsb) = match &part.nodes.1 {
+                PartSelectRange::ConstantRange(x) => {
+                    let msb = self.eval((&x.nodes.0).into())?.value;
+                    let lsb = self.eval((&x.nodes.2).into())?.value;
+                    (msb, lsb)
+                }
+                PartSelectRange::IndexedRange(x) => {
+                    self.indexed_range(&x.nodes.0, &x.nodes.1, &x.nodes.2)?
+                }
+            };
+            value = Self::part_select(value, msb, lsb);
+        }
+        Some(value)
+    }
+
+    fn indexed_range<'c, T, U>(&self, base: &'c T, op: &Symbol, width: &'c U) -> Option<(i64, i64)>
+    where
+        &'c T: Into<RefNode<'c>>,
+        &'c U: Into<RefNode<'c>>,
+    {
+        let base = self.eval(base.into())?.value;
+        let width = self.eval(width.into())?.value;
+        if self.tree.get_str_trim(op)? == "+:" {
+            Some((base + width - 1, base))
+        } else {
+            Some((base, base - width + 1))
+        }
+    }
+
+    fn part_select(value: Value, msb: i64, lsb: i64) -> Value {
+        let (msb, lsb) = if msb >= lsb { (msb, lsb) } else { (lsb, msb) };
+        let width = (msb - lsb + 1) as u64;
+        Value::new(value.value >> lsb, Some(width)).masked()
+    }
+
+    fn unary(&self, op: Locate, x: RefNode) -> Option<Value> {
+        let op = self.tree.get_str(&op)?;
+        let x = self.eval(x)?;
+        let width = x.width.unwrap_or(32);
+        let all = if width >= 64 { -1 } else { (1i64 << width) - 1 };
+        let v = x.masked().value;
+        let ret = match op {
+            "+" => x,
+            "-" => Value::new(x.value.wrapping_neg(), x.width),
+            "!" => Value::bool(x.value == 0),
+            "~" => Value::new(!x.value, x.width).masked(),
+            "&" => Value::bool(v & all == all),
+            "~&" => Value::bool(v & all != all),
+            "|" => Value::bool(v != 0),
+            "~|" => Value::bool(v == 0),
+            "^" => Value::bool(v.count_ones() % 2 == 1),
+            "~^" | "^~" => Value::bool(v.count_ones() % 2 == 0),
+            _ => return None,
+        };
+        Some(ret)
+    }
+
+    /// Evaluate a chain of binary operators.
+    ///
+    /// The parser builds binary expressions as right-leaning chains without operator precedence,
+    /// so the chain is flattened and evaluated again with the precedence of IEEE1800-2017 Table 11-2.
+    fn chain(&self, node: RefNode) -> Option<Value> {
+        let mut operands = Vec::new();
+        let mut operators = Vec::new();
+        let conditional = self.flatten(node, &mut operands, &mut operators);
+
+        let mut values = Vec::new();
+        for x in operands {
+            values.push(self.eval(x)?);
+        }
+        let value = climb(&values, &operators)?;
+
+        if let Some((t, f)) = conditional {
+            if value.value != 0 {
+                self.eval(t)
+            } else {
+                self.eval(f)
+            }
+        } else {
+            Some(value)
+        }
+    }
+
+    fn flatten<'c>(
+        &self,
+        node: RefNode<'c>,
+        operands: &mut Vec<RefNode<'c>>,
+        operators: &mut Vec<&'b str>,
+    ) -> Option<(RefNode<'c>, RefNode<'c>)> {
+        match node {
+            RefNode::ConstantExpression(ConstantExpression::Binary(x)) => {
+                let (ref a, ref op, _, ref b) = x.nodes;
+                operands.push(a.into());
+                operators.push(self.tree.get_str_trim(op).unwrap_or(""));
+                self.flatten(b.into(), operands, operators)
+            }
+            RefNode::Expression(Expression::Binary(x)) => {
+                let (ref a, ref op, _, ref b) = x.nodes;
+                operands.push(a.into());
+                operators.push(self.tree.get_str_trim(op).unwrap_or(""));
+                self.flatten(b.into(), operands, operators)
+            }
+            RefNode::ConstantExpression(ConstantExpression::Ternary(x)) => {
+                let (ref c, _, _, ref t, _, ref f) = x.nodes;
+                self.flatten(c.into(), operands, operators);
+                Some((t.into(), f.into()))
+            }
+            RefNode::Expression(Expression::ConditionalExpression(x)) => {
+                let (ref c, _, _, ref t, _, ref f) = x.nodes;
+                let c = c.nodes.0.contents();
+                if let [ExpressionOrCondPattern::Expression(c)] = c.as_slice() {
+                    self.flatten((&**c).into(), operands, operators);
+                } else {
+                    operands.push(node.clone());
+                    return None;
+                }
+                Some((t.into(), f.into()))
+            }
+            _ => {
+                operands.push(node);
+                None
+            }
+        }
+    }
+}
+
+/// The precedence of a binary operator in IEEE1800-2017 Table 11-2.
+pub(crate) fn precedence(op: &str) -> u8 {
+    match op {
+        "**" => 12,
+        "*" | "/" | "%" => 11,
+        "+" | "-" => 10,
+        "<<" | ">>" | "<<<" | ">>>" => 9,
+        "<" | "<=" | ">" | ">=" => 8,
+        "==" | "!=" | "===" | "!==" | "==?" | "!=?" => 7,
+        "&" => 6,
+        "^" | "~^" | "^~" => 5,
+        "|" => 4,
+        "&&" => 3,
+        "||" => 2,
+        _ => 1,
+    }
+}
+
+fn climb(values: &[Value], operators: &[&str]) -> Option<Value> {
+    let mut vals: Vec<Value> = vec![*values.first()?];
+    let mut ops: Vec<&str> = Vec::new();
+    for (op, value) in operators.iter().zip(values.iter().skip(1)) {
+        while let Some(top) = ops.last() {
+            if precedence(top) >= precedence(op) {
+                let b = vals.pop()?;
+                let a = vals.pop()?;
+                vals.push(binary(ops.pop()?, a, b)?);
+            } else {
+                break;
+            }
+        }
+        ops.push(op);
+        vals.push(*value);
+    }
+    while let Some(op) = ops.pop() {
+        let b = vals.pop()?;
+        let a = vals.pop()?;
+        vals.push(binary(op, a, b)?);
+    }
+    vals.pop()
+}
+
+/// Apply a binary operator to unsized values.
+pub(crate) fn eval_binary(op: &str, a: i64, b: i64) -> Option<i64> {
+    binary(op, Value::new(a, None), Value::new(b, None)).map(|x| x.value)
+}
+
+fn binary(op: &str, a: Value, b: Value) -> Option<Value> {
+    let width = match (a.width, b.width) {
+        (Some(x), Some(y)) => Some(x.max(y)),
+        _ => None,
+    };
+    let (x, y) = (a.value, b.value);
+    let ret = match op {
+        "+" => Value::new(x.wrapping_add(y), width),
+        "-" => Value::new(x.wrapping_sub(y), width),
+        "*" => Value::new(x.wrapping_mul(y), width),
+        "/" => Value::new(x.checked_div(y)?, width),
+        "%" => Value::new(x.checked_rem(y)?, width),
+        "**" => Value::new(x.wrapping_pow(u32::try_from(y).ok()?), a.width),
+        "<<" | "<<<" => Value::new(x.checked_shl(u32::try_from(y).ok()?).unwrap_or(0), a.width),
+        ">>" => Value::new(
+            (a.masked().value as u64)
+                .checked_shr(u32::try_from(y).ok()?)
+                .unwrap_or(0) as i64,
+            a.width,
+        ),
+        ">>>" => Value::new(x >> y.clamp(0, 63), a.width),
+        "<" => Value::bool(x < y),
+        "<=" => Value::bool(x <= y),
+        ">" => Value::bool(x > y),
+        ">=" => Value::bool(x >= y),
+        "==" | "===" | "==?" => Value::bool(x == y),
+        "!=" | "!==" | "!=?" => Value::bool(x != y),
+        "&" => Value::new(x & y, width),
+        "|" => Value::new(x | y, width),
+        "^" => Value::new(x ^ y, width),
+        "~^" | "^~" => Value::new(!(x ^ y), width).masked(),
+        "&&" => Value::bool(x != 0 && y != 0),
+        "||" => Value::bool(x != 0 || y != 0),
+        "->" => Value::bool(x == 0 || y != 0),
+        "<->" => Value::bool((x != 0) == (y != 0)),
+        _ => return None,
+    };
+    Some(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn eval_localparams(src: &str, params: &HashMap<String, i64>) -> Vec<Option<i64>> {
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let mut ret = Vec::new();
+        for node in &tree {
+            if let RefNode::ParamAssignment(x) = node {
+                if let Some((_, ref expr)) = x.nodes.2 {
+                    ret.push(eval_const(&tree, expr, params));
+                }
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn test_precedence() {
+        let src = r##"module m;
+  localparam A = 10 - 2 - 1;
+  localparam B = 1 + 2 * 3;
+  localparam C = (1 + 2) * 3;
+  localparam D = 2 ** 3 ** 2;
+  localparam E = 1 == 1 ? 5 : 6;
+  localparam F = 1 << 4 | 1;
+endmodule"##;
+        let ret = eval_localparams(src, &HashMap::new());
+        assert_eq!(
+            ret,
+            vec![Some(7), Some(7), Some(9), Some(64), Some(5), Some(17)]
+        );
+    }
+
+    #[test]
+    fn test_literal_and_parameter() {
+        let src = r##"module m;
+  localparam A = 8'hf0 | 4'b1010;
+  localparam B = {4'h1, 4'h2};
+  localparam C = {2{2'b10}};
+  localparam D = $clog2(W);
+  localparam E = W[3:2];
+  localparam F = X;
+endmodule"##;
+        let mut params = HashMap::new();
+        params.insert(String::from("W"), 12);
+        let ret = eval_localparams(src, &params);
+        assert_eq!(
+            ret,
+            vec![Some(0xfa), Some(0x12), Some(0b1010), Some(4), Some(3), None]
+        );
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("8'hff"), Some((255, Some(8))));
+        assert_eq!(parse_number("4 'b1_0_1"), Some((5, Some(4))));
+        assert_eq!(parse_number("'sd12"), Some((12, None)));
+        assert_eq!(parse_number("42"), Some((42, None)));
+        assert_eq!(parse_number("4'bx01"), None);
+    }
+}
@@ -0,0 +1,1268 @@
+use crate::interfaces::Direction;
+use crate::types::TypeResolver;
+use crate::utils::*;
+use crate::*;
+use std::collections::HashMap;
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SignalKind {
+    Net,
+    Variable,
+}
+
+/// A net, variable or port declared in a module, interface or program.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signal {
+    pub name: String,
+    pub kind: SignalKind,
+    /// The direction if the signal is a port
+    pub direction: Option<Direction>,
+    pub locate: Locate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AlwaysKind {
+    Always,
+    AlwaysComb,
+    AlwaysFf,
+    AlwaysLatch,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstructKind {
+    /// `assign` or the assignment of a net declaration
+    ContinuousAssign,
+    Always(AlwaysKind),
+    Initial,
+    Final,
+    /// The initializer of a variable declaration
+    Declaration,
+    /// A port of the module itself, which drives an input and loads an output
+    Port,
+    /// A port connection of a module instance
+    Instance {
+        instance: String,
+        port: String,
+        /// `None` if the port of the instantiated module isn't found
+        direction: Option<Direction>,
+    },
+    /// A concurrent or deferred immediate assertion
+    Assertion,
+}
+
+/// The construct containing an access. Accesses sharing a construct are in the same process.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Construct {
+    pub kind: ConstructKind,
+    pub locate: Locate,
+}
+
+/// A bit select, part select or member select of an access.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Select {
+    /// The index if it is a constant
+    Bit(Option<i64>),
+    /// The bit range as `(msb, lsb)` if it is a constant
+    Range(Option<(i64, i64)>),
+    Member(String),
+}
+
+/// A driver or a load of a signal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Access {
+    pub signal: String,
+    /// Selects in order, which is empty if the whole signal is accessed
+    pub select: Vec<Select>,
+    pub construct: Construct,
+    /// The task or function whose output argument drives the signal
+    pub subroutine: Option<String>,
+    pub locate: Locate,
+}
+
+/// Signals of a module with their drivers and loads.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModuleDataflow {
+    pub name: String,
+    pub signals: Vec<Signal>,
+    pub drivers: Vec<Access>,
+    pub loads: Vec<Access>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataflowDiagnostic {
+    /// Overlapping parts of the signal are driven by more than one construct
+    MultipleDrivers {
+        module: String,
+        signal: String,
+        locates: Vec<Locate>,
+    },
+    /// The signal is read but never driven
+    Undriven {
+        module: String,
+        signal: String,
+        locate: Locate,
+    },
+    /// The signal is never read
+    Unused {
+        module: String,
+        signal: String,
+        locate: Locate,
+    },
+    /// Overlapping parts of the signal are driven by both `always_ff` and `always_comb`
+    MixedAlways {
+        module: String,
+        signal: String,
+        locates: Vec<Locate>,
+    },
+}
+
+impl ModuleDataflow {
+    pub fn signal(&self, name: &str) -> Option<&Signal> {
+        self.signals.iter().find(|x| x.name == name)
+    }
+
+    pub fn drivers_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Access> + 'a {
+        self.drivers.iter().filter(move |x| x.signal == name)
+    }
+
+    pub fn loads_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Access> + 'a {
+        self.loads.iter().filter(move |x| x.signal == name)
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+#[derive(Default)]
+struct Declarations {
+    signals: Vec<Signal>,
+    index: HashMap<String, usize>,
+    /// Ports in order with the direction if the port is a signal
+    ports: Vec<(String, Option<Direction>)>,
+}
+
+/// Collects drivers and loads of the signals declared in modules, interfaces and programs.
+///
+/// Accesses in tasks and functions are not collected except through output arguments of the
+/// calls, and hierarchical references into other scopes are ignored. Port connections of
+/// instances whose module isn't found are both drivers and loads.
+pub struct Dataflow {
+    modules: Vec<ModuleDataflow>,
+}
+
+impl Dataflow {
+    pub fn new(tree: &SyntaxTree) -> Self {
+        let resolver = TypeResolver::new(tree);
+        let subroutines = subroutines(tree);
+
+        let mut elements = Vec::new();
+        for node in tree {
+            match node {
+                RefNode::ModuleDeclaration(_)
+                | RefNode::InterfaceDeclaration(_)
+                | RefNode::ProgramDeclaration(_) => (),
+                _ => continue,
+            }
+            if let Some(name) = scope_name(tree, &node) {
+                let x = declarations(tree, node.clone());
+                elements.push((name, node, x));
+            }
+        }
+        let ports: HashMap<String, Vec<(String, Option<Direction>)>> = elements
+            .iter()
+            .map(|(name, _, x)| {
+                let ports = x
+                    .ports
+                    .iter()
+                    .map(|(port, direction)| {
+                        let direction = x
+                            .index
+                            .get(port)
+                            .and_then(|i| x.signals[*i].direction)
+                            .or(*direction);
+                        (port.clone(), direction)
+                    })
+                    .collect();
+                (name.clone(), ports)
+            })
+            .collect();
+
+        let mut modules = Vec::new();
+        for (name, node, declarations) in elements {
+            let mut collector = Collector {
+                tree,
+                resolver: &resolver,
+                module: &name,
+                declarations: &declarations,
+                subroutines: &subroutines,
+                ports: &ports,
+                drivers: Vec::new(),
+                loads: Vec::new(),
+            };
+            collector.walk(node);
+            collector.ports();
+            let (drivers, loads) = (collector.drivers, collector.loads);
+            modules.push(ModuleDataflow {
+                name,
+                signals: declarations.signals,
+                drivers,
+                loads,
+            });
+        }
+        Dataflow { modules }
+    }
+
+    pub fn modules(&self) -> &[ModuleDataflow] {
+        &self.modules
+    }
+
+    pub fn module(&self, name: &str) -> Option<&ModuleDataflow> {
+        self.modules.iter().find(|x| x.name == name)
+    }
+
+    /// Check multiple drivers, undriven signals, unused signals and signals driven from both
+    /// `always_ff` and `always_comb`.
+    ///
+    /// Initializers of variable declarations and connections to unknown ports are not counted
+    /// as conflicting drivers.
+    pub fn check(&self) -> Vec<DataflowDiagnostic> {
+        let mut ret = Vec::new();
+        for module in &self.modules {
+            for signal in &module.signals {
+                let drivers: Vec<&Access> = module.drivers_of(&signal.name).collect();
+                if module.loads_of(&signal.name).next().is_none() {
+                    ret.push(DataflowDiagnostic::Unused {
+                        module: module.name.clone(),
+                        signal: signal.name.clone(),
+                        locate: signal.locate,
+                    });
+                } else if drivers.is_empty() {
+                    ret.push(DataflowDiagnostic::Undriven {
+                        module: module.name.clone(),
+                        signal: signal.name.clone(),
+                        locate: signal.locate,
+                    });
+                }
+
+                let drivers: Vec<&Access> = drivers
+                    .into_iter()
+                    .filter(|x| match &x.construct.kind {
+                        ConstructKind::Declaration => false,
+                        ConstructKind::Instance { direction, .. } => direction.is_some(),
+                        _ => true,
+                    })
+                    .collect();
+                let locates = conflicts(&drivers, |_, _| true);
+                if !locates.is_empty() {
+                    ret.push(DataflowDiagnostic::MultipleDrivers {
+                        module: module.name.clone(),
+                        signal: signal.name.clone(),
+                        locates,
+                    });
+                }
+                let locates = conflicts(&drivers, |x, y| {
+                    matches!(
+                        (x, y),
+                        (
+                            ConstructKind::Always(AlwaysKind::AlwaysFf),
+                            ConstructKind::Always(AlwaysKind::AlwaysComb)
+                        ) | (
+                            ConstructKind::Always(AlwaysKind::AlwaysComb),
+                            ConstructKind::Always(AlwaysKind::AlwaysFf)
+                        )
+                    )
+                });
+                if !locates.is_empty() {
+                    ret.push(DataflowDiagnostic::MixedAlways {
+                        module: module.name.clone(),
+                        signal: signal.name.clone(),
+                        locates,
+                    });
+                }
+            }
+        }
+        ret
+    }
+}
+
+/// Get the locations of drivers from different constructs driving overlapping parts.
+fn conflicts<F: Fn(&ConstructKind, &ConstructKind) -> bool>(
+    drivers: &[&Access],
+    f: F,
+) -> Vec<Locate> {
+    let mut ret: Vec<Locate> = Vec::new();
+    for (i, x) in drivers.iter().enumerate() {
+        for y in &drivers[i + 1..] {
+            if x.construct != y.construct
+                && f(&x.construct.kind, &y.construct.kind)
+                && overlaps(&x.select, &y.select)
+            {
+                for locate in [x.locate, y.locate] {
+                    if !ret.contains(&locate) {
+                        ret.push(locate);
+                    }
+                }
+            }
+        }
+    }
+    ret.sort_by_key(|x| x.offset);
+    ret
+}
+
+/// Whether the selected parts may overlap. Non-constant selects overlap any part.
+fn overlaps(x: &[Select], y: &[Select]) -> bool {
+    for (x, y) in x.iter().zip(y) {
+        match (x, y) {
+            (Select::Member(x), Select::Member(y)) => {
+                if x != y {
+                    return false;
+                }
+            }
+            (Select::Member(_), _) | (_, Select::Member(_)) => return true,
+            (x, y) => {
+                if let (Some(x), Some(y)) = (bit_range(x), bit_range(y)) {
+                    if x.0 < y.1 || y.0 < x.1 {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+    true
+}
+
+/// The range of a constant select as `(high, low)`.
+fn bit_range(x: &Select) -> Option<(i64, i64)> {
+    match x {
+        Select::Bit(Some(x)) => Some((*x, *x)),
+        Select::Range(Some((x, y))) => Some(((*x).max(*y), (*x).min(*y))),
+        _ => None,
+    }
+}
+
+fn port_direction(x: &PortDirection) -> Direction {
+    match x {
+        PortDirection::Input(_) => Direction::Input,
+        PortDirection::Output(_) => Direction::Output,
+        PortDirection::Inout(_) => Direction::Inout,
+        PortDirection::Ref(_) => Direction::Ref,
+    }
+}
+
+fn tf_port_direction(x: &TfPortDirection) -> Direction {
+    match x {
+        TfPortDirection::PortDirection(x) => port_direction(x),
+        TfPortDirection::ConstRef(_) | TfPortDirection::RefStatic(_) => Direction::Ref,
+    }
+}
+
+/// Get the arguments of tasks and functions in the tree.
+fn subroutines(tree: &SyntaxTree) -> HashMap<String, Vec<(String, Direction)>> {
+    let mut ret = HashMap::new();
+    for node in tree {
+        let id: RefNode = match node {
+            RefNode::FunctionDeclaration(x) => match &x.nodes.3 {
+                FunctionBodyDeclaration::WithoutPort(x) => (&x.nodes.2).into(),
+                FunctionBodyDeclaration::WithPort(x) => (&x.nodes.2).into(),
+            },
+            RefNode::TaskDeclaration(x) => match &x.nodes.3 {
+                TaskBodyDeclaration::WithoutPort(x) => (&x.nodes.1).into(),
+                TaskBodyDeclaration::WithPort(x) => (&x.nodes.1).into(),
+            },
+            _ => continue,
+        };
+        let name = match identifier(tree, vec![id]) {
+            Some(x) => x,
+            None => continue,
+        };
+        // The direction is inherited from the previous argument, and input for the first one
+        let mut direction = Direction::Input;
+        let mut args = Vec::new();
+        for x in node {
+            match x {
+                RefNode::TfPortItem(x) => {
+                    if let Some(x) = &x.nodes.1 {
+                        direction = tf_port_direction(x);
+                    }
+                    if let Some((id, _, _)) = &x.nodes.4 {
+                        args.extend(identifier(tree, id).map(|x| (x, direction)));
+                    }
+                }
+                RefNode::TfPortDeclaration(x) => {
+                    let direction = tf_port_direction(&x.nodes.1);
+                    for (id, _, _) in x.nodes.4.nodes.0.contents() {
+                        args.extend(identifier(tree, id).map(|x| (x, direction)));
+                    }
+                }
+                _ => (),
+            }
+        }
+        ret.entry(name).or_insert(args);
+    }
+    ret
+}
+
+impl Declarations {
+    fn declare(
+        &mut self,
+        tree: &SyntaxTree,
+        id: RefNode,
+        kind: SignalKind,
+        direction: Option<Direction>,
+    ) {
+        let (name, locate) = match (
+            identifier(tree, vec![id.clone()]),
+            identifier_locate(vec![id]),
+        ) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return,
+        };
+        match self.index.get(&name) {
+            // A port declared by a port declaration and a data declaration
+            Some(i) => {
+                let signal = &mut self.signals[*i];
+                signal.direction = signal.direction.or(direction);
+            }
+            None => {
+                self.index.insert(name.clone(), self.signals.len());
+                self.signals.push(Signal {
+                    name,
+                    kind,
+                    direction,
+                    locate,
+                });
+            }
+        }
+    }
+
+    fn ansi_port(&mut self, tree: &SyntaxTree, id: &PortIdentifier, direction: Direction) {
+        if let Some(name) = identifier(tree, id) {
+            self.ports.push((name, Some(direction)));
+        }
+    }
+}
+
+/// Get the signals and ports declared in the design element.
+fn declarations(tree: &SyntaxTree, node: RefNode) -> Declarations {
+    let mut ret = Declarations::default();
+    // The direction of an ANSI port is inherited from the previous port, and inout for the first
+    let mut direction = Direction::Inout;
+    walk_items(vec![node], |node| {
+        match node {
+            // Struct members are not signals
+            RefNode::DataTypeStructUnion(_) => return false,
+            RefNode::AnsiPortDeclarationNet(x) => {
+                let (ref header, ref id, _, _) = x.nodes;
+                match header {
+                    Some(NetPortHeaderOrInterfacePortHeader::InterfacePortHeader(_)) => {
+                        ret.ports.extend(identifier(tree, id).map(|x| (x, None)));
+                        return true;
+                    }
+                    Some(NetPortHeaderOrInterfacePortHeader::NetPortHeader(x)) => {
+                        if let Some(x) = &x.nodes.0 {
+                            direction = port_direction(x);
+                        }
+                    }
+                    None => (),
+                }
+                ret.ansi_port(tree, id, direction);
+                ret.declare(tree, id.into(), SignalKind::Net, Some(direction));
+            }
+            RefNode::AnsiPortDeclarationVariable(x) => {
+                if let Some(Some(x)) = x.nodes.0.as_ref().map(|x| &x.nodes.0) {
+                    direction = port_direction(x);
+                }
+                ret.ansi_port(tree, &x.nodes.1, direction);
+                ret.declare(
+                    tree,
+                    (&x.nodes.1).into(),
+                    SignalKind::Variable,
+                    Some(direction),
+                );
+            }
+            RefNode::AnsiPortDeclarationParen(x) => {
+                if let Some(x) = &x.nodes.0 {
+                    direction = port_direction(x);
+                }
+                ret.ansi_port(tree, &x.nodes.2, direction);
+            }
+            RefNode::ListOfPorts(x) => {
+                for x in x.nodes.0.nodes.1.contents() {
+                    let id = match x {
+                        Port::NonNamed(x) => identifier(tree, &x.nodes.0),
+                        Port::Named(x) => identifier(tree, &x.nodes.1),
+                    };
+                    ret.ports.push((id.unwrap_or_default(), None));
+                }
+            }
+            RefNode::InputDeclarationNet(x) => {
+                for (id, _) in x.nodes.2.nodes.0.contents() {
+                    ret.declare(tree, id.into(), SignalKind::Net, Some(Direction::Input));
+                }
+            }
+            RefNode::InputDeclarationVariable(x) => {
+                for (id, _) in x.nodes.2.nodes.0.contents() {
+                    ret.declare(
+                        tree,
+                        id.into(),
+                        SignalKind::Variable,
+                        Some(Direction::Input),
+                    );
+                }
+            }
+            RefNode::OutputDeclarationNet(x) => {
+                for (id, _) in x.nodes.2.nodes.0.contents() {
+                    ret.declare(tree, id.into(), SignalKind::Net, Some(Direction::Output));
+                }
+            }
+            RefNode::OutputDeclarationVariable(x) => {
+                for (id, _, _) in x.nodes.2.nodes.0.contents() {
+                    ret.declare(
+                        tree,
+                        id.into(),
+                        SignalKind::Variable,
+                        Some(Direction::Output),
+                    );
+                }
+            }
+            RefNode::InoutDeclaration(x) => {
+                for (id, _) in x.nodes.2.nodes.0.contents() {
+                    ret.declare(tree, id.into(), SignalKind::Net, Some(Direction::Inout));
+                }
+            }
+            RefNode::RefDeclaration(x) => {
+                for (id, _) in x.nodes.2.nodes.0.contents() {
+                    ret.declare(tree, id.into(), SignalKind::Variable, Some(Direction::Ref));
+                }
+            }
+            RefNode::NetDeclAssignment(x) => {
+                ret.declare(tree, (&x.nodes.0).into(), SignalKind::Net, None);
+            }
+            RefNode::VariableDeclAssignment(x) => {
+                ret.declare(tree, x.into(), SignalKind::Variable, None);
+            }
+            _ => (),
+        }
+        true
+    });
+    ret
+}
+
+// -----------------------------------------------------------------------------
+
+struct Collector<'a, 'b> {
+    tree: &'a SyntaxTree,
+    resolver: &'b TypeResolver<'a>,
+    module: &'b str,
+    declarations: &'b Declarations,
+    subroutines: &'b HashMap<String, Vec<(String, Direction)>>,
+    ports: &'b HashMap<String, Vec<(String, Option<Direction>)>>,
+    drivers: Vec<Access>,
+    loads: Vec<Access>,
+}
+
+/// The signal connected to a port of an instance.
+enum Connected<'c> {
+    Expression(&'c Expression),
+    /// `.name` or `.*`
+    Implicit(String, Locate),
+}
+
+impl<'a, 'b> Collector<'a, 'b> {
+    /// Walk the items of the design element and collect the accesses of each construct.
+    fn walk(&mut self, node: RefNode) {
+        let tree = self.tree;
+        let construct = |kind: ConstructKind, locate: Option<&Locate>| Construct {
+            kind,
+            locate: locate.copied().unwrap_or_default(),
+        };
+        walk_items(vec![node], |node| match node {
+            RefNode::ContinuousAssign(x) => {
+                let construct = construct(ConstructKind::ContinuousAssign, unwrap_locate!(x));
+                self.process(node.clone(), &construct);
+                false
+            }
+            RefNode::NetDeclAssignment(x) => {
+                if let Some((_, expr)) = &x.nodes.2 {
+                    let locate = identifier_locate(&x.nodes.0);
+                    let construct = construct(ConstructKind::ContinuousAssign, locate.as_ref());
+                    self.write(vec![(&x.nodes.0).into()], &construct, None, false);
+                    self.process(expr.into(), &construct);
+                }
+                false
+            }
+            RefNode::VariableDeclAssignmentVariable(x) => {
+                if let Some((_, expr)) = &x.nodes.2 {
+                    let locate = identifier_locate(&x.nodes.0);
+                    let construct = construct(ConstructKind::Declaration, locate.as_ref());
+                    self.write(vec![(&x.nodes.0).into()], &construct, None, false);
+                    self.process(expr.into(), &construct);
+                }
+                false
+            }
+            RefNode::AlwaysConstruct(x) => {
+                let kind = match x.nodes.0 {
+                    AlwaysKeyword::Always(_) => AlwaysKind::Always,
+                    AlwaysKeyword::AlwaysComb(_) => AlwaysKind::AlwaysComb,
+                    AlwaysKeyword::AlwaysFf(_) => AlwaysKind::AlwaysFf,
+                    AlwaysKeyword::AlwaysLatch(_) => AlwaysKind::AlwaysLatch,
+                };
+                let construct = construct(ConstructKind::Always(kind), unwrap_locate!(x));
+                self.process(node.clone(), &construct);
+                false
+            }
+            RefNode::InitialConstruct(x) => {
+                let construct = construct(ConstructKind::Initial, unwrap_locate!(x));
+                self.process(node.clone(), &construct);
+                false
+            }
+            RefNode::FinalConstruct(x) => {
+                let construct = construct(ConstructKind::Final, unwrap_locate!(x));
+                self.process(node.clone(), &construct);
+                false
+            }
+            RefNode::AssertionItem(x) => {
+                let construct = construct(ConstructKind::Assertion, unwrap_locate!(x));
+                self.process(node.clone(), &construct);
+                false
+            }
+            RefNode::ModuleInstantiation(x) => {
+                let (ref cell, _, ref list, _) = x.nodes;
+                let cell = identifier(tree, cell).unwrap_or_default();
+                for x in list.contents() {
+                    self.instance(&cell, x);
+                }
+                false
+            }
+            _ => true,
+        });
+    }
+
+    /// Collect the accesses in a construct.
+    fn process(&mut self, node: RefNode, construct: &Construct) {
+        let tree = self.tree;
+        // Signals driven through output arguments, which are not loads
+        let mut outputs: HashMap<usize, Direction> = HashMap::new();
+        // Depths of read-modify-write assignments like `x += 1` and `x++`
+        let mut rmw: Vec<usize> = Vec::new();
+        let mut depth = 0;
+        for event in node.into_iter().event() {
+            let node = match event {
+                NodeEvent::Enter(x) => {
+                    depth += 1;
+                    x
+                }
+                NodeEvent::Leave(_) => {
+                    depth -= 1;
+                    if rmw.last() == Some(&depth) {
+                        rmw.pop();
+                    }
+                    continue;
+                }
+            };
+            let read = !rmw.is_empty();
+            match node {
+                RefNode::OperatorAssignment(x) if tree.get_str_trim(&x.nodes.1) != Some("=") => {
+                    rmw.push(depth - 1);
+                }
+                RefNode::IncOrDecExpression(_) => rmw.push(depth - 1),
+                RefNode::VariableLvalueIdentifier(x) if x.nodes.0.is_none() => {
+                    let nodes = vec![(&x.nodes.1).into(), (&x.nodes.2).into()];
+                    self.write(nodes, construct, None, read);
+                }
+                RefNode::NonrangeVariableLvalue(x) if x.nodes.0.is_none() => {
+                    let nodes = vec![(&x.nodes.1).into(), (&x.nodes.2).into()];
+                    self.write(nodes, construct, None, read);
+                }
+                RefNode::BlockingAssignmentHierarchicalVariable(x) if x.nodes.0.is_none() => {
+                    let nodes = vec![(&x.nodes.1).into(), (&x.nodes.2).into()];
+                    self.write(nodes, construct, None, false);
+                }
+                RefNode::NetLvalueIdentifier(x) => {
+                    let id: RefNode = match &x.nodes.0 {
+                        PsOrHierarchicalNetIdentifier::PackageScope(x) if x.nodes.0.is_none() => {
+                            (&x.nodes.1).into()
+                        }
+                        PsOrHierarchicalNetIdentifier::PackageScope(_) => continue,
+                        PsOrHierarchicalNetIdentifier::HierarchicalNetIdentifier(x) => {
+                            (&**x).into()
+                        }
+                    };
+                    self.write(vec![id, (&x.nodes.1).into()], construct, None, false);
+                }
+                RefNode::VariableDeclAssignmentVariable(x) if x.nodes.2.is_some() => {
+                    self.write(vec![(&x.nodes.0).into()], construct, None, false);
+                }
+                RefNode::TfCall(x) => self.call(x, construct, &mut outputs),
+                RefNode::PrimaryHierarchical(x) if unscoped(&x.nodes.0) => {
+                    let nodes = vec![(&x.nodes.1).into(), (&x.nodes.2).into()];
+                    if let Some(x) = self.access(nodes, construct, None) {
+                        if outputs.get(&x.locate.offset) != Some(&Direction::Output) {
+                            self.loads.push(x);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Collect the signals driven through output arguments of a task or function call.
+    fn call(&mut self, x: &TfCall, construct: &Construct, outputs: &mut HashMap<usize, Direction>) {
+        let tree = self.tree;
+        let name = match &x.nodes.0 {
+            PsOrHierarchicalTfIdentifier::PackageScope(x) => identifier(tree, &x.nodes.1),
+            PsOrHierarchicalTfIdentifier::HierarchicalTfIdentifier(_) => None,
+        };
+        let (name, ports) = match name.and_then(|x| self.subroutines.get(&x).map(|y| (x, y))) {
+            Some(x) => x,
+            None => return,
+        };
+        let args = match &x.nodes.2 {
+            Some(x) => &x.nodes.1,
+            None => return,
+        };
+
+        let named = |id: &Identifier| {
+            let id = identifier(tree, id)?;
+            ports.iter().find(|x| x.0 == id).map(|x| x.1)
+        };
+        let mut connected: Vec<(Direction, &Expression)> = Vec::new();
+        match args {
+            ListOfArguments::Ordered(x) => {
+                for (arg, port) in x.nodes.0.contents().into_iter().zip(ports) {
+                    if let Some(arg) = arg {
+                        connected.push((port.1, arg));
+                    }
+                }
+                for (_, _, id, arg) in &x.nodes.1 {
+                    if let (Some(direction), Some(arg)) = (named(id), &arg.nodes.1) {
+                        connected.push((direction, arg));
+                    }
+                }
+            }
+            ListOfArguments::Named(x) => {
+                let (_, ref id, ref arg, ref args) = x.nodes;
+                let args = std::iter::once((id, arg)).chain(args.iter().map(|x| (&x.2, &x.3)));
+                for (id, arg) in args {
+                    if let (Some(direction), Some(arg)) = (named(id), &arg.nodes.1) {
+                        connected.push((direction, arg));
+                    }
+                }
+            }
+        }
+
+        for (direction, arg) in connected {
+            if direction == Direction::Input {
+                continue;
+            }
+            if let Expression::Primary(x) = arg {
+                if let Primary::Hierarchical(x) = &**x {
+                    if !unscoped(&x.nodes.0) {
+                        continue;
+                    }
+                    let nodes = vec![(&x.nodes.1).into(), (&x.nodes.2).into()];
+                    if let Some(x) = self.access(nodes, construct, Some(name.clone())) {
+                        outputs.insert(x.locate.offset, direction);
+                        self.drivers.push(x);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Collect the accesses through the port connections of an instance.
+    fn instance(&mut self, cell: &str, x: &HierarchicalInstance) {
+        let tree = self.tree;
+        let (ref name, ref list) = x.nodes;
+        let (instance, locate) = match (identifier(tree, name), identifier_locate(name)) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return,
+        };
+        let ports = self.ports.get(cell);
+        let direction = |port: &str| {
+            ports
+                .and_then(|x| x.iter().find(|x| x.0 == port))
+                .and_then(|x| x.1)
+        };
+
+        let mut connected: Vec<(String, Connected)> = Vec::new();
+        match &list.nodes.1 {
+            Some(ListOfPortConnections::Ordered(x)) => {
+                for (i, x) in x.nodes.0.contents().into_iter().enumerate() {
+                    let port = ports
+                        .and_then(|x| x.get(i))
+                        .map(|x| x.0.clone())
+                        .unwrap_or_default();
+                    if let Some(x) = &x.nodes.1 {
+                        connected.push((port, Connected::Expression(x)));
+                    }
+                }
+            }
+            Some(ListOfPortConnections::Named(x)) => {
+                let mut wildcard = None;
+                for x in x.nodes.0.contents() {
+                    match x {
+                        NamedPortConnection::Identifier(x) => {
+                            let (_, _, ref id, ref expr) = x.nodes;
+                            let port = identifier(tree, id).unwrap_or_default();
+                            match expr {
+                                Some(x) => {
+                                    if let Some(x) = &x.nodes.1 {
+                                        connected.push((port, Connected::Expression(x)));
+                                    }
+                                }
+                                None => {
+                                    let locate = identifier_locate(id).unwrap_or_default();
+                                    connected
+                                        .push((port.clone(), Connected::Implicit(port, locate)));
+                                }
+                            }
+                        }
+                        NamedPortConnection::Asterisk(x) => {
+                            wildcard = unwrap_locate!(&**x).copied();
+                        }
+                    }
+                }
+                // `.*` connects the remaining ports to the signals of the same names
+                if let (Some(locate), Some(ports)) = (wildcard, ports) {
+                    for (port, _) in ports {
+                        if connected.iter().all(|x| x.0 != *port) {
+                            connected
+                                .push((port.clone(), Connected::Implicit(port.clone(), locate)));
+                        }
+                    }
+                }
+            }
+            None => (),
+        }
+
+        for (port, x) in connected {
+            let direction = direction(&port);
+            let drive = !matches!(direction, Some(Direction::Input));
+            let load = !matches!(direction, Some(Direction::Output));
+            let construct = Construct {
+                kind: ConstructKind::Instance {
+                    instance: instance.clone(),
+                    port,
+                    direction,
+                },
+                locate,
+            };
+            match x {
+                Connected::Expression(x) => {
+                    if drive {
+                        for node in x {
+                            if let RefNode::PrimaryHierarchical(x) = node {
+                                if unscoped(&x.nodes.0) {
+                                    let nodes = vec![(&x.nodes.1).into(), (&x.nodes.2).into()];
+                                    self.drivers.extend(self.access(nodes, &construct, None));
+                                }
+                            }
+                        }
+                    }
+                    if load {
+                        self.process(x.into(), &construct);
+                    }
+                }
+                Connected::Implicit(name, locate) => {
+                    if !self.declarations.index.contains_key(&name) {
+                        continue;
+                    }
+                    let x = Access {
+                        signal: name,
+                        select: Vec::new(),
+                        construct,
+                        subroutine: None,
+                        locate,
+                    };
+                    if load {
+                        self.loads.push(x.clone());
+                    }
+                    if drive {
+                        self.drivers.push(x);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Input ports are driven and output ports are loaded from outside of the module.
+    fn ports(&mut self) {
+        for signal in &self.declarations.signals {
+            let direction = match signal.direction {
+                Some(x) => x,
+                None => continue,
+            };
+            let x = Access {
+                signal: signal.name.clone(),
+                select: Vec::new(),
+                construct: Construct {
+                    kind: ConstructKind::Port,
+                    locate: signal.locate,
+                },
+                subroutine: None,
+                locate: signal.locate,
+            };
+            if direction != Direction::Input {
+                self.loads.push(x.clone());
+            }
+            if direction != Direction::Output {
+                self.drivers.push(x);
+            }
+        }
+    }
+
+    fn write(
+        &mut self,
+        nodes: Vec<RefNode>,
+        construct: &Construct,
+        subroutine: Option<String>,
+        read: bool,
+    ) {
+        if let Some(x) = self.access(nodes, construct, subroutine) {
+            if read {
+                self.loads.push(x.clone());
+            }
+            self.drivers.push(x);
+        }
+    }
+
+    /// Get the access of a hierarchical identifier followed by selects, if it is a signal.
+    fn access(
+        &self,
+        nodes: Vec<RefNode>,
+        construct: &Construct,
+        subroutine: Option<String>,
+    ) -> Option<Access> {
+        let tree = self.tree;
+        let mut root: Option<(String, Locate)> = None;
+        let mut select = Vec::new();
+        // Identifiers in index expressions are not a part of the path
+        let mut expression = 0;
+        for event in Iter::new(nodes.into()).event() {
+            match event {
+                NodeEvent::Enter(RefNode::Expression(_))
+                | NodeEvent::Enter(RefNode::ConstantExpression(_)) => expression += 1,
+                NodeEvent::Leave(RefNode::Expression(_))
+                | NodeEvent::Leave(RefNode::ConstantExpression(_)) => expression -= 1,
+                NodeEvent::Enter(node) if expression == 0 => match node {
+                    RefNode::Root(_) => return None,
+                    RefNode::Identifier(x) => {
+                        let name = identifier(tree, x)?;
+                        if root.is_none() {
+                            root = Some((name, identifier_locate(x)?));
+                        } else {
+                            select.push(Select::Member(name));
+                        }
+                    }
+                    RefNode::BitSelect(x) => {
+                        for x in &x.nodes.0 {
+                            select.push(Select::Bit(self.eval(&x.nodes.1)));
+                        }
+                    }
+                    RefNode::ConstantBitSelect(x) => {
+                        for x in &x.nodes.0 {
+                            select.push(Select::Bit(self.eval(&x.nodes.1)));
+                        }
+                    }
+                    RefNode::PartSelectRange(x) => {
+                        let range = match x {
+                            PartSelectRange::ConstantRange(x) => self.range(x),
+                            PartSelectRange::IndexedRange(x) => {
+                                let (ref base, ref op, ref width) = x.nodes;
+                                self.indexed_range(base.into(), op, width)
+                            }
+                        };
+                        select.push(Select::Range(range));
+                    }
+                    RefNode::ConstantPartSelectRange(x) => {
+                        let range = match x {
+                            ConstantPartSelectRange::ConstantRange(x) => self.range(x),
+                            ConstantPartSelectRange::ConstantIndexedRange(x) => {
+                                let (ref base, ref op, ref width) = x.nodes;
+                                self.indexed_range(base.into(), op, width)
+                            }
+                        };
+                        select.push(Select::Range(range));
+                    }
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+        let (signal, locate) = root?;
+        if !self.declarations.index.contains_key(&signal) {
+            return None;
+        }
+        Some(Access {
+            signal,
+            select,
+            construct: construct.clone(),
+            subroutine,
+            locate,
+        })
+    }
+
+    fn eval<'c, T: Into<RefNode<'c>>>(&self, node: T) -> Option<i64> {
+        self.resolver.eval(self.module, node)
+    }
+
+    fn range(&self, x: &ConstantRange) -> Option<(i64, i64)> {
+        Some((self.eval(&x.nodes.0)?, self.eval(&x.nodes.2)?))
+    }
+
+    /// The range of `[base+:width]` or `[base-:width]` as `(msb, lsb)`.
+    fn indexed_range(
+        &self,
+        base: RefNode,
+        op: &Symbol,
+        width: &ConstantExpression,
+    ) -> Option<(i64, i64)> {
+        let base = self.eval(base)?;
+        let width = self.eval(width)?;
+        if self.tree.get_str_trim(op)? == "+:" {
+            Some((base + width - 1, base))
+        } else {
+            Some((base, base - width + 1))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn dataflow(src: &str) -> Dataflow {
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        Dataflow::new(&tree)
+    }
+
+    fn kinds(x: Vec<&Access>) -> Vec<ConstructKind> {
+        x.into_iter().map(|x| x.construct.kind.clone()).collect()
+    }
+
+    #[test]
+    fn test_signals() {
+        let dataflow = dataflow(
+            r##"module top(input logic clk, input logic rst, input logic [7:0] din);
+  typedef struct packed { logic [3:0] hi; logic [3:0] lo; } pair_t;
+  logic [7:0] q;
+  pair_t p;
+  int count = 0;
+
+  always_ff @(posedge clk) begin
+    if (rst) q <= '0;
+    else q <= din;
+    count += 1;
+  end
+endmodule"##,
+        );
+        let top = dataflow.module("top").unwrap();
+
+        assert!(top.signal("hi").is_none());
+        assert_eq!(top.signal("din").unwrap().direction, Some(Direction::Input));
+        assert_eq!(
+            kinds(top.drivers_of("q").collect()),
+            vec![ConstructKind::Always(AlwaysKind::AlwaysFf); 2]
+        );
+        assert_eq!(top.drivers_of("count").count(), 2);
+        assert_eq!(top.loads_of("count").count(), 1);
+    }
+
+    #[test]
+    fn test_selects() {
+        let dataflow = dataflow(
+            r##"module top(input logic [7:0] din, output logic [7:0] dout);
+  logic [7:0] q;
+  logic [1:0] multi;
+  assign q = din;
+  assign multi[0] = q[7];
+  assign multi[1] = ^q[6:4];
+  assign dout = {q[3:0], 2'b0, multi};
+endmodule"##,
+        );
+        let top = dataflow.module("top").unwrap();
+
+        let loads: Vec<&Access> = top.loads_of("q").collect();
+        assert_eq!(loads.len(), 3);
+        assert_eq!(loads[0].select, vec![Select::Bit(Some(7))]);
+        assert_eq!(loads[1].select, vec![Select::Range(Some((6, 4)))]);
+        assert_eq!(loads[2].select, vec![Select::Range(Some((3, 0)))]);
+
+        let drivers: Vec<&Access> = top.drivers_of("multi").collect();
+        assert_eq!(drivers[0].select, vec![Select::Bit(Some(0))]);
+        assert_eq!(drivers[1].select, vec![Select::Bit(Some(1))]);
+        assert_eq!(top.loads_of("multi").next().unwrap().select, vec![]);
+    }
+
+    #[test]
+    fn test_struct_members() {
+        let dataflow = dataflow(
+            r##"module top(input logic [7:0] din, output logic [7:0] dout);
+  typedef struct packed { logic [3:0] hi; logic [3:0] lo; } pair_t;
+  pair_t p;
+  always_comb p.hi = din[7:4];
+  assign p.lo = din[3:0];
+  assign dout = {p.hi[0], p.lo[3:1], 4'b0};
+endmodule"##,
+        );
+        let top = dataflow.module("top").unwrap();
+
+        let drivers: Vec<&Access> = top.drivers_of("p").collect();
+        assert_eq!(drivers.len(), 2);
+        assert_eq!(drivers[0].select, vec![Select::Member(String::from("hi"))]);
+        assert_eq!(drivers[1].select, vec![Select::Member(String::from("lo"))]);
+        assert_eq!(
+            kinds(drivers),
+            vec![
+                ConstructKind::Always(AlwaysKind::AlwaysComb),
+                ConstructKind::ContinuousAssign,
+            ]
+        );
+
+        let loads: Vec<&Access> = top.loads_of("p").collect();
+        assert_eq!(
+            loads[0].select,
+            vec![Select::Member(String::from("hi")), Select::Bit(Some(0))]
+        );
+        assert_eq!(
+            loads[1].select,
+            vec![
+                Select::Member(String::from("lo")),
+                Select::Range(Some((3, 1)))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_port_connections() {
+        let dataflow = dataflow(
+            r##"module sub(input logic a, output logic y);
+  assign y = a;
+endmodule
+
+module top(input logic [1:0] din, output logic dout);
+  logic w;
+  sub u_sub(.a(din[0]), .y(w));
+  sub u_sub2(.a(w), .y(dout));
+endmodule"##,
+        );
+        let top = dataflow.module("top").unwrap();
+
+        let loads: Vec<&Access> = top.loads_of("din").collect();
+        assert_eq!(loads.len(), 1);
+        assert_eq!(loads[0].select, vec![Select::Bit(Some(0))]);
+        assert_eq!(
+            loads[0].construct.kind,
+            ConstructKind::Instance {
+                instance: String::from("u_sub"),
+                port: String::from("a"),
+                direction: Some(Direction::Input),
+            }
+        );
+        assert_eq!(
+            kinds(top.drivers_of("w").collect()),
+            vec![ConstructKind::Instance {
+                instance: String::from("u_sub"),
+                port: String::from("y"),
+                direction: Some(Direction::Output),
+            }]
+        );
+        assert_eq!(
+            kinds(top.loads_of("w").collect()),
+            vec![ConstructKind::Instance {
+                instance: String::from("u_sub2"),
+                port: String::from("a"),
+                direction: Some(Direction::Input),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_subroutine_outputs() {
+        let dataflow = dataflow(
+            r##"module top(input logic din, output logic from_task, output logic from_func);
+  logic undriven_sig;
+
+  task automatic get(output logic o, input logic i);
+    o = i;
+  endtask
+
+  function automatic logic put(output logic o, input logic i);
+    o = i;
+    return i;
+  endfunction
+
+  always_comb begin
+    get(from_task, undriven_sig);
+    void'(put(from_func, din));
+  end
+endmodule"##,
+        );
+        let top = dataflow.module("top").unwrap();
+
+        let drivers: Vec<&Access> = top.drivers_of("from_task").collect();
+        assert_eq!(drivers.len(), 1);
+        assert_eq!(drivers[0].subroutine.as_deref(), Some("get"));
+        assert_eq!(
+            kinds(top.loads_of("from_task").collect()),
+            vec![ConstructKind::Port]
+        );
+        let drivers: Vec<&Access> = top.drivers_of("from_func").collect();
+        assert_eq!(drivers.len(), 1);
+        assert_eq!(drivers[0].subroutine.as_deref(), Some("put"));
+
+        let loads: Vec<&Access> = top.loads_of("undriven_sig").collect();
+        assert_eq!(loads.len(), 1);
+        assert_eq!(loads[0].subroutine, None);
+        assert!(top.drivers_of("undriven_sig").next().is_none());
+    }
+
+    #[test]
+    fn test_check() {
+        let dataflow = dataflow(
+            r##"module top(input logic clk, input logic w, output logic [1:0] dout);
+  logic unused_sig;
+  logic undriven_sig;
+  logic mixed;
+  logic [1:0] multi;
+
+  always_ff @(posedge clk) mixed <= 1'b0;
+  always_comb mixed = 1'b1;
+
+  assign multi[0] = w;
+  assign multi[1] = undriven_sig;
+  assign multi[1] = w;
+  assign dout = multi;
+endmodule"##,
+        );
+        let check = dataflow.check();
+        let diagnostics: Vec<(&str, &str)> = check
+            .iter()
+            .filter_map(|x| match x {
+                DataflowDiagnostic::MultipleDrivers { signal, .. } => {
+                    Some(("multiple", signal.as_str()))
+                }
+                DataflowDiagnostic::Undriven { signal, .. } => Some(("undriven", signal.as_str())),
+                DataflowDiagnostic::Unused { signal, .. } => Some(("unused", signal.as_str())),
+                DataflowDiagnostic::MixedAlways { signal, .. } => Some(("mixed", signal.as_str())),
+            })
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                ("unused", "unused_sig"),
+                ("undriven", "undriven_sig"),
+                ("unused", "mixed"),
+                ("multiple", "mixed"),
+                ("mixed", "mixed"),
+                ("multiple", "multi"),
+            ]
+        );
+    }
+}
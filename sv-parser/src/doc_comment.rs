@@ -0,0 +1,452 @@
+use crate::semantic::{declarations, SemanticKind};
+use crate::utils::*;
+use crate::*;
+use std::collections::BTreeMap;
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocStyle {
+    /// `///` or `//!`
+    Line,
+    /// `/** */` or `/*! */`
+    Block,
+    /// A comment starting with a NaturalDocs heading like `Function: name`
+    NaturalDocs,
+    /// Any other comment
+    Plain,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocComment {
+    pub style: DocStyle,
+    /// The comment text without comment markers, leading `*` and NaturalDocs heading
+    pub text: String,
+    pub locate: Locate,
+}
+
+/// A declaration with the comments documenting it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocItem {
+    pub name: String,
+    pub kind: SemanticKind,
+    /// Names of the enclosing design elements, packages, classes and subroutines
+    pub scope: Vec<String>,
+    /// The `Locate` of the declaring identifier
+    pub locate: Locate,
+    /// Comments on the lines just above the declaration
+    pub leading: Vec<DocComment>,
+    /// A comment following the declaration on the same line
+    pub trailing: Option<DocComment>,
+}
+
+impl DocItem {
+    /// Get the documentation text joining the leading and trailing comments.
+    pub fn text(&self) -> String {
+        let texts: Vec<&str> = self
+            .leading
+            .iter()
+            .chain(self.trailing.iter())
+            .map(|x| x.text.as_str())
+            .filter(|x| !x.is_empty())
+            .collect();
+        texts.join("\n")
+    }
+}
+
+/// Associates comments with the declarations they document.
+///
+/// Comments are attached to the declaration starting on the next line unless a blank line
+/// separates them, and a comment on the same line after a port, parameter or other single item
+/// is attached to it. Design elements, packages, classes and subroutines only take leading
+/// comments because a comment after `endmodule` usually isn't documentation.
+/// Only declarations with at least one comment are included.
+pub struct DocComments {
+    items: Vec<DocItem>,
+}
+
+impl DocComments {
+    pub fn new(tree: &SyntaxTree) -> Self {
+        let text = tree.text.text();
+        let comments = comments(tree);
+        let mut items = Vec::new();
+
+        let mut ancestors: Vec<RefNode> = Vec::new();
+        let mut scope: Vec<(String, usize)> = Vec::new();
+        let mut whitespace = 0;
+        let mut port_declaration = 0;
+
+        for event in tree.into_iter().event() {
+            let node = match event {
+                NodeEvent::Enter(x) => x,
+                NodeEvent::Leave(x) => {
+                    match x {
+                        RefNode::WhiteSpace(_) => whitespace -= 1,
+                        RefNode::PortDeclaration(_) => port_declaration -= 1,
+                        _ => (),
+                    }
+                    ancestors.pop();
+                    if scope.last().map(|x| x.1) == Some(ancestors.len()) {
+                        scope.pop();
+                    }
+                    continue;
+                }
+            };
+
+            match node {
+                RefNode::WhiteSpace(_) => whitespace += 1,
+                RefNode::PortDeclaration(_) => port_declaration += 1,
+                _ => (),
+            }
+            ancestors.push(node.clone());
+            if whitespace > 0 {
+                continue;
+            }
+
+            for (id, kind) in declarations(&node, port_declaration > 0) {
+                let (name, locate) = match (
+                    identifier(tree, vec![id.clone()]),
+                    identifier_locate(vec![id]),
+                ) {
+                    (Some(x), Some(y)) => (x, y),
+                    _ => continue,
+                };
+                let (item, trailing) = item(&ancestors);
+                let (begin, end) = match span(vec![item]) {
+                    Some(x) => x,
+                    None => continue,
+                };
+                let leading = leading(text, &comments, begin);
+                let trailing = if trailing {
+                    trailing_comment(text, &comments, end)
+                } else {
+                    None
+                };
+                if leading.is_empty() && trailing.is_none() {
+                    continue;
+                }
+                items.push(DocItem {
+                    name,
+                    kind,
+                    scope: scope.iter().map(|x| x.0.clone()).collect(),
+                    locate,
+                    leading: leading.into_iter().map(|x| doc_comment(tree, x)).collect(),
+                    trailing: trailing.map(|x| doc_comment(tree, x)),
+                });
+            }
+
+            let name = match node {
+                RefNode::FunctionDeclaration(x) => {
+                    unwrap_node!(x, FunctionIdentifier).and_then(|x| identifier(tree, vec![x]))
+                }
+                RefNode::TaskDeclaration(x) => {
+                    unwrap_node!(x, TaskIdentifier).and_then(|x| identifier(tree, vec![x]))
+                }
+                _ => scope_name(tree, &node),
+            };
+            if let Some(name) = name {
+                scope.push((name, ancestors.len() - 1));
+            }
+        }
+
+        DocComments { items }
+    }
+
+    /// Get all documented declarations in the order of appearance.
+    pub fn items(&self) -> &[DocItem] {
+        &self.items
+    }
+
+    /// Get the documented declaration of the name in the scope.
+    pub fn get(&self, scope: &[&str], name: &str) -> Option<&DocItem> {
+        self.items
+            .iter()
+            .find(|x| x.name == name && x.scope.iter().eq(scope.iter()))
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+/// Get all comments by the offset.
+fn comments(tree: &SyntaxTree) -> BTreeMap<usize, Locate> {
+    let mut ret = BTreeMap::new();
+    for node in tree {
+        if let RefNode::Comment(x) = node {
+            ret.insert(x.nodes.0.offset, x.nodes.0);
+        }
+    }
+    ret
+}
+
+fn is_item(node: &RefNode) -> bool {
+    matches!(
+        node,
+        RefNode::Description(_)
+            | RefNode::ModuleItem(_)
+            | RefNode::NonPortModuleItem(_)
+            | RefNode::ModuleOrGenerateItem(_)
+            | RefNode::InterfaceItem(_)
+            | RefNode::NonPortInterfaceItem(_)
+            | RefNode::ProgramItem(_)
+            | RefNode::NonPortProgramItem(_)
+            | RefNode::PackageItem(_)
+            | RefNode::ClassItem(_)
+            | RefNode::InterfaceClassItem(_)
+            | RefNode::GenerateItem(_)
+            | RefNode::AnsiPortDeclaration(_)
+            | RefNode::ParameterPortDeclaration(_)
+            | RefNode::TfItemDeclaration(_)
+            | RefNode::TfPortItem(_)
+            | RefNode::BlockItemDeclaration(_)
+            | RefNode::StructUnionMember(_)
+            | RefNode::EnumNameDeclaration(_)
+    )
+}
+
+fn is_container(node: &RefNode) -> bool {
+    matches!(
+        node,
+        RefNode::ModuleDeclaration(_)
+            | RefNode::InterfaceDeclaration(_)
+            | RefNode::ProgramDeclaration(_)
+            | RefNode::PackageDeclaration(_)
+            | RefNode::CheckerDeclaration(_)
+            | RefNode::UdpDeclaration(_)
+            | RefNode::ClassDeclaration(_)
+            | RefNode::InterfaceClassDeclaration(_)
+            | RefNode::FunctionDeclaration(_)
+            | RefNode::TaskDeclaration(_)
+    )
+}
+
+/// Get the node whose comments document the last node of `ancestors`, and whether trailing
+/// comments are allowed.
+///
+/// It's the nearest item such as a module item or an ANSI port declaration. A declaration in a
+/// header of a design element without an enclosing item, such as a parameter port, uses itself.
+fn item<'a>(ancestors: &[RefNode<'a>]) -> (RefNode<'a>, bool) {
+    let last = ancestors.len() - 1;
+    let node = &ancestors[last];
+    let item = ancestors.iter().rposition(is_item);
+    let container = ancestors.iter().rposition(is_container);
+    if is_container(node) {
+        return (item.map_or(node, |x| &ancestors[x]).clone(), false);
+    }
+    match (item, container) {
+        (Some(x), Some(y)) if x < y => (node.clone(), true),
+        (Some(x), _) => (ancestors[x].clone(), true),
+        _ => (node.clone(), true),
+    }
+}
+
+/// Get the comments just above `begin` which aren't separated by blank lines.
+fn leading(text: &str, comments: &BTreeMap<usize, Locate>, begin: usize) -> Vec<Locate> {
+    let mut ret = Vec::new();
+    let mut next = begin;
+    for (_, x) in comments.range(..begin).rev() {
+        let end = x.offset + x.len;
+        if end > next {
+            break;
+        }
+        let gap = &text[end..next];
+        let newlines = gap.matches('\n').count() + text[..end].ends_with('\n') as usize;
+        if !gap.trim().is_empty() || newlines > 1 {
+            break;
+        }
+        // A comment after other tokens on the same line is a trailing comment of them
+        let line = text[..x.offset].rfind('\n').map_or(0, |x| x + 1);
+        if !text[line..x.offset].trim().is_empty() {
+            break;
+        }
+        ret.push(*x);
+        next = x.offset;
+    }
+    ret.reverse();
+    ret
+}
+
+/// Get the comment following `end` on the same line after an optional `,` or `;`.
+fn trailing_comment(text: &str, comments: &BTreeMap<usize, Locate>, end: usize) -> Option<Locate> {
+    let is_blank = |c: char| c == ' ' || c == '\t';
+    let rest = text[end..].trim_start_matches(is_blank);
+    let rest = rest
+        .strip_prefix(|c| c == ',' || c == ';')
+        .unwrap_or(rest)
+        .trim_start_matches(is_blank);
+    comments.get(&(text.len() - rest.len())).copied()
+}
+
+const NATURAL_DOCS_KEYWORDS: &[&str] = &[
+    "Class",
+    "Constant",
+    "Define",
+    "Enum",
+    "File",
+    "Function",
+    "Group",
+    "Interface",
+    "Macro",
+    "Method",
+    "Module",
+    "Package",
+    "Parameter",
+    "Port",
+    "Program",
+    "Property",
+    "Section",
+    "Signal",
+    "Struct",
+    "Task",
+    "Title",
+    "Topic",
+    "Type",
+    "Typedef",
+    "Variable",
+];
+
+fn doc_comment(tree: &SyntaxTree, locate: Locate) -> DocComment {
+    let s = tree.get_str(&locate).unwrap_or("");
+    let (style, body) = if let Some(x) = s.strip_prefix("//") {
+        let body = x.trim_end_matches(&['\r', '\n'][..]);
+        match body.strip_prefix(&['/', '!'][..]) {
+            Some(x) => (DocStyle::Line, x.strip_prefix('<').unwrap_or(x)),
+            None => (DocStyle::Plain, body),
+        }
+    } else {
+        let body = s.trim_start_matches("/*").trim_end_matches("*/");
+        let doc = s.len() > 4 && (s.starts_with("/**") || s.starts_with("/*!"));
+        match body.strip_prefix(&['*', '!'][..]) {
+            Some(x) if doc => (DocStyle::Block, x.strip_prefix('<').unwrap_or(x)),
+            _ => (DocStyle::Plain, body),
+        }
+    };
+
+    let mut lines: Vec<&str> = body
+        .lines()
+        .map(|x| {
+            let x = x.trim();
+            let x = x.strip_prefix('*').unwrap_or(x);
+            x.trim()
+        })
+        .collect();
+    while lines.last().is_some_and(|x| x.is_empty()) {
+        lines.pop();
+    }
+    let first = lines
+        .iter()
+        .position(|x| !x.is_empty())
+        .unwrap_or(lines.len());
+    lines.drain(..first);
+
+    let heading = lines
+        .first()
+        .and_then(|x| x.split_once(':'))
+        .is_some_and(|(keyword, name)| {
+            NATURAL_DOCS_KEYWORDS.contains(&keyword) && !name.trim().is_empty()
+        });
+    let style = if heading && style == DocStyle::Plain {
+        lines.remove(0);
+        DocStyle::NaturalDocs
+    } else {
+        style
+    };
+
+    DocComment {
+        style,
+        text: lines.join("\n").trim().to_string(),
+        locate,
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_doc_comments() {
+        let src = r##"module prev;
+endmodule // prev
+
+/// Top module
+/// of the design
+module top #(
+  parameter W = 8, ///< Data width
+  // Depth of FIFO
+  parameter D = 4
+) (
+  input clk, // Clock
+  /** Reset
+   *  (active low)
+   */
+  input rst_n,
+  output [W-1:0] q
+);
+
+  // Not documenting anything
+
+  /*
+   * Function: inc
+   * Increment the value.
+   */
+  function int inc(int v);
+    return v + 1;
+  endfunction
+
+  typedef enum {
+    IDLE, // Idle state
+    BUSY  // Busy state
+  } state_t; // States
+
+endmodule
+
+class C;
+  //! Counter
+  rand int count;
+endclass"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let docs = DocComments::new(&tree);
+
+        let doc = |scope: &[&str], name: &str| docs.get(scope, name).map(|x| x.text());
+        assert_eq!(doc(&[], "prev"), None);
+        assert_eq!(
+            doc(&[], "top"),
+            Some(String::from("Top module\nof the design"))
+        );
+        assert_eq!(doc(&["top"], "W"), Some(String::from("Data width")));
+        assert_eq!(doc(&["top"], "D"), Some(String::from("Depth of FIFO")));
+        assert_eq!(doc(&["top"], "clk"), Some(String::from("Clock")));
+        assert_eq!(
+            doc(&["top"], "rst_n"),
+            Some(String::from("Reset\n(active low)"))
+        );
+        assert_eq!(doc(&["top"], "q"), None);
+        assert_eq!(
+            doc(&["top"], "inc"),
+            Some(String::from("Increment the value."))
+        );
+        assert_eq!(doc(&["top", "inc"], "v"), None);
+        assert_eq!(doc(&["top"], "IDLE"), Some(String::from("Idle state")));
+        assert_eq!(doc(&["top"], "BUSY"), Some(String::from("Busy state")));
+        assert_eq!(doc(&["top"], "state_t"), Some(String::from("States")));
+        assert_eq!(doc(&["C"], "count"), Some(String::from("Counter")));
+
+        let style = |scope: &[&str], name: &str| {
+            let x = docs.get(scope, name).unwrap();
+            x.leading
+                .iter()
+                .chain(x.trailing.iter())
+                .next()
+                .unwrap()
+                .style
+        };
+        assert_eq!(style(&[], "top"), DocStyle::Line);
+        assert_eq!(style(&["top"], "W"), DocStyle::Line);
+        assert_eq!(style(&["top"], "clk"), DocStyle::Plain);
+        assert_eq!(style(&["top"], "rst_n"), DocStyle::Block);
+        assert_eq!(style(&["top"], "inc"), DocStyle::NaturalDocs);
+    }
+}
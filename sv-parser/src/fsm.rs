@@ -0,0 +1,492 @@
+use crate::clocks::ClockDomains;
+use crate::dataflow::{AlwaysKind, ConstructKind, Dataflow};
+use crate::types::{TypeKind, TypeResolver};
+use crate::*;
+use std::collections::HashMap;
+
+// -----------------------------------------------------------------------------
+
+/// A state transition with the conditions written in the next-state logic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transition {
+    pub from: String,
+    pub to: String,
+    /// The conditions of the enclosing `if` and `case` statements joined by `&&`
+    pub guard: Option<String>,
+    /// The location of the assignment
+    pub locate: Locate,
+}
+
+/// A finite-state machine with an enum state register.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fsm {
+    pub module: String,
+    /// The state register
+    pub state: String,
+    /// The next-state variable assigned by `always_comb` if the FSM has separate next-state logic
+    pub next: Option<String>,
+    pub clock: String,
+    /// Enum members of the state type in order
+    pub states: Vec<String>,
+    pub reset: Option<String>,
+    pub transitions: Vec<Transition>,
+}
+
+impl Fsm {
+    /// Get the state diagram in the DOT language of Graphviz.
+    ///
+    /// The reset state is drawn as a double circle, and each transition is labeled by its guard.
+    pub fn to_dot(&self) -> String {
+        let mut ret = format!(
+            "digraph \"{}\" {{\n",
+            escape(&format!("{}.{}", self.module, self.state))
+        );
+        for x in &self.states {
+            if self.reset.as_ref() == Some(x) {
+                ret.push_str(&format!("  \"{}\" [shape=doublecircle];\n", escape(x)));
+            } else {
+                ret.push_str(&format!("  \"{}\" [shape=circle];\n", escape(x)));
+            }
+        }
+        for x in &self.transitions {
+            ret.push_str(&format!(
+                "  \"{}\" -> \"{}\"",
+                escape(&x.from),
+                escape(&x.to)
+            ));
+            if let Some(guard) = &x.guard {
+                ret.push_str(&format!(" [label=\"{}\"]", escape(guard)));
+            }
+            ret.push_str(";\n");
+        }
+        ret.push_str("}\n");
+        ret
+    }
+}
+
+fn escape(x: &str) -> String {
+    x.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Extracts FSMs from the common coding styles.
+///
+/// A state register is a flop of an enum type. Transitions are the assignments of enum members in
+/// the items of `case (state)`, either to the next-state variable in `always_comb` or `always @*`
+/// assigned to the state register, or to the state register itself in the clocked block.
+/// Assignments outside of `case (state)` in the next-state logic are not transitions.
+pub struct StateMachines {
+    fsms: Vec<Fsm>,
+}
+
+impl StateMachines {
+    pub fn new(tree: &SyntaxTree) -> Self {
+        let domains = ClockDomains::new(tree);
+        let dataflow = Dataflow::new(tree);
+        let resolver = TypeResolver::new(tree);
+
+        let mut blocks = HashMap::new();
+        for node in tree {
+            if let RefNode::AlwaysConstruct(x) = node {
+                if let Some(locate) = unwrap_locate!(x) {
+                    blocks.insert(locate.offset, x);
+                }
+            }
+        }
+
+        let mut fsms = Vec::new();
+        for flop in domains.flops() {
+            let states: Vec<String> = match resolver
+                .variable_type(&flop.module, &flop.variable)
+                .map(|x| x.kind)
+            {
+                Some(TypeKind::Enum { members, .. }) => {
+                    members.into_iter().map(|x| x.name).collect()
+                }
+                _ => continue,
+            };
+            let block = match blocks.get(&flop.locate.offset) {
+                Some(x) => x,
+                None => continue,
+            };
+
+            let mut walker = Walker::new(tree, &flop.variable, &flop.variable, &states);
+            walker.statement_item(&block.nodes.1.nodes.2, &None, &mut Vec::new());
+            let mut reset = None;
+            let mut next = None;
+            let mut transitions = Vec::new();
+            for x in walker.assigns {
+                if states.contains(&x.value) {
+                    match &x.from {
+                        Some(from) => transitions.append(&mut x.transitions(from, &flop.variable)),
+                        None => {
+                            reset.get_or_insert(x.value);
+                        }
+                    }
+                } else if next.is_none() && x.value != flop.variable {
+                    next = Some(x.value);
+                }
+            }
+
+            // Next-state logic in combinational blocks
+            let module = dataflow.module(&flop.module);
+            if let (Some(next), Some(module)) = (&next, module) {
+                let mut locates = Vec::new();
+                for x in module.drivers_of(next) {
+                    match x.construct.kind {
+                        ConstructKind::Always(AlwaysKind::AlwaysComb)
+                        | ConstructKind::Always(AlwaysKind::Always) => (),
+                        _ => continue,
+                    }
+                    if !locates.contains(&x.construct.locate) {
+                        locates.push(x.construct.locate);
+                    }
+                }
+                for locate in locates {
+                    let block = match blocks.get(&locate.offset) {
+                        Some(x) => x,
+                        None => continue,
+                    };
+                    let mut walker = Walker::new(tree, &flop.variable, next, &states);
+                    walker.statement_item(&block.nodes.1.nodes.2, &None, &mut Vec::new());
+                    for x in walker.assigns {
+                        let is_state = states.contains(&x.value) || x.value == flop.variable;
+                        if let (Some(from), true) = (&x.from, is_state) {
+                            transitions.append(&mut x.transitions(from, &flop.variable));
+                        }
+                    }
+                }
+            }
+
+            if transitions.is_empty() {
+                continue;
+            }
+            fsms.push(Fsm {
+                module: flop.module.clone(),
+                state: flop.variable.clone(),
+                next,
+                clock: flop.clock.signal.clone(),
+                states,
+                reset,
+                transitions,
+            });
+        }
+        StateMachines { fsms }
+    }
+
+    pub fn fsms(&self) -> &[Fsm] {
+        &self.fsms
+    }
+
+    pub fn fsm(&self, module: &str, state: &str) -> Option<&Fsm> {
+        self.fsms
+            .iter()
+            .find(|x| x.module == module && x.state == state)
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+/// An assignment to the target variable with the enclosing conditions.
+struct Assign {
+    /// The states of the enclosing `case (state)` item
+    from: Option<Vec<String>>,
+    guard: Vec<String>,
+    value: String,
+    locate: Locate,
+}
+
+impl Assign {
+    /// Get the transitions from the states, where the state register as the value holds the state.
+    fn transitions(&self, from: &[String], state: &str) -> Vec<Transition> {
+        let guard = if self.guard.is_empty() {
+            None
+        } else {
+            Some(self.guard.join(" && "))
+        };
+        from.iter()
+            .map(|x| Transition {
+                from: x.clone(),
+                to: if self.value == state {
+                    x.clone()
+                } else {
+                    self.value.clone()
+                },
+                guard: guard.clone(),
+                locate: self.locate,
+            })
+            .collect()
+    }
+}
+
+struct Walker<'a> {
+    tree: &'a SyntaxTree,
+    state: &'a str,
+    target: &'a str,
+    states: &'a [String],
+    assigns: Vec<Assign>,
+}
+
+impl<'a> Walker<'a> {
+    fn new(tree: &'a SyntaxTree, state: &'a str, target: &'a str, states: &'a [String]) -> Self {
+        Walker {
+            tree,
+            state,
+            target,
+            states,
+            assigns: Vec::new(),
+        }
+    }
+
+    fn text<'b, T: Into<RefNodes<'b>>>(&self, x: T) -> String {
+        self.tree
+            .get_str_trim(x)
+            .map(String::from)
+            .unwrap_or_default()
+    }
+
+    fn statement(
+        &mut self,
+        x: &StatementOrNull,
+        from: &Option<Vec<String>>,
+        guard: &mut Vec<String>,
+    ) {
+        if let StatementOrNull::Statement(x) = x {
+            self.statement_item(&x.nodes.2, from, guard);
+        }
+    }
+
+    fn statement_item(
+        &mut self,
+        x: &StatementItem,
+        from: &Option<Vec<String>>,
+        guard: &mut Vec<String>,
+    ) {
+        match x {
+            StatementItem::BlockingAssignment(x) => {
+                if let BlockingAssignment::OperatorAssignment(x) = &x.0 {
+                    if self.text(&x.nodes.1) == "=" {
+                        self.assign(&x.nodes.0, &x.nodes.2, from, guard);
+                    }
+                }
+            }
+            StatementItem::NonblockingAssignment(x) => {
+                self.assign(&x.0.nodes.0, &x.0.nodes.3, from, guard);
+            }
+            StatementItem::SeqBlock(x) => {
+                for x in &x.nodes.3 {
+                    self.statement(x, from, guard);
+                }
+            }
+            StatementItem::ProceduralTimingControlStatement(x) => {
+                self.statement(&x.nodes.1, from, guard);
+            }
+            StatementItem::ConditionalStatement(x) => {
+                let (_, _, ref predicate, ref statement, ref else_ifs, ref otherwise) = x.nodes;
+                let len = guard.len();
+                let branches = std::iter::once((predicate, statement))
+                    .chain(else_ifs.iter().map(|x| (&x.2, &x.3)));
+                for (predicate, statement) in branches {
+                    let predicate = self.text(&predicate.nodes.1);
+                    guard.push(predicate.clone());
+                    self.statement(statement, from, guard);
+                    guard.pop();
+                    guard.push(format!("!({})", predicate));
+                }
+                if let Some((_, statement)) = otherwise {
+                    self.statement(statement, from, guard);
+                }
+                guard.truncate(len);
+            }
+            StatementItem::CaseStatement(x) => {
+                if let CaseStatement::Normal(x) = &**x {
+                    self.case(x, from, guard);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn case(
+        &mut self,
+        x: &CaseStatementNormal,
+        from: &Option<Vec<String>>,
+        guard: &mut Vec<String>,
+    ) {
+        let (_, _, ref expr, ref first, ref items, _) = x.nodes;
+        let expr = self.text(&expr.nodes.1);
+        let items: Vec<&CaseItem> = std::iter::once(first).chain(items).collect();
+        let labels: Vec<Vec<String>> = items
+            .iter()
+            .map(|x| match x {
+                CaseItem::NonDefault(x) => {
+                    x.nodes.0.contents().iter().map(|x| self.text(*x)).collect()
+                }
+                CaseItem::Default(_) => Vec::new(),
+            })
+            .collect();
+
+        if expr == self.state && from.is_none() {
+            let covered: Vec<&String> = labels.iter().flatten().collect();
+            for (item, labels) in items.iter().zip(&labels) {
+                let (states, statement) = match item {
+                    CaseItem::NonDefault(x) => (labels.clone(), &x.nodes.2),
+                    CaseItem::Default(x) => {
+                        let states = self
+                            .states
+                            .iter()
+                            .filter(|x| !covered.contains(x))
+                            .cloned()
+                            .collect();
+                        (states, &x.nodes.2)
+                    }
+                };
+                let states = states
+                    .into_iter()
+                    .filter(|x| self.states.contains(x))
+                    .collect();
+                self.statement(statement, &Some(states), guard);
+            }
+        } else {
+            for (item, item_labels) in items.iter().zip(&labels) {
+                let (condition, statement) = match item {
+                    CaseItem::NonDefault(x) => {
+                        let condition = item_labels
+                            .iter()
+                            .map(|x| format!("{} == {}", expr, x))
+                            .collect::<Vec<_>>()
+                            .join(" || ");
+                        if item_labels.len() > 1 {
+                            (format!("({})", condition), &x.nodes.2)
+                        } else {
+                            (condition, &x.nodes.2)
+                        }
+                    }
+                    CaseItem::Default(x) => {
+                        let condition = labels
+                            .iter()
+                            .flatten()
+                            .map(|x| format!("{} != {}", expr, x))
+                            .collect::<Vec<_>>()
+                            .join(" && ");
+                        (condition, &x.nodes.2)
+                    }
+                };
+                let len = guard.len();
+                if !condition.is_empty() {
+                    guard.push(condition);
+                }
+                self.statement(statement, from, guard);
+                guard.truncate(len);
+            }
+        }
+    }
+
+    fn assign(
+        &mut self,
+        lvalue: &VariableLvalue,
+        value: &Expression,
+        from: &Option<Vec<String>>,
+        guard: &[String],
+    ) {
+        if self.text(lvalue) != self.target {
+            return;
+        }
+        let value = self.text(value);
+        let locate = match unwrap_locate!(lvalue) {
+            Some(x) => *x,
+            None => return,
+        };
+        self.assigns.push(Assign {
+            from: from.clone(),
+            guard: guard.to_vec(),
+            value,
+            locate,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_fsm() {
+        let src = r##"module top(input logic clk, input logic rst_n, input logic start, input logic done,
+           input logic [1:0] mode);
+  typedef enum logic [1:0] {IDLE, RUN, WAIT, STOP} state_t;
+  state_t state, next;
+
+  always_ff @(posedge clk or negedge rst_n) begin
+    if (!rst_n) state <= IDLE;
+    else state <= next;
+  end
+
+  always_comb begin
+    next = state;
+    case (state)
+      IDLE: if (start) next = RUN;
+      RUN: begin
+        if (done) next = IDLE;
+        else if (mode == 2'd1) next = WAIT;
+      end
+      WAIT: case (mode)
+        2'd0, 2'd1: next = RUN;
+        default: next = STOP;
+      endcase
+      default: next = IDLE;
+    endcase
+  end
+endmodule
+
+module single(input logic clk, input logic go);
+  enum {A, B} s;
+  always_ff @(posedge clk)
+    case (s)
+      A: if (go) s <= B;
+      B: s <= A;
+    endcase
+endmodule"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let machines = StateMachines::new(&tree);
+        assert_eq!(machines.fsms().len(), 2);
+
+        let fsm = machines.fsm("top", "state").unwrap();
+        assert_eq!(fsm.next.as_deref(), Some("next"));
+        assert_eq!(fsm.clock, "clk");
+        assert_eq!(fsm.states, vec!["IDLE", "RUN", "WAIT", "STOP"]);
+        assert_eq!(fsm.reset.as_deref(), Some("IDLE"));
+        let transitions: Vec<(&str, &str, Option<&str>)> = fsm
+            .transitions
+            .iter()
+            .map(|x| (x.from.as_str(), x.to.as_str(), x.guard.as_deref()))
+            .collect();
+        assert_eq!(
+            transitions,
+            vec![
+                ("IDLE", "RUN", Some("start")),
+                ("RUN", "IDLE", Some("done")),
+                ("RUN", "WAIT", Some("!(done) && mode == 2'd1")),
+                ("WAIT", "RUN", Some("(mode == 2'd0 || mode == 2'd1)")),
+                ("WAIT", "STOP", Some("mode != 2'd0 && mode != 2'd1")),
+                ("STOP", "IDLE", None),
+            ]
+        );
+
+        let fsm = machines.fsm("single", "s").unwrap();
+        assert_eq!(fsm.next, None);
+        assert_eq!(fsm.reset, None);
+        assert_eq!(
+            fsm.to_dot(),
+            r##"digraph "single.s" {
+  "A" [shape=circle];
+  "B" [shape=circle];
+  "A" -> "B" [label="go"];
+  "B" -> "A";
+}
+"##
+        );
+    }
+}
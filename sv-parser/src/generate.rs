@@ -0,0 +1,619 @@
+use crate::types::*;
+use crate::utils::*;
+use crate::*;
+use std::collections::{HashMap, HashSet};
+
+/// The maximum iteration count of a loop generate construct.
+const LOOP_LIMIT: usize = 65536;
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScopeItemKind {
+    /// An instance of the module, interface, program, checker or UDP
+    Instance(String),
+    /// A net, variable or port
+    Signal,
+}
+
+/// An instance or signal in a generate scope.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScopeItem {
+    pub name: String,
+    /// Hierarchical path such as `top.blk[3].u0`
+    pub path: String,
+    pub kind: ScopeItemKind,
+    pub locate: Locate,
+}
+
+/// An elaborated generate block. The root scope is the module itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenerateScope {
+    /// The scope name such as `genblk1` or `blk[3]`
+    pub name: String,
+    pub path: String,
+    /// The values of genvar and parameters declared in the scope
+    pub values: Vec<(String, i64)>,
+    pub scopes: Vec<GenerateScope>,
+    pub items: Vec<ScopeItem>,
+    /// Generate constructs whose condition could not be evaluated
+    pub unresolved: Vec<Locate>,
+    /// The construct number and the suffix of an unnamed generate block
+    implicit: Option<(usize, String)>,
+    declared: HashSet<String>,
+}
+
+impl GenerateScope {
+    fn new(name: String) -> Self {
+        GenerateScope {
+            name,
+            path: String::new(),
+            values: Vec::new(),
+            scopes: Vec::new(),
+            items: Vec::new(),
+            unresolved: Vec::new(),
+            implicit: None,
+            declared: HashSet::new(),
+        }
+    }
+
+    /// Find the scope by the hierarchical path.
+    pub fn find(&self, path: &str) -> Option<&GenerateScope> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.scopes.iter().find_map(|x| x.find(path))
+    }
+
+    /// Get the items of the scope and all nested scopes.
+    pub fn all_items(&self) -> Vec<&ScopeItem> {
+        let mut ret: Vec<&ScopeItem> = self.items.iter().collect();
+        for x in &self.scopes {
+            ret.append(&mut x.all_items());
+        }
+        ret
+    }
+
+    /// Decide the names of unnamed generate blocks and the paths of all scopes and items.
+    fn finalize(&mut self, path: String) {
+        self.path = path;
+        for x in &mut self.items {
+            x.path = format!("{}.{}", self.path, x.name);
+        }
+        for x in &mut self.scopes {
+            if let Some((number, ref suffix)) = x.implicit {
+                // IEEE1800-2017 27.6: leading zeroes are added while the name conflicts
+                let mut zeros = String::new();
+                let mut name = format!("genblk{}", number);
+                while self.declared.contains(&name) {
+                    zeros.push('0');
+                    name = format!("genblk{}{}", zeros, number);
+                }
+                x.name = format!("{}{}", name, suffix);
+            }
+            x.finalize(format!("{}.{}", self.path, x.name));
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+/// Unrolls generate constructs of modules with parameter values.
+pub struct GenerateElaborator<'a> {
+    tree: &'a SyntaxTree,
+    resolver: TypeResolver<'a>,
+}
+
+struct Walker<'a, 'b> {
+    elaborator: &'b GenerateElaborator<'a>,
+    module: String,
+}
+
+impl<'a> GenerateElaborator<'a> {
+    pub fn new(tree: &'a SyntaxTree) -> Self {
+        GenerateElaborator {
+            tree,
+            resolver: TypeResolver::new(tree),
+        }
+    }
+
+    /// Override the parameter value of the module.
+    pub fn set_parameter(&mut self, module: &str, name: &str, value: i64) {
+        self.resolver
+            .set_parameter(module, name, ParamValue::Value(value));
+    }
+
+    /// Elaborate generate constructs of the module, interface or program.
+    pub fn elaborate(&self, module: &str) -> Option<GenerateScope> {
+        let node = self.tree.into_iter().find(|x| match x {
+            RefNode::ModuleDeclaration(_)
+            | RefNode::InterfaceDeclaration(_)
+            | RefNode::ProgramDeclaration(_) => scope_name(self.tree, x).as_deref() == Some(module),
+            _ => false,
+        })?;
+        let walker = Walker {
+            elaborator: self,
+            module: String::from(module),
+        };
+        let mut ret = GenerateScope::new(String::from(module));
+        walker.walk(vec![node], &mut ret, &HashMap::new(), true);
+        ret.finalize(String::from(module));
+        Some(ret)
+    }
+}
+
+impl<'a, 'b> Walker<'a, 'b> {
+    fn tree(&self) -> &'a SyntaxTree {
+        self.elaborator.tree
+    }
+
+    fn eval<'c, T: Into<RefNode<'c>>>(
+        &self,
+        node: T,
+        locals: &HashMap<String, i64>,
+    ) -> Option<i64> {
+        self.elaborator
+            .resolver
+            .eval_with(&self.module, node, locals)
+    }
+
+    /// Walk items in a scope. Generate constructs are elaborated to nested scopes.
+    fn walk(
+        &self,
+        nodes: Vec<RefNode>,
+        scope: &mut GenerateScope,
+        locals: &HashMap<String, i64>,
+        root: bool,
+    ) {
+        let tree = self.tree();
+        let mut locals = locals.clone();
+        let mut construct = 0;
+        walk_items(nodes, |node| {
+            match node {
+                RefNode::LoopGenerateConstruct(x) => {
+                    construct += 1;
+                    self.loop_generate(x, scope, &locals, construct);
+                    return false;
+                }
+                RefNode::ConditionalGenerateConstruct(x) => {
+                    construct += 1;
+                    self.conditional_generate(x, scope, &locals, construct);
+                    return false;
+                }
+                RefNode::GenerateBlockMultiple(x) => {
+                    if let Some(x) = block_name(tree, x) {
+                        scope.declared.insert(x);
+                    }
+                }
+                RefNode::ParamAssignment(x) => {
+                    if let Some(name) = identifier(tree, &x.nodes.0) {
+                        // Parameters of the module are resolved by TypeResolver
+                        if !root {
+                            if let Some((_, expr)) = &x.nodes.2 {
+                                if let Some(value) = self.eval(expr, &locals) {
+                                    locals.insert(name.clone(), value);
+                                    scope.values.push((name.clone(), value));
+                                }
+                            }
+                        }
+                        scope.declared.insert(name);
+                    }
+                }
+                RefNode::ModuleInstantiation(x) => {
+                    let (ref cell, _, ref list, _) = x.nodes;
+                    self.instances(scope, cell.into(), list.contents().into_iter());
+                }
+                RefNode::InterfaceInstantiation(x) => {
+                    let (ref cell, _, ref list, _) = x.nodes;
+                    self.instances(scope, cell.into(), list.contents().into_iter());
+                }
+                RefNode::ProgramInstantiation(x) => {
+                    let (ref cell, _, ref list, _) = x.nodes;
+                    self.instances(scope, cell.into(), list.contents().into_iter());
+                }
+                RefNode::CheckerInstantiation(x) => {
+                    let (ref cell, ref name, _, _) = x.nodes;
+                    self.instance(scope, cell.into(), name);
+                }
+                RefNode::UdpInstantiation(x) => {
+                    let (ref cell, _, _, ref list, _) = x.nodes;
+                    for x in list.contents() {
+                        if let Some(name) = &x.nodes.0 {
+                            self.instance(scope, cell.into(), name);
+                        }
+                    }
+                }
+                RefNode::VariableDeclAssignment(x) => self.signal(scope, x.into()),
+                RefNode::NetDeclAssignment(x) => self.signal(scope, (&x.nodes.0).into()),
+                RefNode::AnsiPortDeclarationNet(x) => self.signal(scope, (&x.nodes.1).into()),
+                RefNode::AnsiPortDeclarationVariable(x) => self.signal(scope, (&x.nodes.1).into()),
+                RefNode::ListOfPortIdentifiers(x) => {
+                    for (x, _) in x.nodes.0.contents() {
+                        self.signal(scope, x.into());
+                    }
+                }
+                RefNode::ListOfVariableIdentifiers(x) => {
+                    for (x, _) in x.nodes.0.contents() {
+                        self.signal(scope, x.into());
+                    }
+                }
+                RefNode::ListOfVariablePortIdentifiers(x) => {
+                    for x in x.nodes.0.contents() {
+                        self.signal(scope, (&x.0).into());
+                    }
+                }
+                _ => (),
+            }
+            true
+        });
+    }
+
+    fn instances<'c, I: Iterator<Item = &'c HierarchicalInstance>>(
+        &self,
+        scope: &mut GenerateScope,
+        cell: RefNode,
+        list: I,
+    ) {
+        for x in list {
+            self.instance(scope, cell.clone(), &x.nodes.0);
+        }
+    }
+
+    fn instance(&self, scope: &mut GenerateScope, cell: RefNode, name: &NameOfInstance) {
+        let tree = self.tree();
+        let cell = identifier(tree, vec![cell]).unwrap_or_default();
+        if let (Some(name), Some(locate)) = (identifier(tree, name), identifier_locate(name)) {
+            scope.declared.insert(name.clone());
+            scope.items.push(ScopeItem {
+                name,
+                path: String::new(),
+                kind: ScopeItemKind::Instance(cell),
+                locate,
+            });
+        }
+    }
+
+    fn signal(&self, scope: &mut GenerateScope, id: RefNode) {
+        let tree = self.tree();
+        if let (Some(name), Some(locate)) = (
+            identifier(tree, vec![id.clone()]),
+            identifier_locate(vec![id]),
+        ) {
+            scope.declared.insert(name.clone());
+            scope.items.push(ScopeItem {
+                name,
+                path: String::new(),
+                kind: ScopeItemKind::Signal,
+                locate,
+            });
+        }
+    }
+
+    fn loop_generate(
+        &self,
+        x: &LoopGenerateConstruct,
+        scope: &mut GenerateScope,
+        locals: &HashMap<String, i64>,
+        construct: usize,
+    ) {
+        let tree = self.tree();
+        let (_, ref header, ref block) = x.nodes;
+        let (ref init, _, ref cond, _, ref iteration) = header.nodes.1;
+        let genvar = identifier(tree, &init.nodes.1).unwrap_or_default();
+        let label = match block {
+            GenerateBlock::Multiple(x) => block_name(tree, x),
+            GenerateBlock::GenerateItem(_) => None,
+        };
+        if let Some(x) = &label {
+            scope.declared.insert(x.clone());
+        }
+        let locate = unwrap_locate!(x).copied().unwrap_or_default();
+
+        let mut locals = locals.clone();
+        let mut value = match self.eval(&init.nodes.3, &locals) {
+            Some(x) => x,
+            None => {
+                scope.unresolved.push(locate);
+                return;
+            }
+        };
+        for _ in 0..LOOP_LIMIT {
+            locals.insert(genvar.clone(), value);
+            match self.eval(cond, &locals) {
+                Some(0) => return,
+                Some(_) => (),
+                None => {
+                    scope.unresolved.push(locate);
+                    return;
+                }
+            }
+            let suffix = format!("[{}]", value);
+            let mut child = match &label {
+                Some(x) => GenerateScope::new(format!("{}{}", x, suffix)),
+                None => {
+                    let mut x = GenerateScope::new(String::new());
+                    x.implicit = Some((construct, suffix));
+                    x
+                }
+            };
+            child.values.push((genvar.clone(), value));
+            self.block(block, &mut child, &locals);
+            scope.scopes.push(child);
+
+            value = match self.iterate(iteration, value, &locals) {
+                Some(x) => x,
+                None => {
+                    scope.unresolved.push(locate);
+                    return;
+                }
+            };
+        }
+        scope.unresolved.push(locate);
+    }
+
+    fn iterate(
+        &self,
+        x: &GenvarIteration,
+        value: i64,
+        locals: &HashMap<String, i64>,
+    ) -> Option<i64> {
+        let tree = self.tree();
+        match x {
+            GenvarIteration::Assignment(x) => {
+                let (_, ref op, ref expr) = x.nodes;
+                let rhs = self.eval(expr, locals)?;
+                let ret = match tree.get_str_trim(op)? {
+                    "=" => rhs,
+                    "+=" => value.wrapping_add(rhs),
+                    "-=" => value.wrapping_sub(rhs),
+                    "*=" => value.wrapping_mul(rhs),
+                    "/=" => value.checked_div(rhs)?,
+                    "%=" => value.checked_rem(rhs)?,
+                    "&=" => value & rhs,
+                    "|=" => value | rhs,
+                    "^=" => value ^ rhs,
+                    "<<=" | "<<<=" => value.checked_shl(rhs as u32).unwrap_or(0),
+                    ">>=" | ">>>=" => value.checked_shr(rhs as u32).unwrap_or(0),
+                    _ => return None,
+                };
+                Some(ret)
+            }
+            GenvarIteration::Prefix(x) => inc_or_dec(tree, &x.nodes.0, value),
+            GenvarIteration::Suffix(x) => inc_or_dec(tree, &x.nodes.1, value),
+        }
+    }
+
+    fn conditional_generate(
+        &self,
+        x: &ConditionalGenerateConstruct,
+        scope: &mut GenerateScope,
+        locals: &HashMap<String, i64>,
+        construct: usize,
+    ) {
+        let tree = self.tree();
+        let locate = unwrap_locate!(x).copied().unwrap_or_default();
+        // Register all block names of the construct since they are in the same scope
+        for block in conditional_blocks(x) {
+            if let GenerateBlock::Multiple(x) = block {
+                if let Some(x) = block_name(tree, x) {
+                    scope.declared.insert(x);
+                }
+            }
+        }
+
+        let block = match x {
+            ConditionalGenerateConstruct::If(x) => {
+                let (_, ref cond, ref then, ref else_) = x.nodes;
+                match self.eval(&cond.nodes.1, locals) {
+                    Some(0) => else_.as_ref().map(|(_, x)| x),
+                    Some(_) => Some(then),
+                    None => {
+                        scope.unresolved.push(locate);
+                        return;
+                    }
+                }
+            }
+            ConditionalGenerateConstruct::Case(x) => {
+                let (_, ref cond, ref items, _) = x.nodes;
+                let value = match self.eval(&cond.nodes.1, locals) {
+                    Some(x) => x,
+                    None => {
+                        scope.unresolved.push(locate);
+                        return;
+                    }
+                };
+                let mut ret = None;
+                let mut default = None;
+                for item in items {
+                    match item {
+                        CaseGenerateItem::Nondefault(x) => {
+                            let (ref list, _, ref block) = x.nodes;
+                            for x in list.contents() {
+                                match self.eval(x, locals) {
+                                    Some(x) if x == value => ret = ret.or(Some(block)),
+                                    Some(_) => (),
+                                    None => {
+                                        scope.unresolved.push(locate);
+                                        return;
+                                    }
+                                }
+                            }
+                        }
+                        CaseGenerateItem::Default(x) => default = Some(&x.nodes.2),
+                    }
+                }
+                ret.or(default)
+            }
+        };
+        let block = match block {
+            Some(x) => x,
+            None => return,
+        };
+
+        // IEEE1800-2017 27.5: a directly nested conditional construct belongs to the outer one
+        if let Some(x) = directly_nested(tree, block) {
+            self.conditional_generate(x, scope, locals, construct);
+            return;
+        }
+
+        let mut child = match block {
+            GenerateBlock::Multiple(x) => match block_name(tree, x) {
+                Some(x) => GenerateScope::new(x),
+                None => GenerateScope::new(String::new()),
+            },
+            GenerateBlock::GenerateItem(_) => GenerateScope::new(String::new()),
+        };
+        if child.name.is_empty() {
+            child.implicit = Some((construct, String::new()));
+        }
+        self.block(block, &mut child, locals);
+        scope.scopes.push(child);
+    }
+
+    fn block(&self, x: &GenerateBlock, scope: &mut GenerateScope, locals: &HashMap<String, i64>) {
+        let items = match x {
+            GenerateBlock::GenerateItem(x) => vec![(&**x).into()],
+            GenerateBlock::Multiple(x) => x.nodes.3.iter().map(|x| x.into()).collect(),
+        };
+        self.walk(items, scope, locals, false);
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+fn inc_or_dec(tree: &SyntaxTree, op: &IncOrDecOperator, value: i64) -> Option<i64> {
+    match tree.get_str_trim(op)? {
+        "++" => Some(value.wrapping_add(1)),
+        "--" => Some(value.wrapping_sub(1)),
+        _ => None,
+    }
+}
+
+fn block_name(tree: &SyntaxTree, x: &GenerateBlockMultiple) -> Option<String> {
+    let (ref label, _, ref name, _, _, _) = x.nodes;
+    label
+        .as_ref()
+        .map(|(x, _)| x)
+        .or_else(|| name.as_ref().map(|(_, x)| x))
+        .and_then(|x| identifier(tree, x))
+}
+
+fn conditional_blocks(x: &ConditionalGenerateConstruct) -> Vec<&GenerateBlock> {
+    match x {
+        ConditionalGenerateConstruct::If(x) => {
+            let (_, _, ref then, ref else_) = x.nodes;
+            std::iter::once(then)
+                .chain(else_.as_ref().map(|(_, x)| x))
+                .collect()
+        }
+        ConditionalGenerateConstruct::Case(x) => x
+            .nodes
+            .2
+            .iter()
+            .map(|x| match x {
+                CaseGenerateItem::Nondefault(x) => &x.nodes.2,
+                CaseGenerateItem::Default(x) => &x.nodes.2,
+            })
+            .collect(),
+    }
+}
+
+/// Get the conditional generate construct which is the only item of the block without
+/// `begin`-`end`.
+fn directly_nested<'a>(
+    tree: &SyntaxTree,
+    x: &'a GenerateBlock,
+) -> Option<&'a ConditionalGenerateConstruct> {
+    if let GenerateBlock::GenerateItem(item) = x {
+        if let Some(RefNode::ConditionalGenerateConstruct(x)) =
+            unwrap_node!(&**item, ConditionalGenerateConstruct)
+        {
+            if tree.get_str_trim(x) == tree.get_str_trim(&**item) {
+                return Some(x);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_generate() {
+        let src = r##"module top #(parameter N = 2, parameter MODE = 1);
+  parameter genblk2 = 0;
+  genvar i;
+
+  if (genblk2) logic a;
+  else logic b;
+
+  if (MODE == 0) begin : zero
+    logic z;
+  end else if (MODE == 1) begin
+    logic one;
+  end
+
+  for (i = 0; i < N; i++) begin : blk
+    localparam W = i * 2;
+    sub u0();
+    if (W > 0) begin
+      logic [W-1:0] w;
+    end
+  end
+
+  case (N)
+    1: sub u_one();
+    2, 3: begin : two
+      sub u_two();
+    end
+    default: ;
+  endcase
+
+  for (genvar j = 4; j > 0; j -= 2) sub u_j();
+endmodule"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let mut elaborator = GenerateElaborator::new(&tree);
+        let top = elaborator.elaborate("top").unwrap();
+
+        let paths: Vec<&str> = top.all_items().iter().map(|x| x.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "top.genblk1.b",
+                "top.genblk02.one",
+                "top.blk[0].u0",
+                "top.blk[1].u0",
+                "top.blk[1].genblk1.w",
+                "top.two.u_two",
+                "top.genblk5[4].u_j",
+                "top.genblk5[2].u_j",
+            ]
+        );
+        assert_eq!(
+            top.find("top.blk[1]").unwrap().values,
+            vec![(String::from("i"), 1), (String::from("W"), 2)]
+        );
+        assert!(top.unresolved.is_empty());
+
+        elaborator.set_parameter("top", "N", 1);
+        elaborator.set_parameter("top", "MODE", 0);
+        let top = elaborator.elaborate("top").unwrap();
+        let paths: Vec<&str> = top.all_items().iter().map(|x| x.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "top.genblk1.b",
+                "top.zero.z",
+                "top.blk[0].u0",
+                "top.genblk4.u_one",
+                "top.genblk5[4].u_j",
+                "top.genblk5[2].u_j",
+            ]
+        );
+    }
+}
@@ -0,0 +1,451 @@
+use crate::utils::span;
+use crate::*;
+use nom::Slice;
+use sv_parser_parser::{
+    description_parser_with, module_item_parser_with, non_port_module_item_parser_with, IResult,
+};
+use sv_parser_pp::range::Range;
+
+/// A text edit of the source file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextEdit {
+    /// The replaced byte range of the source file before the edit
+    pub range: Range,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range, text: &str) -> Self {
+        TextEdit {
+            range,
+            text: String::from(text),
+        }
+    }
+}
+
+/// Reparse the source file after the edit.
+///
+/// `tree` and `defines` are the result of the previous parse with the options, and `s` is the
+/// source text after the edit. Only the edited module items or descriptions are reparsed and
+/// `Locate`s of the others are shifted. If the edit may affect preprocessing or the edited region
+/// can't be isolated, the whole file is parsed again. The whole file is always parsed with
+/// `translate_off` of the options because an edited comment may be a pragma.
+#[allow(clippy::too_many_arguments)]
+pub fn reparse_sv_str<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    tree: SyntaxTree,
+    defines: Defines,
+    edit: &TextEdit,
+    s: &str,
+    path: T,
+    pre_defines: &Defines<V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    allow_incomplete: bool,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let tree = if options.translate_off.is_none() {
+        reparse(tree, edit, path.as_ref(), options)
+    } else {
+        None
+    };
+    match tree {
+        Some(tree) => {
+            check_version(&tree, options)?;
+            Ok((tree, defines))
+        }
+        None => parse_sv_str_with(
+            s,
+            path,
+            pre_defines,
+            include_paths,
+            ignore_include,
+            allow_incomplete,
+            options,
+        ),
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+/// The edit of the preprocessed text
+struct Shift {
+    begin: usize,
+    end: usize,
+    len: usize,
+    lines: i64,
+}
+
+impl Shift {
+    fn offset(&self, x: usize) -> usize {
+        x + self.len - (self.end - self.begin)
+    }
+
+    fn apply<T: NodeMut>(&self, node: &mut T) {
+        node.locate_mut(&mut |x| {
+            x.offset = self.offset(x.offset);
+            x.line = (i64::from(x.line) + self.lines) as u32;
+        });
+    }
+}
+
+fn reparse(
+    tree: SyntaxTree,
+    edit: &TextEdit,
+    path: &Path,
+    options: &ParseOptions,
+) -> Option<SyntaxTree> {
+    let SyntaxTree { node, mut text } = tree;
+    let mut source = match node {
+        AnyNode::SourceText(x) => x,
+        _ => return None,
+    };
+    // `begin_keywords changes keywords of the following descriptions
+    if text.text().contains("`begin_keywords") {
+        return None;
+    }
+
+    let lines = text.text().matches('\n').count();
+    let range = text.apply_edit(path, edit.range, &edit.text)?;
+    let shift = Shift {
+        begin: range.begin,
+        end: range.end,
+        len: edit.text.len(),
+        lines: text.text().matches('\n').count() as i64 - lines as i64,
+    };
+
+    let descriptions = &mut source.nodes.2;
+    let spans: Vec<_> = descriptions.iter().map(span).collect::<Option<_>>()?;
+    let position = spans
+        .iter()
+        .position(|(begin, end)| *begin < shift.begin && shift.end < *end);
+
+    let reused = position
+        .map(|i| reparse_module_items(&mut descriptions[i], text.text(), &shift, options))
+        .unwrap_or(false);
+    if reused {
+        for x in &mut descriptions[position.unwrap() + 1..] {
+            shift.apply(x);
+        }
+    } else {
+        let parser = |s| {
+            let ParseOptions {
+                standard,
+                ref extensions,
+                macro_preserving,
+                ..
+            } = *options;
+            description_parser_with(s, standard, extensions, macro_preserving)
+        };
+        let limit = text.text().len();
+        reparse_items(descriptions, &spans, text.text(), &shift, limit, parser)?;
+    }
+
+    Some(SyntaxTree {
+        node: AnyNode::SourceText(source),
+        text,
+    })
+}
+
+fn reparse_module_items(
+    x: &mut Description,
+    text: &str,
+    shift: &Shift,
+    options: &ParseOptions,
+) -> bool {
+    let ParseOptions {
+        standard,
+        ref extensions,
+        macro_preserving,
+        ..
+    } = *options;
+    let x = match x {
+        Description::ModuleDeclaration(x) => x,
+        _ => return false,
+    };
+    match &mut **x {
+        ModuleDeclaration::Nonansi(x) => {
+            let (_, _, ref mut items, ref mut end, ref mut label) = x.nodes;
+            let parser = |s| module_item_parser_with(s, standard, extensions, macro_preserving);
+            let spans: Option<Vec<_>> = items.iter().map(span).collect();
+            let ret = spans.and_then(|spans| {
+                let limit = end_limit(end, shift)?;
+                reparse_items(items, &spans, text, shift, limit, parser)
+            });
+            if ret.is_some() {
+                shift.apply(end);
+                shift.apply(label);
+            }
+            ret.is_some()
+        }
+        ModuleDeclaration::Ansi(x) => {
+            let (_, _, ref mut items, ref mut end, ref mut label) = x.nodes;
+            let parser =
+                |s| non_port_module_item_parser_with(s, standard, extensions, macro_preserving);
+            let spans: Option<Vec<_>> = items.iter().map(span).collect();
+            let ret = spans.and_then(|spans| {
+                let limit = end_limit(end, shift)?;
+                reparse_items(items, &spans, text, shift, limit, parser)
+            });
+            if ret.is_some() {
+                shift.apply(end);
+                shift.apply(label);
+            }
+            ret.is_some()
+        }
+        _ => false,
+    }
+}
+
+/// Get the position of `endmodule` in the edited text, which must not be touched by the edit.
+fn end_limit(end: &Keyword, shift: &Shift) -> Option<usize> {
+    let limit = end.nodes.0.offset;
+    if limit < shift.end {
+        None
+    } else {
+        Some(shift.offset(limit))
+    }
+}
+
+/// Replace the items touched by the edit with reparsed ones, and shift the following items.
+///
+/// The reparsed text ends at the next item or at `limit` in the edited text, where the trailing
+/// whitespace of the last item ends. Nothing is changed if reparsing fails or doesn't end there.
+fn reparse_items<'a, T, F>(
+    items: &mut Vec<T>,
+    spans: &[(usize, usize)],
+    text: &'a str,
+    shift: &Shift,
+    limit: usize,
+    parser: F,
+) -> Option<()>
+where
+    T: NodeMut,
+    F: Fn(Span<'a>) -> IResult<Span<'a>, T>,
+{
+    // The preceding item is reparsed too because its trailing whitespace and lookahead may
+    // reach the edit. An edit between items is reparsed with the preceding item.
+    let first = spans
+        .iter()
+        .position(|(_, end)| *end >= shift.begin)
+        .unwrap_or(spans.len())
+        .saturating_sub(1);
+    let last = spans
+        .iter()
+        .rposition(|(begin, _)| *begin <= shift.end)
+        .map_or(first, |x| x.max(first));
+    let (begin, _) = *spans.get(first)?;
+    if begin >= shift.begin {
+        return None;
+    }
+    let end = match spans.get(last + 1) {
+        Some((x, _)) => shift.offset(*x),
+        None => limit,
+    };
+
+    let mut s = Span::new_extra(text, SpanInfo::default()).slice(begin..);
+    let mut reparsed = Vec::new();
+    while s.location_offset() < end {
+        let (rest, x) = parser(s).ok()?;
+        if rest.location_offset() == s.location_offset() {
+            return None;
+        }
+        reparsed.push(x);
+        s = rest;
+    }
+    if s.location_offset() != end {
+        return None;
+    }
+
+    for x in &mut items[last + 1..] {
+        shift.apply(x);
+    }
+    items.splice(first..=last, reparsed);
+    Some(())
+}
+
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn check(src: &str, begin: usize, end: usize, text: &str, incremental: bool) {
+        check_with(src, begin, end, text, incremental, &ParseOptions::default());
+    }
+
+    fn check_with(
+        src: &str,
+        begin: usize,
+        end: usize,
+        text: &str,
+        incremental: bool,
+        options: &ParseOptions,
+    ) {
+        let path = PathBuf::from("test.sv");
+        let defines = HashMap::new();
+        let mut edited = String::from(src);
+        edited.replace_range(begin..end, text);
+        let edit = TextEdit::new(Range::new(begin, end), text);
+        let parse = |s: &str| parse_sv_str_with(s, &path, &defines, &[""], false, false, options);
+
+        let (tree, _) = parse(src).unwrap();
+        assert_eq!(reparse(tree, &edit, &path, options).is_some(), incremental);
+        let (tree, new_defines) = parse(src).unwrap();
+
+        let (expected, _) = parse(&edited).unwrap();
+        let (actual, _) = reparse_sv_str(
+            tree,
+            new_defines,
+            &edit,
+            &edited,
+            &path,
+            &defines,
+            &[""],
+            false,
+            false,
+            options,
+        )
+        .unwrap();
+
+        assert_eq!(actual.text.text(), expected.text.text());
+        assert_eq!(actual.node, expected.node);
+        for i in 0..edited.len() {
+            assert_eq!(actual.text.origin(i), expected.text.origin(i));
+            assert_eq!(actual.text.line_column(i), expected.text.line_column(i));
+        }
+    }
+
+    #[test]
+    fn test_reparse() {
+        let src = r##"`define W 8
+module a(input logic clk);
+  logic [`W-1:0] x;
+  assign x = 1;
+endmodule
+
+module b;
+  wire y;
+endmodule
+"##;
+        let x = src.find("= 1").unwrap() + 2;
+        // replace an expression in a module item
+        check(src, x, x + 1, "2 + 3", true);
+        // insert a module item
+        let y = src.find("  wire").unwrap();
+        check(src, y, y, "  wire z;\n", true);
+        // edit a module header
+        let z = src.find("b;").unwrap();
+        check(src, z, z + 1, "bb", true);
+        // insert a description at the end
+        check(src, src.len(), src.len(), "module c; endmodule\n", true);
+        // edit a line including a macro usage
+        let w = src.find("x;").unwrap();
+        check(src, w, w + 1, "xx", true);
+        // edit a macro definition
+        let u = src.find("8").unwrap();
+        check(src, u, u + 1, "16", false);
+        // delete a module
+        let v = src.find("module b").unwrap();
+        check(src, v, src.len(), "", true);
+    }
+
+    #[test]
+    fn test_reparse_comment_string() {
+        let src = r##"`define W wire w;
+module a;
+  // wire x;
+  /* `W
+     wire y; */
+  initial $display("a // b");
+endmodule
+"##;
+        // edit a line comment
+        let x = src.find("x;").unwrap();
+        check(src, x, x + 1, "xx", true);
+        // edit a block comment spanning lines including a macro usage
+        let y = src.find("y;").unwrap();
+        check(src, y, y + 1, "`y", true);
+        // edit a string including a comment
+        let z = src.find("b\"").unwrap();
+        check(src, z, z + 1, "c /*", true);
+        // uncomment a line
+        let w = src.find("// wire").unwrap();
+        check(src, w, w + 3, "", false);
+        // comment out a line
+        let v = src.find("initial").unwrap();
+        check(src, v, v, "//", false);
+        // move a macro usage out of a block comment
+        let u = src.find("/* `W").unwrap();
+        check(src, u, u + 5, "`W /*", false);
+    }
+
+    #[test]
+    fn test_reparse_options() {
+        let src = "module a;\n  wire x;\nendmodule\n";
+        let x = src.find("wire").unwrap();
+        let options = ParseOptions {
+            standard: Some(Standard::Ieee1800_2023),
+            ..ParseOptions::default()
+        };
+        check_with(src, x, x, "string s = \"\"\"a\"\"\";\n  ", true, &options);
+
+        // a reparsed construct newer than the standard is rejected
+        let path = PathBuf::from("test.sv");
+        let options = ParseOptions {
+            standard: Some(Standard::Ieee1364_2005),
+            ..ParseOptions::default()
+        };
+        let (tree, defines) =
+            parse_sv_str_with(src, &path, &HashMap::new(), &[""], false, false, &options).unwrap();
+        let edit = TextEdit::new(Range::new(x, x + 4), "bit");
+        let mut edited = String::from(src);
+        edited.replace_range(x..x + 4, "bit");
+        let ret = reparse_sv_str(
+            tree,
+            defines,
+            &edit,
+            &edited,
+            &path,
+            &HashMap::new(),
+            &[""],
+            false,
+            false,
+            &options,
+        );
+        assert!(matches!(ret, Err(Error::Version(_))));
+    }
+
+    #[test]
+    fn test_reuse() {
+        let src = "module a;\n  wire x;\n  wire y;\nendmodule\nmodule b;\nendmodule\n";
+        let path = PathBuf::from("test.sv");
+        let (tree, _) = parse_sv_str(src, &path, &HashMap::new(), &[""], false, false).unwrap();
+        let begin = src.find("y;").unwrap();
+        let edit = TextEdit::new(Range::new(begin, begin + 1), "yy");
+        let tree = reparse(tree, &edit, &path, &ParseOptions::default()).unwrap();
+        let ids: Vec<(String, u32)> = tree
+            .into_iter()
+            .filter_map(|x| match x {
+                RefNode::SimpleIdentifier(x) => {
+                    Some((String::from(tree.get_str(x).unwrap()), x.nodes.0.line))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                (String::from("a"), 1),
+                (String::from("x"), 2),
+                (String::from("yy"), 3),
+                (String::from("b"), 5)
+            ]
+        );
+
+        // an edit which changes preprocessing can't be reused
+        let (tree, _) = parse_sv_str(src, &path, &HashMap::new(), &[""], false, false).unwrap();
+        let edit = TextEdit::new(Range::new(begin, begin), "`");
+        assert!(reparse(tree, &edit, &path, &ParseOptions::default()).is_none());
+    }
+}
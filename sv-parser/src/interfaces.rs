@@ -0,0 +1,946 @@
+use crate::utils::*;
+use crate::*;
+use std::collections::HashSet;
+
+const RECURSIVE_LIMIT: usize = 64;
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Input,
+    Output,
+    Inout,
+    Ref,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TfKind {
+    Function,
+    Task,
+}
+
+/// A signal exposed by modport.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModportPort {
+    pub name: String,
+    pub direction: Direction,
+    /// The expression of modport expression `.name(expression)`
+    pub expression: Option<String>,
+    pub locate: Locate,
+}
+
+/// A task or function imported or exported by modport.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModportMethod {
+    pub name: String,
+    pub is_export: bool,
+    /// The kind of the prototype, or `None` if only the name is specified
+    pub kind: Option<TfKind>,
+    pub locate: Locate,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Modport {
+    pub name: String,
+    pub ports: Vec<ModportPort>,
+    pub methods: Vec<ModportMethod>,
+    pub clockings: Vec<String>,
+    pub locate: Locate,
+}
+
+/// A port declared by `InterfacePortHeader` or `InterfacePortDeclaration`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfacePort {
+    pub name: String,
+    /// `None` for a generic interface port declared by `interface`
+    pub interface: Option<String>,
+    pub modport: Option<String>,
+    pub locate: Locate,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionTarget {
+    /// An interface instance in the parent module
+    Instance(String),
+    /// An interface port of the parent module
+    Port(String),
+    /// The connected expression is not an interface
+    Unresolved(String),
+}
+
+/// The connection of an interface port at a module instance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfaceConnection {
+    /// The module which contains the instance
+    pub module: String,
+    pub instance: String,
+    /// The module instantiated by the instance
+    pub cell: String,
+    pub port: String,
+    pub target: ConnectionTarget,
+    pub interface: Option<String>,
+    /// The modport selected by the port declaration, the connection or the parent port
+    pub modport: Option<String>,
+    pub locate: Locate,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InterfaceDiagnostic {
+    UnknownModport {
+        interface: String,
+        modport: String,
+        locate: Locate,
+    },
+    UnknownSignal {
+        interface: String,
+        modport: String,
+        signal: String,
+        locate: Locate,
+    },
+    /// An imported task or function is not defined in the interface nor exported by any modport
+    MissingImport {
+        interface: String,
+        modport: String,
+        method: String,
+        locate: Locate,
+    },
+    /// A module connected through the modport does not define the exported task or function
+    MissingExport {
+        interface: String,
+        modport: String,
+        module: String,
+        method: String,
+        locate: Locate,
+    },
+    /// The prototype in modport is a task but the definition is a function, or vice versa
+    KindMismatch {
+        interface: String,
+        modport: String,
+        method: String,
+        locate: Locate,
+    },
+}
+
+// -----------------------------------------------------------------------------
+
+#[derive(Default)]
+struct Element {
+    name: String,
+    is_interface: bool,
+    ports: Vec<String>,
+    interface_ports: Vec<InterfacePort>,
+    instances: Vec<Instance>,
+    methods: Vec<(String, TfKind)>,
+    /// Methods defined as `port.name` for exporting through modport
+    port_methods: Vec<(String, String, TfKind)>,
+    signals: HashSet<String>,
+    modports: Vec<Modport>,
+}
+
+struct Instance {
+    name: String,
+    cell: String,
+    connections: Vec<Connection>,
+    locate: Locate,
+}
+
+enum Connection {
+    Ordered(Option<(String, Option<String>)>),
+    Named(String, Option<(String, Option<String>)>),
+    Implicit(String),
+    Wildcard,
+}
+
+/// Resolves interface ports, modports and their connections in a `SyntaxTree`.
+pub struct InterfaceResolver {
+    elements: Vec<Element>,
+    connections: Vec<InterfaceConnection>,
+}
+
+impl InterfaceResolver {
+    pub fn new(tree: &SyntaxTree) -> Self {
+        let mut elements = Vec::new();
+        for node in tree {
+            let is_interface = match node {
+                RefNode::ModuleDeclaration(_) | RefNode::ProgramDeclaration(_) => false,
+                RefNode::InterfaceDeclaration(_) => true,
+                _ => continue,
+            };
+            if let Some(name) = scope_name(tree, &node) {
+                let mut x = element(tree, node);
+                x.name = name;
+                x.is_interface = is_interface;
+                elements.push(x);
+            }
+        }
+        let mut ret = InterfaceResolver {
+            elements,
+            connections: Vec::new(),
+        };
+        ret.connections = ret.resolve_connections();
+        ret
+    }
+
+    /// Get the names of interfaces.
+    pub fn interfaces(&self) -> Vec<&str> {
+        self.elements
+            .iter()
+            .filter(|x| x.is_interface)
+            .map(|x| x.name.as_str())
+            .collect()
+    }
+
+    /// Get the modports of the interface.
+    pub fn modports(&self, interface: &str) -> &[Modport] {
+        self.interface(interface)
+            .map(|x| x.modports.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn modport(&self, interface: &str, modport: &str) -> Option<&Modport> {
+        self.modports(interface).iter().find(|x| x.name == modport)
+    }
+
+    /// Get the interface ports of the module, interface or program.
+    pub fn interface_ports(&self, module: &str) -> &[InterfacePort] {
+        self.elements
+            .iter()
+            .find(|x| x.name == module)
+            .map(|x| x.interface_ports.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Get the connections of all interface ports at module instances.
+    pub fn connections(&self) -> &[InterfaceConnection] {
+        &self.connections
+    }
+
+    /// Get the interface instances which the port is connected to, through interface ports
+    /// of the parent modules.
+    ///
+    /// The result is the list of `(module, instance)`.
+    pub fn sources(&self, module: &str, port: &str) -> Vec<(String, String)> {
+        let mut ret = Vec::new();
+        let mut visited = HashSet::new();
+        self.collect_sources(module, port, &mut ret, &mut visited, 0);
+        ret
+    }
+
+    /// Check modport declarations and interface port connections.
+    pub fn check(&self) -> Vec<InterfaceDiagnostic> {
+        let mut ret = Vec::new();
+        for x in &self.elements {
+            for port in &x.interface_ports {
+                if let (Some(interface), Some(modport)) = (&port.interface, &port.modport) {
+                    if self.interface(interface).is_some()
+                        && self.modport(interface, modport).is_none()
+                    {
+                        ret.push(InterfaceDiagnostic::UnknownModport {
+                            interface: interface.clone(),
+                            modport: modport.clone(),
+                            locate: port.locate,
+                        });
+                    }
+                }
+            }
+        }
+        for x in &self.connections {
+            // Modports in port declarations are already checked above
+            let declared = self
+                .interface_ports(&x.cell)
+                .iter()
+                .any(|y| y.name == x.port && y.modport.is_some());
+            if declared {
+                continue;
+            }
+            if let (Some(interface), Some(modport)) = (&x.interface, &x.modport) {
+                if self.interface(interface).is_some() && self.modport(interface, modport).is_none()
+                {
+                    ret.push(InterfaceDiagnostic::UnknownModport {
+                        interface: interface.clone(),
+                        modport: modport.clone(),
+                        locate: x.locate,
+                    });
+                }
+            }
+        }
+
+        for interface in self.elements.iter().filter(|x| x.is_interface) {
+            for modport in &interface.modports {
+                for port in &modport.ports {
+                    if port.expression.is_none() && !interface.signals.contains(&port.name) {
+                        ret.push(InterfaceDiagnostic::UnknownSignal {
+                            interface: interface.name.clone(),
+                            modport: modport.name.clone(),
+                            signal: port.name.clone(),
+                            locate: port.locate,
+                        });
+                    }
+                }
+                for method in &modport.methods {
+                    if method.is_export {
+                        self.check_export(interface, modport, method, &mut ret);
+                    } else {
+                        self.check_import(interface, modport, method, &mut ret);
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    // -------------------------------------------------------------------------
+
+    fn interface(&self, name: &str) -> Option<&Element> {
+        self.elements
+            .iter()
+            .find(|x| x.is_interface && x.name == name)
+    }
+
+    fn check_import(
+        &self,
+        interface: &Element,
+        modport: &Modport,
+        method: &ModportMethod,
+        ret: &mut Vec<InterfaceDiagnostic>,
+    ) {
+        let defined = interface.methods.iter().find(|x| x.0 == method.name);
+        let exported = interface
+            .modports
+            .iter()
+            .flat_map(|x| x.methods.iter())
+            .any(|x| x.is_export && x.name == method.name);
+        match defined {
+            Some((_, kind)) => {
+                if method.kind.is_some() && method.kind != Some(*kind) {
+                    ret.push(InterfaceDiagnostic::KindMismatch {
+                        interface: interface.name.clone(),
+                        modport: modport.name.clone(),
+                        method: method.name.clone(),
+                        locate: method.locate,
+                    });
+                }
+            }
+            None if exported => (),
+            None => ret.push(InterfaceDiagnostic::MissingImport {
+                interface: interface.name.clone(),
+                modport: modport.name.clone(),
+                method: method.name.clone(),
+                locate: method.locate,
+            }),
+        }
+    }
+
+    /// Check that one of the modules connected to each interface instance through the modport
+    /// defines the exported method.
+    fn check_export(
+        &self,
+        interface: &Element,
+        modport: &Modport,
+        method: &ModportMethod,
+        ret: &mut Vec<InterfaceDiagnostic>,
+    ) {
+        // (source instance, connected modules, defined)
+        let mut sources: Vec<((String, String), Vec<&str>, bool)> = Vec::new();
+        for x in &self.connections {
+            if x.interface.as_ref() != Some(&interface.name)
+                || x.modport.as_ref() != Some(&modport.name)
+            {
+                continue;
+            }
+            let cell = match self.elements.iter().find(|y| y.name == x.cell) {
+                Some(x) => x,
+                None => continue,
+            };
+            let defined = cell
+                .port_methods
+                .iter()
+                .find(|(p, m, _)| *p == x.port && *m == method.name);
+            if let Some((_, _, kind)) = defined {
+                if method.kind.is_some() && method.kind != Some(*kind) {
+                    ret.push(InterfaceDiagnostic::KindMismatch {
+                        interface: interface.name.clone(),
+                        modport: modport.name.clone(),
+                        method: method.name.clone(),
+                        locate: method.locate,
+                    });
+                }
+            }
+            for source in self.sources(&x.cell, &x.port) {
+                match sources.iter_mut().find(|(y, _, _)| *y == source) {
+                    Some(y) => {
+                        y.1.push(&x.cell);
+                        y.2 |= defined.is_some();
+                    }
+                    None => sources.push((source, vec![&x.cell], defined.is_some())),
+                }
+            }
+        }
+        for (_, modules, defined) in sources {
+            if !defined {
+                let module = modules.last().copied().unwrap_or_default();
+                let locate = self
+                    .interface_ports(module)
+                    .iter()
+                    .find(|x| x.modport.as_ref() == Some(&modport.name))
+                    .map(|x| x.locate)
+                    .unwrap_or(method.locate);
+                ret.push(InterfaceDiagnostic::MissingExport {
+                    interface: interface.name.clone(),
+                    modport: modport.name.clone(),
+                    module: String::from(module),
+                    method: method.name.clone(),
+                    locate,
+                });
+            }
+        }
+    }
+
+    fn collect_sources(
+        &self,
+        module: &str,
+        port: &str,
+        ret: &mut Vec<(String, String)>,
+        visited: &mut HashSet<(String, String)>,
+        depth: usize,
+    ) {
+        if depth > RECURSIVE_LIMIT || !visited.insert((String::from(module), String::from(port))) {
+            return;
+        }
+        for x in &self.connections {
+            if x.cell != module || x.port != port {
+                continue;
+            }
+            match &x.target {
+                ConnectionTarget::Instance(y) => {
+                    let y = (x.module.clone(), y.clone());
+                    if !ret.contains(&y) {
+                        ret.push(y);
+                    }
+                }
+                ConnectionTarget::Port(y) => {
+                    self.collect_sources(&x.module, y, ret, visited, depth + 1)
+                }
+                ConnectionTarget::Unresolved(_) => (),
+            }
+        }
+    }
+
+    fn resolve_connections(&self) -> Vec<InterfaceConnection> {
+        let mut ret = Vec::new();
+        for parent in &self.elements {
+            for instance in &parent.instances {
+                let cell = match self.elements.iter().find(|x| x.name == instance.cell) {
+                    Some(x) => x,
+                    None => continue,
+                };
+                for port in &cell.interface_ports {
+                    let expr = match connected(cell, instance, &port.name) {
+                        Some(x) => x,
+                        None => continue,
+                    };
+                    let (root, member) = expr;
+                    let mut interface = port.interface.clone();
+                    let mut modport = port.modport.clone();
+                    let target = if let Some(x) = parent.instances.iter().find(|x| x.name == root) {
+                        if self.interface(&x.cell).is_some() {
+                            interface = interface.or_else(|| Some(x.cell.clone()));
+                            modport = modport.or(member);
+                            ConnectionTarget::Instance(root)
+                        } else {
+                            ConnectionTarget::Unresolved(root)
+                        }
+                    } else if let Some(x) = parent.interface_ports.iter().find(|x| x.name == root) {
+                        interface = interface.or_else(|| x.interface.clone());
+                        modport = modport.or(member).or_else(|| x.modport.clone());
+                        ConnectionTarget::Port(root)
+                    } else {
+                        ConnectionTarget::Unresolved(root)
+                    };
+                    ret.push(InterfaceConnection {
+                        module: parent.name.clone(),
+                        instance: instance.name.clone(),
+                        cell: cell.name.clone(),
+                        port: port.name.clone(),
+                        target,
+                        interface,
+                        modport,
+                        locate: instance.locate,
+                    });
+                }
+            }
+        }
+        ret
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+/// Find the expression connected to the port as `(root, member)`.
+fn connected(cell: &Element, instance: &Instance, port: &str) -> Option<(String, Option<String>)> {
+    let index = cell.ports.iter().position(|x| x == port);
+    let mut ordered = 0;
+    let mut wildcard = false;
+    for x in &instance.connections {
+        match x {
+            Connection::Ordered(x) => {
+                if Some(ordered) == index {
+                    return x.clone();
+                }
+                ordered += 1;
+            }
+            Connection::Named(name, x) if name == port => return x.clone(),
+            Connection::Implicit(name) if name == port => return Some((name.clone(), None)),
+            Connection::Wildcard => wildcard = true,
+            _ => (),
+        }
+    }
+    if wildcard {
+        Some((String::from(port), None))
+    } else {
+        None
+    }
+}
+
+/// Get the hierarchical name of `x` or `x.y`.
+fn expression_name(tree: &SyntaxTree, x: &Expression) -> Option<(String, Option<String>)> {
+    if let Expression::Primary(x) = x {
+        if let Primary::Hierarchical(x) = &**x {
+            let (ref scope, ref id, _) = x.nodes;
+            if tree.get_str_trim(scope).is_some() || id.nodes.0.is_some() {
+                return None;
+            }
+            let mut names: Vec<String> = id
+                .nodes
+                .1
+                .iter()
+                .filter_map(|(x, _, _)| identifier(tree, x))
+                .collect();
+            names.push(identifier(tree, &id.nodes.2)?);
+            return match names.as_slice() {
+                [x] => Some((x.clone(), None)),
+                [x, y] => Some((x.clone(), Some(y.clone()))),
+                _ => None,
+            };
+        }
+    }
+    None
+}
+
+fn element(tree: &SyntaxTree, node: RefNode) -> Element {
+    let mut ret = Element::default();
+    // Declarations in classes, tasks, functions and modports are not signals of the element.
+    let mut ignore = 0;
+    let mut nested = 0;
+    for event in node.into_iter().event() {
+        let node = match event {
+            NodeEvent::Enter(x) => x,
+            NodeEvent::Leave(x) => {
+                match x {
+                    RefNode::ClassDeclaration(_)
+                    | RefNode::FunctionDeclaration(_)
+                    | RefNode::TaskDeclaration(_)
+                    | RefNode::ModportDeclaration(_) => ignore -= 1,
+                    RefNode::ModuleDeclaration(_)
+                    | RefNode::InterfaceDeclaration(_)
+                    | RefNode::ProgramDeclaration(_) => nested -= 1,
+                    _ => (),
+                }
+                continue;
+            }
+        };
+        match node {
+            RefNode::ModuleDeclaration(_)
+            | RefNode::InterfaceDeclaration(_)
+            | RefNode::ProgramDeclaration(_) => nested += 1,
+            _ => (),
+        }
+        // Skip nested design elements except the element itself
+        if nested > 1 {
+            continue;
+        }
+        match node {
+            RefNode::ClassDeclaration(_) => ignore += 1,
+            RefNode::FunctionDeclaration(x) => {
+                ignore += 1;
+                if ignore == 1 {
+                    let (scope, id): (_, RefNode) = match &x.nodes.3 {
+                        FunctionBodyDeclaration::WithoutPort(x) => {
+                            (&x.nodes.1, (&x.nodes.2).into())
+                        }
+                        FunctionBodyDeclaration::WithPort(x) => (&x.nodes.1, (&x.nodes.2).into()),
+                    };
+                    method(tree, &mut ret, scope.as_ref(), id, TfKind::Function);
+                }
+            }
+            RefNode::TaskDeclaration(x) => {
+                ignore += 1;
+                if ignore == 1 {
+                    let (scope, id): (_, RefNode) = match &x.nodes.3 {
+                        TaskBodyDeclaration::WithoutPort(x) => (&x.nodes.0, (&x.nodes.1).into()),
+                        TaskBodyDeclaration::WithPort(x) => (&x.nodes.0, (&x.nodes.1).into()),
+                    };
+                    method(tree, &mut ret, scope.as_ref(), id, TfKind::Task);
+                }
+            }
+            RefNode::ModportDeclaration(x) => {
+                ignore += 1;
+                for x in x.nodes.1.contents() {
+                    ret.modports.push(modport(tree, x));
+                }
+            }
+            RefNode::ModuleAnsiHeader(_)
+            | RefNode::InterfaceAnsiHeader(_)
+            | RefNode::ProgramAnsiHeader(_) => {
+                for x in node {
+                    if let RefNode::AnsiPortDeclaration(x) = x {
+                        let id = match x {
+                            AnsiPortDeclaration::Net(x) => identifier(tree, &x.nodes.1),
+                            AnsiPortDeclaration::Variable(x) => identifier(tree, &x.nodes.1),
+                            AnsiPortDeclaration::Paren(x) => identifier(tree, &x.nodes.2),
+                        };
+                        ret.ports.extend(id);
+                    }
+                }
+            }
+            RefNode::ListOfPorts(x) => {
+                for x in x.nodes.0.nodes.1.contents() {
+                    let id = match x {
+                        Port::NonNamed(x) => identifier(tree, &x.nodes.0),
+                        Port::Named(x) => identifier(tree, &x.nodes.1),
+                    };
+                    ret.ports.push(id.unwrap_or_default());
+                }
+            }
+            RefNode::AnsiPortDeclarationNet(x) if ignore == 0 => {
+                let (ref header, ref id, _, _) = x.nodes;
+                if let Some(NetPortHeaderOrInterfacePortHeader::InterfacePortHeader(header)) =
+                    header
+                {
+                    let (interface, modport) = match &**header {
+                        InterfacePortHeader::Identifier(x) => (
+                            identifier(tree, &x.nodes.0),
+                            x.nodes.1.as_ref().and_then(|(_, x)| identifier(tree, x)),
+                        ),
+                        InterfacePortHeader::Interface(x) => (
+                            None,
+                            x.nodes.1.as_ref().and_then(|(_, x)| identifier(tree, x)),
+                        ),
+                    };
+                    if let (Some(name), Some(locate)) =
+                        (identifier(tree, id), identifier_locate(id))
+                    {
+                        ret.interface_ports.push(InterfacePort {
+                            name,
+                            interface,
+                            modport,
+                            locate,
+                        });
+                    }
+                } else {
+                    ret.signals.extend(identifier(tree, id));
+                }
+            }
+            RefNode::InterfacePortDeclaration(x) if ignore == 0 => {
+                let (ref interface, ref modport, ref list) = x.nodes;
+                let interface = identifier(tree, interface);
+                let modport = modport.as_ref().and_then(|(_, x)| identifier(tree, x));
+                for (id, _) in list.nodes.0.contents() {
+                    if let (Some(name), Some(locate)) =
+                        (identifier(tree, id), identifier_locate(id))
+                    {
+                        ret.interface_ports.push(InterfacePort {
+                            name,
+                            interface: interface.clone(),
+                            modport: modport.clone(),
+                            locate,
+                        });
+                    }
+                }
+            }
+            RefNode::AnsiPortDeclarationVariable(x) if ignore == 0 => {
+                ret.signals.extend(identifier(tree, &x.nodes.1));
+            }
+            RefNode::VariableDeclAssignment(x) if ignore == 0 => {
+                ret.signals.extend(identifier(tree, x));
+            }
+            RefNode::NetDeclAssignment(x) if ignore == 0 => {
+                ret.signals.extend(identifier(tree, &x.nodes.0));
+            }
+            RefNode::ListOfPortIdentifiers(x) if ignore == 0 => {
+                for (x, _) in x.nodes.0.contents() {
+                    ret.signals.extend(identifier(tree, x));
+                }
+            }
+            RefNode::ListOfVariableIdentifiers(x) if ignore == 0 => {
+                for (x, _) in x.nodes.0.contents() {
+                    ret.signals.extend(identifier(tree, x));
+                }
+            }
+            RefNode::ListOfVariablePortIdentifiers(x) if ignore == 0 => {
+                for x in x.nodes.0.contents() {
+                    ret.signals.extend(identifier(tree, &x.0));
+                }
+            }
+            RefNode::ClockingDeclaration(x) if ignore == 0 => {
+                ret.signals.extend(
+                    unwrap_node!(x, ClockingIdentifier).and_then(|x| identifier(tree, vec![x])),
+                );
+            }
+            RefNode::ModuleInstantiation(x) if ignore == 0 => {
+                let (ref cell, _, ref list, _) = x.nodes;
+                let cell = identifier(tree, cell).unwrap_or_default();
+                for x in list.contents() {
+                    let (ref name, ref ports) = x.nodes;
+                    let (name, locate) = match (identifier(tree, name), identifier_locate(name)) {
+                        (Some(x), Some(y)) => (x, y),
+                        _ => continue,
+                    };
+                    ret.signals.insert(name.clone());
+                    ret.instances.push(Instance {
+                        name,
+                        cell: cell.clone(),
+                        connections: connections(tree, ports.nodes.1.as_ref()),
+                        locate,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+    ret
+}
+
+fn method(
+    tree: &SyntaxTree,
+    element: &mut Element,
+    scope: Option<&InterfaceIdentifierOrClassScope>,
+    id: RefNode,
+    kind: TfKind,
+) {
+    let name = match identifier(tree, vec![id]) {
+        Some(x) => x,
+        None => return,
+    };
+    match scope {
+        None => element.methods.push((name, kind)),
+        Some(InterfaceIdentifierOrClassScope::InterfaceIdentifier(x)) => {
+            if let Some(port) = identifier(tree, &x.0) {
+                element.port_methods.push((port, name, kind));
+            }
+        }
+        Some(InterfaceIdentifierOrClassScope::ClassScope(_)) => (),
+    }
+}
+
+fn connections(tree: &SyntaxTree, x: Option<&ListOfPortConnections>) -> Vec<Connection> {
+    let mut ret = Vec::new();
+    match x {
+        Some(ListOfPortConnections::Ordered(x)) => {
+            for x in x.nodes.0.contents() {
+                let expr = x.nodes.1.as_ref().and_then(|x| expression_name(tree, x));
+                ret.push(Connection::Ordered(expr));
+            }
+        }
+        Some(ListOfPortConnections::Named(x)) => {
+            for x in x.nodes.0.contents() {
+                match x {
+                    NamedPortConnection::Identifier(x) => {
+                        let (_, _, ref id, ref expr) = x.nodes;
+                        let name = identifier(tree, id).unwrap_or_default();
+                        match expr {
+                            Some(x) => {
+                                let expr =
+                                    x.nodes.1.as_ref().and_then(|x| expression_name(tree, x));
+                                ret.push(Connection::Named(name, expr));
+                            }
+                            None => ret.push(Connection::Implicit(name)),
+                        }
+                    }
+                    NamedPortConnection::Asterisk(_) => ret.push(Connection::Wildcard),
+                }
+            }
+        }
+        None => (),
+    }
+    ret
+}
+
+fn modport(tree: &SyntaxTree, x: &ModportItem) -> Modport {
+    let (ref id, ref list) = x.nodes;
+    let mut ret = Modport {
+        name: identifier(tree, id).unwrap_or_default(),
+        ports: Vec::new(),
+        methods: Vec::new(),
+        clockings: Vec::new(),
+        locate: identifier_locate(id).unwrap_or_default(),
+    };
+    for x in list.nodes.1.contents() {
+        match x {
+            ModportPortsDeclaration::Simple(x) => {
+                let (ref direction, ref list) = x.nodes.1.nodes;
+                let direction = match direction {
+                    PortDirection::Input(_) => Direction::Input,
+                    PortDirection::Output(_) => Direction::Output,
+                    PortDirection::Inout(_) => Direction::Inout,
+                    PortDirection::Ref(_) => Direction::Ref,
+                };
+                for x in list.contents() {
+                    let (id, expression): (&PortIdentifier, _) = match x {
+                        ModportSimplePort::Ordered(x) => (&x.nodes.0, None),
+                        ModportSimplePort::Named(x) => (
+                            &x.nodes.1,
+                            Some(
+                                x.nodes
+                                    .2
+                                    .nodes
+                                    .1
+                                    .as_ref()
+                                    .and_then(|x| tree.get_str_trim(x))
+                                    .map(String::from)
+                                    .unwrap_or_default(),
+                            ),
+                        ),
+                    };
+                    if let (Some(name), Some(locate)) =
+                        (identifier(tree, id), identifier_locate(id))
+                    {
+                        ret.ports.push(ModportPort {
+                            name,
+                            direction,
+                            expression,
+                            locate,
+                        });
+                    }
+                }
+            }
+            ModportPortsDeclaration::Tf(x) => {
+                let (ref import_export, ref list) = x.nodes.1.nodes;
+                let is_export = matches!(import_export, ImportExport::Export(_));
+                for x in list.contents() {
+                    let (id, kind): (RefNode, _) = match x {
+                        ModportTfPort::MethodPrototype(x) => match &**x {
+                            MethodPrototype::TaskPrototype(x) => {
+                                ((&x.nodes.2).into(), Some(TfKind::Task))
+                            }
+                            MethodPrototype::FunctionPrototype(x) => {
+                                ((&x.nodes.3).into(), Some(TfKind::Function))
+                            }
+                        },
+                        ModportTfPort::TfIdentifier(x) => ((&**x).into(), None),
+                    };
+                    if let (Some(name), Some(locate)) = (
+                        identifier(tree, vec![id.clone()]),
+                        identifier_locate(vec![id]),
+                    ) {
+                        ret.methods.push(ModportMethod {
+                            name,
+                            is_export,
+                            kind,
+                            locate,
+                        });
+                    }
+                }
+            }
+            ModportPortsDeclaration::Clocking(x) => {
+                ret.clockings.extend(identifier(tree, &x.nodes.1.nodes.1));
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_interfaces() {
+        let src = r##"interface bus_if;
+  logic req, gnt;
+  logic [7:0] data;
+  function automatic int parity(); return ^data; endfunction
+  modport master(output req, data, input gnt, import parity, export task notify());
+  modport slave(input req, data, output gnt, import function int parity(), import reset);
+  modport monitor(input req, .payload(data), input missing);
+endinterface
+
+module master_m(bus_if.master bus);
+  task bus.notify(); endtask
+endmodule
+
+module slave_m(bus_if bus);
+endmodule
+
+module wrap(bus_if.master bus);
+  master_m u_master(.bus);
+endmodule
+
+module master2_m(bus_if.master bus);
+endmodule
+
+module top;
+  bus_if u_bus();
+  bus_if u_bus2();
+  master2_m u_master2(u_bus2);
+  wrap u_wrap(u_bus);
+  slave_m u_slave(.bus(u_bus.slave));
+endmodule"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let resolver = InterfaceResolver::new(&tree);
+
+        assert_eq!(resolver.interfaces(), vec!["bus_if"]);
+        let master = resolver.modport("bus_if", "master").unwrap();
+        let ports: Vec<(&str, Direction)> = master
+            .ports
+            .iter()
+            .map(|x| (x.name.as_str(), x.direction))
+            .collect();
+        assert_eq!(
+            ports,
+            vec![
+                ("req", Direction::Output),
+                ("data", Direction::Output),
+                ("gnt", Direction::Input)
+            ]
+        );
+        let monitor = resolver.modport("bus_if", "monitor").unwrap();
+        assert_eq!(monitor.ports[1].expression.as_deref(), Some("data"));
+
+        let slave = resolver
+            .connections()
+            .iter()
+            .find(|x| x.instance == "u_slave")
+            .unwrap();
+        assert_eq!(
+            slave.target,
+            ConnectionTarget::Instance(String::from("u_bus"))
+        );
+        assert_eq!(slave.interface.as_deref(), Some("bus_if"));
+        assert_eq!(slave.modport.as_deref(), Some("slave"));
+
+        let inner = resolver
+            .connections()
+            .iter()
+            .find(|x| x.instance == "u_master")
+            .unwrap();
+        assert_eq!(inner.target, ConnectionTarget::Port(String::from("bus")));
+        assert_eq!(
+            resolver.sources("master_m", "bus"),
+            vec![(String::from("top"), String::from("u_bus"))]
+        );
+
+        let diagnostics = resolver.check();
+        assert_eq!(diagnostics.len(), 3);
+        assert!(diagnostics.iter().any(|x| matches!(x,
+            InterfaceDiagnostic::UnknownSignal { signal, .. } if signal == "missing")));
+        assert!(diagnostics.iter().any(|x| matches!(x,
+            InterfaceDiagnostic::MissingImport { method, .. } if method == "reset")));
+        assert!(diagnostics.iter().any(|x| matches!(x,
+            InterfaceDiagnostic::MissingExport { module, .. } if module == "master2_m")));
+    }
+}
@@ -1,19 +1,44 @@
 #![recursion_limit = "256"]
 
 use nom_greedyerror::error_position;
+use std::collections::HashSet;
 use std::fmt;
 use std::hash::BuildHasher;
 use std::path::{Path, PathBuf};
 pub use sv_parser_error::Error;
+pub use sv_parser_parser::lexer::{tokenize, Token, TokenKind};
 use sv_parser_parser::{
-    lib_parser, lib_parser_incomplete, sv_parser, sv_parser_incomplete, Span, SpanInfo,
+    lib_parser, lib_parser_incomplete, sv_parser, sv_parser_incomplete, sv_parser_incomplete_with,
+    sv_parser_with, IResult, Span, SpanInfo, MACRO_USAGE_BEGIN,
 };
+pub use sv_parser_parser::{Extension, Standard};
 pub use sv_parser_pp::preprocess::{
-    preprocess, preprocess_str, Define, DefineText, Defines, PreprocessedText,
+    expand_text_macro_str, expand_text_macro_usage, preprocess, preprocess_str,
+    preprocess_str_with, preprocess_with, Define, DefineText, Defines, Encoding, MacroExpansion,
+    PreprocessOptions, PreprocessedText, TranslateOff,
 };
 pub use sv_parser_syntaxtree::*;
 pub use sv_parser_pp::range as sv_parser_pp_range;
 
+pub mod annotations;
+pub mod class_graph;
+pub mod clocks;
+pub mod comb;
+pub mod const_eval;
+pub mod dataflow;
+pub mod doc_comment;
+pub mod fsm;
+pub mod generate;
+pub mod incremental;
+pub mod interfaces;
+pub mod library;
+pub mod protect;
+pub mod semantic;
+pub mod standard;
+pub mod types;
+pub mod width;
+mod utils;
+
 pub struct SyntaxTree {
     node: AnyNode,
     pub text: PreprocessedText,
@@ -74,6 +99,21 @@ impl SyntaxTree {
     pub fn get_origin(&self, locate: &Locate) -> Option<(&PathBuf, usize)> {
         self.text.origin(locate.offset)
     }
+
+    /// Get byte offset in the source file of the specified `Locate`
+    pub fn get_source_offset(&self, locate: &Locate) -> Option<(&PathBuf, usize)> {
+        self.text.source_offset(locate.offset)
+    }
+
+    /// Get 1-based line and column in the source file of the specified `Locate`
+    pub fn get_line_column(&self, locate: &Locate) -> Option<(&PathBuf, usize, usize)> {
+        self.text.line_column(locate.offset)
+    }
+
+    /// Get 1-based line and column of the specified `Locate` with `line directives applied
+    pub fn get_logical_line_column(&self, locate: &Locate) -> Option<(&PathBuf, usize, usize)> {
+        self.text.logical_line_column(locate.offset)
+    }
 }
 
 impl fmt::Display for SyntaxTree {
@@ -204,12 +244,251 @@ pub fn parse_sv_pp(
     defines: Defines,
     allow_incomplete: bool,
 ) -> Result<(SyntaxTree, Defines), Error> {
-    let span = Span::new_extra(text.text(), SpanInfo::default());
-    let result = if allow_incomplete {
-        sv_parser_incomplete(span)
+    if allow_incomplete {
+        sv_syntax_tree(text, defines, sv_parser_incomplete)
+    } else {
+        sv_syntax_tree(text, defines, sv_parser)
+    }
+}
+
+/// Options of `parse_sv_with` and `parse_sv_pp_with`, which can be combined.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParseOptions {
+    /// The language standard of the source text
+    ///
+    /// The standard selects the keywords and the grammar used outside of `begin_keywords, and
+    /// every construct newer than the standard in effect is reported by `Error::Version`.
+    /// Without the standard, the keywords of IEEE 1800-2017 are used, IEEE 1800-2023 constructs
+    /// are rejected as parse errors and no version is checked.
+    pub standard: Option<Standard>,
+    /// The grammar extensions to enable
+    pub extensions: Vec<Extension>,
+    /// The encoding to decode the source files
+    pub encoding: Encoding,
+    /// Skip regions between comment pragmas like `// synopsys translate_off`
+    pub translate_off: Option<TranslateOff>,
+    /// Keep text macro usages as `MacroUsage*` nodes in front of their expansions
+    pub macro_preserving: bool,
+}
+
+/// Parse with the options.
+///
+/// In the macro-preserving mode, expressions, statements, module items and class items can be
+/// macro usage nodes. Usages whose expansion isn't balanced are expanded as usual by the
+/// preprocessor. If the parse still fails, the usages from the one before the error are expanded
+/// as usual and the file is parsed again, and the file is parsed without the mode if it fails
+/// again.
+pub fn parse_sv_with<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    path: T,
+    pre_defines: &Defines<V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    allow_incomplete: bool,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let preprocess = |x: &PreprocessOptions| {
+        preprocess_with(
+            path.as_ref(),
+            pre_defines,
+            include_paths,
+            false, // strip_comments
+            ignore_include,
+            x,
+        )
+    };
+    parse_with(preprocess, allow_incomplete, options)
+}
+
+/// Parse the string with the options.
+///
+/// See `parse_sv_with` for the macro-preserving mode.
+pub fn parse_sv_str_with<T: AsRef<Path>, U: AsRef<Path>, V: BuildHasher>(
+    s: &str,
+    path: T,
+    pre_defines: &Defines<V>,
+    include_paths: &[U],
+    ignore_include: bool,
+    allow_incomplete: bool,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let preprocess = |x: &PreprocessOptions| {
+        preprocess_str_with(
+            s,
+            path.as_ref(),
+            pre_defines,
+            include_paths,
+            ignore_include,
+            false, // strip_comments
+            x,
+        )
+    };
+    parse_with(preprocess, allow_incomplete, options)
+}
+
+fn preprocess_options(
+    options: &ParseOptions,
+    macro_preserving: Option<HashSet<(PathBuf, usize)>>,
+) -> PreprocessOptions {
+    PreprocessOptions {
+        standard: options.standard,
+        translate_off: options.translate_off.clone(),
+        encoding: options.encoding.clone(),
+        macro_preserving,
+    }
+}
+
+/// Parse the preprocessed text with the options.
+///
+/// `encoding` and `translate_off` aren't used. In the macro-preserving mode, the text is expected
+/// to be given by `preprocess_with` with `PreprocessOptions::macro_preserving`, and no usage is
+/// expanded again if the parse fails.
+pub fn parse_sv_pp_with(
+    text: PreprocessedText,
+    defines: Defines,
+    allow_incomplete: bool,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let (tree, defines) = sv_syntax_tree(text, defines, |x| {
+        sv_parser_options(x, allow_incomplete, options)
+    })?;
+    check_version(&tree, options)?;
+    Ok((tree, defines))
+}
+
+fn sv_parser_options<'a>(
+    s: Span<'a>,
+    allow_incomplete: bool,
+    options: &ParseOptions,
+) -> IResult<Span<'a>, SourceText> {
+    let ParseOptions {
+        standard,
+        ref extensions,
+        macro_preserving,
+        ..
+    } = *options;
+    if allow_incomplete {
+        sv_parser_incomplete_with(s, standard, extensions, macro_preserving)
+    } else {
+        sv_parser_with(s, standard, extensions, macro_preserving)
+    }
+}
+
+// Reject the constructs newer than the standard of the options.
+fn check_version(tree: &SyntaxTree, options: &ParseOptions) -> Result<(), Error> {
+    let standard = match options.standard {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    let violations: Vec<_> = standard::check_standard(tree, standard)
+        .iter()
+        .map(|x| {
+            let origin = tree
+                .text
+                .origin(x.locate.offset)
+                .map(|origin| (origin.0.clone(), origin.1));
+            (origin, x.to_string())
+        })
+        .collect();
+    if violations.is_empty() {
+        Ok(())
     } else {
-        sv_parser(span)
+        Err(Error::Version(violations))
+    }
+}
+
+// Parse the text given by the preprocess function, which can be called again in the
+// macro-preserving mode.
+fn parse_with<F>(
+    preprocess: F,
+    allow_incomplete: bool,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), Error>
+where
+    F: Fn(&PreprocessOptions) -> Result<(PreprocessedText, Defines), Error>,
+{
+    if options.macro_preserving {
+        let preprocess = |fallback: &HashSet<(PathBuf, usize)>| {
+            preprocess(&preprocess_options(options, Some(fallback.clone())))
+        };
+
+        let (text, defines) = preprocess(&HashSet::new())?;
+        let fallback = match macro_preserving_syntax_tree(text, defines, allow_incomplete, options)
+        {
+            Ok((tree, defines)) => {
+                check_version(&tree, options)?;
+                return Ok((tree, defines));
+            }
+            Err(x) => x,
+        };
+        if !fallback.is_empty() {
+            let (text, defines) = preprocess(&fallback)?;
+            if let Ok((tree, defines)) =
+                macro_preserving_syntax_tree(text, defines, allow_incomplete, options)
+            {
+                check_version(&tree, options)?;
+                return Ok((tree, defines));
+            }
+        }
+    }
+
+    let options = ParseOptions {
+        macro_preserving: false,
+        ..options.clone()
+    };
+    let (text, defines) = preprocess(&preprocess_options(&options, None))?;
+    parse_sv_pp_with(text, defines, allow_incomplete, &options)
+}
+
+// Parse the text of the macro-preserving preprocessor, or return the usages to be expanded as
+// usual in the next try.
+fn macro_preserving_syntax_tree(
+    text: PreprocessedText,
+    defines: Defines,
+    allow_incomplete: bool,
+    options: &ParseOptions,
+) -> Result<(SyntaxTree, Defines), HashSet<(PathBuf, usize)>> {
+    let span = Span::new_extra(text.text(), SpanInfo::default());
+    let pos = match sv_parser_options(span, allow_incomplete, options) {
+        Ok((_, x)) => {
+            return Ok((
+                SyntaxTree {
+                    node: x.into(),
+                    text,
+                },
+                defines,
+            ))
+        }
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => error_position(&e),
+        Err(nom::Err::Incomplete(_)) => None,
+    };
+    Err(pos
+        .map(|pos| kept_macro_usages(&text, pos))
+        .unwrap_or_default())
+}
+
+// Find the kept macro usages from the one containing or preceding the parse error, as the file
+// and the offset of each usage.
+fn kept_macro_usages(text: &PreprocessedText, pos: usize) -> HashSet<(PathBuf, usize)> {
+    let s = text.text();
+    let begin = match s[..pos.min(s.len())].rfind(MACRO_USAGE_BEGIN) {
+        Some(x) => x,
+        None => return HashSet::new(),
     };
+    s[begin..]
+        .match_indices(MACRO_USAGE_BEGIN)
+        .filter_map(|(i, _)| text.origin(begin + i + MACRO_USAGE_BEGIN.len()))
+        .map(|(path, offset)| (path.clone(), offset))
+        .collect()
+}
+
+fn sv_syntax_tree<F: Fn(Span) -> IResult<Span, SourceText>>(
+    text: PreprocessedText,
+    defines: Defines,
+    parser: F,
+) -> Result<(SyntaxTree, Defines), Error> {
+    let span = Span::new_extra(text.text(), SpanInfo::default());
+    let result = parser(span);
     match result {
         Ok((_, x)) => Ok((
             SyntaxTree {
@@ -423,4 +702,76 @@ endmodule"##;
         let ret = parse_sv_str(src, &path, &defines, &[""], false, false);
         assert!(ret.is_ok());
     }
+
+    #[test]
+    fn test_macro_preserving() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testcases/macro_usage.sv");
+        let options = ParseOptions {
+            macro_preserving: true,
+            ..ParseOptions::default()
+        };
+        let (syntax_tree, _) =
+            parse_sv_with(&path, &HashMap::new(), &[""], false, false, &options).unwrap();
+
+        // `LHS doesn't form a statement and is expanded by the preprocessor, and `INT fails
+        // to parse as a data type and is expanded in the retry
+        let usages: Vec<_> = syntax_tree
+            .into_iter()
+            .filter_map(|x| match x {
+                RefNode::MacroUsage(x) => syntax_tree.get_str(&x.nodes.1),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            usages,
+            vec!["`REGS", "`ADD(r0, 1)", "`INFO(\"hello\")", "`GETTER(x)"]
+        );
+
+        assert!(unwrap_node!(&syntax_tree, MacroUsageModuleOrGenerateItem).is_some());
+        assert!(unwrap_node!(&syntax_tree, MacroUsageExpression).is_some());
+        assert!(unwrap_node!(&syntax_tree, MacroUsageStatement).is_some());
+        assert!(unwrap_node!(&syntax_tree, MacroUsageClassItem).is_some());
+    }
+    #[test]
+    fn test_parse_options() {
+        // Verilog-AMS on the keywords of IEEE1364-2005, where `logic` is an identifier
+        let src = r##"module res(p, n);
+  inout p, n;
+  electrical p, n;
+  wire logic;
+  analog begin
+    V(p, n) <+ 0.0;
+  end
+endmodule"##;
+        let parse = |options: &ParseOptions| {
+            let (text, defines) =
+                preprocess_str(src, "", &HashMap::new(), &[""], false, false, 0, 0).unwrap();
+            parse_sv_pp_with(text, defines, false, options)
+        };
+        let mut options = ParseOptions {
+            standard: Some(Standard::Ieee1364_2005),
+            extensions: vec![Extension::VerilogAms],
+            ..ParseOptions::default()
+        };
+        assert!(parse(&options).is_ok());
+        options.extensions.clear();
+        assert!(parse(&options).is_err());
+        options.extensions.push(Extension::VerilogAms);
+        options.standard = Some(Standard::Ieee1800_2017);
+        assert!(parse(&options).is_err());
+    }
+
+    #[test]
+    fn test_translate_off() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testcases/translate_off.sv");
+        let parse = |options: &ParseOptions| {
+            parse_sv_with(&path, &HashMap::new(), &[""], false, false, options)
+        };
+        assert!(parse(&ParseOptions::default()).is_err());
+        let options = ParseOptions {
+            translate_off: Some(TranslateOff::default()),
+            ..ParseOptions::default()
+        };
+        assert!(parse(&options).is_ok());
+    }
 }
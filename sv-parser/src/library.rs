@@ -0,0 +1,861 @@
+use crate::utils::*;
+use crate::*;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Component;
+
+const RECURSIVE_LIMIT: usize = 64;
+
+// -----------------------------------------------------------------------------
+
+/// A library declared by `library` statement of IEEE1800-2017 33.3.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Library {
+    pub name: String,
+    /// File path patterns resolved to absolute or lib.map relative paths
+    pub files: Vec<PathBuf>,
+    pub incdirs: Vec<PathBuf>,
+}
+
+/// A cell reference of `[library.]cell` form.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellRef {
+    pub library: Option<String>,
+    pub cell: String,
+}
+
+/// The target of `use` clause.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UseTarget {
+    pub cell: Option<CellRef>,
+    /// Parameter overrides as `(name, value)`
+    pub parameters: Vec<(String, String)>,
+    /// `:config` is specified
+    pub config: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigRule {
+    InstanceLiblist {
+        instance: String,
+        liblist: Vec<String>,
+    },
+    InstanceUse {
+        instance: String,
+        target: UseTarget,
+    },
+    CellLiblist {
+        cell: CellRef,
+        liblist: Vec<String>,
+    },
+    CellUse {
+        cell: CellRef,
+        target: UseTarget,
+    },
+}
+
+/// A configuration declared by `config` of IEEE1800-2017 33.4.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Configuration {
+    pub name: String,
+    pub design: Vec<CellRef>,
+    pub default_liblist: Option<Vec<String>>,
+    pub rules: Vec<ConfigRule>,
+}
+
+/// A cell definition and its instances, which is the input of configuration elaboration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellDefinition {
+    pub library: String,
+    pub name: String,
+    /// Instances as `(instance name, cell name)`
+    pub instances: Vec<(String, String)>,
+}
+
+/// The cell definition bound to an instance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binding {
+    /// Hierarchical instance path such as `top.u0.u1`
+    pub path: String,
+    /// The cell name at the instantiation
+    pub cell: String,
+    /// The bound cell definition, or `None` if it is not found in any library
+    pub definition: Option<CellRef>,
+    pub parameters: Vec<(String, String)>,
+    /// The configuration applied to the instance
+    pub config: String,
+}
+
+// -----------------------------------------------------------------------------
+
+/// Libraries and configurations from library map files.
+#[derive(Clone, Debug)]
+pub struct LibraryMap {
+    libraries: Vec<Library>,
+    configs: Vec<Configuration>,
+    includes: Vec<PathBuf>,
+}
+
+impl std::default::Default for LibraryMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LibraryMap {
+    pub fn new() -> Self {
+        LibraryMap {
+            libraries: Vec::new(),
+            configs: Vec::new(),
+            includes: Vec::new(),
+        }
+    }
+
+    /// Read a library map file and the files included by `include` statements.
+    pub fn from_file<T: AsRef<Path>>(path: T) -> Result<Self, Error> {
+        let mut ret = LibraryMap::new();
+        let mut done = HashSet::new();
+        let mut pending = vec![PathBuf::from(path.as_ref())];
+        while let Some(path) = pending.pop() {
+            if !done.insert(normalize(&path)) {
+                continue;
+            }
+            let defines: Defines = HashMap::new();
+            let include_paths: [&Path; 0] = [];
+            let (tree, _) = parse_lib(&path, &defines, &include_paths, false, false)?;
+            let includes = ret.includes.len();
+            ret.add(&tree, &path);
+            pending.extend(ret.includes[includes..].iter().rev().cloned());
+        }
+        Ok(ret)
+    }
+
+    /// Add libraries and configurations of a `SyntaxTree` parsed from `path`.
+    ///
+    /// Relative file paths in the tree are based on the directory of `path`.
+    /// Configurations in SystemVerilog source files can be added too.
+    pub fn add<T: AsRef<Path>>(&mut self, tree: &SyntaxTree, path: T) {
+        let base = path
+            .as_ref()
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        for node in tree {
+            match node {
+                RefNode::LibraryDeclaration(x) => {
+                    let (_, ref id, ref files, ref incdirs, _) = x.nodes;
+                    let name = identifier(tree, id).unwrap_or_default();
+                    let files = files
+                        .contents()
+                        .into_iter()
+                        .map(|x| base.join(file_path_spec(tree, x)))
+                        .collect();
+                    let incdirs = incdirs
+                        .as_ref()
+                        .map(|(_, x)| {
+                            x.contents()
+                                .into_iter()
+                                .map(|x| base.join(file_path_spec(tree, x)))
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    self.libraries.push(Library {
+                        name,
+                        files,
+                        incdirs,
+                    });
+                }
+                RefNode::IncludeStatement(x) => {
+                    self.includes
+                        .push(base.join(file_path_spec(tree, &x.nodes.1)));
+                }
+                RefNode::ConfigDeclaration(x) => self.configs.push(configuration(tree, x)),
+                _ => (),
+            }
+        }
+    }
+
+    /// Get libraries in declaration order.
+    pub fn libraries(&self) -> &[Library] {
+        &self.libraries
+    }
+
+    /// Get configurations in declaration order.
+    pub fn configs(&self) -> &[Configuration] {
+        &self.configs
+    }
+
+    /// Get the library map files included by `include` statements.
+    pub fn includes(&self) -> &[PathBuf] {
+        &self.includes
+    }
+
+    pub fn library(&self, name: &str) -> Option<&Library> {
+        self.libraries.iter().find(|x| x.name == name)
+    }
+
+    pub fn config(&self, name: &str) -> Option<&Configuration> {
+        self.configs.iter().find(|x| x.name == name)
+    }
+
+    /// Get the library which the source file belongs to.
+    ///
+    /// If the file matches multiple patterns, the most specific one is selected according to
+    /// IEEE1800-2017 33.3.1.1: an explicit file name, then a wildcarded file name, then a
+    /// directory. Files matching no pattern belong to `work`.
+    pub fn library_of<T: AsRef<Path>>(&self, path: T) -> &str {
+        let path = normalize(path.as_ref());
+        let mut ret = ("work", 0);
+        for library in &self.libraries {
+            for pattern in &library.files {
+                let specificity = specificity(pattern);
+                if specificity > ret.1 && matches(&normalize(pattern), &path) {
+                    ret = (&library.name, specificity);
+                }
+            }
+        }
+        ret.0
+    }
+
+    /// Get the source files of the library by expanding the file path patterns.
+    pub fn files(&self, library: &str) -> Vec<PathBuf> {
+        let mut ret = Vec::new();
+        if let Some(x) = self.library(library) {
+            for pattern in &x.files {
+                for file in expand(pattern) {
+                    if self.library_of(&file) == library && !ret.contains(&file) {
+                        ret.push(file);
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// Decide the cell definition of each instance under the design of the configuration.
+    ///
+    /// `cells` are the available cell definitions. Rules are applied in the order of
+    /// IEEE1800-2017 33.4.1: `instance` clauses, `cell` clauses and then `default liblist`.
+    /// Without any liblist, the library of the parent cell is searched first and then the
+    /// libraries in declaration order.
+    pub fn elaborate(&self, config: &str, cells: &[CellDefinition]) -> Vec<Binding> {
+        let config = match self.config(config) {
+            Some(x) => x,
+            None => return Vec::new(),
+        };
+        let mut ret = Vec::new();
+        for top in &config.design {
+            let definition = match &top.library {
+                Some(_) => self.find(cells, &[], top),
+                None => self.search(cells, config, None, None, &top.cell),
+            };
+            let binding = Binding {
+                path: top.cell.clone(),
+                cell: top.cell.clone(),
+                definition,
+                parameters: Vec::new(),
+                config: config.name.clone(),
+            };
+            let root = (top.cell.as_str(), top.cell.as_str());
+            self.elaborate_instance(cells, config, root, binding, None, &mut ret, 0);
+        }
+        ret
+    }
+
+    // -------------------------------------------------------------------------
+
+    #[allow(clippy::too_many_arguments)]
+    fn elaborate_instance(
+        &self,
+        cells: &[CellDefinition],
+        config: &Configuration,
+        root: (&str, &str),
+        binding: Binding,
+        liblist: Option<&[String]>,
+        ret: &mut Vec<Binding>,
+        depth: usize,
+    ) {
+        let path = binding.path.clone();
+        let definition = binding.definition.clone();
+        ret.push(binding);
+        if depth > RECURSIVE_LIMIT {
+            return;
+        }
+        let definition = match definition.and_then(|x| self.find(cells, &[], &x)) {
+            Some(x) => x,
+            None => return,
+        };
+        let definition = cells
+            .iter()
+            .find(|x| Some(&x.library) == definition.library.as_ref() && x.name == definition.cell)
+            .unwrap();
+
+        for (instance, cell) in &definition.instances {
+            let path = format!("{}.{}", path, instance);
+            // Instance paths in rules start with the top cell of the configuration
+            let relative = format!("{}{}", root.1, &path[root.0.len()..]);
+            let mut liblist = liblist;
+            let mut target = None;
+
+            let mut rule_found = false;
+            for rule in &config.rules {
+                match rule {
+                    ConfigRule::InstanceUse {
+                        instance,
+                        target: x,
+                    } if *instance == relative => {
+                        target = Some(x);
+                        rule_found = true;
+                    }
+                    ConfigRule::InstanceLiblist {
+                        instance,
+                        liblist: x,
+                    } if *instance == relative => {
+                        liblist = Some(x);
+                        rule_found = true;
+                    }
+                    _ => (),
+                }
+            }
+            if !rule_found {
+                for rule in &config.rules {
+                    match rule {
+                        ConfigRule::CellUse { cell: x, target: y }
+                            if x.cell == *cell
+                                && self.cell_matches(cells, config, liblist, definition, x) =>
+                        {
+                            target = Some(y);
+                        }
+                        ConfigRule::CellLiblist {
+                            cell: x,
+                            liblist: y,
+                        } if x.cell == *cell
+                            && self.cell_matches(cells, config, liblist, definition, x) =>
+                        {
+                            liblist = Some(y);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+
+            let mut binding = Binding {
+                path: path.clone(),
+                cell: cell.clone(),
+                definition: None,
+                parameters: Vec::new(),
+                config: config.name.clone(),
+            };
+            match target {
+                Some(target) => {
+                    binding.parameters = target.parameters.clone();
+                    let used = target.cell.clone().unwrap_or_else(|| CellRef {
+                        library: None,
+                        cell: cell.clone(),
+                    });
+                    if target.config {
+                        // Hierarchical configuration
+                        if let Some(x) = self.config(&used.cell) {
+                            if let Some(top) = x.design.first() {
+                                let mut binding = binding.clone();
+                                binding.definition = match &top.library {
+                                    Some(_) => self.find(cells, &[], top),
+                                    None => self.search(cells, x, None, None, &top.cell),
+                                };
+                                binding.config = x.name.clone();
+                                self.elaborate_instance(
+                                    cells,
+                                    x,
+                                    (&path, &top.cell),
+                                    binding,
+                                    None,
+                                    ret,
+                                    depth + 1,
+                                );
+                                continue;
+                            }
+                        }
+                    }
+                    binding.definition = match &used.library {
+                        Some(_) => self.find(cells, &[], &used),
+                        None => self.search(
+                            cells,
+                            config,
+                            liblist,
+                            Some(&definition.library),
+                            &used.cell,
+                        ),
+                    };
+                }
+                None => {
+                    binding.definition =
+                        self.search(cells, config, liblist, Some(&definition.library), cell);
+                }
+            }
+            self.elaborate_instance(cells, config, root, binding, liblist, ret, depth + 1);
+        }
+    }
+
+    /// Check whether `cell lib.name` clause applies to the cell instantiated in `parent`.
+    fn cell_matches(
+        &self,
+        cells: &[CellDefinition],
+        config: &Configuration,
+        liblist: Option<&[String]>,
+        parent: &CellDefinition,
+        x: &CellRef,
+    ) -> bool {
+        match &x.library {
+            Some(library) => {
+                let found = self.search(cells, config, liblist, Some(&parent.library), &x.cell);
+                found.and_then(|x| x.library).as_ref() == Some(library)
+            }
+            None => true,
+        }
+    }
+
+    fn find(&self, cells: &[CellDefinition], libraries: &[String], x: &CellRef) -> Option<CellRef> {
+        let mut libraries = libraries.to_vec();
+        if let Some(x) = &x.library {
+            libraries = vec![x.clone()];
+        }
+        for library in libraries {
+            if cells
+                .iter()
+                .any(|y| y.library == library && y.name == x.cell)
+            {
+                return Some(CellRef {
+                    library: Some(library),
+                    cell: x.cell.clone(),
+                });
+            }
+        }
+        None
+    }
+
+    /// Search the cell through the library search order.
+    fn search(
+        &self,
+        cells: &[CellDefinition],
+        config: &Configuration,
+        liblist: Option<&[String]>,
+        parent: Option<&str>,
+        cell: &str,
+    ) -> Option<CellRef> {
+        let libraries: Vec<String> = match liblist.or(config.default_liblist.as_deref()) {
+            Some(x) => x.to_vec(),
+            None => {
+                let mut ret: Vec<String> = parent.map(String::from).into_iter().collect();
+                let declared = self.libraries.iter().map(|x| &x.name);
+                for x in declared.chain(cells.iter().map(|x| &x.library)) {
+                    if !ret.contains(x) {
+                        ret.push(x.clone());
+                    }
+                }
+                ret
+            }
+        };
+        let x = CellRef {
+            library: None,
+            cell: String::from(cell),
+        };
+        self.find(cells, &libraries, &x)
+    }
+}
+
+impl CellDefinition {
+    /// Collect module, interface, program and primitive definitions and their instances in
+    /// the `SyntaxTree`.
+    pub fn from_tree(library: &str, tree: &SyntaxTree) -> Vec<CellDefinition> {
+        let mut ret = Vec::new();
+        for node in tree {
+            let name = match &node {
+                RefNode::UdpDeclaration(_) => unwrap_node!(node.clone(), UdpIdentifier)
+                    .and_then(|x| identifier(tree, vec![x])),
+                _ => scope_name(tree, &node),
+            };
+            let name = match (&node, name) {
+                (RefNode::ModuleDeclaration(_), Some(x))
+                | (RefNode::InterfaceDeclaration(_), Some(x))
+                | (RefNode::ProgramDeclaration(_), Some(x))
+                | (RefNode::UdpDeclaration(_), Some(x)) => x,
+                _ => continue,
+            };
+            let mut instances = Vec::new();
+            for x in node {
+                if let RefNode::ModuleInstantiation(x) = x {
+                    let (ref cell, _, ref list, _) = x.nodes;
+                    let cell = identifier(tree, cell).unwrap_or_default();
+                    for x in list.contents() {
+                        if let Some(instance) = identifier(tree, &x.nodes.0) {
+                            instances.push((instance, cell.clone()));
+                        }
+                    }
+                }
+            }
+            ret.push(CellDefinition {
+                library: String::from(library),
+                name,
+                instances,
+            });
+        }
+        ret
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+fn file_path_spec(tree: &SyntaxTree, x: &FilePathSpec) -> String {
+    let s = tree.get_str_trim(x).unwrap_or("");
+    match x {
+        FilePathSpec::Literal(_) => String::from(s.trim_matches('"')),
+        FilePathSpec::NonLiteral(_) => String::from(s),
+    }
+}
+
+fn cell_ref<'a, T: Into<RefNodes<'a>>>(
+    tree: &SyntaxTree,
+    library: Option<&LibraryIdentifier>,
+    cell: T,
+) -> CellRef {
+    CellRef {
+        library: library.and_then(|x| identifier(tree, x)),
+        cell: identifier(tree, cell).unwrap_or_default(),
+    }
+}
+
+fn liblist(tree: &SyntaxTree, x: &LiblistClause) -> Vec<String> {
+    x.nodes
+        .1
+        .iter()
+        .filter_map(|x| identifier(tree, x))
+        .collect()
+}
+
+fn use_target(tree: &SyntaxTree, x: &UseClause) -> UseTarget {
+    let parameters = |x: &List<Symbol, NamedParameterAssignment>| {
+        x.contents()
+            .into_iter()
+            .filter_map(|x| {
+                let (_, ref id, ref value) = x.nodes;
+                let value = value
+                    .nodes
+                    .1
+                    .as_ref()
+                    .and_then(|x| tree.get_str_trim(x))
+                    .unwrap_or("");
+                identifier(tree, id).map(|x| (x, String::from(value)))
+            })
+            .collect()
+    };
+    match x {
+        UseClause::Cell(x) => {
+            let (_, ref library, ref cell, ref config) = x.nodes;
+            UseTarget {
+                cell: Some(cell_ref(tree, library.as_ref().map(|x| &x.0), cell)),
+                parameters: Vec::new(),
+                config: config.is_some(),
+            }
+        }
+        UseClause::Named(x) => {
+            let (_, ref list, ref config) = x.nodes;
+            UseTarget {
+                cell: None,
+                parameters: parameters(list),
+                config: config.is_some(),
+            }
+        }
+        UseClause::CellNamed(x) => {
+            let (_, ref library, ref cell, ref list, ref config) = x.nodes;
+            UseTarget {
+                cell: Some(cell_ref(tree, library.as_ref().map(|x| &x.0), cell)),
+                parameters: parameters(list),
+                config: config.is_some(),
+            }
+        }
+    }
+}
+
+fn instance_name(tree: &SyntaxTree, x: &InstClause) -> String {
+    let (ref top, ref path) = x.nodes.1.nodes;
+    let mut ret = identifier(tree, top).unwrap_or_default();
+    for (_, x) in path {
+        ret.push('.');
+        ret.push_str(&identifier(tree, x).unwrap_or_default());
+    }
+    ret
+}
+
+fn configuration(tree: &SyntaxTree, x: &ConfigDeclaration) -> Configuration {
+    let (_, ref id, _, _, ref design, ref rules, _, _) = x.nodes;
+    let mut ret = Configuration {
+        name: identifier(tree, id).unwrap_or_default(),
+        design: design
+            .nodes
+            .1
+            .iter()
+            .map(|(library, cell)| cell_ref(tree, library.as_ref().map(|x| &x.0), cell))
+            .collect(),
+        default_liblist: None,
+        rules: Vec::new(),
+    };
+    for rule in rules {
+        match rule {
+            ConfigRuleStatement::Default(x) => {
+                ret.default_liblist = Some(liblist(tree, &x.nodes.1));
+            }
+            ConfigRuleStatement::InstLib(x) => ret.rules.push(ConfigRule::InstanceLiblist {
+                instance: instance_name(tree, &x.nodes.0),
+                liblist: liblist(tree, &x.nodes.1),
+            }),
+            ConfigRuleStatement::InstUse(x) => ret.rules.push(ConfigRule::InstanceUse {
+                instance: instance_name(tree, &x.nodes.0),
+                target: use_target(tree, &x.nodes.1),
+            }),
+            ConfigRuleStatement::CellLib(x) => {
+                let (_, ref library, ref cell) = x.nodes.0.nodes;
+                ret.rules.push(ConfigRule::CellLiblist {
+                    cell: cell_ref(tree, library.as_ref().map(|x| &x.0), cell),
+                    liblist: liblist(tree, &x.nodes.1),
+                })
+            }
+            ConfigRuleStatement::CellUse(x) => {
+                let (_, ref library, ref cell) = x.nodes.0.nodes;
+                ret.rules.push(ConfigRule::CellUse {
+                    cell: cell_ref(tree, library.as_ref().map(|x| &x.0), cell),
+                    target: use_target(tree, &x.nodes.1),
+                })
+            }
+        }
+    }
+    ret
+}
+
+// -----------------------------------------------------------------------------
+
+/// Remove `.` and `..` lexically without accessing the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut ret = PathBuf::new();
+    for x in path.components() {
+        match x {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !ret.pop() {
+                    ret.push("..");
+                }
+            }
+            x => ret.push(x),
+        }
+    }
+    // Keep the trailing `/` of directory pattern
+    if path.to_string_lossy().ends_with('/') {
+        ret.push("");
+    }
+    ret
+}
+
+fn is_wildcard(s: &str) -> bool {
+    s.contains('*') || s.contains('?') || s.contains("...")
+}
+
+/// Specificity of IEEE1800-2017 33.3.1.1.
+fn specificity(pattern: &Path) -> usize {
+    let s = pattern.to_string_lossy();
+    if s.ends_with('/') {
+        1
+    } else if is_wildcard(&s) {
+        2
+    } else {
+        3
+    }
+}
+
+fn components(path: &Path) -> Vec<String> {
+    let mut ret: Vec<String> = path
+        .components()
+        .map(|x| x.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    // A trailing `/` means all files in the directory
+    if path.to_string_lossy().ends_with('/') {
+        ret.push(String::from("*"));
+    }
+    ret
+}
+
+/// Check whether the path matches the pattern including `*`, `?` and `...`.
+fn matches(pattern: &Path, path: &Path) -> bool {
+    let pattern = components(pattern);
+    let path = components(path);
+    match_components(&pattern, &path)
+}
+
+fn match_components(pattern: &[String], path: &[String]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(x), _) if x == "..." => {
+            (0..=path.len()).any(|i| match_components(&pattern[1..], &path[i..]))
+        }
+        (Some(x), Some(y)) => {
+            match_name(x.as_bytes(), y.as_bytes()) && match_components(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+fn match_name(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => (0..=name.len()).any(|i| match_name(&pattern[1..], &name[i..])),
+        (Some(b'?'), Some(_)) => match_name(&pattern[1..], &name[1..]),
+        (Some(x), Some(y)) if x == y => match_name(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// Expand the pattern to existing files.
+fn expand(pattern: &Path) -> Vec<PathBuf> {
+    let pattern_str = pattern.to_string_lossy();
+    if !is_wildcard(&pattern_str) && !pattern_str.ends_with('/') {
+        return if pattern.is_file() {
+            vec![PathBuf::from(pattern)]
+        } else {
+            Vec::new()
+        };
+    }
+    // Walk from the longest directory prefix without wildcards
+    let mut root = PathBuf::new();
+    for x in pattern.components() {
+        if is_wildcard(&x.as_os_str().to_string_lossy()) {
+            break;
+        }
+        root.push(x);
+    }
+    if root == pattern {
+        root = PathBuf::from(pattern);
+    }
+    let mut files = Vec::new();
+    walk(&root, &mut files, 0);
+    let normalized = normalize(pattern);
+    let mut ret: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|x| matches(&normalized, &normalize(x)))
+        .collect();
+    ret.sort();
+    ret
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>, depth: usize) {
+    if depth > RECURSIVE_LIMIT {
+        return;
+    }
+    let dir_or_cur = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    if let Ok(entries) = fs::read_dir(dir_or_cur) {
+        for entry in entries.flatten() {
+            let path = dir.join(entry.file_name());
+            if path.is_dir() {
+                walk(&path, files, depth + 1);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches(Path::new("a/*.v"), Path::new("a/b.v")));
+        assert!(!matches(Path::new("a/*.v"), Path::new("a/c/b.v")));
+        assert!(matches(Path::new("a/.../b?.v"), Path::new("a/c/d/b1.v")));
+        assert!(matches(Path::new("a/.../b?.v"), Path::new("a/b1.v")));
+        assert!(matches(Path::new("a/"), Path::new("a/b.sv")));
+    }
+
+    #[test]
+    fn test_library_map() {
+        let src = r##"library rtl "rtl/*.v", rtl/top.sv;
+library gate gate/ -incdir gate/inc;
+library special rtl/adder.v;
+
+config cfg;
+  design rtl.top;
+  default liblist rtl gate;
+  instance top.u0 liblist gate;
+  instance top.u1 use special.adder;
+  cell mul use gate.mul_fast;
+endconfig
+"##;
+        let (tree, _) = parse_lib_str(
+            src,
+            PathBuf::from("proj/lib.map"),
+            &HashMap::new(),
+            &[""],
+            false,
+            false,
+        )
+        .unwrap();
+        let mut map = LibraryMap::new();
+        map.add(&tree, "proj/lib.map");
+
+        assert_eq!(map.libraries().len(), 3);
+        assert_eq!(
+            map.library("gate").unwrap().incdirs,
+            vec![PathBuf::from("proj/gate/inc")]
+        );
+        assert_eq!(map.library_of("proj/rtl/alu.v"), "rtl");
+        assert_eq!(map.library_of("proj/rtl/adder.v"), "special");
+        assert_eq!(map.library_of("proj/./gate/alu.v"), "gate");
+        assert_eq!(map.library_of("proj/other/alu.v"), "work");
+
+        let cell = |library: &str, name: &str, instances: &[(&str, &str)]| CellDefinition {
+            library: String::from(library),
+            name: String::from(name),
+            instances: instances
+                .iter()
+                .map(|(x, y)| (String::from(*x), String::from(*y)))
+                .collect(),
+        };
+        let cells = vec![
+            cell(
+                "rtl",
+                "top",
+                &[("u0", "adder"), ("u1", "adder"), ("u2", "mul")],
+            ),
+            cell("rtl", "adder", &[("h0", "half")]),
+            cell("rtl", "half", &[]),
+            cell("gate", "adder", &[("h0", "half")]),
+            cell("gate", "half", &[]),
+            cell("gate", "mul_fast", &[]),
+            cell("special", "adder", &[]),
+        ];
+        let bindings: Vec<(String, Option<String>)> = map
+            .elaborate("cfg", &cells)
+            .into_iter()
+            .map(|x| {
+                let definition = x
+                    .definition
+                    .map(|x| format!("{}.{}", x.library.unwrap(), x.cell));
+                (x.path, definition)
+            })
+            .collect();
+        let expected = [
+            ("top", "rtl.top"),
+            ("top.u0", "gate.adder"),
+            ("top.u0.h0", "gate.half"),
+            ("top.u1", "special.adder"),
+            ("top.u2", "gate.mul_fast"),
+        ];
+        let expected: Vec<(String, Option<String>)> = expected
+            .iter()
+            .map(|(x, y)| (String::from(*x), Some(String::from(*y))))
+            .collect();
+        assert_eq!(bindings, expected);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::utils::*;
+use crate::*;
+
+// -----------------------------------------------------------------------------
+
+/// A key protecting the key block of an envelope.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProtectKey {
+    pub keyowner: Option<String>,
+    pub keyname: Option<String>,
+    pub method: Option<String>,
+}
+
+/// A decryption envelope between `pragma protect begin_protected and end_protected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncryptedEnvelope {
+    /// The design element, package or class containing the envelope
+    pub scope: Option<String>,
+    /// The range of the preprocessed text covered by the envelope
+    pub locate: Locate,
+    pub keys: Vec<ProtectKey>,
+    pub data_method: Option<String>,
+    /// The `enctype` of the data block
+    pub encoding: Option<String>,
+    /// All `name=value` pairs of the pragmas in the order of appearance
+    pub metadata: Vec<(String, String)>,
+}
+
+/// Get all decryption envelopes.
+///
+/// An envelope outside of any design element, such as an encrypted whole module, has no scope.
+pub fn encrypted_envelopes(tree: &SyntaxTree) -> Vec<EncryptedEnvelope> {
+    let mut ret = Vec::new();
+    let mut depth = 0;
+    let mut scope: Vec<(String, usize, usize)> = Vec::new();
+
+    for event in tree.into_iter().event() {
+        let node = match event {
+            NodeEvent::Enter(x) => x,
+            NodeEvent::Leave(_) => {
+                depth -= 1;
+                if scope.last().map(|x| x.1) == Some(depth) {
+                    scope.pop();
+                }
+                continue;
+            }
+        };
+
+        if let RefNode::ProtectedEnvelope(x) = node {
+            let locate = span(x).map(|(begin, end)| Locate {
+                offset: begin,
+                line: x.nodes.0.nodes.0.nodes.0.line,
+                len: end - begin,
+            });
+            if let Some(locate) = locate {
+                // Whitespaces after `endmodule` belong to the module declaration
+                let scope = scope
+                    .iter()
+                    .rev()
+                    .find(|x| locate.offset < x.2)
+                    .map(|x| x.0.clone());
+                ret.push(envelope(tree, x, scope, locate));
+            }
+        }
+
+        if let Some(name) = scope_name(tree, &node) {
+            let end = span(vec![node.clone()]).map_or(0, |x| x.1);
+            scope.push((name, depth, end));
+        }
+        depth += 1;
+    }
+    ret
+}
+
+/// Get the names of the design elements, packages and classes containing envelopes.
+///
+/// Envelopes without scope are returned as `$unit`.
+pub fn encrypted_modules(tree: &SyntaxTree) -> Vec<String> {
+    let mut ret: Vec<String> = Vec::new();
+    for x in encrypted_envelopes(tree) {
+        let x = x.scope.unwrap_or_else(|| String::from(UNIT_SCOPE));
+        if !ret.contains(&x) {
+            ret.push(x);
+        }
+    }
+    ret
+}
+
+// -----------------------------------------------------------------------------
+
+fn envelope(
+    tree: &SyntaxTree,
+    x: &ProtectedEnvelope,
+    scope: Option<String>,
+    locate: Locate,
+) -> EncryptedEnvelope {
+    let (ref begin, ref items, ref end) = x.nodes;
+    let mut pragmas = vec![begin];
+    for item in items {
+        if let ProtectedEnvelopeItem::Pragma(x) = item {
+            pragmas.push(x);
+        }
+    }
+    pragmas.push(end);
+
+    let mut ret = EncryptedEnvelope {
+        scope,
+        locate,
+        keys: Vec::new(),
+        data_method: None,
+        encoding: None,
+        metadata: Vec::new(),
+    };
+    let mut key = ProtectKey::default();
+    let mut encoding = None;
+
+    let expressions = pragmas
+        .iter()
+        .filter_map(|x| x.nodes.3.as_ref())
+        .flat_map(|x| x.contents());
+    for expression in expressions {
+        match expression {
+            PragmaExpression::Assignment(x) => {
+                let name = tree.get_str_trim(&x.nodes.0).unwrap_or("");
+                let value = pragma_value(tree, &x.nodes.2);
+                match name {
+                    "key_keyowner" => key.keyowner = Some(value.clone()),
+                    "key_keyname" => key.keyname = Some(value.clone()),
+                    "key_method" => key.method = Some(value.clone()),
+                    "data_method" => ret.data_method = Some(value.clone()),
+                    "encoding" => encoding = enctype(tree, &x.nodes.2),
+                    _ => (),
+                }
+                ret.metadata.push((String::from(name), value));
+            }
+            PragmaExpression::PragmaKeyword(x) => match tree.get_str_trim(&**x) {
+                Some("key_block") => ret.keys.push(std::mem::take(&mut key)),
+                Some("data_block") => ret.encoding = encoding.clone(),
+                _ => (),
+            },
+            PragmaExpression::PragmaValue(_) => (),
+        }
+    }
+    ret
+}
+
+fn pragma_value(tree: &SyntaxTree, x: &PragmaValue) -> String {
+    let s = tree.get_str_trim(x).unwrap_or("");
+    match x {
+        PragmaValue::StringLiteral(_) => String::from(s.trim_matches('"')),
+        _ => String::from(s),
+    }
+}
+
+/// Get `enctype` of `encoding=(enctype="base64", ...)`.
+fn enctype(tree: &SyntaxTree, x: &PragmaValue) -> Option<String> {
+    for node in x {
+        if let RefNode::PragmaExpressionAssignment(x) = node {
+            if tree.get_str_trim(&x.nodes.0) == Some("enctype") {
+                return Some(pragma_value(tree, &x.nodes.2));
+            }
+        }
+    }
+    None
+}
+
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_encrypted_envelopes() {
+        let src = r##"module secret (a, b);
+  input a;
+  output b;
+`pragma protect begin_protected
+`pragma protect key_keyowner="Vendor", key_keyname="VK1", key_method="rsa"
+`pragma protect encoding=(enctype="base64", line_length=76, bytes=8)
+`pragma protect key_block
+ZW5jcnlw/*dGVk*/
+`pragma protect data_method="aes128-cbc"
+`pragma protect encoding=(enctype="raw", bytes=24)
+`pragma protect data_block
+//Ym9keSBv`ZiB0aGU=
+"ZGVzaWdu
+`pragma protect end_protected
+  wire c;
+endmodule
+`pragma protect begin_protected
+`pragma protect data_block
+bW9kdWxl
+`pragma protect end_protected
+module open_module;
+endmodule"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+
+        let envelopes = encrypted_envelopes(&tree);
+        assert_eq!(envelopes.len(), 2);
+        let x = &envelopes[0];
+        assert_eq!(x.scope.as_deref(), Some("secret"));
+        assert_eq!(x.locate.line, 4);
+        assert_eq!(
+            x.keys,
+            vec![ProtectKey {
+                keyowner: Some(String::from("Vendor")),
+                keyname: Some(String::from("VK1")),
+                method: Some(String::from("rsa")),
+            }]
+        );
+        assert_eq!(x.data_method.as_deref(), Some("aes128-cbc"));
+        assert_eq!(x.encoding.as_deref(), Some("raw"));
+        assert_eq!(x.metadata.len(), 6);
+        assert_eq!(envelopes[1].scope, None);
+        assert_eq!(
+            encrypted_modules(&tree),
+            vec![String::from("secret"), String::from("$unit")]
+        );
+
+        let wires = tree
+            .into_iter()
+            .filter(|x| matches!(x, RefNode::NetDeclaration(_)))
+            .count();
+        assert_eq!(wires, 1);
+    }
+}
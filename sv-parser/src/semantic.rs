@@ -0,0 +1,712 @@
+use crate::utils::*;
+use crate::*;
+use std::collections::{HashMap, HashSet};
+use sv_parser_pp::range::Range;
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SemanticKind {
+    /// A module, interface, program, checker or UDP
+    Module,
+    Instance,
+    Port,
+    Net,
+    /// A variable, class property or subroutine argument
+    Variable,
+    /// A value parameter, localparam or specparam
+    Parameter,
+    /// A typedef or type parameter
+    Type,
+    Package,
+    Class,
+    Function,
+    Task,
+    EnumMember,
+    Macro,
+    Genvar,
+}
+
+/// A classified identifier occurrence.
+///
+/// A macro usage, which is replaced by its expansion in the preprocessed text, has an empty
+/// `locate` at the beginning of the expansion and is found by `origin`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SemanticToken {
+    pub name: String,
+    pub locate: Locate,
+    pub kind: SemanticKind,
+    /// The occurrence declares the identifier
+    pub declaration: bool,
+    /// The range in the source file
+    pub origin: Option<(PathBuf, Range)>,
+}
+
+/// Classifies identifier occurrences for semantic highlighting.
+///
+/// Identifiers which can't be resolved, such as members of hierarchical references and
+/// implicit nets, are not included.
+pub struct SemanticTokens {
+    tokens: Vec<SemanticToken>,
+}
+
+impl SemanticTokens {
+    pub fn new(tree: &SyntaxTree) -> Self {
+        let mut collector = Collector {
+            tree,
+            scopes: Vec::new(),
+            packages: HashMap::new(),
+            declarations: HashMap::new(),
+        };
+        collector.collect();
+
+        let mut tokens = collector.classify();
+        let usages = macro_usages(tree, &tokens);
+        tokens.extend(usages);
+        tokens.sort_by_key(|x| (x.locate.offset, x.locate.len));
+        SemanticTokens { tokens }
+    }
+
+    /// Get all tokens ordered by offset.
+    pub fn tokens(&self) -> &[SemanticToken] {
+        &self.tokens
+    }
+
+    /// Get the token at the offset of the preprocessed text.
+    pub fn get(&self, offset: usize) -> Option<&SemanticToken> {
+        let i = self
+            .tokens
+            .partition_point(|x| x.locate.offset + x.locate.len <= offset);
+        self.tokens.get(i).filter(|x| x.locate.offset <= offset)
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+#[derive(Default)]
+struct Scope {
+    parent: Option<usize>,
+    names: HashMap<String, SemanticKind>,
+    /// Imported packages and optional item names
+    imports: Vec<(String, Option<String>)>,
+}
+
+struct Collector<'a> {
+    tree: &'a SyntaxTree,
+    scopes: Vec<Scope>,
+    /// Scopes of packages and classes to resolve `name::`
+    packages: HashMap<String, usize>,
+    /// Declaring identifiers by offset
+    declarations: HashMap<usize, SemanticKind>,
+}
+
+/// Tracks the current scope in the same order through both passes.
+struct ScopeStack {
+    stack: Vec<(usize, usize)>,
+    next: usize,
+}
+
+impl ScopeStack {
+    fn new() -> Self {
+        ScopeStack {
+            stack: vec![(0, 0)],
+            next: 1,
+        }
+    }
+
+    fn current(&self) -> usize {
+        self.stack.last().map_or(0, |x| x.0)
+    }
+
+    /// Enter the node; returns the new scope id if the node opens a scope.
+    fn enter(&mut self, node: &RefNode, depth: usize) -> Option<usize> {
+        if is_scope(node) {
+            let id = self.next;
+            self.next += 1;
+            self.stack.push((id, depth));
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    fn leave(&mut self, depth: usize) {
+        if self.stack.len() > 1 && self.stack.last().map(|x| x.1) == Some(depth) {
+            self.stack.pop();
+        }
+    }
+}
+
+pub(crate) fn is_scope(node: &RefNode) -> bool {
+    matches!(
+        node,
+        RefNode::ModuleDeclaration(_)
+            | RefNode::InterfaceDeclaration(_)
+            | RefNode::ProgramDeclaration(_)
+            | RefNode::PackageDeclaration(_)
+            | RefNode::CheckerDeclaration(_)
+            | RefNode::ClassDeclaration(_)
+            | RefNode::InterfaceClassDeclaration(_)
+            | RefNode::FunctionDeclaration(_)
+            | RefNode::TaskDeclaration(_)
+            | RefNode::SeqBlock(_)
+            | RefNode::ParBlock(_)
+            | RefNode::GenerateBlockMultiple(_)
+            | RefNode::LoopGenerateConstruct(_)
+            | RefNode::LoopStatementFor(_)
+    )
+}
+
+impl<'a> Collector<'a> {
+    /// Collect declarations of all scopes.
+    fn collect(&mut self) {
+        let tree = self.tree;
+        self.scopes.push(Scope::default());
+        let mut scopes = ScopeStack::new();
+        let mut depth = 0;
+        let mut port_declaration = 0;
+        let mut struct_member = 0;
+        // `type_name name;` in a module is parsed as an interface port declaration
+        let mut typed_interface = false;
+
+        for event in tree.into_iter().event() {
+            let node = match event {
+                NodeEvent::Enter(x) => x,
+                NodeEvent::Leave(x) => {
+                    match x {
+                        RefNode::PortDeclaration(_) => port_declaration -= 1,
+                        RefNode::StructUnionMember(_) => struct_member -= 1,
+                        RefNode::InterfacePortDeclaration(_) => typed_interface = false,
+                        _ => (),
+                    }
+                    depth -= 1;
+                    scopes.leave(depth);
+                    continue;
+                }
+            };
+
+            match node {
+                RefNode::PortDeclaration(_) => port_declaration += 1,
+                RefNode::StructUnionMember(_) => struct_member += 1,
+                RefNode::InterfacePortDeclaration(x) => {
+                    typed_interface = identifier(tree, &x.nodes.0)
+                        .and_then(|x| self.lookup(scopes.current(), &x))
+                        == Some(SemanticKind::Type);
+                }
+                RefNode::PackageImportItem(x) => {
+                    let import = match x {
+                        PackageImportItem::Identifier(x) => {
+                            (identifier(tree, &x.nodes.0), identifier(tree, &x.nodes.2))
+                        }
+                        PackageImportItem::Asterisk(x) => (identifier(tree, &x.nodes.0), None),
+                    };
+                    if let (Some(package), name) = import {
+                        let current = scopes.current();
+                        self.scopes[current].imports.push((package, name));
+                    }
+                }
+                _ => (),
+            }
+
+            let parent = scopes.current();
+            for (id, kind) in declarations(&node, port_declaration > 0) {
+                let kind = match node {
+                    RefNode::ListOfInterfaceIdentifiers(_) if typed_interface => {
+                        SemanticKind::Variable
+                    }
+                    _ => kind,
+                };
+                let locate = match identifier_locate(vec![id.clone()]) {
+                    Some(x) => x,
+                    None => continue,
+                };
+                self.declarations.insert(locate.offset, kind);
+                // Struct members and out-of-block method definitions aren't names of the scope
+                if struct_member > 0 || is_out_of_block(&node) {
+                    continue;
+                }
+                if let Some(name) = identifier(tree, vec![id]) {
+                    self.scopes[parent].names.insert(name, kind);
+                }
+            }
+
+            if let Some(id) = scopes.enter(&node, depth) {
+                self.scopes.push(Scope {
+                    parent: Some(parent),
+                    ..Scope::default()
+                });
+                let package = matches!(
+                    node,
+                    RefNode::PackageDeclaration(_)
+                        | RefNode::ClassDeclaration(_)
+                        | RefNode::InterfaceClassDeclaration(_)
+                );
+                if let (true, Some(name)) = (package, scope_name(tree, &node)) {
+                    self.packages.insert(name, id);
+                }
+            }
+            depth += 1;
+        }
+    }
+
+    /// Classify all identifier occurrences.
+    fn classify(&self) -> Vec<SemanticToken> {
+        let tree = self.tree;
+        let mut ret = Vec::new();
+        let mut scopes = ScopeStack::new();
+        let mut depth = 0;
+        let mut ancestors: Vec<RefNode> = Vec::new();
+        let mut whitespace = 0;
+        // The scope name of `name::` qualifying the next identifier
+        let mut qualifier: Option<String> = None;
+        let mut qualified: HashMap<usize, String> = HashMap::new();
+        let mut members: HashSet<usize> = HashSet::new();
+
+        for event in tree.into_iter().event() {
+            let node = match event {
+                NodeEvent::Enter(x) => x,
+                NodeEvent::Leave(x) => {
+                    match x {
+                        RefNode::WhiteSpace(_) => whitespace -= 1,
+                        RefNode::PackageScope(x) => qualifier = package_scope_name(tree, x),
+                        RefNode::ClassScope(x) => {
+                            qualifier = identifier(tree, &x.nodes.0.nodes.0);
+                        }
+                        _ => (),
+                    }
+                    ancestors.pop();
+                    depth -= 1;
+                    scopes.leave(depth);
+                    continue;
+                }
+            };
+
+            match node {
+                RefNode::WhiteSpace(_) => whitespace += 1,
+                RefNode::PackageImportItem(PackageImportItem::Identifier(x)) => {
+                    if let (Some(package), Some(locate)) =
+                        (identifier(tree, &x.nodes.0), identifier_locate(&x.nodes.2))
+                    {
+                        qualified.insert(locate.offset, package);
+                    }
+                }
+                RefNode::HierarchicalIdentifier(x) => {
+                    let (ref root, ref path, ref last) = x.nodes;
+                    let skip = if root.is_some() { 0 } else { 1 };
+                    let ids = path.iter().map(|x| &x.0).chain(std::iter::once(last));
+                    for id in ids.skip(skip) {
+                        if let Some(x) = identifier_locate(id) {
+                            members.insert(x.offset);
+                        }
+                    }
+                }
+                _ => (),
+            }
+
+            let locate = match node {
+                RefNode::SimpleIdentifier(x) => Some(x.nodes.0),
+                RefNode::EscapedIdentifier(x) => Some(x.nodes.0),
+                _ => None,
+            };
+            if let Some(locate) = locate {
+                let qualifier = qualifier
+                    .take()
+                    .or_else(|| qualified.get(&locate.offset).cloned());
+                let name = identifier(tree, vec![node.clone()]);
+                let declared = self.declarations.get(&locate.offset).copied();
+                let kind = match declared {
+                    Some(x) => Some(x),
+                    // Identifiers in compiler directives other than macro names are ignored
+                    None if whitespace > 0 || members.contains(&locate.offset) => None,
+                    None => {
+                        let wrapper = wrapper_kind(&ancestors);
+                        let resolved = name.as_ref().and_then(|name| match qualifier {
+                            Some(ref x) => self.lookup_package(x, name),
+                            None => self.lookup(scopes.current(), name),
+                        });
+                        use SemanticKind::*;
+                        match (wrapper, resolved) {
+                            // Packages and types are ambiguous with class and interface names
+                            (Some(Module), Some(Type))
+                            | (Some(Class), Some(Package))
+                            | (Some(Class), Some(Type)) => resolved,
+                            (Some(Module), _)
+                            | (Some(Package), _)
+                            | (Some(Class), _)
+                            | (Some(Port), _)
+                            | (Some(Parameter), _)
+                            | (Some(Macro), _) => wrapper,
+                            _ => resolved.or(wrapper),
+                        }
+                    }
+                };
+                if let (Some(kind), Some(name)) = (kind, name) {
+                    let origin = tree.text.origin(locate.offset).map(|(path, x)| {
+                        (path.clone(), Range::new(x, x + locate.len))
+                    });
+                    ret.push(SemanticToken {
+                        name,
+                        locate,
+                        kind,
+                        declaration: declared.is_some(),
+                        origin,
+                    });
+                }
+            }
+
+            scopes.enter(&node, depth);
+            ancestors.push(node);
+            depth += 1;
+        }
+        ret
+    }
+
+    fn lookup(&self, scope: usize, name: &str) -> Option<SemanticKind> {
+        let mut scope = Some(scope);
+        while let Some(x) = scope {
+            let x = &self.scopes[x];
+            if let Some(kind) = x.names.get(name) {
+                return Some(*kind);
+            }
+            for (package, item) in &x.imports {
+                if item.as_deref().is_none_or(|x| x == name) {
+                    if let Some(kind) = self.lookup_package(package, name) {
+                        return Some(kind);
+                    }
+                }
+            }
+            scope = x.parent;
+        }
+        None
+    }
+
+    fn lookup_package(&self, package: &str, name: &str) -> Option<SemanticKind> {
+        let scope = self.packages.get(package)?;
+        self.scopes[*scope].names.get(name).copied()
+    }
+}
+
+/// Check whether the node is an out-of-block method definition such as `function C::f`.
+/// Get the macro usages expanded by the preprocessor. The usages kept by the macro-preserving
+/// preprocessor are in the tree and classified already.
+fn macro_usages(tree: &SyntaxTree, tokens: &[SemanticToken]) -> Vec<SemanticToken> {
+    let classified: HashSet<_> = tokens
+        .iter()
+        .filter_map(|x| x.origin.as_ref())
+        .map(|(path, range)| (path, range.begin))
+        .collect();
+    let mut usages: Vec<_> = tree
+        .text
+        .macro_index()
+        .usages
+        .iter()
+        .filter_map(|x| {
+            let (path, range) = x.origin.as_ref()?;
+            // The name follows the backquote
+            let begin = range.begin + 1;
+            if classified.contains(&(path, begin)) {
+                return None;
+            }
+            let origin = (path.clone(), Range::new(begin, begin + x.identifier.len()));
+            Some((x.expansion?.begin, &x.identifier, origin))
+        })
+        .collect();
+    usages.sort_by_key(|x| x.0);
+
+    let text = tree.text.text();
+    let mut line = 1;
+    let mut last = 0;
+    let mut ret = Vec::new();
+    for (offset, name, origin) in usages {
+        line += text[last..offset].matches('\n').count();
+        last = offset;
+        ret.push(SemanticToken {
+            name: name.clone(),
+            locate: Locate {
+                offset,
+                line: line as u32,
+                len: 0,
+            },
+            kind: SemanticKind::Macro,
+            declaration: false,
+            origin: Some(origin),
+        });
+    }
+    ret
+}
+
+fn is_out_of_block(node: &RefNode) -> bool {
+    match node {
+        RefNode::FunctionDeclaration(x) => {
+            unwrap_node!(*x, InterfaceIdentifierOrClassScope).is_some()
+        }
+        RefNode::TaskDeclaration(x) => unwrap_node!(*x, InterfaceIdentifierOrClassScope).is_some(),
+        _ => false,
+    }
+}
+
+/// Get the identifiers declared by the node.
+pub(crate) fn declarations<'a>(
+    node: &RefNode<'a>,
+    port_declaration: bool,
+) -> Vec<(RefNode<'a>, SemanticKind)> {
+    use SemanticKind::*;
+
+    let mut ret: Vec<(RefNode, SemanticKind)> = Vec::new();
+    match node {
+        RefNode::ModuleDeclaration(x) => {
+            ret.extend(unwrap_node!(*x, ModuleIdentifier).map(|x| (x, Module)))
+        }
+        RefNode::InterfaceDeclaration(x) => {
+            ret.extend(unwrap_node!(*x, InterfaceIdentifier).map(|x| (x, Module)))
+        }
+        RefNode::ProgramDeclaration(x) => {
+            ret.extend(unwrap_node!(*x, ProgramIdentifier).map(|x| (x, Module)))
+        }
+        RefNode::CheckerDeclaration(x) => {
+            ret.extend(unwrap_node!(*x, CheckerIdentifier).map(|x| (x, Module)))
+        }
+        RefNode::UdpDeclaration(x) => {
+            ret.extend(unwrap_node!(*x, UdpIdentifier).map(|x| (x, Module)))
+        }
+        RefNode::PackageDeclaration(x) => {
+            ret.extend(unwrap_node!(*x, PackageIdentifier).map(|x| (x, Package)))
+        }
+        RefNode::ClassDeclaration(x) => ret.push(((&x.nodes.4).into(), Class)),
+        RefNode::InterfaceClassDeclaration(x) => ret.push(((&x.nodes.2).into(), Class)),
+        RefNode::FunctionDeclaration(x) => {
+            ret.extend(unwrap_node!(*x, FunctionIdentifier).map(|x| (x, Function)))
+        }
+        RefNode::FunctionPrototype(x) => {
+            ret.extend(unwrap_node!(*x, FunctionIdentifier).map(|x| (x, Function)))
+        }
+        RefNode::TaskDeclaration(x) => {
+            ret.extend(unwrap_node!(*x, TaskIdentifier).map(|x| (x, Task)))
+        }
+        RefNode::TaskPrototype(x) => {
+            ret.extend(unwrap_node!(*x, TaskIdentifier).map(|x| (x, Task)))
+        }
+        RefNode::ParamAssignment(x) => ret.push(((&x.nodes.0).into(), Parameter)),
+        RefNode::SpecparamAssignment(x) => {
+            ret.extend(unwrap_node!(*x, SpecparamIdentifier).map(|x| (x, Parameter)))
+        }
+        RefNode::TypeAssignment(x) => ret.push(((&x.nodes.0).into(), Type)),
+        RefNode::TypeDeclaration(x) => {
+            let id = match x {
+                TypeDeclaration::DataType(x) => &x.nodes.2,
+                TypeDeclaration::Interface(x) => &x.nodes.5,
+                TypeDeclaration::Reserved(x) => &x.nodes.2,
+            };
+            ret.push((id.into(), Type));
+        }
+        RefNode::EnumNameDeclaration(x) => ret.push(((&x.nodes.0).into(), EnumMember)),
+        RefNode::NetDeclAssignment(x) => ret.push(((&x.nodes.0).into(), Net)),
+        RefNode::VariableDeclAssignment(x) => {
+            ret.push((RefNode::VariableDeclAssignment(x), Variable))
+        }
+        RefNode::AnsiPortDeclarationNet(x) => ret.push(((&x.nodes.1).into(), Port)),
+        RefNode::AnsiPortDeclarationVariable(x) => ret.push(((&x.nodes.1).into(), Port)),
+        RefNode::AnsiPortDeclarationParen(x) => ret.push(((&x.nodes.2).into(), Port)),
+        RefNode::ListOfPortIdentifiers(x) => ret.extend(
+            x.nodes
+                .0
+                .contents()
+                .into_iter()
+                .map(|x| ((&x.0).into(), Port)),
+        ),
+        RefNode::ListOfVariablePortIdentifiers(x) => ret.extend(
+            x.nodes
+                .0
+                .contents()
+                .into_iter()
+                .map(|x| ((&x.0).into(), Port)),
+        ),
+        RefNode::ListOfInterfaceIdentifiers(x) => ret.extend(
+            x.nodes
+                .0
+                .contents()
+                .into_iter()
+                .map(|x| ((&x.0).into(), Port)),
+        ),
+        RefNode::ListOfVariableIdentifiers(x) => {
+            let kind = if port_declaration { Port } else { Variable };
+            ret.extend(
+                x.nodes
+                    .0
+                    .contents()
+                    .into_iter()
+                    .map(|x| ((&x.0).into(), kind)),
+            )
+        }
+        RefNode::ListOfTfVariableIdentifiers(x) => ret.extend(
+            x.nodes
+                .0
+                .contents()
+                .into_iter()
+                .map(|x| ((&x.0).into(), Variable)),
+        ),
+        RefNode::TfPortItem(x) => ret.extend(x.nodes.4.as_ref().map(|x| ((&x.0).into(), Variable))),
+        RefNode::ForVariableDeclaration(x) => ret.extend(
+            x.nodes
+                .2
+                .contents()
+                .into_iter()
+                .map(|x| ((&x.0).into(), Variable)),
+        ),
+        RefNode::ListOfGenvarIdentifiers(x) => {
+            ret.extend(x.nodes.0.contents().into_iter().map(|x| (x.into(), Genvar)))
+        }
+        RefNode::GenvarInitialization(x) if x.nodes.0.is_some() => {
+            ret.push(((&x.nodes.1).into(), Genvar))
+        }
+        RefNode::NameOfInstance(x) => ret.push(((&x.nodes.0).into(), Instance)),
+        RefNode::TextMacroDefinition(x) => ret.push(((&x.nodes.2.nodes.0).into(), Macro)),
+        _ => (),
+    }
+    ret
+}
+
+/// Get the kind implied by the identifier type wrapping the innermost identifier.
+fn wrapper_kind(ancestors: &[RefNode]) -> Option<SemanticKind> {
+    use SemanticKind::*;
+
+    let wrapper = ancestors
+        .iter()
+        .rev()
+        .find(|x| !matches!(x, RefNode::Identifier(_)))?;
+    match wrapper {
+        RefNode::ModuleIdentifier(_)
+        | RefNode::InterfaceIdentifier(_)
+        | RefNode::ProgramIdentifier(_)
+        | RefNode::CheckerIdentifier(_)
+        | RefNode::UdpIdentifier(_) => Some(Module),
+        RefNode::InstanceIdentifier(_) => Some(Instance),
+        RefNode::PortIdentifier(_) => Some(Port),
+        RefNode::NetIdentifier(_) => Some(Net),
+        RefNode::VariableIdentifier(_) => Some(Variable),
+        RefNode::ParameterIdentifier(_) | RefNode::SpecparamIdentifier(_) => Some(Parameter),
+        RefNode::TypeIdentifier(_) => Some(Type),
+        RefNode::PackageIdentifier(_) => Some(Package),
+        RefNode::ClassIdentifier(_) => Some(Class),
+        RefNode::FunctionIdentifier(_) | RefNode::TfIdentifier(_) => Some(Function),
+        RefNode::TaskIdentifier(_) => Some(Task),
+        RefNode::EnumIdentifier(_) => Some(EnumMember),
+        RefNode::TextMacroIdentifier(_) => Some(Macro),
+        RefNode::GenvarIdentifier(_) => Some(Genvar),
+        _ => None,
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_semantic_tokens() {
+        let src = r##"`define WIDTH 8
+package pkg;
+  typedef enum logic [1:0] { IDLE, BUSY } state_t;
+  function automatic int inc(int v); return v + 1; endfunction
+endpackage
+
+module sub #(parameter W = 1) (input logic clk, output logic [W-1:0] q);
+endmodule
+
+module top(clk);
+  import pkg::*;
+  input clk;
+  wire [`WIDTH-1:0] bus;
+  state_t state;
+  genvar i;
+  for (i = 0; i < 2; i++) begin : g
+    sub #(.W(`WIDTH)) u_sub (.clk(clk), .q(bus));
+  end
+  task automatic run; state <= pkg::IDLE; endtask
+  always_comb state = inc(bus) ? BUSY : IDLE;
+endmodule"##;
+        let path = PathBuf::from("");
+        let (tree, _) = parse_sv_str(src, &path, &HashMap::new(), &[""], false, false).unwrap();
+        let tokens = SemanticTokens::new(&tree);
+        let names: Vec<(&str, SemanticKind, bool)> = tokens
+            .tokens()
+            .iter()
+            .map(|x| (x.name.as_str(), x.kind, x.declaration))
+            .collect();
+
+        use SemanticKind::*;
+        let expected = vec![
+            ("WIDTH", Macro, true),
+            ("pkg", Package, true),
+            ("IDLE", EnumMember, true),
+            ("BUSY", EnumMember, true),
+            ("state_t", Type, true),
+            ("inc", Function, true),
+            ("v", Variable, true),
+            ("v", Variable, false),
+            ("sub", Module, true),
+            ("W", Parameter, true),
+            ("clk", Port, true),
+            ("W", Parameter, false),
+            ("q", Port, true),
+            ("top", Module, true),
+            ("clk", Port, false),
+            ("pkg", Package, false),
+            ("clk", Port, true),
+            ("WIDTH", Macro, false),
+            ("bus", Net, true),
+            ("state_t", Type, false),
+            ("state", Variable, true),
+            ("i", Genvar, true),
+            ("i", Genvar, false),
+            ("i", Genvar, false),
+            ("i", Genvar, false),
+            ("sub", Module, false),
+            ("W", Parameter, false),
+            ("WIDTH", Macro, false),
+            ("u_sub", Instance, true),
+            ("clk", Port, false),
+            ("clk", Port, false),
+            ("q", Port, false),
+            ("bus", Net, false),
+            ("run", Task, true),
+            ("state", Variable, false),
+            ("pkg", Package, false),
+            ("IDLE", EnumMember, false),
+            ("state", Variable, false),
+            ("inc", Function, false),
+            ("bus", Net, false),
+            ("BUSY", EnumMember, false),
+            ("IDLE", EnumMember, false),
+        ];
+        assert_eq!(names, expected);
+
+        // macro usages are found by the range in the source file
+        let usages: Vec<_> = tokens
+            .tokens()
+            .iter()
+            .filter(|x| x.kind == Macro && !x.declaration)
+            .map(|x| (x.locate.len, x.origin.clone().unwrap().1.begin))
+            .collect();
+        let begin = src.find("`WIDTH-1").unwrap() + 1;
+        let begin2 = src.find("`WIDTH)").unwrap() + 1;
+        assert_eq!(usages, vec![(0, begin), (0, begin2)]);
+
+        // macro usages kept by the macro-preserving preprocessor are found once
+        let options = ParseOptions {
+            macro_preserving: true,
+            ..ParseOptions::default()
+        };
+        let (tree, _) =
+            parse_sv_str_with(src, &path, &HashMap::new(), &[""], false, false, &options).unwrap();
+        let tokens = SemanticTokens::new(&tree);
+        let names: Vec<(&str, SemanticKind, bool)> = tokens
+            .tokens()
+            .iter()
+            .map(|x| (x.name.as_str(), x.kind, x.declaration))
+            .collect();
+        assert_eq!(names, expected);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::*;
+use std::fmt;
+
+// -----------------------------------------------------------------------------
+
+/// A construct which is not a part of the selected language standard.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionViolation {
+    /// The description of the construct such as `always_ff`
+    pub construct: &'static str,
+    /// The first standard containing the construct
+    pub required: Standard,
+    /// The standard in effect at the construct
+    pub standard: Standard,
+    pub locate: Locate,
+}
+
+impl fmt::Display for VersionViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} requires {} but {} is selected",
+            self.construct, self.required, self.standard
+        )
+    }
+}
+
+/// Get all constructs which are newer than the standard.
+///
+/// The standard can be overridden by `begin_keywords in the source text. Directives like `ifdef
+/// macro expressions are not in the tree, and are rejected by the preprocessor instead.
+pub fn check_standard(tree: &SyntaxTree, standard: Standard) -> Vec<VersionViolation> {
+    let mut ret = Vec::new();
+    let mut standards = vec![standard];
+
+    for node in tree {
+        match node {
+            RefNode::KeywordsDirective(x) => {
+                let specifier = tree.get_str_trim(&x.nodes.3).unwrap_or("");
+                standards.push(version_specifier(specifier).unwrap_or(standard));
+            }
+            RefNode::EndkeywordsDirective(_) => {
+                if standards.len() > 1 {
+                    standards.pop();
+                }
+            }
+            _ => {
+                let current = *standards.last().unwrap();
+                if let Some((construct, required)) = construct(tree, &node) {
+                    if required > current {
+                        if let Some(locate) = first_locate(&node) {
+                            ret.push(VersionViolation {
+                                construct,
+                                required,
+                                standard: current,
+                                locate,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    ret
+}
+
+// -----------------------------------------------------------------------------
+
+fn version_specifier(s: &str) -> Option<Standard> {
+    match s {
+        "1364-1995" => Some(Standard::Ieee1364_1995),
+        "1364-2001" | "1364-2001-noconfig" => Some(Standard::Ieee1364_2001),
+        "1364-2005" => Some(Standard::Ieee1364_2005),
+        "1800-2005" => Some(Standard::Ieee1800_2005),
+        "1800-2009" => Some(Standard::Ieee1800_2009),
+        "1800-2012" => Some(Standard::Ieee1800_2012),
+        "1800-2017" => Some(Standard::Ieee1800_2017),
+        "1800-2023" => Some(Standard::Ieee1800_2023),
+        _ => None,
+    }
+}
+
+fn construct(tree: &SyntaxTree, node: &RefNode) -> Option<(&'static str, Standard)> {
+    let v2001 = Standard::Ieee1364_2001;
+    let v2005 = Standard::Ieee1364_2005;
+    let sv2005 = Standard::Ieee1800_2005;
+    let sv2009 = Standard::Ieee1800_2009;
+    let sv2012 = Standard::Ieee1800_2012;
+    let sv2023 = Standard::Ieee1800_2023;
+
+    let ret = match node {
+        // IEEE1364-2001
+        RefNode::AnsiPortDeclaration(_) => ("ANSI style port declaration", v2001),
+        RefNode::GenerateRegion(_) => ("generate region", v2001),
+        RefNode::GenvarDeclaration(_) => ("genvar declaration", v2001),
+        RefNode::LocalParameterDeclaration(_) => ("localparam", v2001),
+        RefNode::AttributeInstance(_) => ("attribute instance", v2001),
+        RefNode::ConfigDeclaration(_) => ("config declaration", v2001),
+        RefNode::ParameterPortList(_) => ("parameter port list", v2001),
+        RefNode::EventControlAsterisk(_) | RefNode::EventControlParenAsterisk(_) => {
+            ("implicit event expression list", v2001)
+        }
+        RefNode::Signing(_) => ("signed and unsigned", v2001),
+        RefNode::Lifetime(_) => ("automatic and static", v2001),
+        RefNode::IndexedRange(_) => ("indexed part-select", v2001),
+        RefNode::BinaryOperator(x) => match tree.get_str_trim(&x.nodes.0) {
+            Some("**") => ("power operator", v2001),
+            Some("<<<") | Some(">>>") => ("arithmetic shift operator", v2001),
+            _ => return None,
+        },
+
+        // IEEE1364-2005
+        RefNode::NetType(NetType::Uwire(_)) => ("uwire", v2005),
+
+        // IEEE1800-2005
+        RefNode::IntegerVectorType(IntegerVectorType::Logic(_)) => ("logic", sv2005),
+        RefNode::IntegerVectorType(IntegerVectorType::Bit(_)) => ("bit", sv2005),
+        RefNode::IntegerAtomType(x) => match x {
+            IntegerAtomType::Byte(_) => ("byte", sv2005),
+            IntegerAtomType::Shortint(_) => ("shortint", sv2005),
+            IntegerAtomType::Int(_) => ("int", sv2005),
+            IntegerAtomType::Longint(_) => ("longint", sv2005),
+            _ => return None,
+        },
+        RefNode::NonIntegerType(NonIntegerType::Shortreal(_)) => ("shortreal", sv2005),
+        RefNode::DataType(x) => match x {
+            DataType::String(_) => ("string", sv2005),
+            DataType::Chandle(_) => ("chandle", sv2005),
+            DataType::Virtual(_) => ("virtual interface", sv2005),
+            DataType::Enum(_) => ("enum", sv2005),
+            DataType::StructUnion(_) => ("struct and union", sv2005),
+            _ => return None,
+        },
+        RefNode::AlwaysKeyword(x) => match x {
+            AlwaysKeyword::AlwaysComb(_) => ("always_comb", sv2005),
+            AlwaysKeyword::AlwaysFf(_) => ("always_ff", sv2005),
+            AlwaysKeyword::AlwaysLatch(_) => ("always_latch", sv2005),
+            AlwaysKeyword::Always(_) => return None,
+        },
+        RefNode::ClassDeclaration(_) => ("class declaration", sv2005),
+        RefNode::InterfaceDeclaration(_) => ("interface declaration", sv2005),
+        RefNode::PackageDeclaration(_) => ("package declaration", sv2005),
+        RefNode::ProgramDeclaration(_) => ("program declaration", sv2005),
+        RefNode::TypeDeclaration(_) => ("typedef", sv2005),
+        RefNode::ModportDeclaration(_) => ("modport declaration", sv2005),
+        RefNode::ClockingDeclaration(ClockingDeclaration::Global(_)) => ("global clocking", sv2009),
+        RefNode::ClockingDeclaration(_) => ("clocking block", sv2005),
+        RefNode::ConstraintDeclaration(_) => ("constraint declaration", sv2005),
+        RefNode::CovergroupDeclaration(_) => ("covergroup declaration", sv2005),
+        RefNode::FinalConstruct(_) => ("final procedure", sv2005),
+        RefNode::PackageImportDeclaration(_) => ("package import", sv2005),
+        RefNode::ConcurrentAssertionItem(_)
+        | RefNode::SimpleImmediateAssertionStatement(_)
+        | RefNode::DeferredImmediateAssertionStatement(_) => ("assertion", sv2005),
+        RefNode::PropertyDeclaration(_) => ("property declaration", sv2005),
+        RefNode::SequenceDeclaration(_) => ("sequence declaration", sv2005),
+        RefNode::LoopStatementForeach(_) => ("foreach", sv2005),
+        RefNode::LoopStatementDoWhile(_) => ("do-while", sv2005),
+        RefNode::JumpStatement(_) => ("jump statement", sv2005),
+        RefNode::IncOrDecExpression(_) => ("increment and decrement operator", sv2005),
+        RefNode::AssignmentOperator(x) => match tree.get_str_trim(&x.nodes.0) {
+            Some("=") | None => return None,
+            _ => ("assignment operator", sv2005),
+        },
+        RefNode::UniquePriority(UniquePriority::Unique0(_)) => ("unique0", sv2009),
+        RefNode::UniquePriority(_) => ("unique and priority", sv2005),
+        RefNode::UnbasedUnsizedLiteral(_) => ("unbased unsized literal", sv2005),
+        RefNode::NamedPortConnectionAsterisk(_) => ("implicit .* port connection", sv2005),
+        RefNode::TimeunitsDeclaration(_) => ("timeunit declaration", sv2005),
+        RefNode::InsideExpression(_) => ("inside operator", sv2005),
+        RefNode::StreamingConcatenation(_) => ("streaming concatenation", sv2005),
+        RefNode::AssignmentPattern(_) => ("assignment pattern", sv2005),
+        RefNode::Cast(_) => ("cast", sv2005),
+        RefNode::DpiImportExport(_) => ("DPI import and export", sv2005),
+
+        // IEEE1800-2009
+        RefNode::CheckerDeclaration(_) => ("checker declaration", sv2009),
+        RefNode::LetDeclaration(_) => ("let declaration", sv2009),
+
+        // IEEE1800-2012
+        RefNode::InterfaceClassDeclaration(_) => ("interface class", sv2012),
+        RefNode::NetTypeDeclaration(_) => ("nettype declaration", sv2012),
+        RefNode::NetDeclarationNetTypeIdentifier(x) => {
+            // A keyword of a newer standard like `logic` is a nettype identifier in the older one
+            let name = tree.get_str_trim(&x.nodes.0).unwrap_or("");
+            keyword_construct(name).unwrap_or(("user-defined nettype", sv2012))
+        }
+        RefNode::NetDeclarationInterconnect(_) | RefNode::NetPortTypeInterconnect(_) => {
+            ("interconnect", sv2012)
+        }
+
+        // IEEE1800-2023
+        RefNode::InitialOrExtendsSpecifier(_) => ("initial and extends specifier", sv2023),
+        RefNode::FinalSpecifier(_) => ("final specifier", sv2023),
+        RefNode::TfPortDirection(TfPortDirection::RefStatic(_)) => ("ref static", sv2023),
+        RefNode::ForwardType(_) => ("type parameter restriction", sv2023),
+        RefNode::StringLiteral(x) => match tree.get_str_trim(*x) {
+            Some(x) if x.starts_with("\"\"\"") => ("triple-quoted string", sv2023),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(ret)
+}
+
+fn keyword_construct(name: &str) -> Option<(&'static str, Standard)> {
+    let standards = [
+        Standard::Ieee1364_1995,
+        Standard::Ieee1364_2001,
+        Standard::Ieee1364_2005,
+        Standard::Ieee1800_2005,
+        Standard::Ieee1800_2009,
+        Standard::Ieee1800_2012,
+        Standard::Ieee1800_2017,
+        Standard::Ieee1800_2023,
+    ];
+    standards.iter().find_map(|standard| {
+        let keyword = standard.keywords().iter().find(|x| **x == name)?;
+        Some((*keyword, *standard))
+    })
+}
+
+fn first_locate(node: &RefNode) -> Option<Locate> {
+    let mut whitespace = 0;
+    for event in node.clone().into_iter().event() {
+        match event {
+            NodeEvent::Enter(RefNode::WhiteSpace(_)) => whitespace += 1,
+            NodeEvent::Leave(RefNode::WhiteSpace(_)) => whitespace -= 1,
+            NodeEvent::Enter(RefNode::Locate(x)) if whitespace == 0 => return Some(*x),
+            _ => (),
+        }
+    }
+    None
+}
+
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_check_standard() {
+        let src = r##"module a #(parameter W = 8) (input [W-1:0] x, output reg y);
+  always @* y = x ** 2;
+endmodule
+`begin_keywords "1800-2017"
+module b;
+  logic z;
+  always_comb z = 1'b0;
+endmodule
+`end_keywords
+module c;
+  reg [7:0] w;
+  initial w[0+:4] = 4'h0;
+endmodule"##;
+        let (tree, _) = parse_sv_str_standard(src, "", Standard::Ieee1364_2005).unwrap();
+
+        assert!(check_standard(&tree, Standard::Ieee1364_2005).is_empty());
+        assert!(check_standard(&tree, Standard::Ieee1800_2023).is_empty());
+
+        let violations = check_standard(&tree, Standard::Ieee1364_1995);
+        let constructs: Vec<_> = violations.iter().map(|x| x.construct).collect();
+        assert_eq!(
+            constructs,
+            vec![
+                "parameter port list",
+                "ANSI style port declaration",
+                "ANSI style port declaration",
+                "implicit event expression list",
+                "power operator",
+                "indexed part-select",
+            ]
+        );
+        assert_eq!(violations[0].locate.line, 1);
+        assert_eq!(violations[5].locate.line, 12);
+        assert_eq!(violations[5].required, Standard::Ieee1364_2001);
+
+        let src = "module d; logic e; bit f; endmodule";
+        let ret = parse_sv_str_standard(src, "", Standard::Ieee1364_2005);
+        match ret {
+            Err(Error::Version(x)) => {
+                let x: Vec<_> = x
+                    .iter()
+                    .map(|(origin, message)| (origin, message.as_str()))
+                    .collect();
+                assert_eq!(
+                    x,
+                    vec![
+                        (
+                            &Some((PathBuf::from(""), 10)),
+                            "logic requires IEEE 1800-2005 but IEEE 1364-2005 is selected"
+                        ),
+                        (
+                            &Some((PathBuf::from(""), 19)),
+                            "bit requires IEEE 1800-2005 but IEEE 1364-2005 is selected"
+                        ),
+                    ]
+                );
+            }
+            _ => panic!("{:?}", ret),
+        }
+        let ret = parse_sv_str_standard(src, "", Standard::Ieee1800_2005);
+        assert!(ret.is_ok());
+
+        let src = r##"class :final e #(type class C = int);
+  function :initial :final void f(ref static int x, string s = """g""");
+  endfunction
+endclass"##;
+        let (tree, _) = parse_sv_str_standard(src, "", Standard::Ieee1800_2023).unwrap();
+        let violations = check_standard(&tree, Standard::Ieee1800_2017);
+        let constructs: Vec<_> = violations.iter().map(|x| x.construct).collect();
+        assert_eq!(
+            constructs,
+            vec![
+                "final specifier",
+                "type parameter restriction",
+                "initial and extends specifier",
+                "final specifier",
+                "ref static",
+                "triple-quoted string",
+            ]
+        );
+        assert!(parse_sv_str_standard(src, "", Standard::Ieee1800_2017).is_err());
+
+        let src = "`ifdef (A || !B)\n`endif\nmodule f; endmodule";
+        let ret = parse_sv_str_standard(src, "", Standard::Ieee1800_2017);
+        assert!(matches!(ret, Err(Error::Preprocess(Some((_, 0))))));
+        assert!(parse_sv_str_standard(src, "", Standard::Ieee1800_2023).is_ok());
+    }
+
+    fn parse_sv_str_standard(
+        s: &str,
+        path: &str,
+        standard: Standard,
+    ) -> Result<(SyntaxTree, Defines), Error> {
+        let options = PreprocessOptions {
+            standard: Some(standard),
+            ..PreprocessOptions::default()
+        };
+        let (text, defines) =
+            preprocess_str_with(s, path, &HashMap::new(), &[""], false, false, &options)?;
+        let options = ParseOptions {
+            standard: Some(standard),
+            ..ParseOptions::default()
+        };
+        parse_sv_pp_with(text, defines, false, &options)
+    }
+}
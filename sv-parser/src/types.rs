@@ -0,0 +1,1526 @@
+use crate::const_eval::*;
+use crate::utils::*;
+use crate::*;
+use std::collections::HashMap;
+
+const RECURSIVE_LIMIT: usize = 64;
+
+// -----------------------------------------------------------------------------
+
+/// Canonical description of a resolved data type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeInfo {
+    /// The outermost typedef name if the type was declared through `typedef`
+    pub name: Option<String>,
+    pub kind: TypeKind,
+    pub signed: bool,
+    /// Packed dimensions as `(left, right)` from the outermost, which are `None` if the bounds
+    /// can't be evaluated
+    pub packed: Vec<Option<(i64, i64)>>,
+    /// Unpacked dimensions from the outermost
+    pub unpacked: Vec<Dimension>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeKind {
+    Bit,
+    Logic,
+    Reg,
+    Byte,
+    Shortint,
+    Int,
+    Longint,
+    Integer,
+    Time,
+    Shortreal,
+    Real,
+    Realtime,
+    String,
+    Chandle,
+    Event,
+    Void,
+    Struct {
+        packed: bool,
+        members: Vec<Member>,
+    },
+    Union {
+        packed: bool,
+        tagged: bool,
+        members: Vec<Member>,
+    },
+    Enum {
+        base: Box<TypeInfo>,
+        members: Vec<EnumMember>,
+    },
+    Class(String),
+    VirtualInterface(String),
+    /// A type which could not be resolved, with the original text
+    Unresolved(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Dimension {
+    Range(i64, i64),
+    Dynamic,
+    Queue(Option<i64>),
+    Associative,
+    /// A fixed size dimension whose bounds can't be evaluated
+    Unresolved,
+}
+
+/// A member of struct or union.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub ty: TypeInfo,
+    /// Bit offset from LSB of the enclosing packed struct/union
+    pub offset: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumMember {
+    pub name: String,
+    pub value: Option<i64>,
+}
+
+/// The value of a parameter override.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamValue {
+    Value(i64),
+    Type(TypeInfo),
+}
+
+impl TypeInfo {
+    fn new(kind: TypeKind) -> Self {
+        TypeInfo {
+            name: None,
+            kind,
+            signed: false,
+            packed: Vec::new(),
+            unpacked: Vec::new(),
+        }
+    }
+
+    /// Get the bit width as `$bits` does.
+    ///
+    /// `None` is returned for types without a fixed width such as `string`, classes and dynamic arrays,
+    /// and for widths overflowing `u64`.
+    pub fn bits(&self) -> Option<u64> {
+        let mut bits = match &self.kind {
+            TypeKind::Bit | TypeKind::Logic | TypeKind::Reg => 1,
+            TypeKind::Byte => 8,
+            TypeKind::Shortint => 16,
+            TypeKind::Int | TypeKind::Integer | TypeKind::Shortreal => 32,
+            TypeKind::Longint | TypeKind::Time | TypeKind::Real | TypeKind::Realtime => 64,
+            TypeKind::Struct { members, .. } => {
+                let mut ret: u64 = 0;
+                for m in members {
+                    ret = ret.checked_add(m.ty.bits()?)?;
+                }
+                ret
+            }
+            TypeKind::Union { members, .. } => {
+                let mut ret = 0;
+                for m in members {
+                    ret = ret.max(m.ty.bits()?);
+                }
+                ret
+            }
+            TypeKind::Enum { base, .. } => base.bits()?,
+            _ => return None,
+        };
+        for x in &self.packed {
+            let (l, r) = (*x)?;
+            bits = bits.checked_mul(range_size(l, r)?)?;
+        }
+        for d in &self.unpacked {
+            match d {
+                Dimension::Range(l, r) => bits = bits.checked_mul(range_size(*l, *r)?)?,
+                _ => return None,
+            }
+        }
+        Some(bits)
+    }
+
+    /// Check whether the type is an integral type, which can be used in packed structures.
+    pub fn is_integral(&self) -> bool {
+        if !self.unpacked.is_empty() {
+            return false;
+        }
+        match &self.kind {
+            TypeKind::Struct { packed, .. } | TypeKind::Union { packed, .. } => *packed,
+            TypeKind::Bit
+            | TypeKind::Logic
+            | TypeKind::Reg
+            | TypeKind::Byte
+            | TypeKind::Shortint
+            | TypeKind::Int
+            | TypeKind::Longint
+            | TypeKind::Integer
+            | TypeKind::Time
+            | TypeKind::Enum { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Get the member of struct or union.
+    pub fn member(&self, name: &str) -> Option<&Member> {
+        match &self.kind {
+            TypeKind::Struct { members, .. } | TypeKind::Union { members, .. } => {
+                members.iter().find(|x| x.name == name)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the value of enum member.
+    pub fn enum_value(&self, name: &str) -> Option<i64> {
+        match &self.kind {
+            TypeKind::Enum { members, .. } => members
+                .iter()
+                .find(|x| x.name == name)
+                .and_then(|x| x.value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeDiagnostic {
+    /// The enum member has the same value as a previous member
+    DuplicateEnumValue {
+        scope: String,
+        name: String,
+        value: i64,
+        locate: Locate,
+    },
+    /// The enum member value can't be represented by the base type
+    EnumValueOverflow {
+        scope: String,
+        name: String,
+        value: i64,
+        width: u64,
+        locate: Locate,
+    },
+}
+
+// -----------------------------------------------------------------------------
+
+enum Param<'a> {
+    Value(Option<&'a ConstantParamExpression>),
+    Type(Option<&'a DataType>),
+}
+
+struct Variable<'a> {
+    data_type: RefNode<'a>,
+    dimensions: Vec<RefNode<'a>>,
+}
+
+#[derive(Default)]
+struct Scope<'a> {
+    parent: Option<String>,
+    typedefs: HashMap<String, &'a TypeDeclarationDataType>,
+    params: HashMap<String, Param<'a>>,
+    param_order: Vec<String>,
+    variables: HashMap<String, Variable<'a>>,
+    enums: Vec<&'a DataTypeEnum>,
+    class: bool,
+    imports: Vec<String>,
+    explicit_imports: HashMap<String, String>,
+}
+
+/// Resolves typedefs, structs, enums and parameters of a `SyntaxTree`.
+///
+/// Declarations are grouped by scope, which is the name of the module, interface, program,
+/// package or class declaring them. Declarations outside of them belong to `$unit`.
+/// A class is named with the enclosing scope like `pkg::cls` unless it's declared in `$unit`.
+pub struct TypeResolver<'a> {
+    tree: &'a SyntaxTree,
+    scopes: HashMap<String, Scope<'a>>,
+    overrides: HashMap<String, HashMap<String, ParamValue>>,
+}
+
+impl<'a> TypeResolver<'a> {
+    pub fn new(tree: &'a SyntaxTree) -> Self {
+        let mut scopes: HashMap<String, Scope> = HashMap::new();
+        scopes.insert(String::from(UNIT_SCOPE), Scope::default());
+
+        let mut stack = vec![String::from(UNIT_SCOPE)];
+        let mut local = 0;
+        for event in tree.into_iter().event() {
+            match event {
+                NodeEvent::Enter(node) => {
+                    if let Some(name) = scope_name(tree, &node) {
+                        let parent = stack.last().cloned().unwrap();
+                        let class = matches!(
+                            node,
+                            RefNode::ClassDeclaration(_) | RefNode::InterfaceClassDeclaration(_)
+                        );
+                        let name = if class { qualify(&parent, &name) } else { name };
+                        let scope = scopes.entry(name.clone()).or_default();
+                        scope.parent = Some(parent);
+                        scope.class = class;
+                        stack.push(name);
+                        continue;
+                    }
+                    let scope = scopes.get_mut(stack.last().unwrap()).unwrap();
+                    match node {
+                        RefNode::LocalParameterDeclaration(_) => local += 1,
+                        RefNode::TypeDeclarationDataType(x) => {
+                            if let Some(name) = identifier(tree, &x.nodes.2) {
+                                scope.typedefs.insert(name, x);
+                            }
+                        }
+                        RefNode::ParamAssignment(x) => {
+                            if let Some(name) = identifier(tree, &x.nodes.0) {
+                                let expr = x.nodes.2.as_ref().map(|(_, x)| x);
+                                if local == 0 {
+                                    scope.param_order.push(name.clone());
+                                }
+                                scope.params.insert(name, Param::Value(expr));
+                            }
+                        }
+                        RefNode::TypeAssignment(x) => {
+                            if let Some(name) = identifier(tree, &x.nodes.0) {
+                                let data_type = x.nodes.1.as_ref().map(|(_, x)| x);
+                                if local == 0 {
+                                    scope.param_order.push(name.clone());
+                                }
+                                scope.params.insert(name, Param::Type(data_type));
+                            }
+                        }
+                        RefNode::DataDeclarationVariable(x) => {
+                            let data_type: RefNode = (&x.nodes.3).into();
+                            for x in x.nodes.4.nodes.0.contents() {
+                                if let VariableDeclAssignment::Variable(x) = x {
+                                    if let Some(name) = identifier(tree, &x.nodes.0) {
+                                        let dimensions =
+                                            x.nodes.1.iter().map(|x| x.into()).collect();
+                                        scope.variables.insert(
+                                            name,
+                                            Variable {
+                                                data_type: data_type.clone(),
+                                                dimensions,
+                                            },
+                                        );
+                                    }
+                                }
+                            }
+                        }
+                        RefNode::NetDeclarationNetType(x) => {
+                            let data_type: RefNode = (&x.nodes.3).into();
+                            for x in x.nodes.5.nodes.0.contents() {
+                                if let Some(name) = identifier(tree, &x.nodes.0) {
+                                    let dimensions = x.nodes.1.iter().map(|x| x.into()).collect();
+                                    scope.variables.insert(
+                                        name,
+                                        Variable {
+                                            data_type: data_type.clone(),
+                                            dimensions,
+                                        },
+                                    );
+                                }
+                            }
+                        }
+                        // `type_identifier variable;` is parsed as a net of a user-defined nettype
+                        RefNode::NetDeclarationNetTypeIdentifier(x) => {
+                            let data_type: RefNode = (&x.nodes.0).into();
+                            for x in x.nodes.2.nodes.0.contents() {
+                                if let Some(name) = identifier(tree, &x.nodes.0) {
+                                    let dimensions = x.nodes.1.iter().map(|x| x.into()).collect();
+                                    scope.variables.insert(
+                                        name,
+                                        Variable {
+                                            data_type: data_type.clone(),
+                                            dimensions,
+                                        },
+                                    );
+                                }
+                            }
+                        }
+                        RefNode::AnsiPortDeclarationNet(x) => {
+                            let data_type: Option<RefNode> = match &x.nodes.0 {
+                                Some(NetPortHeaderOrInterfacePortHeader::NetPortHeader(x)) => {
+                                    match &x.nodes.1 {
+                                        NetPortType::DataType(x) => Some((&x.nodes.1).into()),
+                                        _ => None,
+                                    }
+                                }
+                                _ => None,
+                            };
+                            if let (Some(data_type), Some(name)) =
+                                (data_type, identifier(tree, &x.nodes.1))
+                            {
+                                let dimensions = x.nodes.2.iter().map(|x| x.into()).collect();
+                                scope.variables.insert(
+                                    name,
+                                    Variable {
+                                        data_type,
+                                        dimensions,
+                                    },
+                                );
+                            }
+                        }
+                        RefNode::AnsiPortDeclarationVariable(x) => {
+                            let data_type: Option<RefNode> =
+                                x.nodes.0.as_ref().map(|x| match &x.nodes.1.nodes.0 {
+                                    VarDataType::DataType(x) => (&**x).into(),
+                                    VarDataType::Var(x) => (&x.nodes.1).into(),
+                                });
+                            if let (Some(data_type), Some(name)) =
+                                (data_type, identifier(tree, &x.nodes.1))
+                            {
+                                let dimensions = x.nodes.2.iter().map(|x| x.into()).collect();
+                                scope.variables.insert(
+                                    name,
+                                    Variable {
+                                        data_type,
+                                        dimensions,
+                                    },
+                                );
+                            }
+                        }
+                        RefNode::DataTypeEnum(x) => scope.enums.push(x),
+                        RefNode::PackageImportItem(x) => match x {
+                            PackageImportItem::Identifier(x) => {
+                                if let (Some(package), Some(name)) =
+                                    (identifier(tree, &x.nodes.0), identifier(tree, &x.nodes.2))
+                                {
+                                    scope.explicit_imports.insert(name, package);
+                                }
+                            }
+                            PackageImportItem::Asterisk(x) => {
+                                if let Some(package) = identifier(tree, &x.nodes.0) {
+                                    scope.imports.push(package);
+                                }
+                            }
+                        },
+                        _ => (),
+                    }
+                }
+                NodeEvent::Leave(node) => match node {
+                    RefNode::LocalParameterDeclaration(_) => local -= 1,
+                    _ if scope_name(tree, &node).is_some() => {
+                        stack.pop();
+                    }
+                    _ => (),
+                },
+            }
+        }
+
+        TypeResolver {
+            tree,
+            scopes,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Override the value of a parameter or type parameter in the specified scope.
+    pub fn set_parameter(&mut self, scope: &str, name: &str, value: ParamValue) {
+        self.overrides
+            .entry(String::from(scope))
+            .or_default()
+            .insert(String::from(name), value);
+    }
+
+    /// Get the names of all scopes.
+    pub fn scopes(&self) -> Vec<&str> {
+        let mut ret: Vec<&str> = self.scopes.keys().map(|x| x.as_str()).collect();
+        ret.sort_unstable();
+        ret
+    }
+
+    /// Get the names of typedefs declared in the specified scope.
+    pub fn typedefs(&self, scope: &str) -> Vec<&str> {
+        let mut ret: Vec<&str> = self
+            .scopes
+            .get(scope)
+            .map(|x| x.typedefs.keys().map(|x| x.as_str()).collect())
+            .unwrap_or_default();
+        ret.sort_unstable();
+        ret
+    }
+
+    /// Get the names of parameters in declaration order, excluding localparams.
+    pub fn parameters(&self, scope: &str) -> Vec<&str> {
+        self.scopes
+            .get(scope)
+            .map(|x| x.param_order.iter().map(|x| x.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the value of a parameter, localparam or enum member visible from the specified scope.
+    pub fn parameter(&self, scope: &str, name: &str) -> Option<i64> {
+        self.lookup_value(&self.context(scope, 0), None, name)
+    }
+
+    /// Resolve a typedef or type parameter visible from the specified scope.
+    pub fn resolve_typedef(&self, scope: &str, name: &str) -> Option<TypeInfo> {
+        self.lookup_type(&self.context(scope, 0), None, name)
+    }
+
+    /// Resolve a data type appearing in the specified scope.
+    pub fn resolve_data_type(&self, scope: &str, data_type: &DataType) -> TypeInfo {
+        self.data_type(&self.context(scope, 0), data_type)
+    }
+
+    /// Resolve the type of a variable, net or port declared in the specified scope.
+    pub fn variable_type(&self, scope: &str, name: &str) -> Option<TypeInfo> {
+        self.lookup_variable(&self.context(scope, 0), name)
+    }
+
+    /// Evaluate a constant expression in the specified scope.
+    pub fn eval<'b, T: Into<RefNode<'b>>>(&self, scope: &str, node: T) -> Option<i64> {
+        eval_const(self.tree, node, &self.context(scope, 0))
+    }
+
+    /// Evaluate a constant expression in the specified scope with local values such as genvars.
+    ///
+    /// Local values take precedence over the declarations in the scope.
+    pub fn eval_with<'b, T: Into<RefNode<'b>>>(
+        &self,
+        scope: &str,
+        node: T,
+        locals: &HashMap<String, i64>,
+    ) -> Option<i64> {
+        let context = self.context(scope, 0);
+        let context = LocalContext {
+            context: &context,
+            values: locals.clone(),
+        };
+        eval_const(self.tree, node, &context)
+    }
+
+    /// Check enum members with duplicate values or values not fitting the base type.
+    pub fn check(&self) -> Vec<TypeDiagnostic> {
+        let mut ret = Vec::new();
+        for scope in self.scopes() {
+            let ctx = self.context(scope, 0);
+            for x in &self.scopes[scope].enums {
+                let base = self.enum_base(&ctx, x);
+                let width = base.bits();
+                let mut values: Vec<i64> = Vec::new();
+                for (member, locate) in self.enum_declarations(&ctx, x) {
+                    let value = match member.value {
+                        Some(x) => x,
+                        None => continue,
+                    };
+                    if values.contains(&value) {
+                        ret.push(TypeDiagnostic::DuplicateEnumValue {
+                            scope: String::from(scope),
+                            name: member.name,
+                            value,
+                            locate,
+                        });
+                    } else if let Some(width) = width.filter(|x| !fits(value, *x, base.signed)) {
+                        ret.push(TypeDiagnostic::EnumValueOverflow {
+                            scope: String::from(scope),
+                            name: member.name,
+                            value,
+                            width,
+                            locate,
+                        });
+                    }
+                    values.push(value);
+                }
+            }
+        }
+        ret
+    }
+
+    // -------------------------------------------------------------------------
+
+    fn context(&self, scope: &str, depth: usize) -> Context<'_, 'a> {
+        Context {
+            resolver: self,
+            scope: String::from(scope),
+            overrides: self.overrides.get(scope).cloned().unwrap_or_default(),
+            depth,
+        }
+    }
+
+    fn scope_chain(&self, scope: &str) -> Vec<String> {
+        let mut ret = vec![String::from(scope)];
+        let mut scope = self.scopes.get(scope);
+        while let Some(parent) = scope.and_then(|x| x.parent.as_ref()) {
+            if ret.contains(parent) {
+                break;
+            }
+            ret.push(parent.clone());
+            scope = self.scopes.get(parent);
+        }
+        if !ret.iter().any(|x| x == UNIT_SCOPE) {
+            ret.push(String::from(UNIT_SCOPE));
+        }
+        ret
+    }
+
+    /// Find the scopes to be searched for `name` from `ctx`, with the nearest first.
+    fn visible_scopes<'r>(
+        &'r self,
+        ctx: &Context<'r, 'a>,
+        package: Option<&str>,
+        name: &str,
+    ) -> Vec<Context<'r, 'a>> {
+        let depth = ctx.depth + 1;
+        if let Some(package) = package {
+            let package = self.scope_key(&ctx.scope, package);
+            if package == ctx.scope {
+                return vec![ctx.deeper()];
+            }
+            return vec![self.context(&package, depth)];
+        }
+        let mut ret = Vec::new();
+        for scope in self.scope_chain(&ctx.scope) {
+            if scope == ctx.scope {
+                ret.push(ctx.deeper());
+            } else {
+                ret.push(self.context(&scope, depth));
+            }
+            if let Some(x) = self.scopes.get(&scope) {
+                if let Some(package) = x.explicit_imports.get(name) {
+                    ret.push(self.context(package, depth));
+                }
+                for package in &x.imports {
+                    ret.push(self.context(package, depth));
+                }
+            }
+        }
+        ret
+    }
+
+    fn lookup_type(
+        &self,
+        ctx: &Context<'_, 'a>,
+        package: Option<&str>,
+        name: &str,
+    ) -> Option<TypeInfo> {
+        if ctx.depth > RECURSIVE_LIMIT {
+            return None;
+        }
+        for ctx in self.visible_scopes(ctx, package, name) {
+            if let Some(x) = ctx.overrides.get(name) {
+                match x {
+                    ParamValue::Type(x) => return Some(x.clone()),
+                    ParamValue::Value(_) => return None,
+                }
+            }
+            let scope = match self.scopes.get(&ctx.scope) {
+                Some(x) => x,
+                None => continue,
+            };
+            match scope.params.get(name) {
+                Some(Param::Type(Some(x))) => return Some(self.data_type(&ctx, x)),
+                Some(Param::Type(None)) => {
+                    return Some(TypeInfo::new(TypeKind::Unresolved(String::from(name))))
+                }
+                Some(Param::Value(_)) => return None,
+                None => (),
+            }
+            if let Some(x) = scope.typedefs.get(name) {
+                let (_, ref data_type, _, ref dimensions, _) = x.nodes;
+                let mut ret = self.data_type(&ctx, data_type);
+                let mut unpacked =
+                    self.unpacked_dimensions(&ctx, dimensions.iter().map(|x| x.into()));
+                unpacked.append(&mut ret.unpacked);
+                ret.unpacked = unpacked;
+                ret.name = Some(String::from(name));
+                return Some(ret);
+            }
+        }
+        None
+    }
+
+    fn lookup_value(
+        &self,
+        ctx: &Context<'_, 'a>,
+        package: Option<&str>,
+        name: &str,
+    ) -> Option<i64> {
+        if ctx.depth > RECURSIVE_LIMIT {
+            return None;
+        }
+        for ctx in self.visible_scopes(ctx, package, name) {
+            if let Some(x) = ctx.overrides.get(name) {
+                match x {
+                    ParamValue::Value(x) => return Some(*x),
+                    ParamValue::Type(_) => return None,
+                }
+            }
+            let scope = match self.scopes.get(&ctx.scope) {
+                Some(x) => x,
+                None => continue,
+            };
+            match scope.params.get(name) {
+                Some(Param::Value(Some(x))) => return eval_const(self.tree, *x, &ctx),
+                Some(_) => return None,
+                None => (),
+            }
+            for x in &scope.enums {
+                if enum_names(self.tree, x).iter().any(|x| x == name) {
+                    let members = self.enum_members(&ctx, x);
+                    return members
+                        .into_iter()
+                        .find(|x| x.name == name)
+                        .and_then(|x| x.value);
+                }
+            }
+        }
+        None
+    }
+
+    fn lookup_variable(&self, ctx: &Context<'_, 'a>, name: &str) -> Option<TypeInfo> {
+        if ctx.depth > RECURSIVE_LIMIT {
+            return None;
+        }
+        for ctx in self.visible_scopes(ctx, None, name) {
+            let x = match self
+                .scopes
+                .get(&ctx.scope)
+                .and_then(|x| x.variables.get(name))
+            {
+                Some(x) => x,
+                None => continue,
+            };
+            let mut ret = match x.data_type {
+                RefNode::DataType(x) => self.data_type(&ctx, x),
+                RefNode::DataTypeOrImplicit(x) => self.data_type_or_implicit(&ctx, x),
+                RefNode::NetTypeIdentifier(x) => identifier(self.tree, x)
+                    .and_then(|x| self.lookup_type(&ctx, None, &x))
+                    .unwrap_or_else(|| TypeInfo::new(TypeKind::Unresolved(String::from(name)))),
+                _ => TypeInfo::new(TypeKind::Unresolved(String::from(name))),
+            };
+            let mut unpacked = self.unpacked_dimensions(&ctx, x.dimensions.iter().cloned());
+            unpacked.append(&mut ret.unpacked);
+            ret.unpacked = unpacked;
+            return Some(ret);
+        }
+        None
+    }
+
+    fn data_type_or_implicit(&self, ctx: &Context<'_, 'a>, x: &DataTypeOrImplicit) -> TypeInfo {
+        match x {
+            DataTypeOrImplicit::DataType(x) => self.data_type(ctx, x),
+            DataTypeOrImplicit::ImplicitDataType(x) => {
+                let (ref signing, ref dimensions) = x.nodes;
+                let mut ret = TypeInfo::new(TypeKind::Logic);
+                ret.signed = matches!(signing, Some(Signing::Signed(_)));
+                ret.packed = self.packed_dimensions(ctx, dimensions.iter());
+                ret
+            }
+        }
+    }
+
+    fn data_type(&self, ctx: &Context<'_, 'a>, x: &DataType) -> TypeInfo {
+        let unresolved = || {
+            TypeInfo::new(TypeKind::Unresolved(String::from(
+                self.tree.get_str_trim(x).unwrap_or(""),
+            )))
+        };
+        if ctx.depth > RECURSIVE_LIMIT {
+            return unresolved();
+        }
+        let ctx = &ctx.deeper();
+        match x {
+            DataType::Vector(x) => {
+                let (ref kind, ref signing, ref dimensions) = x.nodes;
+                let mut ret = TypeInfo::new(match kind {
+                    IntegerVectorType::Bit(_) => TypeKind::Bit,
+                    IntegerVectorType::Logic(_) => TypeKind::Logic,
+                    IntegerVectorType::Reg(_) => TypeKind::Reg,
+                });
+                ret.signed = matches!(signing, Some(Signing::Signed(_)));
+                ret.packed = self.packed_dimensions(ctx, dimensions.iter());
+                ret
+            }
+            DataType::Atom(x) => {
+                let (ref kind, ref signing) = x.nodes;
+                atom_type(kind, signing.as_ref())
+            }
+            DataType::NonIntegerType(x) => TypeInfo::new(match &**x {
+                NonIntegerType::Shortreal(_) => TypeKind::Shortreal,
+                NonIntegerType::Real(_) => TypeKind::Real,
+                NonIntegerType::Realtime(_) => TypeKind::Realtime,
+            }),
+            DataType::StructUnion(x) => self.struct_union(ctx, x),
+            DataType::Enum(x) => {
+                let (_, _, _, ref dimensions) = x.nodes;
+                let base = self.enum_base(ctx, x);
+                let mut ret = TypeInfo::new(TypeKind::Enum {
+                    members: self.enum_members(ctx, x),
+                    base: Box::new(base.clone()),
+                });
+                ret.signed = base.signed;
+                ret.packed = self.packed_dimensions(ctx, dimensions.iter());
+                ret
+            }
+            DataType::String(_) => TypeInfo::new(TypeKind::String),
+            DataType::Chandle(_) => TypeInfo::new(TypeKind::Chandle),
+            DataType::Event(_) => TypeInfo::new(TypeKind::Event),
+            DataType::Virtual(x) => {
+                let name = identifier(self.tree, &x.nodes.2).unwrap_or_default();
+                TypeInfo::new(TypeKind::VirtualInterface(name))
+            }
+            DataType::Type(x) => {
+                let (ref scope, ref id, ref dimensions) = x.nodes;
+                let name = match identifier(self.tree, id) {
+                    Some(x) => x,
+                    None => return unresolved(),
+                };
+                let found = match scope {
+                    None => self.lookup_type(ctx, None, &name),
+                    Some(PackageScopeOrClassScope::PackageScope(x)) => {
+                        let package = package_scope_name(self.tree, x).unwrap_or_default();
+                        self.lookup_type(ctx, Some(&package), &name)
+                    }
+                    Some(PackageScopeOrClassScope::ClassScope(x)) => {
+                        let class = self.specialize(ctx, &x.nodes.0);
+                        self.lookup_type(&class, Some(&class.scope.clone()), &name)
+                    }
+                };
+                let mut ret = match found {
+                    Some(x) => x,
+                    None if scope.is_none() && self.is_class(&ctx.scope, &name) => {
+                        TypeInfo::new(TypeKind::Class(name))
+                    }
+                    None => return unresolved(),
+                };
+                let mut packed = self.packed_dimensions(ctx, dimensions.iter());
+                packed.append(&mut ret.packed);
+                ret.packed = packed;
+                ret
+            }
+            DataType::ClassType(x) => {
+                // `C#(...)::T` is parsed as a class type if `T` is not followed by dimensions.
+                let (ref id, _, ref members) = x.nodes;
+                match members.as_slice() {
+                    [] => {
+                        let name = identifier(self.tree, &id.nodes.1).unwrap_or_default();
+                        // `C::T` is parsed as a class type `T` in a package `C`
+                        let package = id
+                            .nodes
+                            .0
+                            .as_ref()
+                            .and_then(|x| package_scope_name(self.tree, x));
+                        match package.and_then(|x| self.lookup_type(ctx, Some(&x), &name)) {
+                            Some(x) => x,
+                            None => TypeInfo::new(TypeKind::Class(name)),
+                        }
+                    }
+                    [(_, member, None)] => {
+                        let name = identifier(self.tree, member).unwrap_or_default();
+                        let class = self.specialize(ctx, x);
+                        match self.lookup_type(&class, Some(&class.scope.clone()), &name) {
+                            Some(x) => x,
+                            None if self.is_class(&ctx.scope, &name) => {
+                                TypeInfo::new(TypeKind::Class(name))
+                            }
+                            None => unresolved(),
+                        }
+                    }
+                    _ => unresolved(),
+                }
+            }
+            DataType::TypeReference(x) => match &**x {
+                TypeReference::DataType(x) => self.data_type(ctx, &x.nodes.1.nodes.1),
+                TypeReference::Expression(x) => self
+                    .expression_type(ctx, &x.nodes.1.nodes.1)
+                    .unwrap_or_else(unresolved),
+            },
+            DataType::PsCovergroupIdentifier(_) => unresolved(),
+        }
+    }
+
+    /// Get the type of `type(expression)`.
+    ///
+    /// Only variables and parameters are supported. A value parameter without explicit type is
+    /// treated as `int` if its value can be evaluated.
+    fn expression_type(&self, ctx: &Context<'_, 'a>, x: &Expression) -> Option<TypeInfo> {
+        let name = match x {
+            Expression::Primary(x) => match &**x {
+                Primary::Hierarchical(x) => {
+                    let (ref scope, ref id, ref select) = x.nodes;
+                    if self.tree.get_str_trim(scope).is_some()
+                        || !id.nodes.1.is_empty()
+                        || self.tree.get_str_trim(select).is_some()
+                    {
+                        return None;
+                    }
+                    identifier(self.tree, &id.nodes.2)?
+                }
+                Primary::FunctionSubroutineCall(x) => match &x.nodes.0 {
+                    SubroutineCall::TfCall(x) if x.nodes.2.is_none() => {
+                        identifier(self.tree, &x.nodes.0)?
+                    }
+                    _ => return None,
+                },
+                _ => return None,
+            },
+            _ => return None,
+        };
+        if let Some(x) = self.lookup_variable(ctx, &name) {
+            return Some(x);
+        }
+        self.lookup_value(ctx, None, &name)
+            .map(|_| atom_type(&IntegerAtomType::Int(Box::new(dummy_keyword())), None))
+    }
+
+    fn struct_union(&self, ctx: &Context<'_, 'a>, x: &DataTypeStructUnion) -> TypeInfo {
+        let (ref kind, ref packed, ref body, ref dimensions) = x.nodes;
+        let is_packed = packed.is_some();
+        let signed = matches!(packed, Some((_, Some(Signing::Signed(_)))));
+
+        let (ref first, ref rest) = body.nodes.1;
+        let mut members = Vec::new();
+        for member in std::iter::once(first).chain(rest.iter()) {
+            let (_, _, ref data_type, ref list, _) = member.nodes;
+            let base = match data_type {
+                DataTypeOrVoid::DataType(x) => self.data_type(ctx, x),
+                DataTypeOrVoid::Void(_) => TypeInfo::new(TypeKind::Void),
+            };
+            for x in list.nodes.0.contents() {
+                if let VariableDeclAssignment::Variable(x) = x {
+                    let (ref id, ref dimensions, _) = x.nodes;
+                    let mut ty = base.clone();
+                    let mut unpacked =
+                        self.unpacked_dimensions(ctx, dimensions.iter().map(|x| x.into()));
+                    unpacked.append(&mut ty.unpacked);
+                    ty.unpacked = unpacked;
+                    members.push(Member {
+                        name: identifier(self.tree, id).unwrap_or_default(),
+                        ty,
+                        offset: None,
+                    });
+                }
+            }
+        }
+
+        let is_struct = matches!(kind, StructUnion::Struct(_));
+        if is_packed {
+            // The first member occupies the most significant bits.
+            let mut offset = Some(0);
+            for m in members.iter_mut().rev() {
+                if is_struct {
+                    m.offset = offset;
+                    offset = offset.and_then(|x| m.ty.bits().map(|y| x + y));
+                } else {
+                    m.offset = Some(0);
+                }
+            }
+        }
+
+        let mut ret = TypeInfo::new(match kind {
+            StructUnion::Struct(_) => TypeKind::Struct {
+                packed: is_packed,
+                members,
+            },
+            StructUnion::Union(_) => TypeKind::Union {
+                packed: is_packed,
+                tagged: false,
+                members,
+            },
+            StructUnion::UnionTagged(_) => TypeKind::Union {
+                packed: is_packed,
+                tagged: true,
+                members,
+            },
+        });
+        ret.signed = signed;
+        ret.packed = self.packed_dimensions(ctx, dimensions.iter());
+        ret
+    }
+
+    fn enum_base(&self, ctx: &Context<'_, 'a>, x: &DataTypeEnum) -> TypeInfo {
+        match &x.nodes.1 {
+            None => atom_type(&IntegerAtomType::Int(Box::new(dummy_keyword())), None),
+            Some(EnumBaseType::Atom(x)) => {
+                let (ref kind, ref signing) = x.nodes;
+                atom_type(kind, signing.as_ref())
+            }
+            Some(EnumBaseType::Vector(x)) => {
+                let (ref kind, ref signing, ref dimension) = x.nodes;
+                let mut ret = TypeInfo::new(match kind {
+                    IntegerVectorType::Bit(_) => TypeKind::Bit,
+                    IntegerVectorType::Logic(_) => TypeKind::Logic,
+                    IntegerVectorType::Reg(_) => TypeKind::Reg,
+                });
+                ret.signed = matches!(signing, Some(Signing::Signed(_)));
+                ret.packed = self.packed_dimensions(ctx, dimension.iter());
+                ret
+            }
+            Some(EnumBaseType::Type(x)) => {
+                let (ref id, ref dimension) = x.nodes;
+                let name = identifier(self.tree, id).unwrap_or_default();
+                let mut ret = self
+                    .lookup_type(ctx, None, &name)
+                    .unwrap_or_else(|| TypeInfo::new(TypeKind::Unresolved(name)));
+                let mut packed = self.packed_dimensions(ctx, dimension.iter());
+                packed.append(&mut ret.packed);
+                ret.packed = packed;
+                ret
+            }
+        }
+    }
+
+    fn enum_members(&self, ctx: &Context<'_, 'a>, x: &DataTypeEnum) -> Vec<EnumMember> {
+        self.enum_declarations(ctx, x)
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect()
+    }
+
+    /// Get the members of enum with the locations of their names.
+    fn enum_declarations(
+        &self,
+        ctx: &Context<'_, 'a>,
+        x: &DataTypeEnum,
+    ) -> Vec<(EnumMember, Locate)> {
+        let (_, _, ref body, _) = x.nodes;
+        let mut ret = Vec::new();
+        let mut local = LocalContext {
+            context: ctx,
+            values: HashMap::new(),
+        };
+        let mut next = Some(0);
+        for x in body.nodes.1.contents() {
+            let (ref id, ref range, ref value) = x.nodes;
+            let name = identifier(self.tree, id).unwrap_or_default();
+            let locate = identifier_locate(id).unwrap_or_default();
+            let mut value = match value {
+                Some((_, x)) => eval_const(self.tree, x, &local),
+                None => next,
+            };
+            for name in expand_enum_name(self.tree, &name, range.as_ref()) {
+                if let Some(value) = value {
+                    local.values.insert(name.clone(), value);
+                }
+                ret.push((EnumMember { name, value }, locate));
+                value = value.map(|x| x + 1);
+            }
+            next = value;
+        }
+        ret
+    }
+
+    fn packed_dimensions<'b, I: Iterator<Item = &'b PackedDimension>>(
+        &self,
+        ctx: &Context<'_, 'a>,
+        x: I,
+    ) -> Vec<Option<(i64, i64)>> {
+        let mut ret = Vec::new();
+        for x in x {
+            if let PackedDimension::Range(x) = x {
+                let (_, ref range, _) = x.nodes.0.nodes;
+                let l = eval_const(self.tree, &range.nodes.0, ctx);
+                let r = eval_const(self.tree, &range.nodes.2, ctx);
+                ret.push(l.zip(r));
+            }
+        }
+        ret
+    }
+
+    fn unpacked_dimensions<'b, I: Iterator<Item = RefNode<'b>>>(
+        &self,
+        ctx: &Context<'_, 'a>,
+        x: I,
+    ) -> Vec<Dimension> {
+        let mut ret = Vec::new();
+        for x in x {
+            let x = match x {
+                RefNode::VariableDimension(VariableDimension::UnpackedDimension(x)) => {
+                    (&**x).into()
+                }
+                RefNode::VariableDimension(VariableDimension::UnsizedDimension(_)) => {
+                    ret.push(Dimension::Dynamic);
+                    continue;
+                }
+                RefNode::VariableDimension(VariableDimension::AssociativeDimension(_)) => {
+                    ret.push(Dimension::Associative);
+                    continue;
+                }
+                RefNode::VariableDimension(VariableDimension::QueueDimension(x)) => {
+                    let (_, (_, ref max), _) = &x.nodes.0.nodes;
+                    let max = max
+                        .as_ref()
+                        .and_then(|(_, x)| eval_const(self.tree, x, ctx));
+                    ret.push(Dimension::Queue(max));
+                    continue;
+                }
+                x => x,
+            };
+            match x {
+                RefNode::UnpackedDimension(UnpackedDimension::Range(x)) => {
+                    let (_, ref range, _) = x.nodes.0.nodes;
+                    let l = eval_const(self.tree, &range.nodes.0, ctx);
+                    let r = eval_const(self.tree, &range.nodes.2, ctx);
+                    match (l, r) {
+                        (Some(l), Some(r)) => ret.push(Dimension::Range(l, r)),
+                        _ => ret.push(Dimension::Unresolved),
+                    }
+                }
+                RefNode::UnpackedDimension(UnpackedDimension::Expression(x)) => {
+                    let (_, ref size, _) = x.nodes.0.nodes;
+                    match eval_const(self.tree, size, ctx) {
+                        Some(size) => ret.push(Dimension::Range(0, size - 1)),
+                        None => ret.push(Dimension::Unresolved),
+                    }
+                }
+                _ => (),
+            }
+        }
+        ret
+    }
+
+    /// Make the context of a class scope with parameter values of the class specialization.
+    fn specialize(&self, ctx: &Context<'_, 'a>, x: &ClassType) -> Context<'_, 'a> {
+        let (ref id, ref params, _) = x.nodes;
+        let name = identifier(self.tree, &id.nodes.1).unwrap_or_default();
+        let package = id
+            .nodes
+            .0
+            .as_ref()
+            .and_then(|x| package_scope_name(self.tree, x));
+        let name = match package {
+            Some(package) => qualify(&package, &name),
+            None => self.scope_key(&ctx.scope, &name),
+        };
+        let mut ret = self.context(&name, ctx.depth + 1);
+        let params = match params {
+            Some(x) => &x.nodes.1.nodes.1,
+            None => return ret,
+        };
+        let order = self.parameters(&name);
+        let mut assign = |name: &str, x: &ParamExpression| {
+            let value = match x {
+                ParamExpression::MintypmaxExpression(x) => {
+                    eval_const(self.tree, &**x, ctx).map(ParamValue::Value)
+                }
+                ParamExpression::DataType(x) => Some(ParamValue::Type(self.data_type(ctx, x))),
+                ParamExpression::Dollar(_) => None,
+            };
+            if let Some(value) = value {
+                ret.overrides.insert(String::from(name), value);
+            }
+        };
+        match params {
+            Some(ListOfParameterAssignments::Ordered(x)) => {
+                for (name, x) in order.iter().zip(x.nodes.0.contents()) {
+                    assign(name, &x.nodes.0);
+                }
+            }
+            Some(ListOfParameterAssignments::Named(x)) => {
+                for x in x.nodes.0.contents() {
+                    let (_, ref id, ref value) = x.nodes;
+                    if let (Some(name), Some(value)) = (identifier(self.tree, id), &value.nodes.1) {
+                        assign(&name, value);
+                    }
+                }
+            }
+            None => (),
+        }
+        ret
+    }
+
+    fn is_class(&self, scope: &str, name: &str) -> bool {
+        matches!(self.scopes.get(&self.scope_key(scope, name)), Some(x) if x.class)
+    }
+
+    /// Get the key of the scope `name` visible from `scope`, where classes declared in other
+    /// scopes are qualified by them.
+    fn scope_key(&self, scope: &str, name: &str) -> String {
+        for scope in self.scope_chain(scope) {
+            let key = qualify(&scope, name);
+            if self.scopes.contains_key(&key) {
+                return key;
+            }
+            if let Some(x) = self.scopes.get(&scope) {
+                let imports = x.explicit_imports.get(name).into_iter();
+                for package in imports.chain(x.imports.iter()) {
+                    let key = qualify(package, name);
+                    if self.scopes.contains_key(&key) {
+                        return key;
+                    }
+                }
+            }
+        }
+        String::from(name)
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+#[derive(Clone)]
+struct Context<'r, 'a> {
+    resolver: &'r TypeResolver<'a>,
+    scope: String,
+    overrides: HashMap<String, ParamValue>,
+    depth: usize,
+}
+
+impl<'r, 'a> Context<'r, 'a> {
+    fn deeper(&self) -> Self {
+        let mut ret = self.clone();
+        ret.depth += 1;
+        ret
+    }
+}
+
+impl<'r, 'a> EvalContext for Context<'r, 'a> {
+    fn value(&self, package: Option<&str>, name: &str) -> Option<i64> {
+        self.resolver.lookup_value(self, package, name)
+    }
+
+    fn bits(&self, package: Option<&str>, name: &str) -> Option<u64> {
+        if let Some(x) = self.resolver.lookup_type(self, package, name) {
+            return x.bits();
+        }
+        if package.is_none() {
+            if let Some(x) = self.resolver.lookup_variable(self, name) {
+                return x.bits();
+            }
+        }
+        None
+    }
+
+    fn data_type_bits(&self, _tree: &SyntaxTree, data_type: &DataType) -> Option<u64> {
+        self.resolver.data_type(self, data_type).bits()
+    }
+}
+
+/// Context with local values such as the preceding enum members and genvars.
+struct LocalContext<'c, 'r, 'a> {
+    context: &'c Context<'r, 'a>,
+    values: HashMap<String, i64>,
+}
+
+impl<'c, 'r, 'a> EvalContext for LocalContext<'c, 'r, 'a> {
+    fn value(&self, package: Option<&str>, name: &str) -> Option<i64> {
+        if package.is_none() {
+            if let Some(x) = self.values.get(name) {
+                return Some(*x);
+            }
+        }
+        self.context.value(package, name)
+    }
+
+    fn bits(&self, package: Option<&str>, name: &str) -> Option<u64> {
+        self.context.bits(package, name)
+    }
+
+    fn data_type_bits(&self, tree: &SyntaxTree, data_type: &DataType) -> Option<u64> {
+        self.context.data_type_bits(tree, data_type)
+    }
+}
+
+fn atom_type(kind: &IntegerAtomType, signing: Option<&Signing>) -> TypeInfo {
+    let (kind, signed) = match kind {
+        IntegerAtomType::Byte(_) => (TypeKind::Byte, true),
+        IntegerAtomType::Shortint(_) => (TypeKind::Shortint, true),
+        IntegerAtomType::Int(_) => (TypeKind::Int, true),
+        IntegerAtomType::Longint(_) => (TypeKind::Longint, true),
+        IntegerAtomType::Integer(_) => (TypeKind::Integer, true),
+        IntegerAtomType::Time(_) => (TypeKind::Time, false),
+    };
+    let mut ret = TypeInfo::new(kind);
+    ret.signed = match signing {
+        Some(Signing::Signed(_)) => true,
+        Some(Signing::Unsigned(_)) => false,
+        None => signed,
+    };
+    ret
+}
+
+/// Get the number of elements in the range `[l:r]`, which is `None` if it overflows.
+pub(crate) fn range_size(l: i64, r: i64) -> Option<u64> {
+    l.checked_sub(r)?.unsigned_abs().checked_add(1)
+}
+
+/// Whether the value can be represented by the integral type of the width.
+fn fits(value: i64, width: u64, signed: bool) -> bool {
+    if width >= 64 {
+        return true;
+    }
+    if signed {
+        let half = 1i64 << (width - 1);
+        (-half..half).contains(&value)
+    } else {
+        (0..1i64 << width).contains(&value)
+    }
+}
+
+fn dummy_keyword() -> Keyword {
+    Keyword {
+        nodes: (Locate::default(), Vec::new()),
+    }
+}
+
+/// Get the member names of enum without evaluating their values.
+fn enum_names(tree: &SyntaxTree, x: &DataTypeEnum) -> Vec<String> {
+    let mut ret = Vec::new();
+    for x in x.nodes.2.nodes.1.contents() {
+        let (ref id, ref range, _) = x.nodes;
+        let name = identifier(tree, id).unwrap_or_default();
+        ret.append(&mut expand_enum_name(tree, &name, range.as_ref()));
+    }
+    ret
+}
+
+type EnumNameRange = Bracket<(IntegralNumber, Option<(Symbol, IntegralNumber)>)>;
+
+/// Expand `name[N]` and `name[N:M]` of IEEE1800-2017 Table 6-10.
+fn expand_enum_name(tree: &SyntaxTree, name: &str, range: Option<&EnumNameRange>) -> Vec<String> {
+    let range = match range {
+        Some(x) => &x.nodes.1,
+        None => return vec![String::from(name)],
+    };
+    let number = |x: &IntegralNumber| tree.get_str_trim(x).and_then(parse_number).map(|x| x.0);
+    let (first, last) = match range {
+        (n, None) => match number(n) {
+            Some(n) if n > 0 => (0, n - 1),
+            _ => return Vec::new(),
+        },
+        (n, Some((_, m))) => match (number(n), number(m)) {
+            (Some(n), Some(m)) => (n, m),
+            _ => return Vec::new(),
+        },
+    };
+    if first <= last {
+        (first..=last).map(|i| format!("{}{}", name, i)).collect()
+    } else {
+        (last..=first)
+            .rev()
+            .map(|i| format!("{}{}", name, i))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn parse(src: &str) -> SyntaxTree {
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_packed_struct() {
+        let tree = parse(
+            r##"package pkg;
+  localparam W = 4;
+  typedef logic [W-1:0] data_t;
+  typedef struct packed {
+    logic       valid;
+    data_t [1:0] data;
+    logic [2:0] tag;
+  } req_t;
+endpackage
+module m import pkg::*; #(parameter N = 2) ();
+  req_t [N-1:0] reqs;
+  localparam B = $bits(req_t);
+endmodule"##,
+        );
+        let resolver = TypeResolver::new(&tree);
+        let req = resolver.resolve_typedef("pkg", "req_t").unwrap();
+        assert_eq!(req.bits(), Some(12));
+        assert_eq!(req.member("valid").unwrap().offset, Some(11));
+        assert_eq!(req.member("data").unwrap().offset, Some(3));
+        assert_eq!(req.member("tag").unwrap().offset, Some(0));
+        assert_eq!(resolver.resolve_typedef("m", "req_t"), Some(req));
+        assert_eq!(
+            resolver.variable_type("m", "reqs").unwrap().bits(),
+            Some(24)
+        );
+        assert_eq!(resolver.parameter("m", "B"), Some(12));
+    }
+
+    #[test]
+    fn test_enum() {
+        let tree = parse(
+            r##"module m;
+  typedef enum logic [3:0] {IDLE, RUN = 3, WAIT, S[2], T[5:4] = 7, LAST = T4 + 1} state_t;
+endmodule"##,
+        );
+        let resolver = TypeResolver::new(&tree);
+        let state = resolver.resolve_typedef("m", "state_t").unwrap();
+        assert_eq!(state.bits(), Some(4));
+        let members: Vec<(String, Option<i64>)> = match state.kind {
+            TypeKind::Enum { members, .. } => {
+                members.into_iter().map(|x| (x.name, x.value)).collect()
+            }
+            _ => unreachable!(),
+        };
+        let expected = [
+            ("IDLE", 0),
+            ("RUN", 3),
+            ("WAIT", 4),
+            ("S0", 5),
+            ("S1", 6),
+            ("T5", 7),
+            ("T4", 8),
+            ("LAST", 9),
+        ];
+        let expected: Vec<(String, Option<i64>)> = expected
+            .iter()
+            .map(|(x, y)| (String::from(*x), Some(*y)))
+            .collect();
+        assert_eq!(members, expected);
+        assert_eq!(resolver.parameter("m", "WAIT"), Some(4));
+        assert_eq!(resolver.check(), vec![]);
+    }
+
+    #[test]
+    fn test_enum_check() {
+        let tree = parse(
+            r##"module m;
+  typedef enum logic [1:0] {A, B, C = 0, D = 4} bad_t;
+  typedef enum byte {E = -128, F = 128} byte_t;
+endmodule"##,
+        );
+        let resolver = TypeResolver::new(&tree);
+        let check: Vec<(String, i64, Option<u64>)> = resolver
+            .check()
+            .into_iter()
+            .map(|x| match x {
+                TypeDiagnostic::DuplicateEnumValue { name, value, .. } => (name, value, None),
+                TypeDiagnostic::EnumValueOverflow {
+                    name, value, width, ..
+                } => (name, value, Some(width)),
+            })
+            .collect();
+        assert_eq!(
+            check,
+            vec![
+                (String::from("C"), 0, None),
+                (String::from("D"), 4, Some(2)),
+                (String::from("F"), 128, Some(8)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unresolved() {
+        let tree = parse(
+            r##"module m #(parameter N = W) ();
+  logic [N-1:0] a;
+  logic [3:0] b [N];
+  logic [3:0] c [2];
+endmodule"##,
+        );
+        let resolver = TypeResolver::new(&tree);
+        let a = resolver.variable_type("m", "a").unwrap();
+        assert_eq!(a.packed, vec![None]);
+        assert_eq!(a.bits(), None);
+        let b = resolver.variable_type("m", "b").unwrap();
+        assert_eq!(b.unpacked, vec![Dimension::Unresolved]);
+        assert_eq!(b.bits(), None);
+        assert_eq!(resolver.variable_type("m", "c").unwrap().bits(), Some(8));
+    }
+
+    #[test]
+    fn test_parameterized() {
+        let tree = parse(
+            r##"class C #(parameter W = 1, type T = bit);
+  typedef logic [W-1:0] word_t;
+  typedef T [1:0] pair_t;
+endclass
+module m #(parameter type U = logic [3:0]) ();
+  typedef C#(8)::word_t w8_t;
+  typedef C#(.T(byte))::pair_t bp_t;
+  typedef U [1:0] u2_t;
+  logic [5:0] v;
+  typedef type(v) v_t;
+endmodule"##,
+        );
+        let mut resolver = TypeResolver::new(&tree);
+        assert_eq!(
+            resolver.resolve_typedef("m", "w8_t").unwrap().bits(),
+            Some(8)
+        );
+        assert_eq!(
+            resolver.resolve_typedef("m", "bp_t").unwrap().bits(),
+            Some(16)
+        );
+        assert_eq!(
+            resolver.resolve_typedef("m", "u2_t").unwrap().bits(),
+            Some(8)
+        );
+        assert_eq!(
+            resolver.resolve_typedef("m", "v_t").unwrap().bits(),
+            Some(6)
+        );
+        assert_eq!(
+            resolver.resolve_typedef("C", "word_t").unwrap().bits(),
+            Some(1)
+        );
+
+        resolver.set_parameter("C", "W", ParamValue::Value(16));
+        assert_eq!(
+            resolver.resolve_typedef("C", "word_t").unwrap().bits(),
+            Some(16)
+        );
+        let int = resolver.resolve_typedef("m", "bp_t").unwrap();
+        resolver.set_parameter("m", "U", ParamValue::Type(int));
+        assert_eq!(
+            resolver.resolve_typedef("m", "u2_t").unwrap().bits(),
+            Some(32)
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let tree = parse(
+            r##"module m;
+  typedef logic [64'h7FFFFFFFFFFFFFFF:0] [3:0] t;
+  typedef logic [1:-64'sh7FFFFFFFFFFFFFFF] u;
+endmodule"##,
+        );
+        let resolver = TypeResolver::new(&tree);
+        assert_eq!(resolver.resolve_typedef("m", "t").unwrap().bits(), None);
+        assert_eq!(resolver.resolve_typedef("m", "u").unwrap().bits(), None);
+    }
+
+    #[test]
+    fn test_scoped_class() {
+        let tree = parse(
+            r##"package p1;
+  class C;
+    typedef logic [3:0] t;
+  endclass
+endpackage
+package p2;
+  class C;
+    typedef logic [7:0] t;
+  endclass
+endpackage
+module m;
+  import p2::*;
+  typedef p1::C::t a_t;
+  typedef C::t b_t;
+endmodule"##,
+        );
+        let resolver = TypeResolver::new(&tree);
+        assert_eq!(
+            resolver.scopes(),
+            vec!["$unit", "m", "p1", "p1::C", "p2", "p2::C"]
+        );
+        assert_eq!(
+            resolver.resolve_typedef("p1::C", "t").unwrap().bits(),
+            Some(4)
+        );
+        assert_eq!(
+            resolver.resolve_typedef("p2::C", "t").unwrap().bits(),
+            Some(8)
+        );
+        assert_eq!(
+            resolver.resolve_typedef("m", "a_t").unwrap().bits(),
+            Some(4)
+        );
+        assert_eq!(
+            resolver.resolve_typedef("m", "b_t").unwrap().bits(),
+            Some(8)
+        );
+    }
+}
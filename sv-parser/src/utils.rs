@@ -0,0 +1,147 @@
+use crate::*;
+
+// -----------------------------------------------------------------------------
+
+/// Get the first identifier string under the specified node.
+///
+/// The leading `\` of an escaped identifier is removed in the same way as the preprocessor.
+pub(crate) fn identifier<'a, T: Into<RefNodes<'a>>>(tree: &SyntaxTree, node: T) -> Option<String> {
+    for x in Iter::new(node.into()) {
+        match x {
+            RefNode::SimpleIdentifier(x) => {
+                return tree.get_str(&x.nodes.0).map(String::from);
+            }
+            RefNode::EscapedIdentifier(x) => {
+                return tree
+                    .get_str(&x.nodes.0)
+                    .map(|x| String::from(x.trim_start_matches('\\')));
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Get the `Locate` of the first identifier under the specified node.
+pub(crate) fn identifier_locate<'a, T: Into<RefNodes<'a>>>(node: T) -> Option<Locate> {
+    for x in Iter::new(node.into()) {
+        match x {
+            RefNode::SimpleIdentifier(x) => return Some(x.nodes.0),
+            RefNode::EscapedIdentifier(x) => return Some(x.nodes.0),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Get the name of the design element, package or class declared by the specified node.
+pub(crate) fn scope_name(tree: &SyntaxTree, node: &RefNode) -> Option<String> {
+    let id = match node {
+        RefNode::ModuleDeclaration(x) => unwrap_node!(*x, ModuleIdentifier),
+        RefNode::InterfaceDeclaration(x) => unwrap_node!(*x, InterfaceIdentifier),
+        RefNode::ProgramDeclaration(x) => unwrap_node!(*x, ProgramIdentifier),
+        RefNode::PackageDeclaration(x) => unwrap_node!(*x, PackageIdentifier),
+        RefNode::CheckerDeclaration(x) => unwrap_node!(*x, CheckerIdentifier),
+        RefNode::ClassDeclaration(x) => Some(RefNode::ClassIdentifier(&x.nodes.4)),
+        RefNode::InterfaceClassDeclaration(x) => Some(RefNode::ClassIdentifier(&x.nodes.2)),
+        _ => None,
+    };
+    id.and_then(|x| identifier(tree, vec![x]))
+}
+
+/// Get the package name of `PackageScope`; `$unit` is returned for `$unit::`.
+pub(crate) fn package_scope_name(tree: &SyntaxTree, x: &PackageScope) -> Option<String> {
+    match x {
+        PackageScope::Package(x) => identifier(tree, &x.nodes.0),
+        PackageScope::Unit(_) => Some(String::from(UNIT_SCOPE)),
+    }
+}
+
+/// Get the range of the preprocessed text covered by the node excluding whitespaces.
+pub(crate) fn span<'a, T: Into<RefNodes<'a>>>(node: T) -> Option<(usize, usize)> {
+    let mut ret = None;
+    let mut whitespace = 0;
+    for event in Iter::new(node.into()).event() {
+        match event {
+            NodeEvent::Enter(RefNode::WhiteSpace(_)) => whitespace += 1,
+            NodeEvent::Leave(RefNode::WhiteSpace(_)) => whitespace -= 1,
+            NodeEvent::Enter(RefNode::Locate(x)) if whitespace == 0 => {
+                let begin = ret.map_or(x.offset, |(begin, _)| begin);
+                ret = Some((begin, x.offset + x.len));
+            }
+            _ => (),
+        }
+    }
+    ret
+}
+
+/// Whether a primary has no scope. An empty class qualifier is parsed for a plain identifier.
+pub(crate) fn unscoped(x: &Option<ClassQualifierOrPackageScope>) -> bool {
+    match x {
+        Some(ClassQualifierOrPackageScope::ClassQualifier(x)) => {
+            x.nodes.0.is_none() && x.nodes.1.is_none()
+        }
+        Some(ClassQualifierOrPackageScope::PackageScope(_)) => false,
+        None => true,
+    }
+}
+
+/// Visit the items of the design elements without entering nested design elements, classes,
+/// functions and tasks.
+///
+/// `f` is called for each node in the items in depth-first order, and returns whether to visit
+/// the nodes under it.
+pub(crate) fn walk_items<'a, I, F>(nodes: I, mut f: F)
+where
+    I: IntoIterator<Item = RefNode<'a>>,
+    F: FnMut(RefNode<'a>) -> bool,
+{
+    let mut depth = 0;
+    let mut skip: Option<usize> = None;
+    let mut design_elements = 0;
+    for event in nodes.into_iter().flat_map(|x| x.into_iter().event()) {
+        let node = match event {
+            NodeEvent::Enter(x) => {
+                depth += 1;
+                x
+            }
+            NodeEvent::Leave(_) => {
+                depth -= 1;
+                if skip == Some(depth) {
+                    skip = None;
+                }
+                continue;
+            }
+        };
+        if skip.is_some() {
+            continue;
+        }
+        let enter = match node {
+            RefNode::ModuleDeclaration(_)
+            | RefNode::InterfaceDeclaration(_)
+            | RefNode::ProgramDeclaration(_) => {
+                design_elements += 1;
+                design_elements == 1
+            }
+            RefNode::ClassDeclaration(_)
+            | RefNode::FunctionDeclaration(_)
+            | RefNode::TaskDeclaration(_) => false,
+            _ => f(node),
+        };
+        if !enter {
+            skip = Some(depth - 1);
+        }
+    }
+}
+
+/// The scope name used for declarations outside of any design element.
+pub(crate) const UNIT_SCOPE: &str = "$unit";
+
+/// Qualify the name by the scope like `pkg::name`; names in `$unit` are not qualified.
+pub(crate) fn qualify(scope: &str, name: &str) -> String {
+    if scope == UNIT_SCOPE {
+        String::from(name)
+    } else {
+        format!("{}::{}", scope, name)
+    }
+}
@@ -0,0 +1,1326 @@
+use crate::const_eval::{eval_binary, precedence};
+use crate::types::{Dimension, TypeInfo, TypeKind, TypeResolver};
+use crate::utils::*;
+use crate::*;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+// -----------------------------------------------------------------------------
+
+/// The width and signedness of an expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExprType {
+    pub width: u64,
+    pub signed: bool,
+}
+
+/// The sizes of an operand in an expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpressionSize {
+    pub text: String,
+    /// The width and signedness determined by the operand itself
+    pub self_determined: Option<ExprType>,
+    /// The width and signedness after the context of the enclosing expression is applied
+    pub context_determined: Option<ExprType>,
+    pub locate: Locate,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WidthDiagnostic {
+    /// The assigned expression is wider than the target
+    Truncation {
+        module: String,
+        target: String,
+        target_width: u64,
+        width: u64,
+        locate: Locate,
+    },
+    /// The assigned expression is narrower than the target, and is sign extended if `signed`
+    Extension {
+        module: String,
+        target: String,
+        target_width: u64,
+        width: u64,
+        signed: bool,
+        locate: Locate,
+    },
+    /// The operands of a comparison have different widths or signedness
+    ComparisonMismatch {
+        module: String,
+        left: ExprType,
+        right: ExprType,
+        locate: Locate,
+    },
+    /// The expression connected to a port has a different width from the port
+    PortMismatch {
+        module: String,
+        instance: String,
+        port: String,
+        port_width: u64,
+        width: u64,
+        locate: Locate,
+    },
+}
+
+/// Infers the width and signedness of expressions by the rules of IEEE1800-2017 11.6 and 11.8.
+///
+/// Unsized literals, parameters and enum members are 32-bit signed values, and are not reported
+/// by `check` if the value fits the other side. Operands of real, string, unpacked array and
+/// unresolved types have unknown width and are not checked.
+pub struct WidthAnalyzer<'a> {
+    tree: &'a SyntaxTree,
+    resolver: TypeResolver<'a>,
+    /// Port names of each module in declaration order
+    ports: HashMap<String, Vec<String>>,
+}
+
+impl<'a> WidthAnalyzer<'a> {
+    pub fn new(tree: &'a SyntaxTree) -> Self {
+        WidthAnalyzer {
+            tree,
+            resolver: TypeResolver::new(tree),
+            ports: port_names(tree),
+        }
+    }
+
+    /// Get the sizes of the expression and its operands in pre-order.
+    ///
+    /// The first entry is the whole expression. `target` is the type of the assignment target,
+    /// which widens the context of the expression.
+    pub fn sizes(
+        &self,
+        scope: &str,
+        expr: &Expression,
+        target: Option<ExprType>,
+    ) -> Vec<ExpressionSize> {
+        let x = self.expression(scope, expr);
+        let mut ret = Vec::new();
+        self.collect(&x, assigned(x.ty, target), &mut ret);
+        ret
+    }
+
+    /// Get the self-determined width and signedness of the expression.
+    pub fn self_determined(&self, scope: &str, expr: &Expression) -> Option<ExprType> {
+        self.expression(scope, expr).ty
+    }
+
+    /// Check assignments, port connections and comparisons of each design element.
+    pub fn check(&self) -> Vec<WidthDiagnostic> {
+        let mut ret = Vec::new();
+        for node in self.tree {
+            match node {
+                RefNode::ModuleDeclaration(_)
+                | RefNode::InterfaceDeclaration(_)
+                | RefNode::ProgramDeclaration(_) => (),
+                _ => continue,
+            }
+            if let Some(module) = scope_name(self.tree, &node) {
+                self.walk(&module, node, &mut ret);
+            }
+        }
+        ret
+    }
+
+    // -------------------------------------------------------------------------
+
+    fn walk(&self, module: &str, node: RefNode, ret: &mut Vec<WidthDiagnostic>) {
+        let tree = self.tree;
+        walk_items(vec![node], |node| match node {
+            RefNode::NetAssignment(x) => {
+                let (ref lvalue, _, ref expr) = x.nodes;
+                let target = self.net_lvalue(module, lvalue);
+                self.assignment(module, lvalue.into(), target, expr, ret);
+                false
+            }
+            RefNode::OperatorAssignment(x) => {
+                let (ref lvalue, ref op, ref expr) = x.nodes;
+                if tree.get_str_trim(op) == Some("=") {
+                    let target = self.variable_lvalue(module, lvalue);
+                    self.assignment(module, lvalue.into(), target, expr, ret);
+                } else {
+                    comparisons(module, &self.expression(module, expr), ret);
+                }
+                false
+            }
+            RefNode::BlockingAssignmentVariable(x) => {
+                let (ref lvalue, _, _, ref expr) = x.nodes;
+                let target = self.variable_lvalue(module, lvalue);
+                self.assignment(module, lvalue.into(), target, expr, ret);
+                false
+            }
+            RefNode::NonblockingAssignment(x) => {
+                let (ref lvalue, _, _, ref expr) = x.nodes;
+                let target = self.variable_lvalue(module, lvalue);
+                self.assignment(module, lvalue.into(), target, expr, ret);
+                false
+            }
+            RefNode::NetDeclAssignment(x) => {
+                if let Some((_, expr)) = &x.nodes.2 {
+                    let target = self.declared(module, &x.nodes.0);
+                    self.assignment(module, (&x.nodes.0).into(), target, expr, ret);
+                }
+                false
+            }
+            RefNode::VariableDeclAssignmentVariable(x) => {
+                if let Some((_, expr)) = &x.nodes.2 {
+                    let target = self.declared(module, &x.nodes.0);
+                    self.assignment(module, (&x.nodes.0).into(), target, expr, ret);
+                }
+                false
+            }
+            RefNode::ModuleInstantiation(x) => {
+                self.instance(module, x, ret);
+                false
+            }
+            RefNode::Expression(x) => {
+                comparisons(module, &self.expression(module, x), ret);
+                false
+            }
+            _ => true,
+        });
+    }
+
+    fn assignment(
+        &self,
+        module: &str,
+        lvalue: RefNode,
+        target: Option<ExprType>,
+        expr: &Expression,
+        ret: &mut Vec<WidthDiagnostic>,
+    ) {
+        let x = self.expression(module, expr);
+        comparisons(module, &x, ret);
+        let (target_width, ty) = match (target, x.ty) {
+            (Some(x), Some(y)) => (x.width, y),
+            _ => return,
+        };
+        if ty.width == target_width || fits(&x, target_width) {
+            return;
+        }
+        let target = String::from(self.tree.get_str_trim(vec![lvalue]).unwrap_or(""));
+        if ty.width > target_width {
+            ret.push(WidthDiagnostic::Truncation {
+                module: String::from(module),
+                target,
+                target_width,
+                width: ty.width,
+                locate: x.locate,
+            });
+        } else {
+            ret.push(WidthDiagnostic::Extension {
+                module: String::from(module),
+                target,
+                target_width,
+                width: ty.width,
+                signed: ty.signed,
+                locate: x.locate,
+            });
+        }
+    }
+
+    fn instance(&self, module: &str, x: &ModuleInstantiation, ret: &mut Vec<WidthDiagnostic>) {
+        let tree = self.tree;
+        let (ref cell, _, ref list, _) = x.nodes;
+        let cell = match identifier(tree, cell) {
+            Some(x) => x,
+            None => return,
+        };
+        let ports = self.ports.get(&cell);
+        for x in list.contents() {
+            let (ref name, ref list) = x.nodes;
+            let instance = identifier(tree, name).unwrap_or_default();
+
+            let mut connected: Vec<(String, &Expression)> = Vec::new();
+            match &list.nodes.1 {
+                Some(ListOfPortConnections::Ordered(x)) => {
+                    for (i, x) in x.nodes.0.contents().into_iter().enumerate() {
+                        if let (Some(port), Some(expr)) = (ports.and_then(|x| x.get(i)), &x.nodes.1)
+                        {
+                            connected.push((port.clone(), expr));
+                        }
+                    }
+                }
+                Some(ListOfPortConnections::Named(x)) => {
+                    for x in x.nodes.0.contents() {
+                        if let NamedPortConnection::Identifier(x) = x {
+                            let (_, _, ref id, ref expr) = x.nodes;
+                            let expr = expr.as_ref().and_then(|x| x.nodes.1.as_ref());
+                            if let (Some(port), Some(expr)) = (identifier(tree, id), expr) {
+                                connected.push((port, expr));
+                            }
+                        }
+                    }
+                }
+                None => (),
+            }
+
+            for (port, expr) in connected {
+                let x = self.expression(module, expr);
+                comparisons(module, &x, ret);
+                let port_width = self
+                    .resolver
+                    .variable_type(&cell, &port)
+                    .and_then(|x| expr_type(&x))
+                    .map(|x| x.width);
+                if let (Some(port_width), Some(ty)) = (port_width, x.ty) {
+                    if ty.width != port_width && !fits(&x, port_width) {
+                        ret.push(WidthDiagnostic::PortMismatch {
+                            module: String::from(module),
+                            instance: instance.clone(),
+                            port,
+                            port_width,
+                            width: ty.width,
+                            locate: x.locate,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the sizes in pre-order with the context of each operand.
+    fn collect(&self, x: &SizedExpr, context: Option<ExprType>, out: &mut Vec<ExpressionSize>) {
+        out.push(ExpressionSize {
+            text: String::from(self.tree.get_str(&x.locate).unwrap_or("")),
+            self_determined: x.ty,
+            context_determined: context,
+            locate: x.locate,
+        });
+        // Operands of comparisons are sized to each other
+        let operand = x
+            .children
+            .iter()
+            .filter(|(role, _)| *role == Role::Operand)
+            .map(|(_, x)| x.ty)
+            .reduce(merge)
+            .flatten();
+        for (role, child) in &x.children {
+            let context = match role {
+                Role::Context => match (context, x.ty) {
+                    (Some(context), Some(ty)) => Some(ExprType {
+                        width: context.width,
+                        signed: ty.signed,
+                    }),
+                    _ => None,
+                },
+                Role::Operand => operand,
+                Role::Assigned(target) => assigned(child.ty, *target),
+            };
+            self.collect(child, context, out);
+        }
+    }
+
+    // -------------------------------------------------------------------------
+
+    fn expression(&self, scope: &str, x: &Expression) -> SizedExpr {
+        match x {
+            Expression::Primary(x) => self.primary(scope, x),
+            Expression::Unary(y) => {
+                let (ref op, _, ref operand) = y.nodes;
+                let operand = self.primary(scope, operand);
+                let (ty, role) = match self.tree.get_str_trim(op) {
+                    Some("+") | Some("-") | Some("~") => (operand.ty, Role::Context),
+                    _ => (Some(BIT), Role::Assigned(None)),
+                };
+                self.sized(scope, x, ty, vec![(role, operand)])
+            }
+            Expression::Binary(_) => self.chain(scope, x),
+            Expression::ConditionalExpression(y) if condition(y).is_some() => self.chain(scope, x),
+            Expression::InsideExpression(y) => {
+                let operand = self.expression(scope, &y.nodes.0);
+                self.sized(scope, x, Some(BIT), vec![(Role::Assigned(None), operand)])
+            }
+            _ => self.sized(scope, x, None, Vec::new()),
+        }
+    }
+
+    /// Size a chain of binary operators.
+    ///
+    /// The parser builds binary expressions as right-leaning chains without operator precedence,
+    /// so the chain is flattened and rebuilt with the precedence of IEEE1800-2017 Table 11-2.
+    fn chain(&self, scope: &str, x: &Expression) -> SizedExpr {
+        let mut operands = Vec::new();
+        let mut operators = Vec::new();
+        let conditional = self.operands(x, &mut operands, &mut operators);
+        let operands: Vec<SizedExpr> = operands
+            .into_iter()
+            .map(|x| self.expression(scope, x))
+            .collect();
+        let ret = match climb(operands, &operators) {
+            Some(x) => x,
+            None => return self.sized(scope, x, None, Vec::new()),
+        };
+        match conditional {
+            Some((t, f)) => {
+                let t = self.expression(scope, t);
+                let f = self.expression(scope, f);
+                let ty = merge(t.ty, f.ty);
+                let children = vec![
+                    (Role::Assigned(None), ret),
+                    (Role::Context, t),
+                    (Role::Context, f),
+                ];
+                self.sized(scope, x, ty, children)
+            }
+            None => ret,
+        }
+    }
+
+    fn operands<'c>(
+        &self,
+        x: &'c Expression,
+        operands: &mut Vec<&'c Expression>,
+        operators: &mut Vec<&'a str>,
+    ) -> Option<(&'c Expression, &'c Expression)> {
+        match x {
+            Expression::Binary(x) => {
+                let (ref a, ref op, _, ref b) = x.nodes;
+                operands.push(a);
+                operators.push(self.tree.get_str_trim(op).unwrap_or(""));
+                self.operands(b, operands, operators)
+            }
+            Expression::ConditionalExpression(y) => match condition(y) {
+                Some(c) => {
+                    let (_, _, _, ref t, _, ref f) = y.nodes;
+                    self.operands(c, operands, operators);
+                    Some((t, f))
+                }
+                None => {
+                    operands.push(x);
+                    None
+                }
+            },
+            _ => {
+                operands.push(x);
+                None
+            }
+        }
+    }
+
+    fn primary(&self, scope: &str, x: &Primary) -> SizedExpr {
+        match x {
+            Primary::PrimaryLiteral(y) => self.sized(scope, x, self.literal(y), Vec::new()),
+            Primary::Hierarchical(y) => {
+                let ty = self.hierarchical(scope, y);
+                self.sized(scope, x, ty, Vec::new())
+            }
+            Primary::Concatenation(y) => {
+                let (ref concat, ref select) = y.nodes;
+                let children = self.items(scope, concat);
+                let ty = match select {
+                    Some(_) => None,
+                    None => concatenated(&children),
+                };
+                self.sized(scope, x, ty, children)
+            }
+            Primary::MultipleConcatenation(y) => {
+                let (ref concat, ref select) = y.nodes;
+                let (ref count, ref concat) = concat.nodes.0.nodes.1;
+                let children = self.items(scope, concat);
+                let count = self.resolver.eval(scope, count).and_then(positive);
+                let ty = match (select, count, concatenated(&children)) {
+                    (None, Some(count), Some(ty)) => {
+                        count.checked_mul(ty.width).map(|width| ExprType {
+                            width,
+                            signed: false,
+                        })
+                    }
+                    _ => None,
+                };
+                self.sized(scope, x, ty, children)
+            }
+            Primary::FunctionSubroutineCall(y) => match &y.nodes.0 {
+                SubroutineCall::SystemTfCall(y) => self.system_call(scope, x, y),
+                _ => self.sized(scope, x, None, Vec::new()),
+            },
+            Primary::MintypmaxExpression(y) => match &y.nodes.0.nodes.1 {
+                MintypmaxExpression::Expression(y) => {
+                    let mut ret = self.expression(scope, y);
+                    ret.locate = place(x.into());
+                    ret
+                }
+                _ => self.sized(scope, x, None, Vec::new()),
+            },
+            Primary::Cast(y) => self.cast(scope, x, y),
+            _ => self.sized(scope, x, None, Vec::new()),
+        }
+    }
+
+    fn items(&self, scope: &str, x: &Concatenation) -> Vec<(Role, SizedExpr)> {
+        x.nodes
+            .0
+            .nodes
+            .1
+            .contents()
+            .into_iter()
+            .map(|x| (Role::Assigned(None), self.expression(scope, x)))
+            .collect()
+    }
+
+    fn literal(&self, x: &PrimaryLiteral) -> Option<ExprType> {
+        match x {
+            PrimaryLiteral::Number(x) => match &**x {
+                Number::IntegralNumber(x) => number_type(self.tree.get_str_trim(&**x)?),
+                Number::RealNumber(_) => None,
+            },
+            PrimaryLiteral::UnbasedUnsizedLiteral(_) => Some(BIT),
+            PrimaryLiteral::StringLiteral(x) => {
+                let s = self.tree.get_str_trim(&**x)?;
+                Some(ExprType {
+                    width: string_bytes(s).checked_mul(8)?,
+                    signed: false,
+                })
+            }
+            PrimaryLiteral::TimeLiteral(_) => None,
+        }
+    }
+
+    fn hierarchical(&self, scope: &str, x: &PrimaryHierarchical) -> Option<ExprType> {
+        let (ref qualifier, ref id, ref select) = x.nodes;
+        if !unscoped(qualifier) {
+            return None;
+        }
+        let (name, mut steps) = self.path(id)?;
+        steps.extend(self.select(scope, select)?);
+        match self.resolver.variable_type(scope, &name) {
+            Some(ty) => typed(ty, &steps),
+            None if steps.is_empty() && self.resolver.parameter(scope, &name).is_some() => {
+                Some(INTEGER)
+            }
+            None => None,
+        }
+    }
+
+    fn system_call(&self, scope: &str, node: &Primary, x: &SystemTfCall) -> SizedExpr {
+        let tree = self.tree;
+        let (name, arg) = match x {
+            SystemTfCall::ArgOptionl(x) => {
+                let (ref id, ref args) = x.nodes;
+                let arg = match args.as_ref().map(|x| &x.nodes.1) {
+                    Some(ListOfArguments::Ordered(x)) => x.nodes.0.nodes.0.as_ref(),
+                    _ => None,
+                };
+                (tree.get_str_trim(id), arg)
+            }
+            SystemTfCall::ArgDataType(x) => (tree.get_str_trim(&x.nodes.0), None),
+            SystemTfCall::ArgExpression(x) => {
+                let (ref id, ref args) = x.nodes;
+                let (ref list, _) = args.nodes.1;
+                (tree.get_str_trim(id), list.nodes.0.as_ref())
+            }
+        };
+        match (name, arg) {
+            (Some(name @ "$signed"), Some(arg)) | (Some(name @ "$unsigned"), Some(arg)) => {
+                let arg = self.expression(scope, arg);
+                let ty = arg.ty.map(|x| ExprType {
+                    width: x.width,
+                    signed: name == "$signed",
+                });
+                self.sized(scope, node, ty, vec![(Role::Assigned(None), arg)])
+            }
+            (Some("$bits"), _)
+            | (Some("$clog2"), _)
+            | (Some("$countones"), _)
+            | (Some("$size"), _)
+            | (Some("$dimensions"), _)
+            | (Some("$left"), _)
+            | (Some("$right"), _)
+            | (Some("$low"), _)
+            | (Some("$high"), _)
+            | (Some("$increment"), _) => self.sized(scope, node, Some(INTEGER), Vec::new()),
+            _ => self.sized(scope, node, None, Vec::new()),
+        }
+    }
+
+    fn cast(&self, scope: &str, node: &Primary, x: &Cast) -> SizedExpr {
+        let (ref casting_type, _, ref expr) = x.nodes;
+        let operand = self.expression(scope, &expr.nodes.1);
+        let (ty, role) = match casting_type {
+            CastingType::ConstantPrimary(x) => {
+                let width = self.resolver.eval(scope, &**x).and_then(positive);
+                let ty = match (width, operand.ty) {
+                    (Some(width), Some(ty)) => Some(ExprType {
+                        width,
+                        signed: ty.signed,
+                    }),
+                    _ => None,
+                };
+                (ty, Role::Assigned(ty))
+            }
+            CastingType::SimpleType(x) => {
+                let ty = self.simple_type(scope, x);
+                (ty, Role::Assigned(ty))
+            }
+            CastingType::Signing(x) => {
+                let ty = operand.ty.map(|ty| ExprType {
+                    width: ty.width,
+                    signed: matches!(**x, Signing::Signed(_)),
+                });
+                (ty, Role::Assigned(None))
+            }
+            _ => (None, Role::Assigned(None)),
+        };
+        self.sized(scope, node, ty, vec![(role, operand)])
+    }
+
+    fn simple_type(&self, scope: &str, x: &SimpleType) -> Option<ExprType> {
+        match x {
+            SimpleType::IntegerType(x) => match &**x {
+                IntegerType::IntegerAtomType(x) => {
+                    let (width, signed) = match &**x {
+                        IntegerAtomType::Byte(_) => (8, true),
+                        IntegerAtomType::Shortint(_) => (16, true),
+                        IntegerAtomType::Int(_) | IntegerAtomType::Integer(_) => (32, true),
+                        IntegerAtomType::Longint(_) => (64, true),
+                        IntegerAtomType::Time(_) => (64, false),
+                    };
+                    Some(ExprType { width, signed })
+                }
+                IntegerType::IntegerVectorType(_) => Some(BIT),
+            },
+            SimpleType::PsTypeIdentifier(x) => {
+                let name = identifier(self.tree, &x.nodes.1)?;
+                expr_type(&self.resolver.resolve_typedef(scope, &name)?)
+            }
+            _ => None,
+        }
+    }
+
+    fn sized<'b, T: Into<RefNode<'b>>>(
+        &self,
+        scope: &str,
+        node: T,
+        ty: Option<ExprType>,
+        children: Vec<(Role, SizedExpr)>,
+    ) -> SizedExpr {
+        let node = node.into();
+        SizedExpr {
+            ty,
+            value: self.resolver.eval(scope, node.clone()),
+            locate: place(node),
+            comparison: false,
+            children,
+        }
+    }
+
+    // -------------------------------------------------------------------------
+
+    fn variable_lvalue(&self, scope: &str, x: &VariableLvalue) -> Option<ExprType> {
+        match x {
+            VariableLvalue::Identifier(x) => {
+                let (ref qualifier, ref id, ref select) = x.nodes;
+                if qualifier.is_some() {
+                    return None;
+                }
+                let (name, mut steps) = self.path(&id.nodes.0)?;
+                steps.extend(self.select(scope, select)?);
+                typed(self.resolver.variable_type(scope, &name)?, &steps)
+            }
+            VariableLvalue::Lvalue(x) => {
+                let mut width = 0;
+                for x in x.nodes.0.nodes.1.contents() {
+                    width = self.variable_lvalue(scope, x)?.width.checked_add(width)?;
+                }
+                Some(ExprType {
+                    width,
+                    signed: false,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn net_lvalue(&self, scope: &str, x: &NetLvalue) -> Option<ExprType> {
+        match x {
+            NetLvalue::Identifier(x) => {
+                let (ref id, ref select) = x.nodes;
+                let (name, mut steps) = match id {
+                    PsOrHierarchicalNetIdentifier::PackageScope(x) if x.nodes.0.is_none() => {
+                        (identifier(self.tree, &x.nodes.1)?, Vec::new())
+                    }
+                    PsOrHierarchicalNetIdentifier::PackageScope(_) => return None,
+                    PsOrHierarchicalNetIdentifier::HierarchicalNetIdentifier(x) => {
+                        self.path(&x.nodes.0)?
+                    }
+                };
+                steps.extend(self.constant_select(scope, select)?);
+                typed(self.resolver.variable_type(scope, &name)?, &steps)
+            }
+            NetLvalue::Lvalue(x) => {
+                let mut width = 0;
+                for x in x.nodes.0.nodes.1.contents() {
+                    width = self.net_lvalue(scope, x)?.width.checked_add(width)?;
+                }
+                Some(ExprType {
+                    width,
+                    signed: false,
+                })
+            }
+            NetLvalue::Pattern(_) => None,
+        }
+    }
+
+    fn declared<'b, T: Into<RefNodes<'b>>>(&self, scope: &str, id: T) -> Option<ExprType> {
+        let name = identifier(self.tree, id)?;
+        expr_type(&self.resolver.variable_type(scope, &name)?)
+    }
+
+    /// Get the variable name and the member selects of a hierarchical identifier like `s.a[1].b`.
+    fn path(&self, x: &HierarchicalIdentifier) -> Option<(String, Vec<Step>)> {
+        let (ref root, ref path, ref id) = x.nodes;
+        if root.is_some() {
+            return None;
+        }
+        let mut name = None;
+        let mut steps = Vec::new();
+        for (id, select, _) in path {
+            let id = identifier(self.tree, id)?;
+            match name {
+                Some(_) => steps.push(Step::Member(id)),
+                None => name = Some(id),
+            }
+            steps.extend(select.nodes.0.iter().map(|_| Step::Index));
+        }
+        let id = identifier(self.tree, id)?;
+        match name {
+            Some(name) => {
+                steps.push(Step::Member(id));
+                Some((name, steps))
+            }
+            None => Some((id, steps)),
+        }
+    }
+
+    fn select(&self, scope: &str, x: &Select) -> Option<Vec<Step>> {
+        let (ref members, ref bits, ref part) = x.nodes;
+        let mut steps = Vec::new();
+        if let Some((list, _, last)) = members {
+            for (_, id, bits) in list {
+                steps.push(Step::Member(identifier(self.tree, id)?));
+                steps.extend(bits.nodes.0.iter().map(|_| Step::Index));
+            }
+            steps.push(Step::Member(identifier(self.tree, last)?));
+        }
+        steps.extend(bits.nodes.0.iter().map(|_| Step::Index));
+        if let Some(part) = part {
+            let width = match &part.nodes.1 {
+                PartSelectRange::ConstantRange(x) => {
+                    self.range_width(scope, &x.nodes.0, &x.nodes.2)
+                }
+                PartSelectRange::IndexedRange(x) => {
+                    self.resolver.eval(scope, &x.nodes.2).and_then(positive)
+                }
+            };
+            steps.push(Step::Range(width));
+        }
+        Some(steps)
+    }
+
+    fn constant_select(&self, scope: &str, x: &ConstantSelect) -> Option<Vec<Step>> {
+        let (ref members, ref bits, ref part) = x.nodes;
+        let mut steps = Vec::new();
+        if let Some((list, _, last)) = members {
+            for (_, id, bits) in list {
+                steps.push(Step::Member(identifier(self.tree, id)?));
+                steps.extend(bits.nodes.0.iter().map(|_| Step::Index));
+            }
+            steps.push(Step::Member(identifier(self.tree, last)?));
+        }
+        steps.extend(bits.nodes.0.iter().map(|_| Step::Index));
+        if let Some(part) = part {
+            let width = match &part.nodes.1 {
+                ConstantPartSelectRange::ConstantRange(x) => {
+                    self.range_width(scope, &x.nodes.0, &x.nodes.2)
+                }
+                ConstantPartSelectRange::ConstantIndexedRange(x) => {
+                    self.resolver.eval(scope, &x.nodes.2).and_then(positive)
+                }
+            };
+            steps.push(Step::Range(width));
+        }
+        Some(steps)
+    }
+
+    fn range_width(
+        &self,
+        scope: &str,
+        msb: &ConstantExpression,
+        lsb: &ConstantExpression,
+    ) -> Option<u64> {
+        let msb = self.resolver.eval(scope, msb)?;
+        let lsb = self.resolver.eval(scope, lsb)?;
+        msb.checked_sub(lsb)?.unsigned_abs().checked_add(1)
+    }
+}
+
+// -----------------------------------------------------------------------------
+
+const BIT: ExprType = ExprType {
+    width: 1,
+    signed: false,
+};
+
+const INTEGER: ExprType = ExprType {
+    width: 32,
+    signed: true,
+};
+
+/// How the context of an expression is applied to an operand.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Role {
+    /// Context-determined operand, which is sized to the enclosing expression
+    Context,
+    /// Operand of a comparison, which is sized to the other operand
+    Operand,
+    /// Self-determined operand, which is widened to the target of casts
+    Assigned(Option<ExprType>),
+}
+
+#[derive(Debug)]
+struct SizedExpr {
+    ty: Option<ExprType>,
+    /// The value if the expression is constant
+    value: Option<i64>,
+    locate: Locate,
+    comparison: bool,
+    children: Vec<(Role, SizedExpr)>,
+}
+
+/// A step of selecting a part of a variable.
+enum Step {
+    Index,
+    Range(Option<u64>),
+    Member(String),
+}
+
+fn climb(operands: Vec<SizedExpr>, operators: &[&str]) -> Option<SizedExpr> {
+    let mut operands = operands.into_iter();
+    let mut vals: Vec<SizedExpr> = vec![operands.next()?];
+    let mut ops: Vec<&str> = Vec::new();
+    for (op, x) in operators.iter().zip(operands) {
+        while let Some(top) = ops.last() {
+            if precedence(top) >= precedence(op) {
+                let b = vals.pop()?;
+                let a = vals.pop()?;
+                vals.push(binary(ops.pop()?, a, b));
+            } else {
+                break;
+            }
+        }
+        ops.push(op);
+        vals.push(x);
+    }
+    while let Some(op) = ops.pop() {
+        let b = vals.pop()?;
+        let a = vals.pop()?;
+        vals.push(binary(op, a, b));
+    }
+    vals.pop()
+}
+
+fn binary(op: &str, a: SizedExpr, b: SizedExpr) -> SizedExpr {
+    let (ty, roles) = match op {
+        "+" | "-" | "*" | "/" | "%" | "&" | "|" | "^" | "~^" | "^~" => {
+            (merge(a.ty, b.ty), [Role::Context, Role::Context])
+        }
+        "<" | "<=" | ">" | ">=" | "==" | "!=" | "===" | "!==" | "==?" | "!=?" => {
+            (Some(BIT), [Role::Operand, Role::Operand])
+        }
+        "<<" | ">>" | "<<<" | ">>>" | "**" => (a.ty, [Role::Context, Role::Assigned(None)]),
+        _ => (Some(BIT), [Role::Assigned(None), Role::Assigned(None)]),
+    };
+    let value = match (a.value, b.value) {
+        (Some(x), Some(y)) => eval_binary(op, x, y),
+        _ => None,
+    };
+    let locate = Locate {
+        offset: a.locate.offset,
+        line: a.locate.line,
+        len: (b.locate.offset + b.locate.len).saturating_sub(a.locate.offset),
+    };
+    SizedExpr {
+        ty,
+        value,
+        locate,
+        comparison: roles[0] == Role::Operand,
+        children: vec![(roles[0], a), (roles[1], b)],
+    }
+}
+
+/// Check comparisons in the expression.
+fn comparisons(module: &str, x: &SizedExpr, ret: &mut Vec<WidthDiagnostic>) {
+    if let [(_, a), (_, b)] = x.children.as_slice() {
+        if let (true, Some(left), Some(right)) = (x.comparison, a.ty, b.ty) {
+            if left != right && !fits(a, right.width) && !fits(b, left.width) {
+                ret.push(WidthDiagnostic::ComparisonMismatch {
+                    module: String::from(module),
+                    left,
+                    right,
+                    locate: x.locate,
+                });
+            }
+        }
+    }
+    for (_, x) in &x.children {
+        comparisons(module, x, ret);
+    }
+}
+
+/// Whether the expression is a constant whose value fits the width as signed or unsigned.
+fn fits(x: &SizedExpr, width: u64) -> bool {
+    match x.value {
+        Some(_) if width >= 64 => true,
+        Some(x) if width > 0 => {
+            let x = x as i128;
+            x >= -(1i128 << (width - 1)) && x < (1i128 << width)
+        }
+        _ => false,
+    }
+}
+
+fn merge(a: Option<ExprType>, b: Option<ExprType>) -> Option<ExprType> {
+    let (a, b) = (a?, b?);
+    Some(ExprType {
+        width: a.width.max(b.width),
+        signed: a.signed && b.signed,
+    })
+}
+
+/// The context of an expression assigned to the target.
+fn assigned(ty: Option<ExprType>, target: Option<ExprType>) -> Option<ExprType> {
+    let ty = ty?;
+    match target {
+        Some(target) => Some(ExprType {
+            width: ty.width.max(target.width),
+            signed: ty.signed,
+        }),
+        None => Some(ty),
+    }
+}
+
+fn concatenated(items: &[(Role, SizedExpr)]) -> Option<ExprType> {
+    let mut width = 0;
+    for (_, x) in items {
+        width = x.ty?.width.checked_add(width)?;
+    }
+    Some(ExprType {
+        width,
+        signed: false,
+    })
+}
+
+fn typed(mut ty: TypeInfo, steps: &[Step]) -> Option<ExprType> {
+    for step in steps {
+        ty = apply(ty, step)?;
+    }
+    expr_type(&ty)
+}
+
+/// Get the type of a bit-select, part-select or member of the type.
+///
+/// Selects of packed dimensions are unsigned.
+fn apply(mut ty: TypeInfo, step: &Step) -> Option<TypeInfo> {
+    match step {
+        Step::Member(name) => {
+            if !ty.packed.is_empty() || !ty.unpacked.is_empty() {
+                return None;
+            }
+            ty.member(name).map(|x| x.ty.clone())
+        }
+        Step::Index => {
+            if !ty.unpacked.is_empty() {
+                ty.unpacked.remove(0);
+            } else if !ty.packed.is_empty() {
+                ty.packed.remove(0);
+                ty.signed = false;
+            } else if ty.is_integral() {
+                ty = vector(1);
+            } else {
+                return None;
+            }
+            Some(ty)
+        }
+        Step::Range(width) => {
+            let width = i64::try_from((*width)?).ok()?;
+            if !ty.unpacked.is_empty() {
+                ty.unpacked[0] = Dimension::Range(width - 1, 0);
+            } else if !ty.packed.is_empty() {
+                ty.packed[0] = Some((width - 1, 0));
+                ty.signed = false;
+            } else if ty.is_integral() {
+                ty = vector(width);
+            } else {
+                return None;
+            }
+            Some(ty)
+        }
+    }
+}
+
+fn vector(width: i64) -> TypeInfo {
+    TypeInfo {
+        name: None,
+        kind: TypeKind::Logic,
+        signed: false,
+        packed: vec![Some((width - 1, 0))],
+        unpacked: Vec::new(),
+    }
+}
+
+fn expr_type(x: &TypeInfo) -> Option<ExprType> {
+    if !x.is_integral() {
+        return None;
+    }
+    Some(ExprType {
+        width: x.bits()?,
+        signed: x.signed,
+    })
+}
+
+/// Get the type of an integral number literal such as `8'shff` or `42`.
+fn number_type(s: &str) -> Option<ExprType> {
+    let s: String = s
+        .chars()
+        .filter(|x| !x.is_whitespace() && *x != '_')
+        .collect();
+    match s.find('\'') {
+        Some(pos) => {
+            let width = if pos == 0 {
+                32
+            } else {
+                s[..pos].parse::<u64>().ok()?
+            };
+            let signed = matches!(s[pos + 1..].chars().next(), Some('s') | Some('S'));
+            Some(ExprType { width, signed })
+        }
+        None => Some(INTEGER),
+    }
+}
+
+/// Count the bytes of a string literal with escape sequences.
+fn string_bytes(s: &str) -> u64 {
+    let mut ret = 0;
+    let mut chars = s.trim_matches('"').chars();
+    while let Some(x) = chars.next() {
+        if x == '\\' {
+            chars.next();
+        }
+        ret += 1;
+    }
+    ret
+}
+
+fn positive(x: i64) -> Option<u64> {
+    if x > 0 {
+        Some(x as u64)
+    } else {
+        None
+    }
+}
+
+/// Get the condition if it is a plain expression rather than a pattern match.
+fn condition(x: &ConditionalExpression) -> Option<&Expression> {
+    let c = x.nodes.0.nodes.0.contents();
+    match c.as_slice() {
+        [ExpressionOrCondPattern::Expression(c)] => Some(&**c),
+        _ => None,
+    }
+}
+
+/// Get the range of the text covered by the node.
+fn place(node: RefNode) -> Locate {
+    let line = unwrap_locate!(node.clone()).map_or(0, |x| x.line);
+    match span(vec![node]) {
+        Some((begin, end)) => Locate {
+            offset: begin,
+            line,
+            len: end - begin,
+        },
+        None => Locate::default(),
+    }
+}
+
+/// Get the port names of each module in declaration order.
+fn port_names(tree: &SyntaxTree) -> HashMap<String, Vec<String>> {
+    let mut ret = HashMap::new();
+    for node in tree {
+        let (name, ports): (_, RefNodes) = match node {
+            RefNode::ModuleAnsiHeader(x) => (identifier(tree, &x.nodes.3), (&x.nodes.6).into()),
+            RefNode::ModuleNonansiHeader(x) => (identifier(tree, &x.nodes.3), (&x.nodes.6).into()),
+            _ => continue,
+        };
+        let mut names = Vec::new();
+        for x in Iter::new(ports) {
+            if let RefNode::PortIdentifier(x) = x {
+                names.extend(identifier(tree, x));
+            }
+        }
+        if let Some(name) = name {
+            ret.insert(name, names);
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_width() {
+        let src = r##"module sub(input logic [7:0] d, output logic [3:0] q);
+endmodule
+
+module top(input logic [3:0] a, input logic [3:0] b, input logic signed [3:0] s,
+           output logic [4:0] y, output logic [2:0] z);
+  localparam int W = 4;
+  logic [7:0] w;
+  logic [3:0] q;
+  logic [3:0] v = 4'd3;
+
+  assign y = a + b;
+  assign z = a;
+  assign w = {a, $signed(s) >>> 1};
+  assign q = s;
+
+  always_comb begin
+    if (a == w) v = 8'd1;
+    if (a == W - 1) v = '1;
+    if (s < b) v = b;
+  end
+
+  sub u_sub (.d(a), .q(q));
+endmodule"##;
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        let analyzer = WidthAnalyzer::new(&tree);
+
+        let assigns: Vec<&Expression> = tree
+            .into_iter()
+            .filter_map(|x| match x {
+                RefNode::NetAssignment(x) => Some(&x.nodes.2),
+                _ => None,
+            })
+            .collect();
+        let ty = |width, signed| Some(ExprType { width, signed });
+
+        let sizes: Vec<(&str, Option<ExprType>, Option<ExprType>)> = analyzer
+            .sizes("top", assigns[0], ty(5, false))
+            .iter()
+            .map(|x| {
+                (
+                    tree.get_str(&x.locate).unwrap(),
+                    x.self_determined,
+                    x.context_determined,
+                )
+            })
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![
+                ("a + b", ty(4, false), ty(5, false)),
+                ("a", ty(4, false), ty(5, false)),
+                ("b", ty(4, false), ty(5, false)),
+            ]
+        );
+        let sizes = analyzer.sizes("top", assigns[2], None);
+        assert_eq!(sizes[0].self_determined, ty(8, false));
+        assert_eq!(sizes[2].text, "$signed(s) >>> 1");
+        assert_eq!(sizes[2].self_determined, ty(4, true));
+        assert_eq!(sizes[3].context_determined, ty(4, true));
+
+        let check = analyzer.check();
+        let diagnostics: Vec<String> = check
+            .iter()
+            .map(|x| match x {
+                WidthDiagnostic::Truncation {
+                    target,
+                    target_width,
+                    width,
+                    ..
+                } => format!("truncation {} {} {}", target, target_width, width),
+                WidthDiagnostic::Extension {
+                    target,
+                    target_width,
+                    width,
+                    signed,
+                    ..
+                } => format!("extension {} {} {} {}", target, target_width, width, signed),
+                WidthDiagnostic::ComparisonMismatch { left, right, .. } => format!(
+                    "comparison {}{} {}{}",
+                    left.width, left.signed, right.width, right.signed
+                ),
+                WidthDiagnostic::PortMismatch {
+                    instance,
+                    port,
+                    port_width,
+                    width,
+                    ..
+                } => format!("port {}.{} {} {}", instance, port, port_width, width),
+            })
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "extension y 5 4 false",
+                "truncation z 3 4",
+                "comparison 4false 8false",
+                "comparison 4true 4false",
+                "port u_sub.d 8 4",
+            ]
+        );
+    }
+
+    type Size<'a> = (&'a str, Option<ExprType>, Option<ExprType>);
+
+    /// Get the sizes of the continuous assignments in `top` with the width of each target.
+    fn sizes<'a>(tree: &'a SyntaxTree, targets: &[(u64, bool)]) -> Vec<Vec<Size<'a>>> {
+        let analyzer = WidthAnalyzer::new(tree);
+        tree.into_iter()
+            .filter_map(|x| match x {
+                RefNode::NetAssignment(x) => Some(&x.nodes.2),
+                _ => None,
+            })
+            .zip(targets)
+            .map(|(x, &(width, signed))| {
+                analyzer
+                    .sizes("top", x, Some(ExprType { width, signed }))
+                    .iter()
+                    .map(|x| {
+                        (
+                            tree.get_str(&x.locate).unwrap(),
+                            x.self_determined,
+                            x.context_determined,
+                        )
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn parse(src: &str) -> SyntaxTree {
+        let (tree, _) =
+            parse_sv_str(src, PathBuf::from(""), &HashMap::new(), &[""], false, false).unwrap();
+        tree
+    }
+
+    fn ty(width: u64, signed: bool) -> Option<ExprType> {
+        Some(ExprType { width, signed })
+    }
+
+    #[test]
+    fn test_shift_power() {
+        let tree = parse(
+            r##"module top(input logic [3:0] a, input logic [2:0] b, input logic signed [3:0] s,
+           output logic [7:0] y0, output logic [7:0] y1, output logic [7:0] y2);
+  assign y0 = a << (b + 1'b1);
+  assign y1 = s >>> b;
+  assign y2 = a ** s;
+endmodule"##,
+        );
+        let sizes = sizes(&tree, &[(8, false), (8, false), (8, false)]);
+        // The right operand is self-determined
+        assert_eq!(
+            sizes[0],
+            vec![
+                ("a << (b + 1'b1)", ty(4, false), ty(8, false)),
+                ("a", ty(4, false), ty(8, false)),
+                ("(b + 1'b1)", ty(3, false), ty(3, false)),
+                ("b", ty(3, false), ty(3, false)),
+                ("1'b1", ty(1, false), ty(3, false)),
+            ]
+        );
+        assert_eq!(
+            sizes[1],
+            vec![
+                ("s >>> b", ty(4, true), ty(8, true)),
+                ("s", ty(4, true), ty(8, true)),
+                ("b", ty(3, false), ty(3, false)),
+            ]
+        );
+        assert_eq!(
+            sizes[2],
+            vec![
+                ("a ** s", ty(4, false), ty(8, false)),
+                ("a", ty(4, false), ty(8, false)),
+                ("s", ty(4, true), ty(4, true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conditional() {
+        let tree = parse(
+            r##"module top(input logic [3:0] a, input logic [7:0] w, input logic [1:0] c,
+           output logic [9:0] y);
+  assign y = c ? a : w;
+endmodule"##,
+        );
+        let sizes = sizes(&tree, &[(10, false)]);
+        // The condition is self-determined, and the others are sized to each other
+        assert_eq!(
+            sizes[0],
+            vec![
+                ("c ? a : w", ty(8, false), ty(10, false)),
+                ("c", ty(2, false), ty(2, false)),
+                ("a", ty(4, false), ty(10, false)),
+                ("w", ty(8, false), ty(10, false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_signedness() {
+        let tree = parse(
+            r##"module top(input logic [3:0] a, input logic signed [3:0] s, input logic signed [5:0] t,
+           output logic signed [7:0] y0, output logic signed [7:0] y1,
+           output logic y2);
+  assign y0 = s + t;
+  assign y1 = s + a;
+  assign y2 = s < 4'sd2;
+endmodule"##,
+        );
+        let sizes = sizes(&tree, &[(8, true), (8, true), (1, false)]);
+        // The expression is unsigned if any operand is unsigned (11.8.1)
+        assert_eq!(
+            sizes[0],
+            vec![
+                ("s + t", ty(6, true), ty(8, true)),
+                ("s", ty(4, true), ty(8, true)),
+                ("t", ty(6, true), ty(8, true)),
+            ]
+        );
+        assert_eq!(
+            sizes[1],
+            vec![
+                ("s + a", ty(4, false), ty(8, false)),
+                ("s", ty(4, true), ty(8, false)),
+                ("a", ty(4, false), ty(8, false)),
+            ]
+        );
+        assert_eq!(
+            sizes[2],
+            vec![
+                ("s < 4'sd2", ty(1, false), ty(1, false)),
+                ("s", ty(4, true), ty(4, true)),
+                ("4'sd2", ty(4, true), ty(4, true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let tree = parse(
+            r##"module top(input logic [3:0] a, output logic [7:0] y0, output logic [7:0] y1);
+  localparam longint N = 64'h7fff_ffff_ffff_ffff;
+  logic [N:-N] big;
+  assign y0 = {N{a}};
+  assign y1 = big;
+endmodule"##,
+        );
+        let sizes = sizes(&tree, &[(8, false), (8, false)]);
+        assert_eq!(sizes[0][0].1, None);
+        assert_eq!(sizes[1][0].1, None);
+    }
+}
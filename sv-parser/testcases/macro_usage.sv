@@ -0,0 +1,23 @@
+`define WIDTH 8
+`define ADD(a, b) (a + b)
+`define INFO(msg) $display(msg);
+`define REGS logic [`WIDTH-1:0] r0; logic [`WIDTH-1:0] r1;
+`define GETTER(name) function int get_``name(); return name; endfunction
+`define LHS lhs =
+`define INT int
+
+module A;
+  `REGS
+  logic [7:0] lhs;
+  assign lhs = `ADD(r0, 1);
+  initial begin
+    `INFO("hello")
+    `LHS 0;
+  end
+endmodule
+
+class C;
+  int x;
+  `GETTER(x)
+  `INT y;
+endclass
@@ -0,0 +1,6 @@
+module A;
+wire a;
+// synopsys translate_off
+this is not SystemVerilog;
+// synopsys translate_on
+endmodule